@@ -0,0 +1,52 @@
+//! Measures how long stitching a large tile map takes. Run it with and without the rayon feature to compare
+//! the sequential and the parallel stitching:
+//!
+//! cargo bench --bench stitch_tiles
+//! cargo bench --bench stitch_tiles --features rayon
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy_render::prelude::*;
+use bevy_render::render_asset::RenderAssetUsages;
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_texture_utils::tile_map_texture::TileMapTextureCreator;
+use pad::p;
+
+const MAP_SIZE: isize = 256;
+const TILE_SIZE: usize = 32;
+const ITERATIONS: u32 = 10;
+
+fn main() {
+    let tiles = (0..16u8)
+        .map(|i| Image::new(
+            Extent3d { width: TILE_SIZE as u32, height: TILE_SIZE as u32, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            [i * 16, 255 - i * 16, i, 255].repeat(TILE_SIZE * TILE_SIZE),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        ))
+        .collect::<Vec<_>>();
+    let positions_and_images = (0..MAP_SIZE)
+        .flat_map(|x| (0..MAP_SIZE).map(move |y| p!(x, y)))
+        .map(|pos| (pos, &tiles[(pos.x * 7 + pos.y * 3) as usize % tiles.len()]))
+        .collect::<Vec<_>>();
+    let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, TILE_SIZE, TILE_SIZE);
+
+    // warm up, so the allocations of the first run are not measured
+    black_box(creator.create_tile_map_image_from_images(positions_and_images.iter().copied()).unwrap());
+
+    let mut total = Duration::ZERO;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(creator.create_tile_map_image_from_images(positions_and_images.iter().copied()).unwrap());
+        total += start.elapsed();
+    }
+
+    println!(
+        "stitching a {MAP_SIZE}x{MAP_SIZE} map of {TILE_SIZE}px tiles ({}): {:?} per map",
+        if cfg!(feature = "rayon") { "parallel" } else { "sequential" },
+        total / ITERATIONS
+    );
+}
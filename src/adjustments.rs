@@ -0,0 +1,106 @@
+use bevy_render::prelude::*;
+
+use crate::error::TextureUtilsError;
+use crate::texture_modification::modify_texture_colors;
+
+// All adjustments work with the 8-bit RGBA and BGRA formats and keep the alpha of the pixels.
+// An amount of 0.0 keeps the texture unchanged.
+
+/// Make the texture brighter or darker by scaling the light of every pixel, which is done in linear space,
+/// so the hues don't shift. An amount of -1.0 makes the texture black, and 1.0 doubles the light.
+pub fn adjust_brightness(texture: &mut Image, amount: f32) -> Result<(), TextureUtilsError> {
+    let factor = (1.0 + amount).max(0.0);
+
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+        Color::rgba_linear(r * factor, g * factor, b * factor, a)
+    })
+}
+
+/// Increase or decrease the difference between bright and dark pixels around the perceived middle gray.
+/// An amount of -1.0 makes the texture completely gray, and 1.0 doubles the contrast.
+pub fn adjust_contrast(texture: &mut Image, amount: f32) -> Result<(), TextureUtilsError> {
+    let factor = (1.0 + amount).max(0.0);
+    let stretch = |value: f32| ((value - 0.5) * factor + 0.5).clamp(0.0, 1.0);
+
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+        Color::rgba(stretch(r), stretch(g), stretch(b), a)
+    })
+}
+
+/// Rotate the hue of every pixel by the given amount of degrees, for example to create color variations of sprites.
+pub fn adjust_hue(texture: &mut Image, degrees: f32) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| {
+        let [hue, saturation, lightness, alpha] = color.as_hsla_f32();
+        Color::hsla((hue + degrees).rem_euclid(360.0), saturation, lightness, alpha)
+    })
+}
+
+/// Make the colors more or less intense by moving them away from or towards their luminance, which is
+/// computed in linear space. An amount of -1.0 makes the texture grayscale, and 1.0 doubles the saturation.
+pub fn adjust_saturation(texture: &mut Image, amount: f32) -> Result<(), TextureUtilsError> {
+    let factor = (1.0 + amount).max(0.0);
+
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+        let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let saturate = |value: f32| (luminance + (value - luminance) * factor).max(0.0);
+
+        Color::rgba_linear(saturate(r), saturate(g), saturate(b), a)
+    })
+}
+
+/// Apply a gamma curve to the perceived brightness of every pixel. A gamma greater than 1.0 brightens
+/// the dark tones, a gamma smaller than 1.0 darkens them, and 1.0 keeps the texture unchanged.
+pub fn gamma_correct(texture: &mut Image, gamma: f32) -> Result<(), TextureUtilsError> {
+    if gamma <= 0.0 {
+        return Err(TextureUtilsError::OutOfRange { name: "gamma", value: gamma as f64, expected: "positive" });
+    }
+
+    let correct = |value: f32| value.powf(1.0 / gamma);
+
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+        Color::rgba(correct(r), correct(g), correct(b), a)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::adjustments::{adjust_brightness, adjust_contrast, adjust_hue, adjust_saturation, gamma_correct};
+    use crate::builders::create_image;
+
+    fn pixel(texture: &Image) -> [u8; 4] {
+        [texture.data[0], texture.data[1], texture.data[2], texture.data[3]]
+    }
+
+    #[test]
+    fn adjustments_work() {
+        // arrange
+        let gray = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::rgb_u8(128, 128, 128)]);
+        let mut brightened = gray.clone();
+        let mut contrasted = gray.clone();
+        let mut corrected = gray.clone();
+        let mut rotated = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let mut desaturated = create_image((1, 1), TextureFormat::Bgra8UnormSrgb, [Color::BLUE]);
+
+        // act
+        adjust_brightness(&mut brightened, -1.0).unwrap();
+        adjust_contrast(&mut contrasted, 1.0).unwrap();
+        gamma_correct(&mut corrected, 2.0).unwrap();
+        adjust_hue(&mut rotated, 120.0).unwrap();
+        adjust_saturation(&mut desaturated, -1.0).unwrap();
+
+        // assert
+        assert_eq!([0, 0, 0, 255], pixel(&brightened));
+        assert_eq!([129, 129, 129, 255], pixel(&contrasted));
+        assert_eq!([181, 181, 181, 255], pixel(&corrected));
+        assert_eq!([0, 255, 0, 255], pixel(&rotated));
+        // the BGRA image contains red, which has a luminance of 0.2126 in linear space
+        assert_eq!([127, 127, 127, 255], pixel(&desaturated));
+    }
+}
@@ -0,0 +1,283 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+
+/// Write the given frames as an animated image to the given path, so generated animations can be
+/// previewed outside of the engine. The file type is chosen by the extension of the path: '.gif'
+/// creates an animated GIF, '.png' or '.apng' creates an animated PNG.
+/// Every frame needs a delay and all frames must have the same size. The animation loops forever.
+/// The frames must be 8-bit RGBA or BGRA images, every other format results in an error.
+pub fn export_animation<'a>(
+    frames: impl IntoIterator<Item=&'a Image>,
+    delays: impl IntoIterator<Item=Duration>,
+    path: impl AsRef<Path>,
+) -> Result<(), TextureUtilsError> {
+    let frames = frames.into_iter().collect::<Vec<_>>();
+    let delays = delays.into_iter().collect::<Vec<_>>();
+    let path = path.as_ref();
+
+    let data = frames
+        .iter()
+        .map(|frame| rgba_data(frame))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (width, height) = validate_frames(&frames, &delays)?;
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    match extension.as_deref() {
+        Some("gif") => write_gif(&data, &delays, width, height, path),
+        Some("png") | Some("apng") => write_apng(&data, &delays, width, height, path),
+        _ => Err(TextureUtilsError::InvalidParameter(format!("The path '{}' has no supported animation file extension (gif, png, apng).", path.display())))
+    }
+}
+
+fn validate_frames(frames: &[&Image], delays: &[Duration]) -> Result<(u32, u32), TextureUtilsError> {
+    let first = frames.first().ok_or(TextureUtilsError::NoImagesProvided)?;
+
+    if frames.len() != delays.len() {
+        return Err(TextureUtilsError::CountMismatch { kind: "delays", expected: frames.len(), actual: delays.len() });
+    }
+
+    let (width, height) = (first.width(), first.height());
+
+    if frames.iter().any(|frame| frame.width() != width || frame.height() != height) {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    if frames.iter().any(|frame| frame.data.len() != (width * height * 4) as usize) {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    Ok((width, height))
+}
+
+/// Return the pixels of the given frame in RGBA order, as the encoders expect them.
+fn rgba_data(frame: &Image) -> Result<Vec<u8>, TextureUtilsError> {
+    match frame.texture_descriptor.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Ok(frame.data.clone()),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Ok(frame.data
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect()),
+        format => Err(TextureUtilsError::UnsupportedFormat { format })
+    }
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, TextureUtilsError> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(TextureUtilsError::from)
+}
+
+fn write_gif(frames: &[Vec<u8>], delays: &[Duration], width: u32, height: u32, path: &Path) -> Result<(), TextureUtilsError> {
+    let width = u16::try_from(width).map_err(|_| TextureUtilsError::Encoding("The frames are too wide for a GIF.".to_string()))?;
+    let height = u16::try_from(height).map_err(|_| TextureUtilsError::Encoding("The frames are too high for a GIF.".to_string()))?;
+
+    let mut encoder = gif::Encoder::new(create_file(path)?, width, height, &[])
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not create the GIF encoder: {e}")))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not set the GIF repeat mode: {e}")))?;
+
+    for (data, delay) in frames.iter().zip(delays) {
+        let mut data = data.clone();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut data, 10);
+        // GIF delays are given in hundredths of a second
+        frame.delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+
+        encoder
+            .write_frame(&frame)
+            .map_err(|e| TextureUtilsError::Encoding(format!("Could not write a GIF frame: {e}")))?;
+    }
+
+    Ok(())
+}
+
+fn write_apng(frames: &[Vec<u8>], delays: &[Duration], width: u32, height: u32, path: &Path) -> Result<(), TextureUtilsError> {
+    let mut encoder = png::Encoder::new(create_file(path)?, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not configure the APNG animation: {e}")))?;
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not write the APNG header: {e}")))?;
+
+    for (data, delay) in frames.iter().zip(delays) {
+        // APNG delays are a fraction, so milliseconds are given as millis / 1000
+        let millis = delay.as_millis().min(u16::MAX as u128) as u16;
+
+        writer
+            .set_frame_delay(millis, 1000)
+            .map_err(|e| TextureUtilsError::Encoding(format!("Could not set an APNG frame delay: {e}")))?;
+        writer
+            .write_image_data(data)
+            .map_err(|e| TextureUtilsError::Encoding(format!("Could not write an APNG frame: {e}")))?;
+    }
+
+    writer
+        .finish()
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not finish the APNG: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use uuid::Uuid;
+
+    use crate::animation_export::export_animation;
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+
+    fn create_frames() -> [Image; 2] {
+        [
+            create_image(
+                (2, 2),
+                TextureFormat::Rgba8UnormSrgb,
+                [
+                    Color::RED, Color::BLUE,
+                    Color::BLUE, Color::RED
+                ],
+            ),
+            create_image(
+                (2, 2),
+                TextureFormat::Rgba8UnormSrgb,
+                [
+                    Color::BLUE, Color::RED,
+                    Color::RED, Color::BLUE
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn export_animation_as_gif_works() {
+        // arrange
+        let frames = create_frames();
+        let path = std::env::temp_dir().join(format!("{}.gif", Uuid::new_v4()));
+
+        // act
+        let result = export_animation(
+            &frames,
+            [Duration::from_millis(100), Duration::from_millis(200)],
+            &path,
+        );
+
+        // assert
+        assert!(result.is_ok());
+
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path).unwrap()).unwrap();
+        let mut delays = vec![];
+
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vec![10, 20], delays, "The GIF should contain two frames with the given delays, but didn't.");
+    }
+
+    #[test]
+    fn export_animation_as_apng_works() {
+        // arrange
+        let frames = create_frames();
+        let path = std::env::temp_dir().join(format!("{}.png", Uuid::new_v4()));
+
+        // act
+        let result = export_animation(
+            &frames,
+            [Duration::from_millis(100), Duration::from_millis(200)],
+            &path,
+        );
+
+        // assert
+        assert!(result.is_ok());
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let animation_control = reader.info().animation_control.unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(2, animation_control.num_frames, "The APNG should contain two frames, but didn't.");
+    }
+
+    /// Every frame needs a delay, so mismatching amounts result in an error.
+    #[test]
+    fn export_animation_with_missing_delays_fails() {
+        // arrange
+        let frames = create_frames();
+        let path = std::env::temp_dir().join(format!("{}.gif", Uuid::new_v4()));
+
+        // act
+        let result = export_animation(&frames, [Duration::from_millis(100)], &path);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::CountMismatch { kind: "delays", expected: 2, actual: 1 })));
+        assert!(!path.exists());
+    }
+
+    /// BGRA frames are written with their red and blue channels in the right order.
+    #[test]
+    fn export_animation_with_bgra_frames_works() {
+        // arrange
+        let mut frames = create_frames();
+        frames.iter_mut().for_each(|frame| {
+            frame.data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+            frame.texture_descriptor.format = TextureFormat::Bgra8UnormSrgb;
+        });
+        let path = std::env::temp_dir().join(format!("{}.png", Uuid::new_v4()));
+
+        // act
+        let result = export_animation(
+            &frames,
+            [Duration::from_millis(100), Duration::from_millis(200)],
+            &path,
+        );
+
+        // assert
+        assert!(result.is_ok());
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buffer).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&create_frames()[0].data[..], &buffer[..16], "The first frame should be written as RGBA, but wasn't.");
+    }
+
+    #[test]
+    fn export_animation_with_unsupported_format_fails() {
+        // arrange
+        let mut frames = create_frames();
+        frames.iter_mut().for_each(|frame| frame.texture_descriptor.format = TextureFormat::R32Float);
+        let path = std::env::temp_dir().join(format!("{}.gif", Uuid::new_v4()));
+
+        // act
+        let result = export_animation(
+            &frames,
+            [Duration::from_millis(100), Duration::from_millis(200)],
+            &path,
+        );
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R32Float })));
+        assert!(!path.exists());
+    }
+}
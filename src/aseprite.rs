@@ -0,0 +1,677 @@
+use std::io::Read;
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_asset::{Asset, AssetApp, AssetLoader, AsyncReadExt, LoadContext};
+use bevy_asset::io::Reader;
+use bevy_reflect::TypePath;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_utils::BoxedFuture;
+use flate2::read::ZlibDecoder;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::layered_image::{BlendMode, Layer, LayeredImage};
+use crate::pixel_rect::PixelRect;
+
+/// Registers the [AsepriteLoader], so .ase and .aseprite files can be loaded as [AsepriteFile]s.
+pub struct AsepritePlugin;
+
+impl Plugin for AsepritePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<AsepriteFile>()
+            .register_asset_loader(AsepriteLoader);
+    }
+}
+
+/// Loads Aseprite files.
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = AsepriteFile;
+    type Settings = ();
+    type Error = TextureUtilsError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = vec![];
+            reader
+                .read_to_end(&mut bytes)
+                .await?;
+
+            AsepriteFile::from_bytes(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ase", "aseprite"]
+    }
+}
+
+/// The content of an Aseprite file: its layers, frames, tags and slices.
+/// Every cel is converted to an image with 4-byte RGBA pixels.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct AsepriteFile {
+    pub width: usize,
+    pub height: usize,
+    /// The layers from bottom to top
+    pub layers: Vec<AsepriteLayer>,
+    pub frames: Vec<AsepriteFrame>,
+    pub tags: Vec<AsepriteTag>,
+    pub slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsepriteLayer {
+    pub name: String,
+    pub visible: bool,
+    /// Group layers only structure other layers and have no cels
+    pub is_group: bool,
+    /// The opacity (0.0 to 1.0) of the layer
+    pub opacity: f32,
+    /// Blend modes without a counterpart in [BlendMode] fall back to normal blending
+    pub blend_mode: BlendMode,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsepriteFrame {
+    pub duration: Duration,
+    pub cels: Vec<AsepriteCel>,
+}
+
+/// The content of a layer in a frame.
+#[derive(Clone, Debug)]
+pub struct AsepriteCel {
+    /// The index of the layer in [AsepriteFile::layers]
+    pub layer: usize,
+    /// The position of the top left pixel of the cel on the canvas
+    pub position: (isize, isize),
+    /// The opacity (0.0 to 1.0) of the cel
+    pub opacity: f32,
+    pub image: Image,
+}
+
+/// Tells in which direction the frames of a tag are played.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TagDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// A named range of frames, usually an animation.
+#[derive(Clone, Debug)]
+pub struct AsepriteTag {
+    pub name: String,
+    /// The first frame of the tag
+    pub from: usize,
+    /// The last frame of the tag (inclusive)
+    pub to: usize,
+    pub direction: TagDirection,
+}
+
+/// A named area of the canvas, which can change from frame to frame.
+#[derive(Clone, Debug)]
+pub struct AsepriteSlice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// The area of a slice, valid from the given frame until the frame of the next key.
+#[derive(Clone, Debug)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub bounds: PixelRect,
+    /// The center of a nine-slice, relative to the bounds
+    pub center: Option<PixelRect>,
+    /// The pivot, relative to the bounds
+    pub pivot: Option<(isize, isize)>,
+}
+
+impl AsepriteFile {
+    /// Parse the given bytes of an Aseprite file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextureUtilsError> {
+        AsepriteParser::new(bytes).parse()
+    }
+
+    /// Get the given frame as a [LayeredImage] with one layer per visible cel,
+    /// ready to be edited further or flattened.
+    pub fn layered_image(&self, frame: usize) -> Result<LayeredImage, TextureUtilsError> {
+        let frame = self.frames.get(frame).ok_or(TextureUtilsError::NotFound { kind: "frame", name: frame.to_string() })?;
+        let mut layered = LayeredImage::new((self.width, self.height));
+
+        for cel in &frame.cels {
+            let layer = &self.layers[cel.layer];
+
+            let mut image_layer = Layer::new(layer.name.clone(), cel.image.clone(), cel.position)
+                .with_opacity(layer.opacity * cel.opacity)
+                .with_blend_mode(layer.blend_mode);
+            image_layer.visible = layer.visible;
+
+            layered.add_layer(image_layer)?;
+        }
+
+        Ok(layered)
+    }
+
+    /// Get the given frame with all visible layers flattened to a single image.
+    pub fn frame_image(&self, frame: usize, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+        Ok(self.layered_image(frame)?.with_options(options).flatten())
+    }
+
+    /// Get the flattened images and durations of all frames of the tag with the given name,
+    /// in the order they are played. Ping-pong tags play every frame once in each direction.
+    pub fn tag_frames(&self, name: &str, options: ImageOptions) -> Result<Vec<(Image, Duration)>, TextureUtilsError> {
+        let tag = self.tags
+            .iter()
+            .find(|tag| tag.name == name)
+            .ok_or(TextureUtilsError::NotFound { kind: "tag", name: name.to_string() })?;
+
+        let forward = (tag.from..=tag.to).collect::<Vec<_>>();
+        let reverse = forward.iter().rev().copied().collect::<Vec<_>>();
+
+        let order = match tag.direction {
+            TagDirection::Forward => forward,
+            TagDirection::Reverse => reverse,
+            TagDirection::PingPong => forward.iter().chain(reverse.iter().skip(1)).copied().collect(),
+            TagDirection::PingPongReverse => reverse.iter().chain(forward.iter().skip(1)).copied().collect(),
+        };
+
+        order
+            .into_iter()
+            .map(|frame| Ok((self.frame_image(frame, options.clone())?, self.frames[frame].duration)))
+            .collect()
+    }
+}
+
+/// The color depth of an Aseprite file.
+#[derive(Copy, Clone)]
+enum ColorDepth {
+    Rgba,
+    Grayscale,
+    Indexed { transparent_index: u8 },
+}
+
+struct AsepriteParser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> AsepriteParser<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn parse(mut self) -> Result<AsepriteFile, TextureUtilsError> {
+        self.skip(4)?;
+
+        if self.word()? != 0xA5E0 {
+            return Err(TextureUtilsError::Decoding("The file is not an Aseprite file.".to_string()));
+        }
+
+        let frame_count = self.word()? as usize;
+        let width = self.word()? as usize;
+        let height = self.word()? as usize;
+        let depth = self.word()?;
+        self.skip(14)?;
+        let transparent_index = self.byte()?;
+
+        let color_depth = match depth {
+            32 => ColorDepth::Rgba,
+            16 => ColorDepth::Grayscale,
+            8 => ColorDepth::Indexed { transparent_index },
+            _ => return Err(TextureUtilsError::Decoding(format!("The color depth {} is not supported.", depth)))
+        };
+
+        self.position = 128;
+
+        let mut file = AsepriteFile { width, height, layers: vec![], frames: vec![], tags: vec![], slices: vec![] };
+        let mut palette = vec![[0u8; 4]; 256];
+
+        for _ in 0..frame_count {
+            let frame_start = self.position;
+            let frame_size = self.dword()? as usize;
+
+            if self.word()? != 0xF1FA {
+                return Err(TextureUtilsError::Decoding("The file contains a corrupted frame.".to_string()));
+            }
+
+            let old_chunk_count = self.word()? as usize;
+            let duration = Duration::from_millis(self.word()? as u64);
+            self.skip(2)?;
+            let chunk_count = match self.dword()? as usize {
+                0 => old_chunk_count,
+                count => count
+            };
+
+            let mut frame = AsepriteFrame { duration, cels: vec![] };
+
+            for _ in 0..chunk_count {
+                let chunk_start = self.position;
+                let chunk_size = self.dword()? as usize;
+                let chunk_type = self.word()?;
+
+                match chunk_type {
+                    0x2004 => file.layers.push(self.layer()?),
+                    0x2005 => if let Some(cel) = self.cel(&file, chunk_start + chunk_size, color_depth, &palette)? {
+                        frame.cels.push(cel)
+                    },
+                    0x2018 => file.tags.extend(self.tags()?),
+                    0x2019 => self.palette(&mut palette)?,
+                    0x2022 => file.slices.push(self.slice()?),
+                    _ => {}
+                }
+
+                self.position = chunk_start + chunk_size;
+            }
+
+            // cels are stored in any order, but layers are drawn from bottom to top
+            frame.cels.sort_by_key(|cel| cel.layer);
+            file.frames.push(frame);
+            self.position = frame_start + frame_size;
+        }
+
+        Ok(file)
+    }
+
+    fn layer(&mut self) -> Result<AsepriteLayer, TextureUtilsError> {
+        let flags = self.word()?;
+        let layer_type = self.word()?;
+        self.skip(6)?;
+        let blend_mode = match self.word()? {
+            1 => BlendMode::Multiply,
+            2 => BlendMode::Screen,
+            16 => BlendMode::Add,
+            _ => BlendMode::Normal
+        };
+        let opacity = self.byte()? as f32 / 255.0;
+        self.skip(3)?;
+        let name = self.string()?;
+
+        Ok(AsepriteLayer {
+            name,
+            visible: flags & 1 != 0,
+            is_group: layer_type == 1,
+            opacity,
+            blend_mode,
+        })
+    }
+
+    fn cel(
+        &mut self,
+        file: &AsepriteFile,
+        chunk_end: usize,
+        color_depth: ColorDepth,
+        palette: &[[u8; 4]],
+    ) -> Result<Option<AsepriteCel>, TextureUtilsError> {
+        let layer = self.word()? as usize;
+        let x = self.short()? as isize;
+        let y = self.short()? as isize;
+        let opacity = self.byte()? as f32 / 255.0;
+        let cel_type = self.word()?;
+        self.skip(7)?;
+
+        if layer >= file.layers.len() {
+            return Err(TextureUtilsError::Decoding(format!("A cel references the unknown layer {}.", layer)));
+        }
+
+        let (width, height, pixels) = match cel_type {
+            0 | 2 => {
+                let width = self.word()? as usize;
+                let height = self.word()? as usize;
+                let raw = self.slice_until(chunk_end)?;
+
+                let pixels = match cel_type {
+                    0 => raw.to_vec(),
+                    _ => {
+                        let mut pixels = vec![];
+                        ZlibDecoder::new(raw)
+                            .read_to_end(&mut pixels)
+                            .map_err(|e| TextureUtilsError::Decoding(format!("Could not decompress a cel: {e}")))?;
+                        pixels
+                    }
+                };
+
+                (width, height, pixels)
+            }
+            1 => {
+                let linked_frame = self.word()? as usize;
+
+                return Ok(file.frames
+                    .get(linked_frame)
+                    .and_then(|f| f.cels.iter().find(|c| c.layer == layer))
+                    .map(|cel| AsepriteCel { position: (x, y), opacity, ..cel.clone() }));
+            }
+            // tilemap cels are not supported
+            _ => return Ok(None)
+        };
+
+        let rgba = match color_depth {
+            ColorDepth::Rgba => pixels,
+            ColorDepth::Grayscale => pixels
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            ColorDepth::Indexed { transparent_index } => pixels
+                .iter()
+                .flat_map(|index| match *index == transparent_index {
+                    true => [0; 4],
+                    false => palette[*index as usize]
+                })
+                .collect(),
+        };
+
+        if rgba.len() != width * height * 4 {
+            return Err(TextureUtilsError::Decoding("A cel has less pixels than its size requires.".to_string()));
+        }
+
+        Ok(Some(AsepriteCel {
+            layer,
+            position: (x, y),
+            opacity,
+            image: ImageOptions::default().create_image((width, height), rgba, TextureFormat::Rgba8UnormSrgb),
+        }))
+    }
+
+    fn tags(&mut self) -> Result<Vec<AsepriteTag>, TextureUtilsError> {
+        let count = self.word()? as usize;
+        self.skip(8)?;
+
+        (0..count)
+            .map(|_| {
+                let from = self.word()? as usize;
+                let to = self.word()? as usize;
+                let direction = match self.byte()? {
+                    1 => TagDirection::Reverse,
+                    2 => TagDirection::PingPong,
+                    3 => TagDirection::PingPongReverse,
+                    _ => TagDirection::Forward
+                };
+                self.skip(12)?;
+                let name = self.string()?;
+
+                Ok(AsepriteTag { name, from, to, direction })
+            })
+            .collect()
+    }
+
+    fn palette(&mut self, palette: &mut [[u8; 4]]) -> Result<(), TextureUtilsError> {
+        self.skip(4)?;
+        let first = self.dword()? as usize;
+        let last = self.dword()? as usize;
+        self.skip(8)?;
+
+        for index in first..=last {
+            let flags = self.word()?;
+            let color = [self.byte()?, self.byte()?, self.byte()?, self.byte()?];
+
+            if let Some(entry) = palette.get_mut(index) {
+                *entry = color;
+            }
+
+            if flags & 1 != 0 {
+                self.string()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn slice(&mut self) -> Result<AsepriteSlice, TextureUtilsError> {
+        let key_count = self.dword()? as usize;
+        let flags = self.dword()?;
+        self.skip(4)?;
+        let name = self.string()?;
+
+        let keys = (0..key_count)
+            .map(|_| {
+                let frame = self.dword()? as usize;
+                let bounds = self.rect()?;
+                let center = match flags & 1 != 0 {
+                    true => Some(self.rect()?),
+                    false => None
+                };
+                let pivot = match flags & 2 != 0 {
+                    true => Some((self.long()? as isize, self.long()? as isize)),
+                    false => None
+                };
+
+                Ok(SliceKey { frame, bounds, center, pivot })
+            })
+            .collect::<Result<Vec<_>, TextureUtilsError>>()?;
+
+        Ok(AsepriteSlice { name, keys })
+    }
+
+    fn rect(&mut self) -> Result<PixelRect, TextureUtilsError> {
+        let x = self.long()?.max(0) as usize;
+        let y = self.long()?.max(0) as usize;
+        let width = self.dword()? as usize;
+        let height = self.dword()? as usize;
+
+        Ok(PixelRect::new(x, y, width, height))
+    }
+
+    fn slice_until(&mut self, end: usize) -> Result<&'a [u8], TextureUtilsError> {
+        let bytes = self.bytes
+            .get(self.position..end)
+            .ok_or(TextureUtilsError::Decoding("The file ended unexpectedly.".to_string()))?;
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], TextureUtilsError> {
+        let end = self.position + N;
+        let bytes = self.slice_until(end)?;
+
+        Ok(bytes.try_into().expect("the slice has the requested length"))
+    }
+
+    fn skip(&mut self, amount: usize) -> Result<(), TextureUtilsError> {
+        self.slice_until(self.position + amount).map(|_| ())
+    }
+
+    fn byte(&mut self) -> Result<u8, TextureUtilsError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, TextureUtilsError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn short(&mut self) -> Result<i16, TextureUtilsError> {
+        Ok(i16::from_le_bytes(self.take()?))
+    }
+
+    fn dword(&mut self) -> Result<u32, TextureUtilsError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn long(&mut self) -> Result<i32, TextureUtilsError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    fn string(&mut self) -> Result<String, TextureUtilsError> {
+        let length = self.word()? as usize;
+        let bytes = self.slice_until(self.position + length)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| TextureUtilsError::Decoding("The file contains an invalid string.".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::time::Duration;
+
+    use bevy_render::prelude::*;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use crate::aseprite::{AsepriteFile, TagDirection};
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::pixel_rect::PixelRect;
+
+    fn string(name: &str) -> Vec<u8> {
+        [(name.len() as u16).to_le_bytes().to_vec(), name.as_bytes().to_vec()].concat()
+    }
+
+    fn chunk(chunk_type: u16, data: Vec<u8>) -> Vec<u8> {
+        [((data.len() + 6) as u32).to_le_bytes().to_vec(), chunk_type.to_le_bytes().to_vec(), data].concat()
+    }
+
+    fn layer(name: &str) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend(1u16.to_le_bytes()); // visible
+        data.extend([0; 10]); // type, child level, default size, blend mode normal
+        data.push(255); // opacity
+        data.extend([0; 3]);
+        data.extend(string(name));
+        chunk(0x2004, data)
+    }
+
+    fn cel(layer: u16, (x, y): (i16, i16), cel_type: u16, (width, height): (u16, u16), pixels: Vec<u8>) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend(layer.to_le_bytes());
+        data.extend(x.to_le_bytes());
+        data.extend(y.to_le_bytes());
+        data.push(255); // opacity
+        data.extend(cel_type.to_le_bytes());
+        data.extend([0; 7]);
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        data.extend(pixels);
+        chunk(0x2005, data)
+    }
+
+    fn frame(duration: u16, chunks: Vec<Vec<u8>>) -> Vec<u8> {
+        let content = chunks.concat();
+        let mut data = vec![];
+        data.extend(((content.len() + 16) as u32).to_le_bytes());
+        data.extend(0xF1FAu16.to_le_bytes());
+        data.extend((chunks.len() as u16).to_le_bytes());
+        data.extend(duration.to_le_bytes());
+        data.extend([0; 2]);
+        data.extend((chunks.len() as u32).to_le_bytes());
+        data.extend(content);
+        data
+    }
+
+    /// Create a 2x1 RGBA file with two layers, two frames, a tag and a slice.
+    fn create_file() -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&Color::BLUE.as_rgba_u8()).unwrap();
+        let compressed_blue = encoder.finish().unwrap();
+
+        let mut tags = vec![];
+        tags.extend(1u16.to_le_bytes());
+        tags.extend([0; 8]);
+        tags.extend(0u16.to_le_bytes());
+        tags.extend(1u16.to_le_bytes());
+        tags.push(2); // ping-pong
+        tags.extend([0; 12]);
+        tags.extend(string("walk"));
+
+        let mut slice = vec![];
+        slice.extend(1u32.to_le_bytes());
+        slice.extend(0u32.to_le_bytes());
+        slice.extend(0u32.to_le_bytes());
+        slice.extend(string("hitbox"));
+        slice.extend(0u32.to_le_bytes());
+        slice.extend(1i32.to_le_bytes());
+        slice.extend(0i32.to_le_bytes());
+        slice.extend(1u32.to_le_bytes());
+        slice.extend(1u32.to_le_bytes());
+
+        let frames = [
+            frame(100, vec![
+                layer("background"),
+                layer("foreground"),
+                cel(1, (1, 0), 2, (1, 1), compressed_blue),
+                cel(0, (0, 0), 0, (2, 1), [Color::RED.as_rgba_u8(), Color::RED.as_rgba_u8()].concat()),
+                chunk(0x2018, tags),
+                chunk(0x2022, slice),
+            ]),
+            frame(200, vec![
+                cel(0, (0, 0), 0, (2, 1), [Color::GREEN.as_rgba_u8(), Color::GREEN.as_rgba_u8()].concat()),
+            ]),
+        ].concat();
+
+        let mut header = vec![];
+        header.extend(((frames.len() + 128) as u32).to_le_bytes());
+        header.extend(0xA5E0u16.to_le_bytes());
+        header.extend(2u16.to_le_bytes());
+        header.extend(2u16.to_le_bytes());
+        header.extend(1u16.to_le_bytes());
+        header.extend(32u16.to_le_bytes());
+        header.resize(128, 0);
+
+        [header, frames].concat()
+    }
+
+    #[test]
+    fn from_bytes_works() {
+        // act
+        let result = AsepriteFile::from_bytes(&create_file());
+
+        // assert
+        assert!(result.is_ok());
+        let file = result.unwrap();
+
+        assert_eq!((2, 1), (file.width, file.height));
+        assert_eq!(vec!["background", "foreground"], file.layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(vec![Duration::from_millis(100), Duration::from_millis(200)], file.frames.iter().map(|f| f.duration).collect::<Vec<_>>());
+        assert_eq!(("walk", 0, 1, TagDirection::PingPong), (file.tags[0].name.as_str(), file.tags[0].from, file.tags[0].to, file.tags[0].direction));
+        assert_eq!("hitbox", file.slices[0].name);
+        assert_eq!(PixelRect::new(1, 0, 1, 1), file.slices[0].keys[0].bounds);
+    }
+
+    #[test]
+    fn frame_image_works() {
+        // arrange
+        let file = AsepriteFile::from_bytes(&create_file()).unwrap();
+
+        // act
+        let first = file.frame_image(0, ImageOptions::default()).unwrap();
+        let second = file.frame_image(1, ImageOptions::default()).unwrap();
+
+        // assert
+        assert_eq!([Color::RED.as_rgba_u8(), Color::BLUE.as_rgba_u8()].concat(), first.data);
+        assert_eq!([Color::GREEN.as_rgba_u8(), Color::GREEN.as_rgba_u8()].concat(), second.data);
+    }
+
+    #[test]
+    fn tag_frames_works() {
+        // arrange
+        let file = AsepriteFile::from_bytes(&create_file()).unwrap();
+
+        // act
+        let frames = file.tag_frames("walk", ImageOptions::default()).unwrap();
+
+        // assert
+        assert_eq!(
+            vec![Duration::from_millis(100), Duration::from_millis(200), Duration::from_millis(100)],
+            frames.iter().map(|(_, duration)| *duration).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn from_bytes_with_wrong_magic_number_fails() {
+        // act
+        let result = AsepriteFile::from_bytes(&[0; 128]);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::Decoding(message)) if message == "The file is not an Aseprite file."));
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use bevy_asset::prelude::*;
+use bevy_math::{Rect, Vec2};
+use bevy_render::prelude::*;
+use bevy_render::texture::TextureFormatPixelInfo;
+use bevy_sprite::TextureAtlasLayout;
+
+use crate::blit::blit;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
+
+/// A texture with sprites of different sizes packed by an [AtlasPacker].
+#[derive(Clone, Debug)]
+pub struct PackedAtlas {
+    pub texture: Handle<Image>,
+    /// Contains the area of every sprite in pixels, in the order the images were given to the packer
+    pub layout: TextureAtlasLayout,
+    indices: HashMap<AssetId<Image>, usize>,
+}
+
+impl PackedAtlas {
+    /// Get the index of the sprite of the given image in the layout.
+    pub fn index(&self, handle: &Handle<Image>) -> Option<usize> {
+        self.indices.get(&handle.id()).copied()
+    }
+}
+
+/// Packs images of arbitrary sizes into a single texture, for sprite collections which
+/// don't fit into a grid like the one of [crate::sprite_atlas::SpriteAtlas].
+/// The images are placed with a skyline bottom-left algorithm, starting with the smallest
+/// square power-of-two texture the images could fit in and growing it up to the maximum size until they fit.
+#[derive(Clone, Debug)]
+pub struct AtlasPacker {
+    /// The maximum width and height of the packed texture
+    max_size: (usize, usize),
+    /// The amount of transparent pixels between the sprites
+    padding: usize,
+    /// The options for the packed textures
+    options: ImageOptions,
+}
+
+impl AtlasPacker {
+    pub fn new(max_width: usize, max_height: usize) -> Self {
+        Self { max_size: (max_width, max_height), padding: 0, options: ImageOptions::default() }
+    }
+
+    /// Set the amount of transparent pixels between the sprites, so they don't bleed into each other.
+    pub fn with_padding(mut self, padding: usize) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Set the options for the packed textures.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Pack the images of the given handles into a single texture, which is added to the images.
+    /// All images must have the same texture format.
+    pub fn pack(
+        &self,
+        images: &mut Assets<Image>,
+        handles: impl IntoIterator<Item=Handle<Image>>,
+    ) -> Result<PackedAtlas, TextureUtilsError> {
+        let handles = handles.into_iter().collect::<Vec<_>>();
+        let textures = handles
+            .iter()
+            .map(|handle| images
+                .get(handle)
+                .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+            )
+            .collect::<Result<Vec<_>, TextureUtilsError>>()?;
+
+        let (texture, layout) = self.pack_images(textures)?;
+        let indices = handles
+            .iter()
+            .enumerate()
+            .map(|(index, handle)| (handle.id(), index))
+            .collect();
+
+        Ok(PackedAtlas { texture: images.add(texture), layout, indices })
+    }
+
+    /// Like [AtlasPacker::pack], but takes the images directly and returns the packed texture
+    /// instead of adding it to the images. The layout contains the sprites in the order of the given images.
+    pub fn pack_images<'a>(
+        &self,
+        images: impl IntoIterator<Item=&'a Image>,
+    ) -> Result<(Image, TextureAtlasLayout), TextureUtilsError> {
+        let images = images.into_iter().collect::<Vec<_>>();
+        let first = images.first().ok_or(TextureUtilsError::NoImagesProvided)?;
+        let format = first.texture_descriptor.format;
+
+        let sizes = images
+            .iter()
+            .map(|image| (image.width() as usize + self.padding, image.height() as usize + self.padding))
+            .collect::<Vec<_>>();
+        let (width, positions) = self.place(&sizes)?;
+        let height = images
+            .iter()
+            .zip(&positions)
+            .map(|(image, (_, y))| y + image.height() as usize)
+            .max()
+            .unwrap_or_default();
+
+        let mut texture = self.options.create_image((width, height), vec![0; width * height * format.pixel_size()], format);
+        let mut layout = TextureAtlasLayout::new_empty(Vec2::new(width as f32, height as f32));
+
+        for (image, (x, y)) in images.iter().zip(positions) {
+            let (image_width, image_height) = (image.width() as usize, image.height() as usize);
+            blit(image, PixelRect::new(0, 0, image_width, image_height), &mut texture, (x as isize, y as isize))?;
+
+            let min = Vec2::new(x as f32, y as f32);
+            layout.add_texture(Rect::from_corners(min, min + Vec2::new(image_width as f32, image_height as f32)));
+        }
+
+        Ok((texture, layout))
+    }
+
+    /// Find the width of the packed texture and the position of every rectangle with the given sizes in it.
+    fn place(&self, sizes: &[(usize, usize)]) -> Result<(usize, Vec<(usize, usize)>), TextureUtilsError> {
+        let area = sizes.iter().map(|(w, h)| w * h).sum::<usize>();
+        let widest = sizes.iter().map(|(w, _)| *w).max().unwrap_or_default();
+        let highest = sizes.iter().map(|(_, h)| *h).max().unwrap_or_default();
+
+        let (max_width, max_height) = self.max_size;
+        let side = ((area as f32).sqrt().ceil() as usize).max(widest).max(highest).next_power_of_two();
+        let (mut width, mut height) = (side.min(max_width), side.min(max_height));
+
+        // place the highest rectangles first, which packs them more tightly
+        let mut order = (0..sizes.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| std::cmp::Reverse(sizes[*i].1));
+
+        loop {
+            if let Some(positions) = Skyline::new(width).place_all(sizes, &order, height) {
+                return Ok((width, positions));
+            }
+
+            if width == max_width && height == max_height {
+                return Err(TextureUtilsError::InvalidParameter(format!(
+                    "The images don't fit into a texture of the maximum size {:?}.",
+                    self.max_size
+                )));
+            }
+
+            match (width <= height && width < max_width) || height == max_height {
+                true => width = (width * 2).min(max_width),
+                false => height = (height * 2).min(max_height)
+            }
+        }
+    }
+}
+
+/// The upper edge of the already placed rectangles, as segments from left to right.
+struct Skyline {
+    width: usize,
+    /// The x position, height and width of every segment
+    segments: Vec<(usize, usize, usize)>,
+}
+
+impl Skyline {
+    fn new(width: usize) -> Self {
+        Self { width, segments: vec![(0, 0, width)] }
+    }
+
+    /// Place the rectangles with the given sizes in the given order. Returns the position of every
+    /// rectangle in the order of the sizes, or None if they don't fit below the given height.
+    fn place_all(mut self, sizes: &[(usize, usize)], order: &[usize], height: usize) -> Option<Vec<(usize, usize)>> {
+        let mut positions = vec![(0, 0); sizes.len()];
+
+        for index in order {
+            let (w, h) = sizes[*index];
+            let (x, y) = self.find_position(w, h, height)?;
+            self.add(x, y + h, w);
+            positions[*index] = (x, y);
+        }
+
+        Some(positions)
+    }
+
+    /// Find the lowest, then leftmost position where a rectangle of the given size fits.
+    fn find_position(&self, w: usize, h: usize, height: usize) -> Option<(usize, usize)> {
+        self.segments
+            .iter()
+            .filter(|(x, _, _)| x + w <= self.width)
+            .map(|(x, _, _)| {
+                let y = self.segments
+                    .iter()
+                    .filter(|(sx, _, sw)| sx + sw > *x && *sx < x + w)
+                    .map(|(_, sy, _)| *sy)
+                    .max()
+                    .unwrap_or_default();
+                (*x, y)
+            })
+            .filter(|(_, y)| y + h <= height)
+            .min_by_key(|(x, y)| (*y, *x))
+    }
+
+    /// Raise the skyline to the given height between x and x + w.
+    fn add(&mut self, x: usize, y: usize, w: usize) {
+        let end = x + w;
+        let mut segments = Vec::with_capacity(self.segments.len() + 2);
+
+        for (sx, sy, sw) in self.segments.drain(..) {
+            let segment_end = sx + sw;
+
+            if segment_end <= x || sx >= end {
+                segments.push((sx, sy, sw));
+                continue;
+            }
+
+            if sx < x {
+                segments.push((sx, sy, x - sx));
+            }
+
+            if segment_end > end {
+                segments.push((end, sy, segment_end - end));
+            }
+        }
+
+        segments.push((x, y, w));
+        segments.sort_by_key(|(sx, _, _)| *sx);
+        self.segments = segments;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_math::{Rect, Vec2};
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::atlas_packer::AtlasPacker;
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+
+    #[test]
+    fn pack_works() {
+        // arrange
+        let packer = AtlasPacker::new(64, 64);
+        let mut images = Assets::<Image>::default();
+        let big = images.add(create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 6]));
+        let tall = images.add(create_image((1, 3), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 3]));
+        let small = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+
+        // act
+        let atlas = packer.pack(&mut images, [big.clone(), tall.clone(), small.clone()]).unwrap();
+
+        // assert
+        let texture = images.get(&atlas.texture).unwrap();
+        let rects = [&big, &tall, &small].map(|handle| atlas.layout.textures[atlas.index(handle).unwrap()]);
+
+        assert_eq!(Vec2::new(4.0, 3.0), atlas.layout.size);
+        assert_eq!(Vec2::new(3.0, 2.0), rects[0].size());
+        assert_eq!(Vec2::new(1.0, 3.0), rects[1].size());
+        assert_eq!(Vec2::new(1.0, 1.0), rects[2].size());
+
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.max.x <= 4.0 && a.max.y <= 3.0, "{:?} is outside of the texture.", a);
+
+            for b in &rects[i + 1..] {
+                assert!(a.intersect(*b).is_empty(), "{:?} and {:?} overlap.", a, b);
+            }
+        }
+
+        for (rect, color) in rects.iter().zip([Color::RED, Color::GREEN, Color::BLUE]) {
+            let index = (rect.min.y as usize * 4 + rect.min.x as usize) * 4;
+            assert_eq!(color.as_rgba_u8(), texture.data[index..index + 4]);
+        }
+    }
+
+    #[test]
+    fn pack_images_with_padding_works() {
+        // arrange
+        let packer = AtlasPacker::new(64, 64).with_padding(1);
+        let red = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+
+        // act
+        let (texture, layout) = packer.pack_images([&red, &red]).unwrap();
+
+        // assert
+        let expected = create_image((4, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::NONE, Color::RED, Color::NONE]);
+
+        assert_eq!(expected.data, texture.data);
+        assert_eq!(Rect::new(2.0, 0.0, 3.0, 1.0), layout.textures[1]);
+    }
+
+    #[test]
+    fn pack_images_too_big_fails() {
+        // arrange
+        let packer = AtlasPacker::new(4, 4);
+        let big = create_image((3, 3), TextureFormat::Rgba8UnormSrgb, [Color::RED; 9]);
+
+        // act
+        let result = packer.pack_images([&big, &big]);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TextureUtilsError::InvalidParameter(_)));
+    }
+}
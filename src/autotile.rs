@@ -0,0 +1,256 @@
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+use pad::{p, Position};
+
+use crate::collision_mask::BitGrid;
+use crate::error::TextureUtilsError;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+// The bits of the neighbours of a cell in the masks
+const NORTH: u8 = 1;
+const NORTH_EAST: u8 = 2;
+const EAST: u8 = 4;
+const SOUTH_EAST: u8 = 8;
+const SOUTH: u8 = 16;
+const SOUTH_WEST: u8 = 32;
+const WEST: u8 = 64;
+const NORTH_WEST: u8 = 128;
+
+/// The kind of tileset used by an [Autotiler].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AutotileKind {
+    /// 16 tiles which only consider the horizontal and vertical neighbours of a cell. The index of a tile
+    /// is the sum of 1 for a terrain neighbour to the north, 2 to the east, 4 to the south and 8 to the west.
+    Edges16,
+    /// 47 tiles which also consider the diagonal neighbours, to draw inner corners. A diagonal neighbour only counts
+    /// if both neighbours next to it are terrain as well. Every tile has a mask with the sum of 1 for north, 2 for
+    /// north-east, 4 for east, 8 for south-east, 16 for south, 32 for south-west, 64 for west and 128 for north-west,
+    /// and the tiles are ordered by their mask, see [blob_masks].
+    Blob47,
+}
+
+impl AutotileKind {
+    /// The amount of tiles a tileset of this kind consists of.
+    pub fn tile_count(&self) -> usize {
+        match self {
+            AutotileKind::Edges16 => 16,
+            AutotileKind::Blob47 => 47,
+        }
+    }
+}
+
+/// Selects the tiles of a terrain, like walls or water, based on which neighbours of every cell are terrain as well,
+/// so the edges and corners of the terrain match.
+#[derive(Clone, Debug)]
+pub struct Autotiler {
+    kind: AutotileKind,
+    tiles: Vec<Handle<Image>>,
+    /// If the cells outside of the grid count as terrain, so the terrain continues beyond the tile map
+    outside_is_terrain: bool,
+}
+
+impl Autotiler {
+    /// Create an autotiler with the tiles of the given kind, ordered by their index. The tiles can be cut
+    /// out of a tileset image with [slice_grid_into_assets](crate::slicing::slice_grid_into_assets).
+    pub fn new(kind: AutotileKind, tiles: Vec<Handle<Image>>) -> Result<Self, TextureUtilsError> {
+        if tiles.len() != kind.tile_count() {
+            return Err(TextureUtilsError::CountMismatch { kind: "tiles", expected: kind.tile_count(), actual: tiles.len() });
+        }
+
+        Ok(Self { kind, tiles, outside_is_terrain: false })
+    }
+
+    /// Treat the cells outside of the grid as terrain, so the terrain has no edges at the border of the tile map.
+    pub fn with_outside_as_terrain(mut self, outside_is_terrain: bool) -> Self {
+        self.outside_is_terrain = outside_is_terrain;
+        self
+    }
+
+    /// Get the tile index of every terrain cell of the grid. The top left cell of the grid gets the position (0, height - 1),
+    /// so the tile map looks like the grid, as positions start at the bottom left.
+    pub fn tile_indices(&self, terrain: &BitGrid) -> Vec<(Position, usize)> {
+        let (width, height) = (terrain.width() as isize, terrain.height() as isize);
+        let is_terrain = |x: isize, y: isize| match x >= 0 && y >= 0 && x < width && y < height {
+            true => terrain.get(x as usize, y as usize),
+            false => self.outside_is_terrain
+        };
+        let blob_masks = blob_masks();
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|(x, y)| terrain.get(*x as usize, *y as usize))
+            .map(|(x, y)| {
+                let mask = neighbour_mask(|dx, dy| is_terrain(x + dx, y + dy));
+                let index = match self.kind {
+                    AutotileKind::Edges16 => edges_index(mask),
+                    AutotileKind::Blob47 => blob_masks
+                        .iter()
+                        .position(|blob_mask| *blob_mask == reduce_corners(mask))
+                        .expect("Every reduced mask is a blob mask"),
+                };
+
+                (p!(x, height - 1 - y), index)
+            })
+            .collect()
+    }
+
+    /// Create a tile map texture with the selected tiles of all terrain cells of the grid.
+    pub fn create_tile_map_texture(
+        &self,
+        creator: &TileMapTextureCreator,
+        images: &mut Assets<Image>,
+        terrain: &BitGrid,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let positions_and_textures = self
+            .tile_indices(terrain)
+            .into_iter()
+            .map(|(pos, index)| (pos, self.tiles[index].clone()));
+
+        creator.create_tile_map_texture(images, positions_and_textures)
+    }
+}
+
+/// The masks of the 47 tiles of an [AutotileKind::Blob47] tileset in ascending order.
+pub fn blob_masks() -> Vec<u8> {
+    (0..=255).filter(|mask| reduce_corners(*mask) == *mask).collect()
+}
+
+/// The mask of the neighbours for which the given function returns true. It gets the offset of the neighbour
+/// in the grid, where y points downwards.
+fn neighbour_mask(is_terrain: impl Fn(isize, isize) -> bool) -> u8 {
+    [
+        (NORTH, (0, -1)),
+        (NORTH_EAST, (1, -1)),
+        (EAST, (1, 0)),
+        (SOUTH_EAST, (1, 1)),
+        (SOUTH, (0, 1)),
+        (SOUTH_WEST, (-1, 1)),
+        (WEST, (-1, 0)),
+        (NORTH_WEST, (-1, -1)),
+    ]
+        .into_iter()
+        .filter(|(_, (dx, dy))| is_terrain(*dx, *dy))
+        .map(|(bit, _)| bit)
+        .sum()
+}
+
+/// Remove the diagonal neighbours from the mask whose adjacent horizontal or vertical neighbours are missing.
+fn reduce_corners(mask: u8) -> u8 {
+    [
+        (NORTH_EAST, NORTH | EAST),
+        (SOUTH_EAST, SOUTH | EAST),
+        (SOUTH_WEST, SOUTH | WEST),
+        (NORTH_WEST, NORTH | WEST),
+    ]
+        .into_iter()
+        .filter(|(_, sides)| mask & sides != *sides)
+        .fold(mask, |mask, (corner, _)| mask & !corner)
+}
+
+fn edges_index(mask: u8) -> usize {
+    [(NORTH, 1), (EAST, 2), (SOUTH, 4), (WEST, 8)]
+        .into_iter()
+        .filter(|(bit, _)| mask & bit != 0)
+        .map(|(_, value)| value)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::autotile::{blob_masks, AutotileKind, Autotiler};
+    use crate::builders::create_image;
+    use crate::collision_mask::BitGrid;
+    use crate::error::TextureUtilsError;
+    use crate::tile_map_texture::TileMapTextureCreator;
+
+    fn create_terrain(rows: &[&str]) -> BitGrid {
+        let mut terrain = BitGrid::new((rows[0].len(), rows.len()));
+
+        for (y, row) in rows.iter().enumerate() {
+            row.chars().enumerate().for_each(|(x, c)| terrain.set(x, y, c == '#'));
+        }
+
+        terrain
+    }
+
+    #[test]
+    fn blob_masks_contain_47_tiles() {
+        // act
+        let masks = blob_masks();
+
+        // assert
+        assert_eq!(47, masks.len());
+        assert_eq!((0, 255), (masks[0], masks[46]));
+    }
+
+    #[test]
+    fn tile_indices_works() {
+        // arrange
+        let terrain = create_terrain(&[
+            "##.",
+            "###",
+        ]);
+        let edges = Autotiler::new(AutotileKind::Edges16, vec![Handle::default(); 16]).unwrap();
+        let blob = Autotiler::new(AutotileKind::Blob47, vec![Handle::default(); 47]).unwrap();
+        let bordered = Autotiler::new(AutotileKind::Edges16, vec![Handle::default(); 16]).unwrap().with_outside_as_terrain(true);
+
+        // act
+        let mut edge_indices = edges.tile_indices(&terrain);
+        let mut blob_indices = blob.tile_indices(&terrain);
+        edge_indices.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        blob_indices.sort_by_key(|(pos, _)| (pos.y, pos.x));
+
+        // assert
+        let blob_index = |mask: u8| blob_masks().iter().position(|m| *m == mask).unwrap();
+
+        assert_eq!(
+            vec![(p!(0, 0), 3), (p!(1, 0), 11), (p!(2, 0), 8), (p!(0, 1), 6), (p!(1, 1), 12)],
+            edge_indices
+        );
+        assert_eq!(
+            vec![
+                // the bottom left cell has terrain to the north, north-east and east
+                (p!(0, 0), blob_index(1 + 2 + 4)),
+                (p!(1, 0), blob_index(1 + 4 + 64 + 128)),
+                (p!(2, 0), blob_index(64)),
+                (p!(0, 1), blob_index(4 + 8 + 16)),
+                (p!(1, 1), blob_index(16 + 32 + 64)),
+            ],
+            blob_indices
+        );
+        assert!(bordered.tile_indices(&terrain).iter().any(|(pos, index)| *pos == p!(0, 0) && *index == 15));
+    }
+
+    #[test]
+    fn create_tile_map_texture_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tiles = (0..16)
+            .map(|i| images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::rgb_u8(i, 0, 0)])))
+            .collect();
+        let autotiler = Autotiler::new(AutotileKind::Edges16, tiles).unwrap();
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+
+        // act
+        let handle = autotiler.create_tile_map_texture(&creator, &mut images, &create_terrain(&["##"])).unwrap();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::rgb_u8(2, 0, 0), Color::rgb_u8(8, 0, 0)]);
+
+        assert_eq!(expected.data, images.get(handle).unwrap().data);
+    }
+
+    #[test]
+    fn autotiler_with_wrong_tile_count_fails() {
+        // act
+        let result = Autotiler::new(AutotileKind::Blob47, vec![Handle::default(); 16]);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::CountMismatch { kind: "tiles", expected: 47, actual: 16 })));
+    }
+}
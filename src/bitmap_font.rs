@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use bevy_render::prelude::*;
+
+use crate::blit::blit_tinted;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+/// A single character of a [BitmapFont].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Glyph {
+    /// The area of the glyph in the atlas
+    pub rect: PixelRect,
+    /// Where the glyph is drawn relative to the current pen position
+    pub offset: (isize, isize),
+    /// How far the pen moves to the right after the glyph
+    pub advance: usize,
+}
+
+/// A font whose glyphs are images in an atlas, to draw text onto textures with [draw_text].
+/// The glyphs should be white, so they can be drawn in any color.
+#[derive(Clone, Debug)]
+pub struct BitmapFont {
+    pub atlas: Image,
+    pub glyphs: HashMap<char, Glyph>,
+    /// The distance between two lines in pixels
+    pub line_height: usize,
+}
+
+impl BitmapFont {
+    /// Create a font from an atlas where all glyphs are cells of the given size, ordered like the given
+    /// characters from left to right and top to bottom.
+    pub fn from_grid(atlas: Image, (cell_width, cell_height): (usize, usize), characters: &str) -> Result<Self, TextureUtilsError> {
+        let columns = atlas.width() as usize / cell_width.max(1);
+        let rows = atlas.height() as usize / cell_height.max(1);
+        let characters = characters.chars().collect::<Vec<_>>();
+
+        if cell_width == 0 || cell_height == 0 || characters.len() > columns * rows {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "{} characters with a size of {}x{} don't fit into the atlas.",
+                characters.len(),
+                cell_width,
+                cell_height
+            )));
+        }
+
+        let glyphs = characters
+            .into_iter()
+            .enumerate()
+            .map(|(i, character)| (character, Glyph {
+                rect: PixelRect::new(i % columns * cell_width, i / columns * cell_height, cell_width, cell_height),
+                offset: (0, 0),
+                advance: cell_width,
+            }))
+            .collect();
+
+        Ok(Self { atlas, glyphs, line_height: cell_height })
+    }
+
+    /// Create a font from an atlas and the glyph metrics of an AngelCode BMFont file in the text format.
+    /// Only fonts with a single page are supported and kerning is ignored.
+    pub fn from_bmfont(atlas: Image, fnt: &str) -> Result<Self, TextureUtilsError> {
+        let mut line_height = None;
+        let mut glyphs = HashMap::new();
+
+        for line in fnt.lines() {
+            let mut tokens = line.split_whitespace();
+            let tag = tokens.next();
+            let values = tokens
+                .filter_map(|token| token.split_once('='))
+                .collect::<HashMap<_, _>>();
+            let value = |key: &str| -> Result<isize, TextureUtilsError> {
+                values
+                    .get(key)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or(TextureUtilsError::Decoding(format!("The line '{line}' has no valid value for '{key}'.")))
+            };
+
+            match tag {
+                Some("common") => line_height = Some(value("lineHeight")? as usize),
+                Some("char") => {
+                    let character = match char::from_u32(value("id")? as u32) {
+                        Some(character) => character,
+                        None => continue
+                    };
+
+                    glyphs.insert(character, Glyph {
+                        rect: PixelRect::new(value("x")? as usize, value("y")? as usize, value("width")? as usize, value("height")? as usize),
+                        offset: (value("xoffset")?, value("yoffset")?),
+                        advance: value("xadvance")? as usize,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let line_height = line_height.ok_or(TextureUtilsError::Decoding("The font has no 'common' line.".to_string()))?;
+        let atlas_size = (atlas.width() as usize, atlas.height() as usize);
+
+        if let Some(glyph) = glyphs.values().find(|glyph| !glyph.rect.fits_into(atlas_size)) {
+            return Err(TextureUtilsError::RectOutOfBounds { rect: glyph.rect });
+        }
+
+        Ok(Self { atlas, glyphs, line_height })
+    }
+
+    /// The width and height of the given text in pixels, like it would be drawn by [draw_text].
+    pub fn measure_text(&self, text: &str) -> (usize, usize) {
+        let width = text
+            .lines()
+            .map(|line| line.chars().filter_map(|c| self.glyphs.get(&c)).map(|glyph| glyph.advance).sum())
+            .max()
+            .unwrap_or_default();
+
+        (width, text.lines().count() * self.line_height)
+    }
+}
+
+/// Draw the given text with the given color onto the image, where the position is the top left corner of the text.
+/// Line breaks start a new line and characters without a glyph in the font are skipped. The atlas of the font
+/// must have the format of the image, which must be an 8-bit RGBA or BGRA format.
+pub fn draw_text(
+    image: &mut Image,
+    font: &BitmapFont,
+    text: &str,
+    position: (isize, isize),
+    color: Color,
+) -> Result<(), TextureUtilsError> {
+    for (line_index, line) in text.lines().enumerate() {
+        let y = position.1 + (line_index * font.line_height) as isize;
+        let mut x = position.0;
+
+        for character in line.chars() {
+            let glyph = match font.glyphs.get(&character) {
+                Some(glyph) => glyph,
+                None => continue
+            };
+
+            blit_tinted(&font.atlas, glyph.rect, image, (x + glyph.offset.0, y + glyph.offset.1), color)?;
+            x += glyph.advance as isize;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::bitmap_font::{BitmapFont, draw_text, Glyph};
+    use crate::builders::create_image;
+    use crate::pixel_rect::PixelRect;
+
+    /// A font with the two glyphs 'I', a vertical line, and '-', a horizontal line.
+    fn create_atlas() -> Image {
+        create_image(
+            (4, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::WHITE, Color::NONE, Color::NONE, Color::NONE,
+                Color::WHITE, Color::NONE, Color::WHITE, Color::WHITE,
+            ],
+        )
+    }
+
+    #[test]
+    fn draw_text_works() {
+        // arrange
+        let font = BitmapFont::from_grid(create_atlas(), (2, 2), "I-").unwrap();
+        let mut image = create_image((5, 4), TextureFormat::Rgba8UnormSrgb, [Color::BLACK; 20]);
+
+        // act
+        draw_text(&mut image, &font, "I-?I\n-", (0, 0), Color::RED).unwrap();
+
+        // assert
+        let expected = create_image(
+            (5, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::BLACK, Color::BLACK, Color::BLACK, Color::RED,
+                Color::RED, Color::BLACK, Color::RED, Color::RED, Color::RED,
+                Color::BLACK, Color::BLACK, Color::BLACK, Color::BLACK, Color::BLACK,
+                Color::RED, Color::RED, Color::BLACK, Color::BLACK, Color::BLACK,
+            ],
+        );
+
+        assert_eq!(expected.data, image.data);
+        assert_eq!((6, 4), font.measure_text("I-?I\n-"));
+    }
+
+    #[test]
+    fn from_bmfont_works() {
+        // arrange
+        let fnt = r#"info face="Tiny Font" size=2
+common lineHeight=3 base=2 scaleW=4 scaleH=2 pages=1
+page id=0 file="tiny.png"
+chars count=2
+char id=73 x=0 y=0 width=1 height=2 xoffset=0 yoffset=1 xadvance=2 page=0 chnl=15
+char id=45 x=2 y=1 width=2 height=1 xoffset=0 yoffset=1 xadvance=3 page=0 chnl=15"#;
+
+        // act
+        let font = BitmapFont::from_bmfont(create_atlas(), fnt).unwrap();
+
+        // assert
+        assert_eq!(3, font.line_height);
+        assert_eq!(Some(&Glyph { rect: PixelRect::new(0, 0, 1, 2), offset: (0, 1), advance: 2 }), font.glyphs.get(&'I'));
+        assert_eq!(Some(&Glyph { rect: PixelRect::new(2, 1, 2, 1), offset: (0, 1), advance: 3 }), font.glyphs.get(&'-'));
+    }
+}
@@ -0,0 +1,415 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+/// Copy the given area of the source image into the destination image, so the top left pixel
+/// of the area ends up at the given position. The position can be partially or completely outside
+/// of the destination, in which case only the overlapping pixels are copied.
+/// Both images must have the same texture format, but it can be any format.
+pub fn blit(
+    src: &Image,
+    src_rect: PixelRect,
+    dst: &mut Image,
+    dst_pos: (isize, isize),
+) -> Result<(), TextureUtilsError> {
+    check_formats(src, dst)?;
+
+    let src_size = (src.width() as usize, src.height() as usize);
+    let dst_size = (dst.width() as usize, dst.height() as usize);
+    blit_data(&src.data, src_size, src_rect, &mut dst.data, dst_size, dst_pos, src.texture_descriptor.format.pixel_size())
+}
+
+/// Like [blit], but works with the raw pixel data of the given sizes instead of images,
+/// so parts of a larger buffer can be written. Both buffers must have the given amount of bytes per pixel.
+pub(crate) fn blit_data(
+    src: &[u8],
+    src_size: (usize, usize),
+    src_rect: PixelRect,
+    dst: &mut [u8],
+    dst_size: (usize, usize),
+    dst_pos: (isize, isize),
+    bytes_per_pixel: usize,
+) -> Result<(), TextureUtilsError> {
+    check_data_length(src, src_size, bytes_per_pixel)?;
+    check_data_length(dst, dst_size, bytes_per_pixel)?;
+
+    let area = match BlitArea::clip(src_size, src_rect, dst_size, dst_pos)? {
+        Some(area) => area,
+        None => return Ok(())
+    };
+
+    for (src_start, dst_start, length) in area.rows(src_size.0, dst_size.0, bytes_per_pixel) {
+        dst[dst_start..dst_start + length].copy_from_slice(&src[src_start..src_start + length]);
+    }
+
+    Ok(())
+}
+
+/// Like [blit_data], but copies an area of the buffer to another position in the same buffer.
+/// The area and its copy must not overlap.
+pub(crate) fn blit_within(
+    data: &mut [u8],
+    size: (usize, usize),
+    src_rect: PixelRect,
+    dst_pos: (isize, isize),
+    bytes_per_pixel: usize,
+) -> Result<(), TextureUtilsError> {
+    check_data_length(data, size, bytes_per_pixel)?;
+
+    let area = match BlitArea::clip(size, src_rect, size, dst_pos)? {
+        Some(area) => area,
+        None => return Ok(())
+    };
+
+    for (src_start, dst_start, length) in area.rows(size.0, size.0, bytes_per_pixel) {
+        data.copy_within(src_start..src_start + length, dst_start);
+    }
+
+    Ok(())
+}
+
+/// Like [blit], but only copies the pixels of the source for which the given predicate returns true,
+/// so for example sprites with a transparent background can be stamped onto a canvas with [is_visible].
+/// The predicate gets the bytes of a single source pixel.
+pub fn blit_masked(
+    src: &Image,
+    src_rect: PixelRect,
+    dst: &mut Image,
+    dst_pos: (isize, isize),
+    predicate: impl Fn(&[u8]) -> bool,
+) -> Result<(), TextureUtilsError> {
+    let bytes_per_pixel = src.texture_descriptor.format.pixel_size();
+    let src_width = src.width() as usize;
+
+    blit_pixels(src, src_rect, dst, dst_pos, |x, y| {
+        let index = (y * src_width + x) * bytes_per_pixel;
+        predicate(&src.data[index..index + bytes_per_pixel])
+    })
+}
+
+/// Like [blit], but only copies the pixels of the source for which the given predicate returns true
+/// for the pixel at the same position in the mask. The mask must have the size of the source,
+/// but can have any texture format.
+pub fn blit_with_mask(
+    src: &Image,
+    src_rect: PixelRect,
+    dst: &mut Image,
+    dst_pos: (isize, isize),
+    mask: &Image,
+    predicate: impl Fn(&[u8]) -> bool,
+) -> Result<(), TextureUtilsError> {
+    if mask.width() != src.width() || mask.height() != src.height() {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    let bytes_per_pixel = mask.texture_descriptor.format.pixel_size();
+    let mask_width = mask.width() as usize;
+
+    blit_pixels(src, src_rect, dst, dst_pos, |x, y| {
+        let index = (y * mask_width + x) * bytes_per_pixel;
+        predicate(&mask.data[index..index + bytes_per_pixel])
+    })
+}
+
+/// Like [blit], but multiplies the colors of the source with the tint and blends them over the destination
+/// by their alpha, instead of replacing the destination pixels. This stamps sprites or glyphs in any color.
+/// The colors are multiplied and blended like they are stored, so in sRGB space for the sRGB formats.
+/// Only works with 8-bit RGBA and BGRA images.
+pub fn blit_tinted(
+    src: &Image,
+    src_rect: PixelRect,
+    dst: &mut Image,
+    dst_pos: (isize, isize),
+    tint: Color,
+) -> Result<(), TextureUtilsError> {
+    let area = match BlitArea::new(src, src_rect, dst, dst_pos)? {
+        Some(area) => area,
+        None => return Ok(())
+    };
+
+    let [r, g, b, a] = tint.as_rgba_f32();
+    let tint = match src.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => [r, g, b],
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => [b, g, r],
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+    let src_width = src.width() as usize;
+    let dst_width = dst.width() as usize;
+
+    for y in 0..area.height {
+        for x in 0..area.width {
+            let src_index = ((area.src_y + y) * src_width + area.src_x + x) * 4;
+            let source = &src.data[src_index..src_index + 4];
+            let dst_index = ((area.dst_y + y) * dst_width + area.dst_x + x) * 4;
+            let target = &mut dst.data[dst_index..dst_index + 4];
+
+            let alpha = source[3] as f32 / 255.0 * a.clamp(0.0, 1.0);
+            let target_alpha = target[3] as f32 / 255.0;
+            let result_alpha = alpha + target_alpha * (1.0 - alpha);
+
+            if result_alpha <= 0.0 {
+                continue;
+            }
+
+            for i in 0..3 {
+                let color = source[i] as f32 * tint[i];
+                let blended = (color * alpha + target[i] as f32 * target_alpha * (1.0 - alpha)) / result_alpha;
+                target[i] = blended.round().clamp(0.0, 255.0) as u8;
+            }
+
+            target[3] = (result_alpha * 255.0).round() as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Tells if the given pixel of a 4-byte-pixel-image is not fully transparent.
+pub fn is_visible(pixel: &[u8]) -> bool {
+    pixel[3] > 0
+}
+
+/// Copy the pixels of the blit area for which the filter returns true. The filter gets the
+/// coordinates of the pixel in the source.
+fn blit_pixels(
+    src: &Image,
+    src_rect: PixelRect,
+    dst: &mut Image,
+    dst_pos: (isize, isize),
+    filter: impl Fn(usize, usize) -> bool,
+) -> Result<(), TextureUtilsError> {
+    let area = match BlitArea::new(src, src_rect, dst, dst_pos)? {
+        Some(area) => area,
+        None => return Ok(())
+    };
+
+    let bytes_per_pixel = src.texture_descriptor.format.pixel_size();
+    let src_width = src.width() as usize;
+    let dst_width = dst.width() as usize;
+
+    for y in 0..area.height {
+        for x in 0..area.width {
+            let (src_x, src_y) = (area.src_x + x, area.src_y + y);
+
+            if !filter(src_x, src_y) {
+                continue;
+            }
+
+            let src_start = (src_y * src_width + src_x) * bytes_per_pixel;
+            let dst_start = ((area.dst_y + y) * dst_width + area.dst_x + x) * bytes_per_pixel;
+            dst.data[dst_start..dst_start + bytes_per_pixel].copy_from_slice(&src.data[src_start..src_start + bytes_per_pixel]);
+        }
+    }
+
+    Ok(())
+}
+
+/// The part of a blit which is inside of both images.
+struct BlitArea {
+    src_x: usize,
+    src_y: usize,
+    dst_x: usize,
+    dst_y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl BlitArea {
+    /// Check if the images can be blitted and clip the area to the destination.
+    /// Returns None if nothing of the area is inside of the destination.
+    fn new(src: &Image, src_rect: PixelRect, dst: &Image, dst_pos: (isize, isize)) -> Result<Option<Self>, TextureUtilsError> {
+        check_formats(src, dst)?;
+        check_data_length(&src.data, (src.width() as usize, src.height() as usize), src.texture_descriptor.format.pixel_size())?;
+        check_data_length(&dst.data, (dst.width() as usize, dst.height() as usize), dst.texture_descriptor.format.pixel_size())?;
+
+        Self::clip((src.width() as usize, src.height() as usize), src_rect, (dst.width() as usize, dst.height() as usize), dst_pos)
+    }
+
+    /// Check if the area is inside of the source and clip it to the destination.
+    /// Returns None if nothing of the area is inside of the destination.
+    fn clip(src_size: (usize, usize), src_rect: PixelRect, dst_size: (usize, usize), (x, y): (isize, isize)) -> Result<Option<Self>, TextureUtilsError> {
+        if !src_rect.fits_into(src_size) {
+            return Err(TextureUtilsError::RectOutOfBounds { rect: src_rect });
+        }
+
+        let clip = |pos: isize, length: usize, dst_length: usize| {
+            let start = pos.max(0);
+            let end = (pos + length as isize).min(dst_length as isize);
+            (end > start).then(|| ((start - pos) as usize, start as usize, (end - start) as usize))
+        };
+
+        match (clip(x, src_rect.width, dst_size.0), clip(y, src_rect.height, dst_size.1)) {
+            (Some((skipped_x, dst_x, width)), Some((skipped_y, dst_y, height))) => Ok(Some(Self {
+                src_x: src_rect.x + skipped_x,
+                src_y: src_rect.y + skipped_y,
+                dst_x,
+                dst_y,
+                width,
+                height,
+            })),
+            _ => Ok(None)
+        }
+    }
+
+    /// The start indices in the source and the destination and the length in bytes of every row of the area.
+    fn rows(&self, src_width: usize, dst_width: usize, bytes_per_pixel: usize) -> impl Iterator<Item=(usize, usize, usize)> + '_ {
+        (0..self.height).map(move |y| (
+            ((self.src_y + y) * src_width + self.src_x) * bytes_per_pixel,
+            ((self.dst_y + y) * dst_width + self.dst_x) * bytes_per_pixel,
+            self.width * bytes_per_pixel,
+        ))
+    }
+}
+
+fn check_formats(src: &Image, dst: &Image) -> Result<(), TextureUtilsError> {
+    match src.texture_descriptor.format == dst.texture_descriptor.format {
+        true => Ok(()),
+        false => Err(TextureUtilsError::FormatMismatch {
+            expected: dst.texture_descriptor.format,
+            actual: src.texture_descriptor.format,
+            position: None,
+        })
+    }
+}
+
+/// Check if the buffer contains all pixels of the given size, so blitting can't index out of it.
+fn check_data_length(data: &[u8], (width, height): (usize, usize), bytes_per_pixel: usize) -> Result<(), TextureUtilsError> {
+    match data.len() >= width * height * bytes_per_pixel {
+        true => Ok(()),
+        false => Err(TextureUtilsError::SizeMismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::blit::{blit, blit_masked, blit_with_mask, is_visible};
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+
+    #[test]
+    fn blit_works() {
+        // arrange
+        let src = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN, Color::BLUE,
+                Color::WHITE, Color::BLACK, Color::RED,
+            ],
+        );
+        let mut dst = create_image((3, 3), TextureFormat::Rgba8UnormSrgb, [Color::NONE; 9]);
+
+        // act
+        blit(&src, PixelRect::new(1, 0, 2, 2), &mut dst, (0, 1)).unwrap();
+
+        // assert
+        let expected = create_image(
+            (3, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::NONE, Color::NONE, Color::NONE,
+                Color::GREEN, Color::BLUE, Color::NONE,
+                Color::BLACK, Color::RED, Color::NONE,
+            ],
+        );
+
+        assert_eq!(expected.data, dst.data);
+    }
+
+    #[test]
+    fn blit_clips_to_destination() {
+        // arrange
+        let src = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN,
+                Color::BLUE, Color::WHITE,
+            ],
+        );
+        let mut dst = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::NONE; 4]);
+
+        // act
+        blit(&src, PixelRect::new(0, 0, 2, 2), &mut dst, (-1, 1)).unwrap();
+        blit(&src, PixelRect::new(0, 0, 2, 2), &mut dst, (5, 5)).unwrap();
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::NONE, Color::NONE,
+                Color::GREEN, Color::NONE,
+            ],
+        );
+
+        assert_eq!(expected.data, dst.data);
+    }
+
+    #[test]
+    fn blit_masked_works() {
+        // arrange
+        let src = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::NONE]);
+        let mut dst = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 2]);
+
+        // act
+        blit_masked(&src, PixelRect::new(0, 0, 2, 1), &mut dst, (0, 0), is_visible).unwrap();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        assert_eq!(expected.data, dst.data);
+    }
+
+    #[test]
+    fn blit_with_mask_works() {
+        // arrange
+        let src = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 3]);
+        let mask = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE, Color::BLACK, Color::WHITE]);
+        let mut dst = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 2]);
+
+        // act
+        blit_with_mask(&src, PixelRect::new(1, 0, 2, 1), &mut dst, (0, 0), &mask, |p| p[0] > 0).unwrap();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::RED]);
+
+        assert_eq!(expected.data, dst.data);
+    }
+
+    #[test]
+    fn blit_with_different_formats_fails() {
+        // arrange
+        let src = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::RED]);
+        let mut dst = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::NONE]);
+
+        // act
+        let result = blit(&src, PixelRect::new(0, 0, 1, 1), &mut dst, (0, 0));
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::FormatMismatch { expected: TextureFormat::Rgba8UnormSrgb, actual: TextureFormat::Rgba8Unorm, position: None })));
+    }
+
+    /// A destination with less data than its size requires must be rejected instead of panicking.
+    #[test]
+    fn blit_with_missing_data_fails() {
+        // arrange
+        let src = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let mut dst = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::NONE, Color::NONE]);
+        dst.data.truncate(4);
+
+        // act
+        let result = blit(&src, PixelRect::new(0, 0, 1, 1), &mut dst, (1, 0));
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::SizeMismatch)));
+    }
+}
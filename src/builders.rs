@@ -0,0 +1,98 @@
+use bevy_render::prelude::*;
+use bevy_render::render_asset::RenderAssetUsages;
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use half::f16;
+
+use crate::image_options::ImageOptions;
+use crate::placeholder::generate_placeholder;
+
+/// Create an image with the given dimension, texture format and colors for each pixel.
+/// Dimension and given pixel must match in size. The first pixel is top left of the image
+/// and the last one is bottom right.
+/// The red, green, blue and alpha values of the colors are written to the channels of the format in this order,
+/// without any conversion. So for BGRA formats, the red value ends up in the blue channel, and formats with less
+/// channels only keep the first values. Supports all 8-bit RGBA and BGRA formats, R8Unorm, Rg8Unorm, Rgba16Float
+/// and Rgba32Float.
+pub fn create_image(
+    (width, height): (usize, usize),
+    texture_format: TextureFormat,
+    pixel_colors: impl IntoIterator<Item=Color>,
+) -> Image {
+    let pixel_colors = pixel_colors.into_iter().collect::<Vec<_>>();
+
+    if pixel_colors.len() != width * height {
+        panic!("Given data and dimension don't match!")
+    }
+
+    let data = pixel_colors
+        .into_iter()
+        .flat_map(|c| match texture_format {
+            TextureFormat::R8Unorm => c.as_rgba_u8()[..1].to_vec(),
+            TextureFormat::Rg8Unorm => c.as_rgba_u8()[..2].to_vec(),
+            TextureFormat::Rgba16Float => c.as_rgba_f32().into_iter().flat_map(|v| f16::from_f32(v).to_le_bytes()).collect(),
+            TextureFormat::Rgba32Float => c.as_rgba_f32().into_iter().flat_map(f32::to_le_bytes).collect(),
+            TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm | TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => c.as_rgba_u8().to_vec(),
+            format => panic!("Images with the format {format:?} can't be created.")
+        })
+        .collect::<Vec<_>>();
+
+    Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        texture_format,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Create an image where every pixel has the given color, written like by [create_image].
+pub fn solid_color_image(size: (usize, usize), color: Color, texture_format: TextureFormat) -> Image {
+    create_image(size, texture_format, vec![color; size.0 * size.1])
+}
+
+/// Create an Rgba8UnormSrgb image with a checkerboard pattern of square cells with the given size in pixels,
+/// where the top left cell has the first color. Like [generate_placeholder] with the default options.
+pub fn checkerboard_image(size: (usize, usize), cell: usize, a: Color, b: Color) -> Image {
+    generate_placeholder(size, cell, a, b, ImageOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::{checkerboard_image, create_image, solid_color_image};
+
+    #[test]
+    fn create_image_with_other_formats_works() {
+        // act
+        let r8 = create_image((2, 1), TextureFormat::R8Unorm, [Color::rgb_u8(1, 2, 3), Color::rgb_u8(4, 5, 6)]);
+        let float = solid_color_image((1, 1), Color::rgba(0.25, 0.5, 1.0, 0.0), TextureFormat::Rgba32Float);
+
+        // assert
+        assert_eq!(vec![1, 4], r8.data);
+        assert_eq!([0.25f32, 0.5, 1.0, 0.0].into_iter().flat_map(f32::to_le_bytes).collect::<Vec<_>>(), float.data);
+    }
+
+    #[test]
+    fn checkerboard_image_works() {
+        // act
+        let checkerboard = checkerboard_image((3, 2), 2, Color::WHITE, Color::BLACK);
+
+        // assert
+        let expected = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::WHITE, Color::WHITE, Color::BLACK,
+                Color::WHITE, Color::WHITE, Color::BLACK,
+            ],
+        );
+
+        assert_eq!(expected.data, checkerboard.data);
+    }
+}
@@ -0,0 +1,333 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// A single color channel of a 4-byte pixel.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+impl Channel {
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Channel::R => 0,
+            Channel::G => 1,
+            Channel::B => 2,
+            Channel::A => 3,
+        }
+    }
+}
+
+/// Tells which channel of which image should be used as input
+/// for a channel of a packed texture.
+#[derive(Copy, Clone)]
+pub struct ChannelSource<'a> {
+    image: &'a Image,
+    channel: Channel,
+}
+
+impl<'a> ChannelSource<'a> {
+    pub fn new(image: &'a Image, channel: Channel) -> Self {
+        Self { image, channel }
+    }
+}
+
+/// Pack channels of up to four images into a single texture, like roughness, metallic and
+/// ambient occlusion maps for custom materials.
+/// Each source tells which channel of its image is written to the red, green, blue or alpha
+/// channel of the new texture. Channels without a source are filled with a default: the color
+/// channels are 0 and the alpha channel is 255, so the texture is fully opaque.
+/// The images can have any of the formats R8Unorm, Rg8Unorm, Rgba8Unorm(Srgb) or Bgra8Unorm(Srgb) and must have the
+/// same size. At least one source must be given. The packed texture has the format Rgba8Unorm, as material
+/// maps contain linear data.
+pub fn pack_channels(
+    r: Option<ChannelSource>,
+    g: Option<ChannelSource>,
+    b: Option<ChannelSource>,
+    a: Option<ChannelSource>,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let targets = [Channel::R, Channel::G, Channel::B, Channel::A];
+    let sources = [r, g, b, a];
+
+    // the first given image determines the size of the packed texture
+    let first = targets
+        .iter()
+        .zip(&sources)
+        .find_map(|(target, source)| source.map(|source| (*target, source.image.size())));
+
+    let (size_channel, size) = match first {
+        Some(first) => first,
+        None => return Err(TextureUtilsError::NoImagesProvided)
+    };
+
+    let sources = targets
+        .into_iter()
+        .zip(sources)
+        .map(|(target, source)| match source {
+            Some(source) if source.image.size() != size => Err(TextureUtilsError::ChannelSizeMismatch {
+                channel: target,
+                expected: (size.x, size.y),
+                expected_from: size_channel,
+                actual: (source.image.width(), source.image.height()),
+            }),
+            Some(source) => convert_to_rgba(source.image).map(|rgba| Some((rgba, source.channel))),
+            None => Ok(None)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (width, height) = (size.x, size.y);
+    let data = (0..(width * height) as usize)
+        .flat_map(|pixel| targets.iter().zip(&sources).map(move |(target, source)| match (source, target) {
+            (Some((rgba, channel)), _) => rgba.data[pixel * 4 + channel.index()],
+            (None, Channel::A) => u8::MAX,
+            (None, _) => 0
+        }))
+        .collect::<Vec<_>>();
+
+    Ok(options.create_image(
+        (width as usize, height as usize),
+        data,
+        TextureFormat::Rgba8Unorm,
+    ))
+}
+
+/// Assemble an occlusion-roughness-metallic texture following the glTF convention:
+/// occlusion is written to the red, roughness to the green and metallic to the blue channel.
+/// The inputs are interpreted as grayscale maps, so only their red channel is used. They can have
+/// any of the formats R8Unorm, Rg8Unorm, Rgba8Unorm(Srgb) or Bgra8Unorm(Srgb) and are converted
+/// as needed. The stored bytes are used as they are, no color space conversion happens.
+/// All images must have the same size.
+pub fn assemble_orm(
+    occlusion: &Image,
+    roughness: &Image,
+    metallic: &Image,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let occlusion = convert_to_rgba(occlusion)?;
+    let roughness = convert_to_rgba(roughness)?;
+    let metallic = convert_to_rgba(metallic)?;
+
+    pack_channels(
+        Some(ChannelSource::new(&occlusion, Channel::R)),
+        Some(ChannelSource::new(&roughness, Channel::R)),
+        Some(ChannelSource::new(&metallic, Channel::R)),
+        None,
+        options,
+    )
+}
+
+/// Split the given image into one R8Unorm image per channel, ordered red, green, blue and alpha,
+/// for example to edit a single map of a packed material texture. This is the inverse of [pack_channels].
+/// The image can have any of the formats R8Unorm, Rg8Unorm, Rgba8Unorm(Srgb) or Bgra8Unorm(Srgb), where missing color
+/// channels are black and a missing alpha channel is opaque. The stored bytes are used as they are.
+pub fn split_channels(image: &Image, options: ImageOptions) -> Result<[Image; 4], TextureUtilsError> {
+    let rgba = convert_to_rgba(image)?;
+    let size = (image.width() as usize, image.height() as usize);
+
+    Ok([Channel::R, Channel::G, Channel::B, Channel::A].map(|channel| {
+        let data = rgba.data
+            .chunks_exact(4)
+            .map(|pixel| pixel[channel.index()])
+            .collect();
+
+        options.create_image(size, data, TextureFormat::R8Unorm)
+    }))
+}
+
+/// Convert the given image to an image with 4-byte RGBA pixels.
+fn convert_to_rgba(image: &Image) -> Result<Image, TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+
+    let data = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => image.data.clone(),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => image.data
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect(),
+        TextureFormat::Rg8Unorm => image.data
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[1], 0, u8::MAX])
+            .collect(),
+        TextureFormat::R8Unorm => image.data
+            .iter()
+            .flat_map(|v| [*v, 0, 0, u8::MAX])
+            .collect(),
+        _ => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    let mut rgba = image.clone();
+    rgba.data = data;
+    rgba.texture_descriptor.format = TextureFormat::Rgba8Unorm;
+
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::channel_packing::{assemble_orm, Channel, ChannelSource, pack_channels, split_channels};
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn pack_channels_works() {
+        // arrange
+        let red = create_image(
+            (2, 1),
+            TextureFormat::Rgba8Unorm,
+            [Color::RED, Color::BLACK],
+        );
+        let green = create_image(
+            (2, 1),
+            TextureFormat::Rgba8Unorm,
+            [Color::BLACK, Color::GREEN],
+        );
+        let blue = create_image(
+            (2, 1),
+            TextureFormat::Rgba8Unorm,
+            [Color::BLUE, Color::BLUE],
+        );
+
+        // act
+        let result = pack_channels(
+            Some(ChannelSource::new(&red, Channel::R)),
+            Some(ChannelSource::new(&green, Channel::G)),
+            Some(ChannelSource::new(&blue, Channel::R)),
+            Some(ChannelSource::new(&red, Channel::R)),
+            ImageOptions::default(),
+        );
+
+        // assert
+        assert!(result.is_ok());
+        assert_eq!(
+            vec![
+                255, 0, 0, 255,
+                0, 255, 0, 0
+            ],
+            result.unwrap().data,
+            "The channels should be taken from the configured sources, but weren't."
+        );
+    }
+
+    /// Without an alpha source, the packed texture is fully opaque.
+    #[test]
+    fn pack_channels_without_alpha_works() {
+        // arrange
+        let image = create_image(
+            (1, 1),
+            TextureFormat::Rgba8Unorm,
+            [Color::rgba_u8(10, 20, 30, 0)],
+        );
+
+        // act
+        let result = pack_channels(
+            Some(ChannelSource::new(&image, Channel::B)),
+            Some(ChannelSource::new(&image, Channel::G)),
+            Some(ChannelSource::new(&image, Channel::R)),
+            None,
+            ImageOptions::default(),
+        );
+
+        // assert
+        assert_eq!(vec![30, 20, 10, 255], result.unwrap().data);
+    }
+
+    /// Missing color channels are black, and the sources can have other formats.
+    #[test]
+    fn pack_channels_with_missing_channels_works() {
+        // arrange
+        let mask = ImageOptions::default().create_image((2, 1), vec![7, 9], TextureFormat::R8Unorm);
+
+        // act
+        let result = pack_channels(None, Some(ChannelSource::new(&mask, Channel::R)), None, None, ImageOptions::default());
+        let empty_result = pack_channels(None, None, None, None, ImageOptions::default());
+
+        // assert
+        assert_eq!(vec![0, 7, 0, 255, 0, 9, 0, 255], result.unwrap().data);
+        assert!(matches!(empty_result, Err(TextureUtilsError::NoImagesProvided)));
+    }
+
+    #[test]
+    fn pack_channels_with_different_sizes_fails() {
+        // arrange
+        let small = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::RED]);
+        let big = create_image((2, 1), TextureFormat::Rgba8Unorm, [Color::RED, Color::RED]);
+
+        // act
+        let result = pack_channels(
+            None,
+            Some(ChannelSource::new(&small, Channel::R)),
+            Some(ChannelSource::new(&big, Channel::R)),
+            None,
+            ImageOptions::default(),
+        );
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::ChannelSizeMismatch { channel: Channel::B, expected: (1, 1), expected_from: Channel::G, actual: (2, 1) })));
+    }
+
+    #[test]
+    fn assemble_orm_works() {
+        // arrange
+        let occlusion = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::rgba_u8(10, 10, 10, 255)]);
+        let roughness = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(20, 20, 20, 255)]);
+        let mut metallic = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::BLACK]);
+        metallic.data = vec![30];
+        metallic.texture_descriptor.format = TextureFormat::R8Unorm;
+
+        // act
+        let result = assemble_orm(&occlusion, &roughness, &metallic, ImageOptions::default());
+
+        // assert
+        assert!(result.is_ok());
+        let orm = result.unwrap();
+
+        assert_eq!(TextureFormat::Rgba8Unorm, orm.texture_descriptor.format);
+        assert_eq!(vec![10, 20, 30, 255], orm.data, "The maps should be packed into the ORM channels, but weren't.");
+    }
+
+    #[test]
+    fn assemble_orm_with_unsupported_format_fails() {
+        // arrange
+        let map = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::BLACK]);
+        let mut unsupported = map.clone();
+        unsupported.texture_descriptor.format = TextureFormat::Rgba16Float;
+
+        // act
+        let result = assemble_orm(&map, &unsupported, &map, ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rgba16Float })));
+    }
+
+    #[test]
+    fn split_channels_works() {
+        // arrange
+        // the colors are written as RGBA bytes, so the red and blue bytes are swapped in a BGRA image
+        let image = create_image((2, 1), TextureFormat::Bgra8Unorm, [Color::rgba_u8(1, 2, 3, 4), Color::rgba_u8(5, 6, 7, 8)]);
+
+        // act
+        let result = split_channels(&image, ImageOptions::default());
+
+        // assert
+        let channels = result.unwrap();
+
+        assert!(channels.iter().all(|channel| channel.texture_descriptor.format == TextureFormat::R8Unorm));
+        assert_eq!(
+            [vec![3, 7], vec![2, 6], vec![1, 5], vec![4, 8]],
+            channels.map(|channel| channel.data)
+        );
+    }
+}
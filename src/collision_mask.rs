@@ -0,0 +1,136 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::morphology::mask_channel;
+
+/// A compact grid with one bit per pixel, which tells if the pixel is solid, for example for physics or picking.
+/// The top left pixel has the coordinates (0, 0).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Create a grid of the given size where no pixel is solid.
+    pub fn new((width, height): (usize, usize)) -> Self {
+        Self { width, height, bits: vec![0; (width * height).div_ceil(64)] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Tells if the given pixel is solid. Pixels outside of the grid never are.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let index = y * self.width + x;
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Make the given pixel solid or empty. Pixels outside of the grid are ignored.
+    pub fn set(&mut self, x: usize, y: usize, solid: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = y * self.width + x;
+
+        match solid {
+            true => self.bits[index / 64] |= 1 << (index % 64),
+            false => self.bits[index / 64] &= !(1 << (index % 64))
+        }
+    }
+
+    /// The amount of solid pixels.
+    pub fn count_solid(&self) -> usize {
+        self.bits.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+}
+
+/// Create a grid where all pixels of the image are solid whose alpha is at least the given threshold.
+/// Works with the 8-bit RGBA and BGRA formats and with R8Unorm masks, where the only channel is used as alpha.
+pub fn extract_collision_mask(image: &Image, alpha_threshold: u8) -> Result<BitGrid, TextureUtilsError> {
+    let (pixel_size, channel) = mask_channel(image.texture_descriptor.format)?;
+    let mut grid = BitGrid::new((image.width() as usize, image.height() as usize));
+
+    for (i, pixel) in image.data.chunks_exact(pixel_size).enumerate() {
+        if pixel[channel] >= alpha_threshold {
+            grid.set(i % grid.width, i / grid.width, true);
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Create an Rgba8UnormSrgb image of the grid for debugging, where the solid pixels are white and the others transparent,
+/// so it can be shown on top of the original image.
+pub fn mask_to_image(grid: &BitGrid, options: ImageOptions) -> Image {
+    let data = (0..grid.width * grid.height)
+        .flat_map(|i| match grid.get(i % grid.width, i / grid.width) {
+            true => [255; 4],
+            false => [0; 4]
+        })
+        .collect();
+
+    options.create_image((grid.width, grid.height), data, TextureFormat::Rgba8UnormSrgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::collision_mask::{extract_collision_mask, mask_to_image};
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn extract_collision_mask_works() {
+        // arrange
+        let image = create_image(
+            (3, 2),
+            TextureFormat::Bgra8UnormSrgb,
+            [
+                Color::RED, Color::NONE, Color::rgba(0.0, 0.0, 1.0, 0.4),
+                Color::NONE, Color::rgba(0.0, 1.0, 0.0, 0.6), Color::NONE,
+            ],
+        );
+
+        // act
+        let grid = extract_collision_mask(&image, 128).unwrap();
+        let debug_image = mask_to_image(&grid, ImageOptions::default());
+
+        // assert
+        let solid = (0..6).map(|i| grid.get(i % 3, i / 3)).collect::<Vec<_>>();
+        let expected_image = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::WHITE, Color::NONE, Color::NONE,
+                Color::NONE, Color::WHITE, Color::NONE,
+            ],
+        );
+
+        assert_eq!(
+            vec![
+                true, false, false,
+                false, true, false,
+            ],
+            solid
+        );
+        assert_eq!(2, grid.count_solid());
+        assert!(!grid.get(3, 0), "Pixels outside of the grid should not be solid, but were.");
+        assert_eq!(expected_image.data, debug_image.data);
+    }
+}
@@ -0,0 +1,419 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::texture_modification::{modify_texture_colors, PixelBytes};
+
+/// A type of color blindness.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColorBlindness {
+    /// No perception of red
+    Protanopia,
+    /// No perception of green
+    Deuteranopia,
+    /// No perception of blue
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// The simulation matrices for linear RGB by Machado, Oliveira and Fernandes (2009) with full severity.
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            ColorBlindness::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorBlindness::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorBlindness::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Create a copy of the given texture as it is perceived with the given color blindness,
+/// for example to check if tiles or UI elements are still distinguishable. Alpha stays unchanged.
+/// Supports the same formats as [color_to_pixel_bytes].
+pub fn simulate_color_blindness(texture: &Image, color_blindness: ColorBlindness) -> Result<Image, TextureUtilsError> {
+    let matrix = color_blindness.matrix();
+    let mut simulated = texture.clone();
+
+    modify_texture_colors(&mut simulated, |_, _, color| {
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+        let [r, g, b] = matrix.map(|row| row[0] * r + row[1] * g + row[2] * b);
+
+        Color::rgba_linear(r, g, b, a)
+    })?;
+
+    Ok(simulated)
+}
+
+/// Provides a pixel mapper which simulates the given color blindness, so it can be combined
+/// with other pixel mappers or restricted to a selection. The pixels are expected to be sRGB encoded RGBA.
+pub fn color_blindness_mapper(color_blindness: ColorBlindness) -> impl Fn(usize, usize, PixelBytes) -> PixelBytes {
+    let matrix = color_blindness.matrix();
+
+    move |_, _, pixel| {
+        let linear = [0, 1, 2].map(|i| srgb_to_linear(pixel[i]));
+        let simulated = matrix.map(|row| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]);
+
+        [
+            linear_to_srgb(simulated[0]),
+            linear_to_srgb(simulated[1]),
+            linear_to_srgb(simulated[2]),
+            pixel[3],
+        ]
+    }
+}
+
+/// Get the bytes of a single pixel with the given color in the given texture format.
+/// Supports the 8-bit RGBA and BGRA formats, where the sRGB formats get sRGB encoded bytes
+/// and the others linear bytes.
+pub fn color_to_pixel_bytes(color: Color, format: TextureFormat) -> Result<Vec<u8>, TextureUtilsError> {
+    let unorm = |values: [f32; 4]| values.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    let srgb = || unorm(color.as_rgba_f32());
+    let linear = || unorm(color.as_linear_rgba_f32());
+    let bgra = |[r, g, b, a]: [u8; 4]| [b, g, r, a];
+
+    let bytes = match format {
+        TextureFormat::Rgba8UnormSrgb => srgb(),
+        TextureFormat::Rgba8Unorm => linear(),
+        TextureFormat::Bgra8UnormSrgb => bgra(srgb()),
+        TextureFormat::Bgra8Unorm => bgra(linear()),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    Ok(bytes.to_vec())
+}
+
+/// Get the color of a single pixel with the given bytes in the given texture format.
+/// The inverse of [color_to_pixel_bytes], which supports the same formats.
+pub fn pixel_bytes_to_color(bytes: &[u8], format: TextureFormat) -> Result<Color, TextureUtilsError> {
+    let linear = |[r, g, b, a]: [u8; 4]| Color::rgba_linear(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0);
+
+    match (format, bytes) {
+        (TextureFormat::Rgba8UnormSrgb, [r, g, b, a]) => Ok(Color::rgba_u8(*r, *g, *b, *a)),
+        (TextureFormat::Rgba8Unorm, [r, g, b, a]) => Ok(linear([*r, *g, *b, *a])),
+        (TextureFormat::Bgra8UnormSrgb, [b, g, r, a]) => Ok(Color::rgba_u8(*r, *g, *b, *a)),
+        (TextureFormat::Bgra8Unorm, [b, g, r, a]) => Ok(linear([*r, *g, *b, *a])),
+        (TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm | TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm, _) => Err(TextureUtilsError::UnsupportedPixelSize),
+        (format, _) => Err(TextureUtilsError::UnsupportedFormat { format })
+    }
+}
+
+/// Make all pixels of the given image transparent whose color is similar to the key color, like the magenta
+/// background of an exported sprite sheet. The colors are compared in the perceptual Oklab color space, where a
+/// tolerance of 0.0 only removes the exact key color and about 0.1 removes similar shades. The alpha of the pixels
+/// is ignored. The removed pixels become transparent black, so the key color does not bleed into filtered edges.
+/// Supports the same formats as [color_to_pixel_bytes].
+pub fn color_key_to_alpha(image: &mut Image, key: Color, tolerance: f32) -> Result<(), TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+    let key = oklab(key);
+    let transparent = color_to_pixel_bytes(Color::NONE, format)?;
+
+    for pixel in image.data.chunks_exact_mut(transparent.len()) {
+        let color = oklab(pixel_bytes_to_color(pixel, format)?);
+        let distance = color.iter().zip(key).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt();
+
+        // a small epsilon, so rounding errors don't prevent exact matches
+        if distance <= tolerance + 1e-4 {
+            pixel.copy_from_slice(&transparent);
+        }
+    }
+
+    Ok(())
+}
+
+/// The average color of the given image, for example to tint UI elements to match it. The colors are weighted by
+/// their alpha, so transparent pixels don't darken the average. sRGB formats are averaged in linear space.
+/// Only works with 8-bit RGBA and BGRA formats.
+pub fn average_color(image: &Image) -> Result<Color, TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+    let average = average_pixel_bytes(image.data.chunks_exact(4), is_srgb_rgba8(format)?);
+
+    pixel_bytes_to_color(&average, format)
+}
+
+/// The at most k most prominent colors of the given image, ordered by how many pixels they represent, for example to
+/// create a palette from it. The colors are found with the median cut algorithm: the pixels are repeatedly split
+/// at the median of the color channel with the largest range, until there are k groups or no group can be split anymore.
+/// Every color is the average of a group. Completely transparent pixels are ignored.
+/// Only works with 8-bit RGBA and BGRA formats.
+pub fn dominant_colors(image: &Image, k: usize) -> Result<Vec<Color>, TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+    let srgb = is_srgb_rgba8(format)?;
+    let pixels = image.data.chunks_exact(4).filter(|pixel| pixel[3] > 0).collect::<Vec<_>>();
+    let mut groups = match pixels.is_empty() {
+        true => vec![],
+        false => vec![pixels]
+    };
+
+    while groups.len() < k {
+        let widest = groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| (i, widest_channel(group)))
+            .max_by_key(|(_, (_, range))| *range);
+
+        let (index, channel) = match widest {
+            Some((index, (channel, range))) if range > 0 => (index, channel),
+            _ => break
+        };
+
+        let mut lower = groups.swap_remove(index);
+        lower.sort_unstable_by_key(|pixel| pixel[channel]);
+        let median = lower[lower.len() / 2][channel];
+        // split where the value changes, so pixels with the same value stay together
+        let split = match lower.iter().position(|pixel| pixel[channel] >= median) {
+            Some(split) if split > 0 => split,
+            _ => lower.iter().position(|pixel| pixel[channel] > median).unwrap_or(lower.len() / 2)
+        };
+        let upper = lower.split_off(split);
+        groups.push(lower);
+        groups.push(upper);
+    }
+
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+    groups
+        .iter()
+        .map(|group| pixel_bytes_to_color(&average_pixel_bytes(group.iter().copied(), srgb), format))
+        .collect()
+}
+
+/// The color channel of the given pixels with the largest range, together with the range.
+fn widest_channel(pixels: &[&[u8]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels
+                .iter()
+                .fold((u8::MAX, u8::MIN), |(min, max), pixel| (min.min(pixel[channel]), max.max(pixel[channel])));
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// The coordinates of the given color in the Oklab color space, where the euclidean distance between
+/// colors matches how different they are perceived. The alpha is ignored.
+pub(crate) fn oklab(color: Color) -> [f32; 3] {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// The average of the given 8-bit RGBA or BGRA pixels, where the colors are weighted by their alpha, so transparent
+/// pixels don't darken the average. sRGB encoded colors are averaged in linear space. The channels keep their order.
+pub(crate) fn average_pixel_bytes<'a>(pixels: impl IntoIterator<Item=&'a [u8]>, srgb: bool) -> PixelBytes {
+    let mut sum = [0.0f32; 4];
+    let mut count = 0;
+
+    for pixel in pixels {
+        let alpha = pixel[3] as f32 / 255.0;
+
+        for (i, value) in pixel[..3].iter().enumerate() {
+            sum[i] += match srgb {
+                true => srgb_to_linear(*value),
+                false => *value as f32 / 255.0
+            } * alpha;
+        }
+
+        sum[3] += alpha;
+        count += 1;
+    }
+
+    if sum[3] <= 0.0 {
+        return [0; 4];
+    }
+
+    let channel = |value: f32| match srgb {
+        true => linear_to_srgb(value / sum[3]),
+        false => (value / sum[3] * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    [channel(sum[0]), channel(sum[1]), channel(sum[2]), (sum[3] / count as f32 * 255.0).round() as u8]
+}
+
+/// Tells if the given 8-bit RGBA or BGRA format is sRGB encoded. Fails for all other formats.
+pub(crate) fn is_srgb_rgba8(format: TextureFormat) -> Result<bool, TextureUtilsError> {
+    match format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => Ok(true),
+        TextureFormat::Rgba8Unorm | TextureFormat::Bgra8Unorm => Ok(false),
+        format => Err(TextureUtilsError::UnsupportedFormat { format })
+    }
+}
+
+pub(crate) fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+
+    let encoded = match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::color::{average_color, color_key_to_alpha, ColorBlindness, dominant_colors, simulate_color_blindness};
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+
+    /// Gray tones are perceived the same with every color blindness.
+    #[test]
+    fn simulate_color_blindness_keeps_gray_tones() {
+        // arrange
+        let texture = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE, Color::BLACK, Color::rgba(0.5, 0.5, 0.5, 0.5)]);
+
+        for color_blindness in [ColorBlindness::Protanopia, ColorBlindness::Deuteranopia, ColorBlindness::Tritanopia] {
+            // act
+            let simulated = simulate_color_blindness(&texture, color_blindness).unwrap();
+
+            // assert
+            let difference = texture.data.iter().zip(&simulated.data).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+            assert!(difference <= 1, "{:?} changed a gray tone by {}.", color_blindness, difference);
+        }
+    }
+
+    /// With protanopia, red and green are hard to tell apart.
+    #[test]
+    fn simulate_protanopia_works() {
+        // arrange
+        let texture = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::GREEN]);
+
+        // act
+        let simulated = simulate_color_blindness(&texture, ColorBlindness::Protanopia).unwrap();
+
+        // assert
+        let red = &simulated.data[0..4];
+        let green = &simulated.data[4..8];
+
+        for pixel in [red, green] {
+            assert!(pixel[0].abs_diff(pixel[1]) < 30 && pixel[2] == 0, "Red and green should both be perceived as yellow tones, but one was {:?}.", pixel);
+        }
+
+        assert_eq!(255, red[3]);
+    }
+
+    #[test]
+    fn simulate_color_blindness_with_bgra_texture_works() {
+        // arrange
+        let mut texture = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::GREEN]);
+        let expected = simulate_color_blindness(&texture, ColorBlindness::Tritanopia).unwrap();
+        texture.data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        texture.texture_descriptor.format = TextureFormat::Bgra8UnormSrgb;
+
+        // act
+        let mut simulated = simulate_color_blindness(&texture, ColorBlindness::Tritanopia).unwrap();
+
+        // assert
+        simulated.data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        assert_eq!(expected.data, simulated.data);
+    }
+
+    #[test]
+    fn simulate_color_blindness_with_unsupported_format_fails() {
+        // arrange
+        let texture = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let result = simulate_color_blindness(&texture, ColorBlindness::Protanopia);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+
+    #[test]
+    fn color_key_to_alpha_works() {
+        // arrange
+        let almost_magenta = Color::rgb_u8(250, 5, 250);
+        let mut image = create_image(
+            (4, 1),
+            TextureFormat::Bgra8UnormSrgb,
+            [Color::FUCHSIA, almost_magenta, Color::PURPLE, Color::WHITE],
+        );
+        let mut exact = image.clone();
+
+        // act
+        // the colors are written as RGBA bytes, which keeps magenta the same in a BGRA image
+        color_key_to_alpha(&mut image, Color::FUCHSIA, 0.05).unwrap();
+        color_key_to_alpha(&mut exact, Color::FUCHSIA, 0.0).unwrap();
+
+        // assert
+        let expected = create_image(
+            (4, 1),
+            TextureFormat::Bgra8UnormSrgb,
+            [Color::NONE, Color::NONE, Color::PURPLE, Color::WHITE],
+        );
+        let expected_exact = create_image(
+            (4, 1),
+            TextureFormat::Bgra8UnormSrgb,
+            [Color::NONE, almost_magenta, Color::PURPLE, Color::WHITE],
+        );
+
+        assert_eq!(expected.data, image.data);
+        assert_eq!(expected_exact.data, exact.data);
+    }
+
+    #[test]
+    fn average_color_works() {
+        // arrange
+        let image = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE, Color::NONE]);
+
+        // act
+        let average = average_color(&image).unwrap();
+
+        // assert
+        // averaged in linear space and ignoring the transparent pixel, but with its alpha
+        assert_eq!([188, 0, 188, 170], average.as_rgba_u8());
+    }
+
+    #[test]
+    fn dominant_colors_works() {
+        // arrange
+        let image = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::RED, Color::BLUE,
+                Color::RED, Color::NONE, Color::NONE,
+            ],
+        );
+
+        // act
+        let colors = dominant_colors(&image, 2).unwrap();
+        let more_colors_than_exist = dominant_colors(&image, 5).unwrap();
+
+        // assert
+        assert_eq!(vec![Color::RED.as_rgba_u8(), Color::BLUE.as_rgba_u8()], colors.iter().map(|c| c.as_rgba_u8()).collect::<Vec<_>>());
+        assert_eq!(2, more_colors_than_exist.len());
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use bevy_math::Vec2;
+use bevy_render::prelude::*;
+
+use crate::collision_mask::{extract_collision_mask, BitGrid};
+use crate::error::TextureUtilsError;
+
+/// Trace the outlines of all areas of the image whose alpha is at least the given threshold, for example to create
+/// colliders for sprites or baked tile maps. See [extract_collision_mask] for the supported formats and [trace_outlines]
+/// for the created outlines.
+pub fn extract_outlines(image: &Image, alpha_threshold: u8) -> Result<Vec<Vec<Vec2>>, TextureUtilsError> {
+    Ok(trace_outlines(&extract_collision_mask(image, alpha_threshold)?))
+}
+
+/// Trace the outlines of all solid areas of the grid with marching squares. Every outline is a closed polygon, where the
+/// last point connects to the first one. The points are in pixel coordinates, where (0, 0) is the top left corner of the
+/// grid and y points downwards. The outlines run through the edges between the centers of solid and empty pixels,
+/// so the corners of the areas are cut diagonally. Only horizontally and vertically adjacent pixels are connected.
+/// Outer outlines run counterclockwise in these coordinates, and holes get their own outlines, which run clockwise.
+/// Points on straight lines are removed, and the outlines can be simplified further with [simplify_outline].
+pub fn trace_outlines(grid: &BitGrid) -> Vec<Vec<Vec2>> {
+    let solid = |x: isize, y: isize| x >= 0 && y >= 0 && grid.get(x as usize, y as usize);
+    // every segment is stored at its start point, doubled so the midpoints of the cell edges are integers
+    let mut segments = HashMap::new();
+
+    // the cells connect the centers of four pixels, starting one pixel outside of the grid, so all outlines are closed
+    for cy in -1..grid.height() as isize {
+        for cx in -1..grid.width() as isize {
+            // the corners clockwise from the top left and the midpoints of the edges clockwise from the top
+            let corners = [(cx, cy), (cx + 1, cy), (cx + 1, cy + 1), (cx, cy + 1)].map(|(x, y)| solid(x, y));
+            let midpoints = [(2 * cx + 2, 2 * cy + 1), (2 * cx + 3, 2 * cy + 2), (2 * cx + 2, 2 * cy + 3), (2 * cx + 1, 2 * cy + 2)];
+            // going clockwise, the segments start at the edges where an empty corner is followed by a solid one
+            // and end at the next edge where a solid corner is followed by an empty one
+            let entering = (0..4).filter(|edge| !corners[*edge] && corners[(edge + 1) % 4]).collect::<Vec<_>>();
+            let exiting = (0..4).filter(|edge| corners[*edge] && !corners[(edge + 1) % 4]).collect::<Vec<_>>();
+
+            let pairs = match entering.len() {
+                1 => vec![(entering[0], exiting[0])],
+                // diagonal solid corners are not connected, so both are cut off separately
+                2 => entering.iter().map(|edge| (*edge, (edge + 1) % 4)).collect(),
+                _ => vec![]
+            };
+
+            for (start, end) in pairs {
+                segments.insert(midpoints[start], midpoints[end]);
+            }
+        }
+    }
+
+    let mut outlines = vec![];
+
+    while let Some(&first) = segments.keys().next() {
+        let mut outline = vec![];
+        let mut point = first;
+
+        while let Some(next) = segments.remove(&point) {
+            outline.push(Vec2::new(point.0 as f32, point.1 as f32) / 2.0);
+            point = next;
+        }
+
+        outlines.push(remove_collinear_points(outline));
+    }
+
+    outlines
+}
+
+/// Reduce the points of the given closed outline with the Ramer-Douglas-Peucker algorithm, where no removed point is
+/// further away than epsilon from the simplified outline. Outlines with less than four points are not changed.
+pub fn simplify_outline(outline: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    if outline.len() < 4 {
+        return outline.to_vec();
+    }
+
+    // split the closed outline at the first point and the point furthest away from it
+    let furthest = (1..outline.len())
+        .max_by(|a, b| outline[0].distance(outline[*a]).total_cmp(&outline[0].distance(outline[*b])))
+        .expect("The outline has more than one point");
+
+    let mut closed = outline.to_vec();
+    closed.push(outline[0]);
+
+    let mut simplified = simplify_polyline(&closed[..=furthest], epsilon);
+    simplified.pop();
+    simplified.extend(simplify_polyline(&closed[furthest..], epsilon));
+    simplified.pop();
+
+    simplified
+}
+
+fn remove_collinear_points(outline: Vec<Vec2>) -> Vec<Vec2> {
+    let len = outline.len();
+
+    (0..len)
+        .filter(|i| {
+            let (previous, point, next) = (outline[(i + len - 1) % len], outline[*i], outline[(i + 1) % len]);
+            (point - previous).perp_dot(next - point).abs() > f32::EPSILON
+        })
+        .map(|i| outline[i])
+        .collect()
+}
+
+/// The Ramer-Douglas-Peucker algorithm for an open line, which keeps its first and last point.
+fn simplify_polyline(points: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let distance = |point: Vec2| match first == last {
+        true => point.distance(first),
+        false => (last - first).perp_dot(point - first).abs() / first.distance(last)
+    };
+
+    let furthest = (1..points.len() - 1).max_by(|a, b| distance(points[*a]).total_cmp(&distance(points[*b])));
+
+    match furthest {
+        Some(index) if distance(points[index]) > epsilon => {
+            let mut simplified = simplify_polyline(&points[..=index], epsilon);
+            simplified.pop();
+            simplified.extend(simplify_polyline(&points[index..], epsilon));
+            simplified
+        }
+        _ => vec![first, last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Vec2;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::collision_mask::BitGrid;
+    use crate::contours::{extract_outlines, simplify_outline, trace_outlines};
+
+    /// Rotate the outline so it starts with its smallest point, to compare it independent of its start.
+    fn normalize(outline: &[Vec2]) -> Vec<Vec2> {
+        let start = (0..outline.len())
+            .min_by(|a, b| (outline[*a].y, outline[*a].x).partial_cmp(&(outline[*b].y, outline[*b].x)).unwrap())
+            .unwrap();
+
+        outline[start..].iter().chain(&outline[..start]).copied().collect()
+    }
+
+    #[test]
+    fn extract_outlines_works() {
+        // arrange
+        let n = Color::NONE;
+        let w = Color::WHITE;
+        let image = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                w, w, n,
+                w, w, n,
+            ],
+        );
+
+        // act
+        let outlines = extract_outlines(&image, 128).unwrap();
+
+        // assert
+        assert_eq!(1, outlines.len());
+        assert_eq!(
+            vec![
+                Vec2::new(0.5, 0.0),
+                Vec2::new(0.0, 0.5),
+                Vec2::new(0.0, 1.5),
+                Vec2::new(0.5, 2.0),
+                Vec2::new(1.5, 2.0),
+                Vec2::new(2.0, 1.5),
+                Vec2::new(2.0, 0.5),
+                Vec2::new(1.5, 0.0),
+            ],
+            normalize(&outlines[0])
+        );
+    }
+
+    #[test]
+    fn trace_outlines_separates_holes_and_diagonal_pixels() {
+        // arrange
+        let mut ring = BitGrid::new((3, 3));
+        (0..9).filter(|i| *i != 4).for_each(|i| ring.set(i % 3, i / 3, true));
+
+        let mut diagonal = BitGrid::new((2, 2));
+        diagonal.set(0, 0, true);
+        diagonal.set(1, 1, true);
+
+        // act
+        let ring_outlines = trace_outlines(&ring);
+        let diagonal_outlines = trace_outlines(&diagonal);
+
+        // assert
+        assert_eq!(2, ring_outlines.len());
+        assert!(ring_outlines.iter().any(|outline| normalize(outline) == vec![
+            Vec2::new(1.5, 1.0),
+            Vec2::new(2.0, 1.5),
+            Vec2::new(1.5, 2.0),
+            Vec2::new(1.0, 1.5),
+        ]), "The hole should be outlined clockwise, but wasn't.");
+        assert_eq!(2, diagonal_outlines.len());
+        assert!(diagonal_outlines.iter().all(|outline| outline.len() == 4));
+    }
+
+    #[test]
+    fn simplify_outline_works() {
+        // arrange
+        let outline = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(1.0, 2.1),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 0.0),
+        ];
+
+        // act
+        let coarse = simplify_outline(&outline, 0.5);
+        let fine = simplify_outline(&outline, 0.05);
+
+        // assert
+        assert_eq!(vec![Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0), Vec2::new(2.0, 2.0), Vec2::new(2.0, 0.0)], coarse);
+        assert_eq!(outline, fine);
+    }
+}
@@ -0,0 +1,274 @@
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_asset::AssetId;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+use bevy_render::render_asset::RenderAssets;
+use bevy_render::render_resource::{Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, TextureAspect};
+use bevy_render::renderer::RenderQueue;
+use bevy_render::texture::TextureFormatPixelInfo;
+use bevy_render::{Extract, ExtractSchedule, Render, RenderApp, RenderSet};
+use pad::Position;
+
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+use crate::texture_modification::PixelBytes;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+/// Uploads the changed areas of all [DirtyRegions] to the GPU every frame.
+/// Requires the RenderPlugin.
+pub struct DirtyRegionsPlugin;
+
+impl Plugin for DirtyRegionsPlugin {
+    fn build(&self, app: &mut App) {
+        // the regions were extracted at the end of the last frame
+        app.add_systems(First, clear_dirty_regions);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return
+        };
+
+        render_app
+            .init_resource::<PendingRegionUploads>()
+            .add_systems(ExtractSchedule, extract_dirty_regions)
+            .add_systems(Render, upload_dirty_regions.in_set(RenderSet::PrepareResources));
+    }
+}
+
+/// A texture which is modified on the CPU, where only the modified areas are written to the texture on the GPU.
+/// Modifying an image in the assets uploads the whole image again, which is wasteful if only a few tiles changed.
+/// So this component keeps its own copy of the image, and the texture is only uploaded as a whole once, when it
+/// is added to the assets. Afterwards, all changes must be done with this component.
+/// The texture can be added with [RenderAssetUsages::RENDER_WORLD](bevy_render::render_asset::RenderAssetUsages::RENDER_WORLD),
+/// as its data in the assets is not updated.
+#[derive(Component, Clone, Debug)]
+pub struct DirtyRegions {
+    /// The texture on the GPU which gets the changes
+    texture: Handle<Image>,
+    /// The copy of the texture which is modified
+    image: Image,
+    /// The areas which changed since the last upload
+    rects: Vec<PixelRect>,
+}
+
+impl DirtyRegions {
+    /// Create the regions of the given texture, where the image must have the same size and format as it.
+    pub fn new(texture: Handle<Image>, image: Image) -> Self {
+        Self { texture, image, rects: Vec::new() }
+    }
+
+    pub fn texture(&self) -> &Handle<Image> {
+        &self.texture
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Get the image to modify it directly. The modified areas must be marked with [DirtyRegions::mark_dirty].
+    pub fn image_mut(&mut self) -> &mut Image {
+        &mut self.image
+    }
+
+    /// The areas which changed since the last upload.
+    pub fn dirty_rects(&self) -> &[PixelRect] {
+        &self.rects
+    }
+
+    /// Upload the given area in the next frame. The area must be inside of the image.
+    pub fn mark_dirty(&mut self, rect: PixelRect) -> Result<(), TextureUtilsError> {
+        if !rect.fits_into((self.image.width() as usize, self.image.height() as usize)) {
+            return Err(TextureUtilsError::RectOutOfBounds { rect });
+        }
+
+        self.rects.push(rect);
+        Ok(())
+    }
+
+    /// Like [modify_texture](crate::texture_modification::modify_texture), but only modifies the pixels in the given area.
+    /// The mapper gets the coordinates of the pixels in the whole image.
+    /// Only works with 4-byte-pixel-images.
+    pub fn modify_texture_region(
+        &mut self,
+        rect: PixelRect,
+        pixel_mapper: impl Fn(usize, usize, PixelBytes) -> PixelBytes,
+    ) -> Result<(), TextureUtilsError> {
+        if self.image.texture_descriptor.format.pixel_size() != 4 {
+            return Err(TextureUtilsError::UnsupportedPixelSize);
+        }
+
+        self.mark_dirty(rect)?;
+        let width = self.image.width() as usize;
+
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                let index = (y * width + x) * 4;
+                let pixel = [
+                    self.image.data[index],
+                    self.image.data[index + 1],
+                    self.image.data[index + 2],
+                    self.image.data[index + 3],
+                ];
+
+                self.image.data[index..index + 4].copy_from_slice(&pixel_mapper(x, y, pixel));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [TileMapTextureCreator::replace_tile], where the image must be a tile map created by the given creator.
+    pub fn replace_tile(
+        &mut self,
+        creator: &TileMapTextureCreator,
+        position: Position,
+        new_tile: &Image,
+    ) -> Result<(), TextureUtilsError> {
+        let rect = creator.replace_tile_in_image(&mut self.image, position, new_tile)?;
+        self.mark_dirty(rect)
+    }
+}
+
+/// The pixels of a changed area of a texture, which are written to it on the GPU.
+struct RegionUpload {
+    texture: AssetId<Image>,
+    rect: PixelRect,
+    bytes_per_pixel: usize,
+    data: Vec<u8>,
+}
+
+/// The uploads are kept until their texture is on the GPU.
+#[derive(Resource, Default)]
+struct PendingRegionUploads(Vec<RegionUpload>);
+
+fn clear_dirty_regions(mut regions: Query<&mut DirtyRegions>) {
+    for mut regions in &mut regions {
+        // prevent triggering change detection if nothing changed
+        if !regions.rects.is_empty() {
+            regions.rects.clear();
+        }
+    }
+}
+
+fn extract_dirty_regions(
+    mut uploads: ResMut<PendingRegionUploads>,
+    regions: Extract<Query<&DirtyRegions>>,
+) {
+    for regions in regions.iter() {
+        let bytes_per_pixel = regions.image.texture_descriptor.format.pixel_size();
+
+        uploads.0.extend(regions.rects.iter().map(|rect| RegionUpload {
+            texture: regions.texture.id(),
+            rect: *rect,
+            bytes_per_pixel,
+            data: region_data(&regions.image, *rect),
+        }));
+    }
+}
+
+fn upload_dirty_regions(
+    mut uploads: ResMut<PendingRegionUploads>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_queue: Res<RenderQueue>,
+) {
+    uploads.0.retain(|upload| {
+        let gpu_image = match gpu_images.get(upload.texture) {
+            Some(gpu_image) => gpu_image,
+            None => return true
+        };
+
+        render_queue.write_texture(
+            ImageCopyTexture {
+                texture: &gpu_image.texture,
+                mip_level: 0,
+                origin: Origin3d { x: upload.rect.x as u32, y: upload.rect.y as u32, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            &upload.data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some((upload.rect.width * upload.bytes_per_pixel) as u32),
+                rows_per_image: None,
+            },
+            Extent3d { width: upload.rect.width as u32, height: upload.rect.height as u32, depth_or_array_layers: 1 },
+        );
+
+        false
+    });
+}
+
+/// Copy the pixels of the given area of the image, row by row.
+fn region_data(image: &Image, rect: PixelRect) -> Vec<u8> {
+    let bytes_per_pixel = image.texture_descriptor.format.pixel_size();
+    let row_length = image.width() as usize * bytes_per_pixel;
+
+    (rect.y..rect.y + rect.height)
+        .flat_map(|y| {
+            let start = y * row_length + rect.x * bytes_per_pixel;
+            image.data[start..start + rect.width * bytes_per_pixel].iter().copied()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::dirty_regions::{region_data, DirtyRegions};
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+    use crate::tile_map_texture::TileMapTextureCreator;
+
+    const R: Color = Color::RED;
+    const B: Color = Color::BLUE;
+
+    #[test]
+    fn dirty_regions_records_modified_rects() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut regions = DirtyRegions::new(Handle::default(), create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [R; 6]));
+
+        // act
+        regions.modify_texture_region(PixelRect::new(1, 0, 2, 1), |_, _, _| [0, 0, 255, 255]).unwrap();
+        regions.replace_tile(&creator, p!(0, 0), &create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [B])).unwrap();
+        let result = regions.mark_dirty(PixelRect::new(2, 1, 2, 1));
+
+        // assert
+        let expected = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                R, B, B,
+                B, R, R,
+            ],
+        );
+
+        assert_eq!(expected.data, regions.image().data);
+        assert_eq!(&[PixelRect::new(1, 0, 2, 1), PixelRect::new(0, 1, 1, 1)], regions.dirty_rects());
+        assert!(matches!(result, Err(TextureUtilsError::RectOutOfBounds { rect }) if rect == PixelRect::new(2, 1, 2, 1)));
+    }
+
+    #[test]
+    fn region_data_works() {
+        // arrange
+        let image = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                R, R, B,
+                R, R, B,
+            ],
+        );
+
+        // act
+        let data = region_data(&image, PixelRect::new(1, 0, 2, 2));
+
+        // assert
+        assert_eq!(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [R, B, R, B]).data, data);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy_render::prelude::*;
+
+use crate::color::{color_to_pixel_bytes, is_srgb_rgba8, pixel_bytes_to_color};
+use crate::error::TextureUtilsError;
+
+/// How [dither] distributes the difference between the colors of the image and the palette.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DitherMethod {
+    /// Ordered dithering with a 4x4 Bayer matrix, which creates a regular cross-hatch pattern
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix, which creates finer gradients than [DitherMethod::Bayer4]
+    Bayer8,
+    /// Error diffusion, which passes the error of every pixel on to its neighbours and creates
+    /// the most accurate, but irregular result
+    FloydSteinberg,
+}
+
+/// Replace the color of every pixel with a color from the given palette, using dithering to simulate
+/// the colors which are missing in the palette. The alpha of the pixels is kept.
+/// The colors are compared by their perceived (sRGB encoded) values. Ordered dithering spreads every channel
+/// by the largest gap between the values of the palette colors in this channel.
+/// Works with the 8-bit RGBA and BGRA formats.
+pub fn dither(texture: &mut Image, palette: &[Color], method: DitherMethod) -> Result<(), TextureUtilsError> {
+    let format = texture.texture_descriptor.format;
+    is_srgb_rgba8(format)?;
+
+    if palette.is_empty() {
+        return Err(TextureUtilsError::NoColorsProvided);
+    }
+
+    let width = texture.width() as usize;
+    let palette = palette.iter().map(|color| rgb(*color)).collect::<Vec<_>>();
+    let mut colors = texture.data
+        .chunks_exact(4)
+        .map(|pixel| pixel_bytes_to_color(pixel, format).expect("The format was checked before"))
+        .collect::<Vec<_>>();
+
+    match method {
+        DitherMethod::Bayer4 => ordered_dither(&mut colors, width, &palette, 4),
+        DitherMethod::Bayer8 => ordered_dither(&mut colors, width, &palette, 8),
+        DitherMethod::FloydSteinberg => error_diffusion_dither(&mut colors, width, &palette)
+    }
+
+    for (pixel, color) in texture.data.chunks_exact_mut(4).zip(colors) {
+        pixel.copy_from_slice(&color_to_pixel_bytes(color, format).expect("The format was checked before"));
+    }
+
+    Ok(())
+}
+
+fn ordered_dither(colors: &mut [Color], width: usize, palette: &[[f32; 3]], matrix_size: usize) {
+    let spread = [0, 1, 2].map(|channel| {
+        let mut values = palette.iter().map(|color| color[channel]).collect::<Vec<_>>();
+        values.sort_by(f32::total_cmp);
+        values.windows(2).map(|pair| pair[1] - pair[0]).fold(0.0, f32::max)
+    });
+
+    for (i, color) in colors.iter_mut().enumerate() {
+        let threshold = (bayer_value(i % width, i / width, matrix_size) as f32 + 0.5) / (matrix_size * matrix_size) as f32 - 0.5;
+        let value = rgb(*color);
+        let shifted = [0, 1, 2].map(|channel| value[channel] + threshold * spread[channel]);
+
+        *color = with_rgb(*color, nearest(palette, shifted));
+    }
+}
+
+fn error_diffusion_dither(colors: &mut [Color], width: usize, palette: &[[f32; 3]]) {
+    let height = colors.len() / width;
+    let mut values = colors.iter().map(|color| rgb(*color)).collect::<Vec<_>>();
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let value = values[index];
+            let new_value = nearest(palette, value);
+            let error = [0, 1, 2].map(|channel| value[channel] - new_value[channel]);
+            colors[index] = with_rgb(colors[index], new_value);
+
+            let neighbours = [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
+
+            for (dx, dy, weight) in neighbours {
+                let (nx, ny) = (x as isize + dx, y + dy);
+
+                if nx < 0 || nx >= width as isize || ny >= height {
+                    continue;
+                }
+
+                let neighbour = &mut values[ny * width + nx as usize];
+
+                for channel in 0..3 {
+                    neighbour[channel] += error[channel] * weight / 16.0;
+                }
+            }
+        }
+    }
+}
+
+/// The value of the Bayer matrix with the given size, which must be a power of two, at the given position.
+/// The values range from 0 to size * size - 1.
+fn bayer_value(x: usize, y: usize, size: usize) -> usize {
+    if size == 1 {
+        return 0;
+    }
+
+    let half = size / 2;
+    let quadrant = [[0, 2], [3, 1]][(y % size) / half][(x % size) / half];
+
+    4 * bayer_value(x % half, y % half, half) + quadrant
+}
+
+/// Find the color of the palette which is closest to the given one.
+pub(crate) fn nearest(palette: &[[f32; 3]], value: [f32; 3]) -> [f32; 3] {
+    let distance = |color: &[f32; 3]| (0..3).map(|channel| (color[channel] - value[channel]).powi(2)).sum::<f32>();
+
+    *palette
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .expect("The palette is not empty")
+}
+
+pub(crate) fn rgb(color: Color) -> [f32; 3] {
+    let [r, g, b, _] = color.as_rgba_f32();
+    [r, g, b]
+}
+
+pub(crate) fn with_rgb(color: Color, [r, g, b]: [f32; 3]) -> Color {
+    Color::rgba(r, g, b, color.a())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::dithering::{bayer_value, dither, DitherMethod};
+    use crate::error::TextureUtilsError;
+
+    const B: Color = Color::BLACK;
+    const W: Color = Color::WHITE;
+
+    fn gray_texture(size: (usize, usize)) -> Image {
+        create_image(size, TextureFormat::Rgba8UnormSrgb, vec![Color::rgb_u8(128, 128, 128); size.0 * size.1])
+    }
+
+    #[test]
+    fn dither_works() {
+        // arrange
+        let mut ordered = gray_texture((2, 2));
+        let mut diffused = gray_texture((2, 1));
+
+        // act
+        dither(&mut ordered, &[B, W], DitherMethod::Bayer4).unwrap();
+        dither(&mut diffused, &[B, W], DitherMethod::FloydSteinberg).unwrap();
+
+        // assert
+        assert_eq!(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [B, W, W, B]).data, ordered.data);
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [W, B]).data, diffused.data);
+    }
+
+    #[test]
+    fn bayer_value_works() {
+        // act
+        let first_row = (0..4).map(|x| bayer_value(x, 0, 4)).collect::<Vec<_>>();
+        let values = (0..64).map(|i| bayer_value(i % 8, i / 8, 8)).collect::<Vec<_>>();
+
+        // assert
+        assert_eq!(vec![0, 8, 2, 10], first_row);
+        assert!((0..64).all(|value| values.contains(&value)), "Every value should occur once in the 8x8 matrix, but didn't.");
+    }
+
+    #[test]
+    fn dither_with_empty_palette_fails() {
+        // act
+        let result = dither(&mut gray_texture((1, 1)), &[], DitherMethod::Bayer8);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::NoColorsProvided)));
+    }
+}
@@ -0,0 +1,365 @@
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+
+use crate::error::TextureUtilsError;
+
+/// A texture consisting of a front and a back buffer, both stored as images in the assets.
+/// The front buffer is the one to display, while updates like tile patches are applied to the
+/// back buffer. When the update is complete, the buffers get swapped, so a half-updated texture
+/// is never visible, even if a large update is spread over multiple frames.
+pub struct DoubleBufferedTexture {
+    front: Handle<Image>,
+    back: Handle<Image>,
+}
+
+impl DoubleBufferedTexture {
+    /// Create a double buffered texture from the given texture, which becomes the front buffer.
+    /// The back buffer is a copy of it.
+    pub fn new(images: &mut Assets<Image>, texture: Handle<Image>) -> Result<Self, TextureUtilsError> {
+        let back_image = images
+            .get(&texture)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: texture.id().untyped() })?
+            .clone();
+
+        Ok(Self {
+            back: images.add(back_image),
+            front: texture,
+        })
+    }
+
+    /// The handle of the buffer to display. It changes with every swap.
+    pub fn front(&self) -> &Handle<Image> {
+        &self.front
+    }
+
+    /// Get the back buffer to apply updates to.
+    pub fn back_mut<'a>(&self, images: &'a mut Assets<Image>) -> Option<&'a mut Image> {
+        images.get_mut(&self.back)
+    }
+
+    /// Swap the front and back buffer after an update is complete. Afterwards, the new back buffer
+    /// gets the content of the new front buffer, so the next update starts from the current state.
+    /// The displayed buffer is never written.
+    pub fn swap(&mut self, images: &mut Assets<Image>) -> Result<(), TextureUtilsError> {
+        std::mem::swap(&mut self.front, &mut self.back);
+        Self::copy_data(images, &self.front, &self.back)
+    }
+
+    /// Apply the given update to the back buffer and swap the buffers if it succeeded.
+    /// If it failed, the back buffer is reset to the content of the front buffer.
+    pub fn update(
+        &mut self,
+        images: &mut Assets<Image>,
+        update: impl FnOnce(&mut Image) -> Result<(), TextureUtilsError>,
+    ) -> Result<(), TextureUtilsError> {
+        let back = self.back_mut(images).ok_or(TextureUtilsError::ImageNotLoaded { handle: self.back.id().untyped() })?;
+
+        match update(back) {
+            Ok(_) => self.swap(images),
+            Err(e) => {
+                Self::copy_data(images, &self.front, &self.back)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Copy the pixel data of one buffer to the other.
+    fn copy_data(images: &mut Assets<Image>, from: &Handle<Image>, to: &Handle<Image>) -> Result<(), TextureUtilsError> {
+        let data = images
+            .get(from)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: from.id().untyped() })?
+            .data
+            .clone();
+
+        let target = images
+            .get_mut(to)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: to.id().untyped() })?;
+
+        if target.data.len() != data.len() {
+            return Err(TextureUtilsError::SizeMismatch);
+        }
+
+        target.data.copy_from_slice(&data);
+
+        Ok(())
+    }
+}
+
+/// A canvas to draw on every frame, like a minimap or a paint mechanic, consisting of two textures in the assets
+/// and the image which is drawn on. Drawing, for example with [draw_line](crate::draw::draw_line) or
+/// [modify_texture](crate::texture_modification::modify_texture), only changes the image, which is copied to the
+/// hidden texture and swapped to the front when the canvas is presented. So the textures are only uploaded when
+/// a frame is complete, no matter how often it is drawn on.
+/// The [TextureUtilsPlugin](crate::plugin::TextureUtilsPlugin) presents changed canvases at the end of every frame
+/// and sets the image handle of their entity to the front texture.
+#[derive(Component, Clone, Debug)]
+pub struct DynamicCanvas {
+    front: Handle<Image>,
+    back: Handle<Image>,
+    /// The image which is drawn on
+    canvas: Image,
+    /// If the canvas was drawn on since it was presented
+    changed: bool,
+}
+
+impl DynamicCanvas {
+    /// Create a canvas with the given image as initial content, which is added to the assets as both textures.
+    pub fn new(images: &mut Assets<Image>, image: Image) -> Self {
+        Self {
+            front: images.add(image.clone()),
+            back: images.add(image.clone()),
+            canvas: image,
+            changed: false,
+        }
+    }
+
+    /// The handle of the texture to display. It changes every time the canvas is presented.
+    pub fn front(&self) -> &Handle<Image> {
+        &self.front
+    }
+
+    pub fn canvas(&self) -> &Image {
+        &self.canvas
+    }
+
+    /// Get the image to draw on. The changes become visible when the canvas is presented.
+    pub fn canvas_mut(&mut self) -> &mut Image {
+        self.changed = true;
+        &mut self.canvas
+    }
+
+    /// Tells if the canvas was drawn on since it was presented.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Copy the canvas to the hidden texture and swap it to the front, if it was drawn on since it was presented.
+    /// Returns if the textures were swapped.
+    pub fn present(&mut self, images: &mut Assets<Image>) -> Result<bool, TextureUtilsError> {
+        if !self.changed {
+            return Ok(false);
+        }
+
+        let back = images
+            .get_mut(&self.back)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: self.back.id().untyped() })?;
+        // reuses the allocation of the texture, even if the canvas was resized
+        back.data.clone_from(&self.canvas.data);
+        back.texture_descriptor.size = self.canvas.texture_descriptor.size;
+
+        std::mem::swap(&mut self.front, &mut self.back);
+        self.changed = false;
+
+        Ok(true)
+    }
+}
+
+pub(crate) fn present_dynamic_canvases(
+    mut canvases: Query<(&mut DynamicCanvas, Option<&mut Handle<Image>>)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (mut canvas, handle) in &mut canvases {
+        // not the change detection of bevy, which also triggers for accesses without drawing
+        if !DynamicCanvas::is_changed(&canvas) {
+            continue;
+        }
+
+        match canvas.present(&mut images) {
+            Ok(true) => if let Some(mut handle) = handle {
+                *handle = canvas.front().clone();
+            },
+            Ok(false) => {}
+            Err(e) => bevy_log::warn!("Could not present the canvas: {e}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_asset::prelude::*;
+    use bevy_ecs::event::Events;
+    use bevy_ecs::prelude::*;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::double_buffer::{DoubleBufferedTexture, DynamicCanvas};
+    use crate::draw::fill_rect;
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+    use crate::plugin::TextureUtilsPlugin;
+    use crate::texture_modification::modify_texture;
+
+    #[test]
+    fn update_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let texture = images.add(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::RED]));
+        let mut double_buffered = DoubleBufferedTexture::new(&mut images, texture.clone()).unwrap();
+
+        // act
+        let result = double_buffered.update(&mut images, |back| {
+            modify_texture(back, |x, _, pixel| if x == 0 { Color::BLUE.as_rgba_u8() } else { pixel });
+            Ok(())
+        });
+
+        // assert
+        assert!(result.is_ok());
+
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::RED]);
+
+        assert_ne!(&texture, double_buffered.front(), "The buffers should be swapped, but weren't.");
+        assert_eq!(expected.data, images.get(double_buffered.front()).unwrap().data);
+        assert_eq!(expected.data, double_buffered.back_mut(&mut images).unwrap().data, "The back buffer should be synced, but wasn't.");
+    }
+
+    /// Updates applied to the back buffer must not be visible in the front buffer before the swap.
+    #[test]
+    fn back_buffer_updates_are_not_visible_before_swap() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let texture = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let double_buffered = DoubleBufferedTexture::new(&mut images, texture).unwrap();
+
+        // act
+        double_buffered.back_mut(&mut images).unwrap().data = Color::BLUE.as_rgba_u8().to_vec();
+
+        // assert
+        assert_eq!(Color::RED.as_rgba_u8().to_vec(), images.get(double_buffered.front()).unwrap().data);
+    }
+
+    /// Swapping only writes the new back buffer, so the displayed texture is never re-uploaded.
+    #[test]
+    fn swap_does_not_modify_the_front_buffer() {
+        // arrange
+        let mut world = World::new();
+        world.init_resource::<Events<AssetEvent<Image>>>();
+        let mut images = Assets::<Image>::default();
+        let texture = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let mut double_buffered = DoubleBufferedTexture::new(&mut images, texture.clone()).unwrap();
+        double_buffered.back_mut(&mut images).unwrap().data = Color::BLUE.as_rgba_u8().to_vec();
+        world.insert_resource(images);
+        world.run_system_once(Assets::<Image>::asset_events);
+        world.resource_mut::<Events<AssetEvent<Image>>>().clear();
+
+        // act
+        double_buffered.swap(&mut world.resource_mut::<Assets<Image>>()).unwrap();
+        world.run_system_once(Assets::<Image>::asset_events);
+
+        // assert
+        let modified = world
+            .resource_mut::<Events<AssetEvent<Image>>>()
+            .drain()
+            .filter_map(|event| match event {
+                AssetEvent::Modified { id } => Some(id),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+
+        assert_ne!(&texture, double_buffered.front());
+        assert!(!modified.contains(&double_buffered.front().id()), "The displayed buffer should not be modified, but was.");
+        // the former front buffer is hidden now and gets synced
+        assert_eq!(vec![texture.id()], modified);
+        assert_eq!(Color::BLUE.as_rgba_u8().to_vec(), world.resource::<Assets<Image>>().get(double_buffered.front()).unwrap().data);
+    }
+
+    /// A failed update must not swap the buffers and must not leave parts of the update in the back buffer.
+    #[test]
+    fn failed_update_does_not_swap() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let texture = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let mut double_buffered = DoubleBufferedTexture::new(&mut images, texture.clone()).unwrap();
+
+        // act
+        let result = double_buffered.update(&mut images, |back| {
+            back.data = Color::BLUE.as_rgba_u8().to_vec();
+            Err(TextureUtilsError::InvalidParameter("Patch failed".to_string()))
+        });
+
+        // assert
+        assert_eq!("Patch failed", result.unwrap_err().to_string());
+        assert_eq!(&texture, double_buffered.front());
+        assert_eq!(Color::RED.as_rgba_u8().to_vec(), double_buffered.back_mut(&mut images).unwrap().data);
+    }
+
+    #[test]
+    fn present_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let mut canvas = DynamicCanvas::new(&mut images, create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]));
+        let front = canvas.front().clone();
+
+        // act
+        fill_rect(canvas.canvas_mut(), PixelRect::new(1, 0, 1, 1), Color::BLUE).unwrap();
+        let visible_before_present = images.get(canvas.front()).unwrap().data.clone();
+        let presented = canvas.present(&mut images).unwrap();
+        let presented_again = canvas.present(&mut images).unwrap();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]).data, visible_before_present);
+        assert!(presented);
+        assert!(!presented_again);
+        assert_ne!(&front, canvas.front());
+        assert_eq!(expected.data, images.get(canvas.front()).unwrap().data);
+    }
+
+    /// Changed canvases are presented at the end of the frame, and the handle of their entity shows the new front.
+    #[test]
+    fn dynamic_canvases_are_presented() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let canvas = DynamicCanvas::new(
+            &mut app.world.resource_mut::<Assets<Image>>(),
+            create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]),
+        );
+        let entity = app.world.spawn((canvas.front().clone(), canvas)).id();
+
+        // act
+        fill_rect(app.world.get_mut::<DynamicCanvas>(entity).unwrap().canvas_mut(), PixelRect::new(0, 0, 1, 1), Color::BLUE).unwrap();
+        app.update();
+
+        // assert
+        let handle = app.world.get::<Handle<Image>>(entity).unwrap();
+
+        assert_eq!(app.world.get::<DynamicCanvas>(entity).unwrap().front(), handle);
+        assert_eq!(Color::BLUE.as_rgba_u8().to_vec(), app.world.resource::<Assets<Image>>().get(handle).unwrap().data);
+    }
+
+    /// A canvas nobody draws on is never presented, so the handle of its entity never changes.
+    #[test]
+    fn dynamic_canvas_without_drawing_keeps_its_handle() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let canvas = DynamicCanvas::new(
+            &mut app.world.resource_mut::<Assets<Image>>(),
+            create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]),
+        );
+        let front = canvas.front().clone();
+        let entity = app.world.spawn((front.clone(), canvas)).id();
+        let spawned_tick = app.world.entity(entity).get_change_ticks::<Handle<Image>>().unwrap().last_changed_tick();
+
+        // act
+        for _ in 0..6 {
+            app.world.get_mut::<DynamicCanvas>(entity).unwrap().canvas();
+            app.update();
+        }
+
+        // assert
+        let last_changed_tick = app.world.entity(entity).get_change_ticks::<Handle<Image>>().unwrap().last_changed_tick();
+
+        assert_eq!(spawned_tick, last_changed_tick, "The handle should not be changed, but was.");
+        assert_eq!(&front, app.world.get::<Handle<Image>>(entity).unwrap());
+    }
+}
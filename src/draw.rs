@@ -0,0 +1,315 @@
+use bevy_render::prelude::*;
+
+use crate::color::color_to_pixel_bytes;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+/// Draw a one pixel wide line between the given points, including both of them.
+/// Parts of the line outside of the image are clipped.
+pub fn draw_line(image: &mut Image, from: (isize, isize), to: (isize, isize), color: Color) -> Result<(), TextureUtilsError> {
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    loop {
+        plot(image, &bytes, x, y);
+
+        if (x, y) == to {
+            return Ok(());
+        }
+
+        let doubled_error = 2 * error;
+
+        if doubled_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+
+        if doubled_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Draw the one pixel wide outline of the given rectangle. Parts outside of the image are clipped.
+pub fn draw_rect(image: &mut Image, rect: PixelRect, color: Color) -> Result<(), TextureUtilsError> {
+    if rect.width == 0 || rect.height == 0 {
+        return Ok(());
+    }
+
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+    let (left, top) = (rect.x as isize, rect.y as isize);
+    let (right, bottom) = (left + rect.width as isize - 1, top + rect.height as isize - 1);
+
+    for x in left..=right {
+        plot(image, &bytes, x, top);
+        plot(image, &bytes, x, bottom);
+    }
+
+    for y in top..=bottom {
+        plot(image, &bytes, left, y);
+        plot(image, &bytes, right, y);
+    }
+
+    Ok(())
+}
+
+/// Fill the given rectangle with the color. Parts outside of the image are clipped.
+pub fn fill_rect(image: &mut Image, rect: PixelRect, color: Color) -> Result<(), TextureUtilsError> {
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    for y in rect.y..(rect.y + rect.height).min(height) {
+        for x in rect.x..(rect.x + rect.width).min(width) {
+            plot(image, &bytes, x as isize, y as isize);
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the one pixel wide outline of the circle with the given center and radius.
+/// Parts of the circle outside of the image are clipped.
+pub fn draw_circle(image: &mut Image, center: (isize, isize), radius: usize, color: Color) -> Result<(), TextureUtilsError> {
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+
+    for (x, y) in circle_octant(radius) {
+        for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+            plot(image, &bytes, center.0 + dx, center.1 + dy);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fill the circle with the given center and radius, including its outline drawn by [draw_circle].
+/// Parts of the circle outside of the image are clipped.
+pub fn fill_circle(image: &mut Image, center: (isize, isize), radius: usize, color: Color) -> Result<(), TextureUtilsError> {
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+
+    for (x, y) in circle_octant(radius) {
+        for (half_width, dy) in [(x, y), (x, -y), (y, x), (y, -x)] {
+            for dx in -half_width..=half_width {
+                plot(image, &bytes, center.0 + dx, center.1 + dy);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the color of the area connected to the given pixel, like a paint bucket tool. The area consists of all
+/// pixels with exactly the same bytes as the given one, where only horizontally and vertically adjacent pixels are connected.
+pub fn flood_fill(image: &mut Image, (x, y): (usize, usize), color: Color) -> Result<(), TextureUtilsError> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    if x >= width || y >= height {
+        return Err(TextureUtilsError::RectOutOfBounds { rect: PixelRect::new(x, y, 1, 1) });
+    }
+
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+    let bytes_per_pixel = bytes.len();
+    let index = |x: usize, y: usize| (y * width + x) * bytes_per_pixel;
+    let target = image.data[index(x, y)..index(x, y) + bytes_per_pixel].to_vec();
+
+    if target == bytes {
+        return Ok(());
+    }
+
+    let mut stack = vec![(x, y)];
+
+    while let Some((x, y)) = stack.pop() {
+        let i = index(x, y);
+
+        if image.data[i..i + bytes_per_pixel] != target[..] {
+            continue;
+        }
+
+        image.data[i..i + bytes_per_pixel].copy_from_slice(&bytes);
+
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+
+        stack.extend(neighbours.into_iter().filter(|(nx, ny)| *nx < width && *ny < height));
+    }
+
+    Ok(())
+}
+
+/// The points of a circle around the origin with the given radius from the top to 45 degrees, using the midpoint algorithm.
+/// The other seven octants are mirrors of these points.
+fn circle_octant(radius: usize) -> Vec<(isize, isize)> {
+    let mut points = Vec::new();
+    let (mut x, mut y) = (0, radius as isize);
+    let mut decision = 1 - y;
+
+    while x <= y {
+        points.push((x, y));
+        x += 1;
+
+        match decision < 0 {
+            true => decision += 2 * x + 1,
+            false => {
+                y -= 1;
+                decision += 2 * (x - y) + 1;
+            }
+        }
+    }
+
+    points
+}
+
+/// Set the given pixel to the bytes if it is inside of the image.
+fn plot(image: &mut Image, bytes: &[u8], x: isize, y: isize) {
+    let (width, height) = (image.width() as isize, image.height() as isize);
+
+    if x < 0 || y < 0 || x >= width || y >= height {
+        return;
+    }
+
+    let index = (y * width + x) as usize * bytes.len();
+    image.data[index..index + bytes.len()].copy_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::draw::{draw_circle, draw_line, draw_rect, fill_circle, fill_rect, flood_fill};
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+
+    const B: Color = Color::BLACK;
+    const R: Color = Color::RED;
+
+    #[test]
+    fn draw_line_works() {
+        // arrange
+        let mut image = create_image((4, 3), TextureFormat::Rgba8UnormSrgb, [B; 12]);
+
+        // act
+        draw_line(&mut image, (0, 0), (3, 2), R).unwrap();
+        // completely outside, so nothing is drawn
+        draw_line(&mut image, (-5, -1), (-1, -4), R).unwrap();
+
+        // assert
+        let expected = create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                R, B, B, B,
+                B, R, R, B,
+                B, B, B, R,
+            ],
+        );
+
+        assert_eq!(expected.data, image.data);
+    }
+
+    #[test]
+    fn draw_and_fill_rect_works() {
+        // arrange
+        let mut image = create_image((5, 4), TextureFormat::Rgba8UnormSrgb, [B; 20]);
+
+        // act
+        draw_rect(&mut image, PixelRect::new(1, 0, 4, 4), R).unwrap();
+        fill_rect(&mut image, PixelRect::new(0, 3, 9, 9), Color::WHITE).unwrap();
+
+        // assert
+        let w = Color::WHITE;
+        let expected = create_image(
+            (5, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                B, R, R, R, R,
+                B, R, B, B, R,
+                B, R, B, B, R,
+                w, w, w, w, w,
+            ],
+        );
+
+        assert_eq!(expected.data, image.data);
+    }
+
+    #[test]
+    fn draw_and_fill_circle_works() {
+        // arrange
+        let mut outline = create_image((5, 5), TextureFormat::Rgba8UnormSrgb, [B; 25]);
+        let mut filled = outline.clone();
+
+        // act
+        draw_circle(&mut outline, (2, 2), 2, R).unwrap();
+        fill_circle(&mut filled, (2, 2), 2, R).unwrap();
+
+        // assert
+        let expected_outline = create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                B, R, R, R, B,
+                R, B, B, B, R,
+                R, B, B, B, R,
+                R, B, B, B, R,
+                B, R, R, R, B,
+            ],
+        );
+        let expected_filled = create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                B, R, R, R, B,
+                R, R, R, R, R,
+                R, R, R, R, R,
+                R, R, R, R, R,
+                B, R, R, R, B,
+            ],
+        );
+
+        assert_eq!(expected_outline.data, outline.data);
+        assert_eq!(expected_filled.data, filled.data);
+    }
+
+    #[test]
+    fn flood_fill_works() {
+        // arrange
+        let w = Color::WHITE;
+        let mut image = create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                B, B, w, B,
+                w, B, w, B,
+                B, w, B, B,
+            ],
+        );
+
+        // act
+        flood_fill(&mut image, (0, 0), R).unwrap();
+        let result = flood_fill(&mut image, (4, 0), R);
+
+        // assert
+        let expected = create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                R, R, w, B,
+                w, R, w, B,
+                B, w, B, B,
+            ],
+        );
+
+        assert_eq!(expected.data, image.data);
+        assert!(matches!(result, Err(TextureUtilsError::RectOutOfBounds { rect }) if rect == PixelRect::new(4, 0, 1, 1)));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+use bevy_render::prelude::*;
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+/// A recorded edit: the pixels of the edited rectangle before and after the edit.
+struct Patch {
+    rect: PixelRect,
+    before: Vec<u8>,
+    after: Vec<u8>,
+}
+
+impl Patch {
+    fn memory(&self) -> usize {
+        self.before.len() + self.after.len()
+    }
+}
+
+/// Records the edits made to an image, so they can be undone and redone, like in a painting
+/// or map-editor tool. Only the pixels in the rectangle of an edit are recorded.
+/// The recorded edits never take more memory than the configured cap. If an edit exceeds it,
+/// the oldest edits are forgotten.
+pub struct EditHistory {
+    /// The maximum amount of bytes the recorded edits may take
+    memory_cap: usize,
+    /// The amount of bytes the recorded edits currently take
+    memory: usize,
+    undo_stack: VecDeque<Patch>,
+    redo_stack: Vec<Patch>,
+}
+
+impl EditHistory {
+    pub fn new(memory_cap: usize) -> Self {
+        Self { memory_cap, memory: 0, undo_stack: VecDeque::new(), redo_stack: vec![] }
+    }
+
+    /// Apply the given edit to the image and record it. The edit may only change pixels
+    /// inside the given rectangle, as changes outside of it can't be undone.
+    /// Recording an edit clears the edits that could be redone.
+    pub fn edit(
+        &mut self,
+        image: &mut Image,
+        rect: PixelRect,
+        edit: impl FnOnce(&mut Image),
+    ) -> Result<(), TextureUtilsError> {
+        if !rect.fits_into((image.width() as usize, image.height() as usize)) {
+            return Err(TextureUtilsError::RectOutOfBounds { rect });
+        }
+
+        let before = Self::read_rect(image, &rect);
+        edit(image);
+        let after = Self::read_rect(image, &rect);
+
+        self.memory -= self.redo_stack.drain(..).map(|p| p.memory()).sum::<usize>();
+        self.push_undo(Patch { rect, before, after });
+
+        Ok(())
+    }
+
+    /// Undo the latest recorded edit. Returns false if there is nothing to undo.
+    pub fn undo(&mut self, image: &mut Image) -> bool {
+        match self.undo_stack.pop_back() {
+            Some(patch) => {
+                Self::write_rect(image, &patch.rect, &patch.before);
+                self.redo_stack.push(patch);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Redo the latest undone edit. Returns false if there is nothing to redo.
+    pub fn redo(&mut self, image: &mut Image) -> bool {
+        match self.redo_stack.pop() {
+            Some(patch) => {
+                Self::write_rect(image, &patch.rect, &patch.after);
+                self.undo_stack.push_back(patch);
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    fn push_undo(&mut self, patch: Patch) {
+        self.memory += patch.memory();
+        self.undo_stack.push_back(patch);
+
+        while self.memory > self.memory_cap {
+            match self.undo_stack.pop_front() {
+                Some(oldest) => self.memory -= oldest.memory(),
+                None => break
+            }
+        }
+    }
+
+    fn read_rect(image: &Image, rect: &PixelRect) -> Vec<u8> {
+        let bytes_per_pixel = image.texture_descriptor.format.pixel_size();
+        let row_length = rect.width * bytes_per_pixel;
+
+        (rect.y..rect.y + rect.height)
+            .flat_map(|y| {
+                let start = (y * image.width() as usize + rect.x) * bytes_per_pixel;
+                image.data[start..start + row_length].iter().copied()
+            })
+            .collect()
+    }
+
+    fn write_rect(image: &mut Image, rect: &PixelRect, data: &[u8]) {
+        let bytes_per_pixel = image.texture_descriptor.format.pixel_size();
+        let row_length = rect.width * bytes_per_pixel;
+        let width = image.width() as usize;
+
+        for (row, y) in (rect.y..rect.y + rect.height).enumerate() {
+            let start = (y * width + rect.x) * bytes_per_pixel;
+            image.data[start..start + row_length].copy_from_slice(&data[row * row_length..(row + 1) * row_length]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::edit_history::EditHistory;
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+    use crate::texture_modification::modify_texture;
+
+    fn paint_blue(image: &mut Image) {
+        modify_texture(image, |x, y, pixel| match (x, y) {
+            (1, 0) | (1, 1) => Color::BLUE.as_rgba_u8(),
+            _ => pixel
+        });
+    }
+
+    #[test]
+    fn undo_and_redo_work() {
+        // arrange
+        let mut history = EditHistory::new(1024);
+        let original = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]);
+        let mut image = original.clone();
+        history.edit(&mut image, PixelRect::new(1, 0, 1, 2), paint_blue).unwrap();
+        let edited = image.clone();
+
+        // act
+        let undone = history.undo(&mut image);
+        let data_after_undo = image.data.clone();
+        let redone = history.redo(&mut image);
+
+        // assert
+        assert!(undone && redone);
+        assert_eq!(original.data, data_after_undo, "The edit should be undone, but wasn't.");
+        assert_eq!(edited.data, image.data, "The edit should be redone, but wasn't.");
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+    }
+
+    /// If the recorded edits exceed the memory cap, the oldest edits are forgotten.
+    #[test]
+    fn memory_cap_forgets_oldest_edits() {
+        // arrange
+        // every edit of a single pixel takes 8 bytes, so only two edits fit
+        let mut history = EditHistory::new(16);
+        let mut image = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 3]);
+
+        // act
+        for x in 0..3 {
+            history.edit(&mut image, PixelRect::new(x, 0, 1, 1), |image| {
+                modify_texture(image, |px, _, pixel| if px == x { Color::BLUE.as_rgba_u8() } else { pixel })
+            }).unwrap();
+        }
+
+        // assert
+        assert!(history.undo(&mut image));
+        assert!(history.undo(&mut image));
+        assert!(!history.undo(&mut image), "The first edit should be forgotten, but wasn't.");
+
+        let expected = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::RED, Color::RED]);
+        assert_eq!(expected.data, image.data);
+    }
+
+    #[test]
+    fn edit_outside_of_image_fails() {
+        // arrange
+        let mut history = EditHistory::new(1024);
+        let mut image = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]);
+
+        // act
+        let result = history.edit(&mut image, PixelRect::new(1, 1, 2, 1), paint_blue);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::RectOutOfBounds { rect }) if rect == PixelRect::new(1, 1, 2, 1)));
+    }
+}
@@ -0,0 +1,389 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::error::TextureUtilsError;
+use crate::random::Random;
+
+/// Configures the erosion simulation run by [erode].
+#[derive(Copy, Clone, Debug)]
+pub enum ErosionParams {
+    /// Moves material from a cell to its lower neighbours if the slope between them
+    /// is steeper than the talus threshold, smoothing out cliffs and spikes.
+    Thermal {
+        /// The height difference (0.0 to 1.0) between neighbours that is considered stable.
+        talus: f32,
+        /// The fraction (0.0 to 1.0) of the unstable material that is moved per iteration.
+        strength: f32,
+    },
+    /// Simulates water droplets flowing downhill, which pick up material on steep slopes
+    /// and deposit it when they slow down, carving valleys and forming sediment.
+    Hydraulic {
+        /// The amount of droplets simulated per iteration.
+        droplets: usize,
+        /// The maximum amount of steps a droplet moves before it evaporates.
+        max_steps: usize,
+        /// How much a droplet keeps its direction (0.0) or follows the slope (1.0 - inertia).
+        inertia: f32,
+        /// The amount of sediment a droplet can carry relative to its speed, water and slope.
+        capacity: f32,
+        /// The fraction of the free capacity that is eroded per step.
+        erosion_rate: f32,
+        /// The fraction of the surplus sediment that is deposited per step.
+        deposition_rate: f32,
+        /// The fraction of water that evaporates per step.
+        evaporation: f32,
+        /// The seed for the random droplet start positions.
+        seed: u64,
+    },
+}
+
+impl ErosionParams {
+    /// Thermal erosion parameters with reasonable default values.
+    pub fn thermal() -> Self {
+        ErosionParams::Thermal { talus: 0.01, strength: 0.5 }
+    }
+
+    /// Hydraulic erosion parameters with reasonable default values.
+    pub fn hydraulic() -> Self {
+        ErosionParams::Hydraulic {
+            droplets: 1000,
+            max_steps: 30,
+            inertia: 0.05,
+            capacity: 4.0,
+            erosion_rate: 0.3,
+            deposition_rate: 0.3,
+            evaporation: 0.01,
+            seed: 0,
+        }
+    }
+}
+
+/// Run an erosion simulation on the given heightmap to make generated terrain look more realistic.
+/// The heightmap is either a single-channel texture (R8Unorm, R16Unorm or R32Float) or an 8-bit RGBA or BGRA texture,
+/// in which case the height is read from the red channel and the eroded height is written to
+/// the red, green and blue channel. Alpha stays unchanged. The heights of R32Float heightmaps are not clamped.
+pub fn erode(
+    height_image: &Image,
+    iterations: usize,
+    params: ErosionParams,
+) -> Result<Image, TextureUtilsError> {
+    let width = height_image.width() as usize;
+    let height = height_image.height() as usize;
+    let format = height_image.texture_descriptor.format;
+    let bytes_per_pixel = format.pixel_size();
+
+    match format {
+        TextureFormat::R8Unorm
+        | TextureFormat::R16Unorm
+        | TextureFormat::R32Float
+        | TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => {}
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    }
+
+    if height_image.data.len() != width * height * bytes_per_pixel {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let mut heights = height_image.data
+        .chunks_exact(bytes_per_pixel)
+        .map(|pixel| read_height(format, pixel))
+        .collect::<Vec<_>>();
+
+    let mut map = HeightMap { width, height, heights: &mut heights };
+
+    match params {
+        ErosionParams::Thermal { talus, strength } => for _ in 0..iterations {
+            map.thermal_step(talus, strength)
+        },
+        ErosionParams::Hydraulic { seed, .. } => {
+            let mut random = Random::new(seed);
+
+            for _ in 0..iterations {
+                map.hydraulic_step(&params, &mut random)
+            }
+        }
+    }
+
+    let mut eroded = height_image.clone();
+
+    for (pixel, h) in eroded.data.chunks_exact_mut(bytes_per_pixel).zip(heights) {
+        write_height(format, pixel, h);
+    }
+
+    Ok(eroded)
+}
+
+/// Read the height (0.0 to 1.0 for the normalized formats) of a pixel of one of the supported formats.
+fn read_height(format: TextureFormat, pixel: &[u8]) -> f32 {
+    match format {
+        TextureFormat::R16Unorm => u16::from_le_bytes([pixel[0], pixel[1]]) as f32 / u16::MAX as f32,
+        TextureFormat::R32Float => f32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]),
+        // BGRA stores the red channel third
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => pixel[2] as f32 / 255.0,
+        _ => pixel[0] as f32 / 255.0
+    }
+}
+
+/// Write the height to a pixel of one of the supported formats. The color channels of RGBA and BGRA pixels all get the height.
+fn write_height(format: TextureFormat, pixel: &mut [u8], height: f32) {
+    match format {
+        TextureFormat::R16Unorm => {
+            let value = (height * u16::MAX as f32).round().clamp(0.0, u16::MAX as f32) as u16;
+            pixel.copy_from_slice(&value.to_le_bytes());
+        }
+        TextureFormat::R32Float => pixel.copy_from_slice(&height.to_le_bytes()),
+        _ => {
+            let value = (height * 255.0).round().clamp(0.0, 255.0) as u8;
+            let channels = pixel.len().min(3);
+            pixel[..channels].fill(value);
+        }
+    }
+}
+
+struct HeightMap<'a> {
+    width: usize,
+    height: usize,
+    heights: &'a mut [f32],
+}
+
+impl<'a> HeightMap<'a> {
+    fn thermal_step(&mut self, talus: f32, strength: f32) {
+        let mut deltas = vec![0.0; self.heights.len()];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = y * self.width + x;
+                let current = self.heights[index];
+
+                for (nx, ny) in self.neighbours(x, y) {
+                    let neighbour_index = ny * self.width + nx;
+                    let difference = current - self.heights[neighbour_index];
+
+                    if difference > talus {
+                        // a quarter, so a cell never gives away more than it has with four neighbours
+                        let amount = strength * (difference - talus) / 4.0;
+                        deltas[index] -= amount;
+                        deltas[neighbour_index] += amount;
+                    }
+                }
+            }
+        }
+
+        self.heights.iter_mut().zip(deltas).for_each(|(h, d)| *h += d);
+    }
+
+    fn hydraulic_step(&mut self, params: &ErosionParams, random: &mut Random) {
+        let ErosionParams::Hydraulic { droplets, max_steps, inertia, capacity, erosion_rate, deposition_rate, evaporation, .. } = *params else {
+            return;
+        };
+
+        if self.width < 2 || self.height < 2 {
+            return;
+        }
+
+        for _ in 0..droplets {
+            let mut pos = (
+                random.next_f32() * (self.width - 1) as f32,
+                random.next_f32() * (self.height - 1) as f32
+            );
+            let mut dir = (0.0f32, 0.0f32);
+            let mut speed = 1.0f32;
+            let mut water = 1.0f32;
+            let mut sediment = 0.0f32;
+
+            for _ in 0..max_steps {
+                let (old_height, gradient) = self.height_and_gradient(pos);
+
+                dir = (
+                    dir.0 * inertia - gradient.0 * (1.0 - inertia),
+                    dir.1 * inertia - gradient.1 * (1.0 - inertia)
+                );
+                let length = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+
+                if length == 0.0 {
+                    break;
+                }
+
+                dir = (dir.0 / length, dir.1 / length);
+                let old_pos = pos;
+                pos = (pos.0 + dir.0, pos.1 + dir.1);
+
+                if pos.0 < 0.0 || pos.1 < 0.0 || pos.0 >= (self.width - 1) as f32 || pos.1 >= (self.height - 1) as f32 {
+                    break;
+                }
+
+                let (new_height, _) = self.height_and_gradient(pos);
+                let delta = new_height - old_height;
+                let max_sediment = (-delta * speed * water * capacity).max(0.001);
+
+                if sediment > max_sediment || delta > 0.0 {
+                    let amount = match delta > 0.0 {
+                        true => delta.min(sediment),
+                        false => (sediment - max_sediment) * deposition_rate
+                    };
+                    sediment -= amount;
+                    self.distribute(old_pos, amount);
+                } else {
+                    let amount = ((max_sediment - sediment) * erosion_rate).min(-delta);
+                    sediment += amount;
+                    self.distribute(old_pos, -amount);
+                }
+
+                speed = (speed * speed - delta).max(0.0).sqrt();
+                water *= 1.0 - evaporation;
+            }
+        }
+    }
+
+    /// Get the bilinear interpolated height and gradient at the given position.
+    fn height_and_gradient(&self, (x, y): (f32, f32)) -> (f32, (f32, f32)) {
+        let (cx, cy) = (x as usize, y as usize);
+        let (u, v) = (x - cx as f32, y - cy as f32);
+
+        let nw = self.heights[cy * self.width + cx];
+        let ne = self.heights[cy * self.width + cx + 1];
+        let sw = self.heights[(cy + 1) * self.width + cx];
+        let se = self.heights[(cy + 1) * self.width + cx + 1];
+
+        let gradient_x = (ne - nw) * (1.0 - v) + (se - sw) * v;
+        let gradient_y = (sw - nw) * (1.0 - u) + (se - ne) * u;
+        let height = nw * (1.0 - u) * (1.0 - v) + ne * u * (1.0 - v) + sw * (1.0 - u) * v + se * u * v;
+
+        (height, (gradient_x, gradient_y))
+    }
+
+    /// Add the given amount to the four cells around the given position, weighted by their distance.
+    fn distribute(&mut self, (x, y): (f32, f32), amount: f32) {
+        let (cx, cy) = (x as usize, y as usize);
+        let (u, v) = (x - cx as f32, y - cy as f32);
+
+        self.heights[cy * self.width + cx] += amount * (1.0 - u) * (1.0 - v);
+        self.heights[cy * self.width + cx + 1] += amount * u * (1.0 - v);
+        self.heights[(cy + 1) * self.width + cx] += amount * (1.0 - u) * v;
+        self.heights[(cy + 1) * self.width + cx + 1] += amount * u * v;
+    }
+
+    fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item=(usize, usize)> {
+        let (width, height) = (self.width, self.height);
+
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .map(move |(dx, dy)| (x as isize + dx, y as isize + dy))
+            .filter(move |(nx, ny)| *nx >= 0 && *ny >= 0 && *nx < width as isize && *ny < height as isize)
+            .map(|(nx, ny)| (nx as usize, ny as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::erosion::{erode, ErosionParams};
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+
+    fn gray(value: u8) -> Color {
+        Color::rgba_u8(value, value, value, 255)
+    }
+
+    /// Thermal erosion should flatten a spike by moving material to its neighbours.
+    #[test]
+    fn erode_thermal_works() {
+        // arrange
+        let spike = create_image(
+            (3, 3),
+            TextureFormat::Rgba8Unorm,
+            [
+                gray(0), gray(0), gray(0),
+                gray(0), gray(200), gray(0),
+                gray(0), gray(0), gray(0),
+            ],
+        );
+
+        // act
+        let result = erode(&spike, 5, ErosionParams::thermal());
+
+        // assert
+        assert!(result.is_ok());
+        let eroded = result.unwrap();
+
+        assert!(eroded.data[16] < 200, "The spike should be lower after erosion, but wasn't.");
+        assert!(eroded.data[4] > 0, "The neighbours of the spike should be higher after erosion, but weren't.");
+        assert_eq!(255, eroded.data[19], "The alpha channel should not change.");
+    }
+
+    /// Hydraulic erosion moves material around, but a perfectly flat heightmap has no slopes to erode.
+    #[test]
+    fn erode_hydraulic_on_flat_map_changes_nothing() {
+        // arrange
+        let flat = create_image((4, 4), TextureFormat::Rgba8Unorm, [gray(100); 16]);
+
+        // act
+        let result = erode(&flat, 3, ErosionParams::hydraulic());
+
+        // assert
+        assert_eq!(flat.data, result.unwrap().data);
+    }
+
+    /// Using the same seed twice must lead to the same result.
+    #[test]
+    fn erode_hydraulic_is_deterministic() {
+        // arrange
+        let slope = ImageOptions::default().create_image(
+            (8, 8),
+            (0..64).map(|i| ((i % 8) * 30 + (i / 8) * 4) as u8).collect(),
+            TextureFormat::R8Unorm,
+        );
+
+        // act
+        let first = erode(&slope, 2, ErosionParams::hydraulic()).unwrap();
+        let second = erode(&slope, 2, ErosionParams::hydraulic()).unwrap();
+
+        // assert
+        assert_eq!(first.data, second.data);
+        assert_ne!(slope.data, first.data, "The slope should be eroded, but wasn't.");
+    }
+
+    /// Float heightmaps are eroded with their full precision instead of being read from single bytes.
+    #[test]
+    fn erode_float_heightmap_works() {
+        // arrange
+        let heights = [0.0f32, 0.0, 0.0, 0.0, 0.8, 0.0, 0.0, 0.0, 0.0];
+        let spike = ImageOptions::default().create_image(
+            (3, 3),
+            heights.iter().flat_map(|h| h.to_le_bytes()).collect(),
+            TextureFormat::R32Float,
+        );
+
+        // act
+        let eroded = erode(&spike, 5, ErosionParams::thermal()).unwrap();
+
+        // assert
+        let eroded_heights = eroded.data
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect::<Vec<_>>();
+
+        assert!(eroded_heights[4] < 0.8 && eroded_heights[4] > 0.0, "The spike should be lower after erosion, but wasn't.");
+        assert!(eroded_heights[1] > 0.0, "The neighbours of the spike should be higher after erosion, but weren't.");
+        assert!((eroded_heights.iter().sum::<f32>() - 0.8).abs() < 0.001, "Thermal erosion should keep the material, but didn't.");
+    }
+
+    #[test]
+    fn erode_with_unsupported_format_fails() {
+        // arrange
+        let image = ImageOptions::default().create_image((1, 1), vec![0; 4], TextureFormat::Rg16Uint);
+
+        // act
+        let result = erode(&image, 1, ErosionParams::thermal());
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rg16Uint })));
+    }
+}
@@ -0,0 +1,66 @@
+use bevy_asset::UntypedAssetId;
+use bevy_render::render_resource::TextureFormat;
+use pad::Position;
+use thiserror::Error;
+
+use crate::channel_packing::Channel;
+use crate::pixel_rect::PixelRect;
+
+/// The error returned by all fallible functions of this crate.
+#[derive(Error, Debug)]
+pub enum TextureUtilsError {
+    /// The image of a handle is not loaded yet or does not exist anymore
+    #[error("The image {handle:?} is not loaded.")]
+    ImageNotLoaded { handle: UntypedAssetId },
+    /// An image does not have the expected texture format. The position is the tile position, if the image is a tile.
+    #[error("Expected the texture format '{expected:?}', but got '{actual:?}'.")]
+    FormatMismatch { expected: TextureFormat, actual: TextureFormat, position: Option<Position> },
+    #[error("The texture format '{format:?}' is not supported.")]
+    UnsupportedFormat { format: TextureFormat },
+    #[error("No tiles were provided!")]
+    NoTilesProvided,
+    #[error("No images were provided!")]
+    NoImagesProvided,
+    #[error("No colors were provided!")]
+    NoColorsProvided,
+    /// A tile does not have the expected size. The position is the tile position, if known.
+    #[error("Expected a tile size of {expected:?}, but got {actual:?}.")]
+    TileSizeMismatch { expected: (usize, usize), actual: (usize, usize), position: Option<Position> },
+    #[error("Not all images have the same size.")]
+    SizeMismatch,
+    /// The image of a channel does not have the size of the image of another channel
+    #[error("The image for the {channel:?} channel has the size {actual:?}, but the image for the {expected_from:?} channel has the size {expected:?}.")]
+    ChannelSizeMismatch { channel: Channel, expected: (u32, u32), expected_from: Channel, actual: (u32, u32) },
+    #[error("Not all images consist of 4-byte-pixels.")]
+    UnsupportedPixelSize,
+    #[error("The rectangle {rect:?} is not inside of the image.")]
+    RectOutOfBounds { rect: PixelRect },
+    #[error("The position {position:?} is outside of the tile map.")]
+    PositionOutOfBounds { position: Position },
+    /// Something with the given name, like a layer or a tile, does not exist
+    #[error("No {kind} with the name '{name}' exists.")]
+    NotFound { kind: &'static str, name: String },
+    /// Something with the given name, like a layer or a sprite, exists more than once
+    #[error("A {kind} with the name '{name}' already exists.")]
+    DuplicateName { kind: &'static str, name: String },
+    /// A size, like the size of an image, a tile or a canvas, is zero in at least one dimension
+    #[error("The size {size:?} must not be zero.")]
+    ZeroSize { size: (usize, usize) },
+    /// A size can't be split into cells of the given size, like a tile map into its tiles
+    #[error("The size {size:?} can't be split into cells of the size {cell_size:?}.")]
+    InvalidCellSize { size: (usize, usize), cell_size: (usize, usize) },
+    /// A numeric parameter is outside of its valid range, which is described by expected
+    #[error("The {name} must be {expected}, but was {value}.")]
+    OutOfRange { name: &'static str, value: f64, expected: &'static str },
+    /// The amount of something, like tiles or delays, is not the required one
+    #[error("Expected {expected} {kind}, but got {actual}.")]
+    CountMismatch { kind: &'static str, expected: usize, actual: usize },
+    #[error("{0}")]
+    InvalidParameter(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Encoding(String),
+    #[error("{0}")]
+    Decoding(String),
+}
@@ -0,0 +1,259 @@
+use std::fs::File;
+use std::io::BufWriter;
+#[cfg(feature = "ktx2")]
+use std::io::Write;
+use std::path::Path;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// Write the given image as a PNG to the given path, for example to cache baked tile maps on disk.
+/// Supports images with the formats Rgba8UnormSrgb, Rgba8Unorm, Bgra8UnormSrgb and Bgra8Unorm.
+pub fn save_image_png(image: &Image, path: impl AsRef<Path>) -> Result<(), TextureUtilsError> {
+    let path = path.as_ref();
+    let data = match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => image.data.clone(),
+        // PNG only knows RGBA, so the red and blue channels have to be swapped
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => image.data
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect(),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    let mut encoder = png::Encoder::new(create_file(path)?, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not write the PNG header: {e}")))?;
+    writer
+        .write_image_data(&data)
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not write the PNG data: {e}")))?;
+    writer
+        .finish()
+        .map_err(|e| TextureUtilsError::Encoding(format!("Could not finish the PNG: {e}")))
+}
+
+/// Read the PNG at the given path into an image with the given texture format, like one written by [save_image_png].
+/// Supports the same formats. PNGs without alpha channel, grayscale and palette PNGs are converted to RGBA.
+pub fn load_image_png(path: impl AsRef<Path>, texture_format: TextureFormat, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8() | png::Transformations::ALPHA);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| TextureUtilsError::Decoding(format!("Could not read the PNG header: {e}")))?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut data)
+        .map_err(|e| TextureUtilsError::Decoding(format!("Could not read the PNG data: {e}")))?;
+    data.truncate(info.buffer_size());
+
+    // after the transformations, every pixel is either RGBA or gray with alpha
+    if info.color_type == png::ColorType::GrayscaleAlpha {
+        data = data
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect();
+    }
+
+    let data = match texture_format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => data,
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => data
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect(),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    Ok(options.create_image((info.width as usize, info.height as usize), data, texture_format))
+}
+
+/// Write the given image as an uncompressed KTX2 texture with a single mip level to the given path.
+/// Unlike PNG, KTX2 keeps the texture format, so the image is loaded exactly like it was saved.
+/// Supports images with the formats Rgba8UnormSrgb, Rgba8Unorm, Bgra8UnormSrgb and Bgra8Unorm.
+#[cfg(feature = "ktx2")]
+pub fn save_image_ktx2(image: &Image, path: impl AsRef<Path>) -> Result<(), TextureUtilsError> {
+    let path = path.as_ref();
+    let format = image.texture_descriptor.format;
+    let (vk_format, srgb, channels) = match format {
+        TextureFormat::Rgba8UnormSrgb => (ktx2::Format::R8G8B8A8_SRGB, true, [0, 1, 2]),
+        TextureFormat::Rgba8Unorm => (ktx2::Format::R8G8B8A8_UNORM, false, [0, 1, 2]),
+        TextureFormat::Bgra8UnormSrgb => (ktx2::Format::B8G8R8A8_SRGB, true, [2, 1, 0]),
+        TextureFormat::Bgra8Unorm => (ktx2::Format::B8G8R8A8_UNORM, false, [2, 1, 0]),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    let mut file = create_file(path)?;
+    file
+        .write_all(&ktx2_bytes(image, vk_format.0.get(), srgb, channels))
+        .and_then(|_| file.flush())
+        .map_err(TextureUtilsError::from)
+}
+
+/// The header, level index, data format descriptor and pixels of a KTX2 file.
+/// The channels are the KTX channel ids of the first three bytes of a pixel.
+#[cfg(feature = "ktx2")]
+fn ktx2_bytes(image: &Image, vk_format: u32, srgb: bool, channels: [u8; 3]) -> Vec<u8> {
+    const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+    const HEADER_LENGTH: u32 = 80;
+    const LEVEL_INDEX_LENGTH: u32 = 24;
+    const DESCRIPTOR_BLOCK_LENGTH: u32 = 24 + 4 * 16;
+    const DFD_LENGTH: u32 = 4 + DESCRIPTOR_BLOCK_LENGTH;
+
+    let dfd_offset = HEADER_LENGTH + LEVEL_INDEX_LENGTH;
+    // the data must be aligned to 4 bytes, which the descriptor already is
+    let data_offset = (dfd_offset + DFD_LENGTH) as u64;
+    let data_length = image.data.len() as u64;
+
+    let mut bytes = Vec::with_capacity(data_offset as usize + image.data.len());
+    bytes.extend(IDENTIFIER);
+
+    // header: format, type size, width, height, depth, layers, faces, levels, supercompression
+    for value in [vk_format, 1, image.width(), image.height(), 0, 0, 1, 1, 0] {
+        bytes.extend(value.to_le_bytes());
+    }
+
+    // index: descriptor offset and length, no key values and no supercompression data
+    bytes.extend(dfd_offset.to_le_bytes());
+    bytes.extend(DFD_LENGTH.to_le_bytes());
+    bytes.extend([0; 8 + 16]);
+
+    // level index: offset, length and uncompressed length of the only level
+    for value in [data_offset, data_length, data_length] {
+        bytes.extend(value.to_le_bytes());
+    }
+
+    // data format descriptor with a basic descriptor block for 8 bit RGBA pixels
+    bytes.extend(DFD_LENGTH.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend(2u16.to_le_bytes());
+    bytes.extend((DESCRIPTOR_BLOCK_LENGTH as u16).to_le_bytes());
+    // color model RGBSDA, primaries BT709, transfer function sRGB or linear, straight alpha
+    bytes.extend([1, 1, if srgb { 2 } else { 1 }, 0]);
+    // texel block dimensions
+    bytes.extend([0; 4]);
+    // bytes per plane
+    bytes.extend([4, 0, 0, 0, 0, 0, 0, 0]);
+
+    // alpha is always linear
+    let samples = channels.into_iter().chain([15 | 0x10]);
+
+    for (i, channel) in samples.enumerate() {
+        bytes.extend((i as u16 * 8).to_le_bytes());
+        bytes.extend([7, channel]);
+        bytes.extend([0; 4]);
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(255u32.to_le_bytes());
+    }
+
+    bytes.extend(&image.data);
+    bytes
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, TextureUtilsError> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(TextureUtilsError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use uuid::Uuid;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::export::{load_image_png, save_image_png};
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn save_image_png_works() {
+        // arrange
+        // the colors are written as RGBA bytes, so red is blue in a BGRA image
+        let image = create_image((2, 1), TextureFormat::Bgra8UnormSrgb, [Color::RED, Color::BLUE]);
+        let path = std::env::temp_dir().join(format!("{}.png", Uuid::new_v4()));
+
+        // act
+        let result = save_image_png(&image, &path);
+
+        // assert
+        assert!(result.is_ok());
+
+        let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((2, 1), (reader.info().width, reader.info().height));
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::RED]).data, data);
+    }
+
+    #[test]
+    fn load_image_png_works() {
+        // arrange
+        let image = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::GREEN, Color::BLUE, Color::NONE]);
+        let path = std::env::temp_dir().join(format!("{}.png", Uuid::new_v4()));
+        save_image_png(&image, &path).unwrap();
+
+        // act
+        let result = load_image_png(&path, TextureFormat::Bgra8UnormSrgb, ImageOptions::default());
+
+        // assert
+        std::fs::remove_file(&path).unwrap();
+        let loaded = result.unwrap();
+        let expected = create_image((2, 2), TextureFormat::Bgra8UnormSrgb, [Color::BLUE, Color::GREEN, Color::RED, Color::NONE]);
+
+        assert_eq!(TextureFormat::Bgra8UnormSrgb, loaded.texture_descriptor.format);
+        assert_eq!(expected.data, loaded.data);
+    }
+
+    #[test]
+    fn save_image_png_with_unsupported_format_fails() {
+        // arrange
+        let mut image = Image::default();
+        image.texture_descriptor.format = TextureFormat::Rgba32Float;
+        let path = std::env::temp_dir().join(format!("{}.png", Uuid::new_v4()));
+
+        // act
+        let result = save_image_png(&image, &path);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rgba32Float })));
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "ktx2")]
+    #[test]
+    fn save_image_ktx2_works() {
+        // arrange
+        let image = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+        let path = std::env::temp_dir().join(format!("{}.ktx2", Uuid::new_v4()));
+
+        // act
+        let result = crate::export::save_image_ktx2(&image, &path);
+
+        // assert
+        assert!(result.is_ok());
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let reader = ktx2::Reader::new(bytes).unwrap();
+        let header = reader.header();
+
+        assert_eq!(Some(ktx2::Format::R8G8B8A8_SRGB), header.format);
+        assert_eq!((2, 1), (header.pixel_width, header.pixel_height));
+        assert_eq!(vec![image.data.as_slice()], reader.levels().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,120 @@
+use bevy_render::prelude::*;
+
+use crate::error::TextureUtilsError;
+use crate::texture_modification::modify_texture_colors;
+
+// All filters work with the 8-bit RGBA and BGRA formats and keep the alpha of the pixels.
+
+/// Replace the color of every pixel with its luminance, which is computed in linear space.
+pub fn grayscale(texture: &mut Image) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+        let luminance = linear_luminance(r, g, b);
+
+        Color::rgba_linear(luminance, luminance, luminance, a)
+    })
+}
+
+/// Invert the perceived color of every pixel, so black becomes white and red becomes cyan.
+pub fn invert(texture: &mut Image) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+        Color::rgba(1.0 - r, 1.0 - g, 1.0 - b, a)
+    })
+}
+
+/// Tint the texture brown like an old photograph, with the classic sepia matrix.
+pub fn sepia(texture: &mut Image) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+
+        Color::rgba(
+            (0.393 * r + 0.769 * g + 0.189 * b).min(1.0),
+            (0.349 * r + 0.686 * g + 0.168 * b).min(1.0),
+            (0.272 * r + 0.534 * g + 0.131 * b).min(1.0),
+            a,
+        )
+    })
+}
+
+/// Reduce every color channel to the given amount of evenly spaced perceived values, like a retro palette.
+/// At least two levels are required.
+pub fn posterize(texture: &mut Image, levels: usize) -> Result<(), TextureUtilsError> {
+    if levels < 2 {
+        return Err(TextureUtilsError::OutOfRange { name: "amount of levels", value: levels as f64, expected: "at least 2" });
+    }
+
+    let steps = (levels - 1) as f32;
+    let quantize = |value: f32| (value * steps).round() / steps;
+
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+        Color::rgba(quantize(r), quantize(g), quantize(b), a)
+    })
+}
+
+/// Make every pixel white whose perceived brightness is at least the given value between 0.0 and 1.0,
+/// and all other pixels black, for example to create masks.
+pub fn threshold(texture: &mut Image, value: f32) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| {
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+        // the luminance is encoded like sRGB, so the value matches the perceived brightness
+        let brightness = Color::rgb_linear(linear_luminance(r, g, b), 0.0, 0.0).r();
+
+        match brightness >= value {
+            true => Color::rgba(1.0, 1.0, 1.0, a),
+            false => Color::rgba(0.0, 0.0, 0.0, a)
+        }
+    })
+}
+
+fn linear_luminance(r: f32, g: f32, b: f32) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::filters::{grayscale, invert, posterize, sepia, threshold};
+
+    fn create_texture() -> Image {
+        create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::rgba_u8(100, 150, 200, 50), Color::WHITE])
+    }
+
+    #[test]
+    fn filters_work() {
+        // arrange
+        let mut gray = create_texture();
+        let mut inverted = create_texture();
+        let mut sepia_toned = create_texture();
+        let mut posterized = create_texture();
+        let mut thresholded = create_texture();
+
+        // act
+        grayscale(&mut gray).unwrap();
+        invert(&mut inverted).unwrap();
+        sepia(&mut sepia_toned).unwrap();
+        posterize(&mut posterized, 2).unwrap();
+        threshold(&mut thresholded, 0.5).unwrap();
+
+        // assert
+        assert_eq!(vec![127, 127, 127, 255, 146, 146, 146, 50, 255, 255, 255, 255], gray.data);
+        assert_eq!(vec![0, 255, 255, 255, 155, 105, 55, 50, 0, 0, 0, 255], inverted.data);
+        assert_eq!([100, 89, 69, 255], sepia_toned.data[..4]);
+        assert_eq!(vec![255, 0, 0, 255, 0, 255, 255, 50, 255, 255, 255, 255], posterized.data);
+        assert_eq!(vec![0, 0, 0, 255, 255, 255, 255, 50, 255, 255, 255, 255], thresholded.data);
+    }
+
+    #[test]
+    fn posterize_with_one_level_fails() {
+        // act
+        let result = posterize(&mut create_texture(), 1);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::OutOfRange { name: "amount of levels", .. })));
+    }
+}
@@ -0,0 +1,216 @@
+use bevy_asset::prelude::*;
+use bevy_math::Vec2;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use pad::{p, Position};
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// How revealed tiles of a [FogOfWar] fade back once they are not visible anymore.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FogDecay {
+    /// The visibility explored tiles fade to and keep, for example to show them dimmed
+    pub explored: u8,
+    /// How much the visibility decreases per second, where 255 is fully visible
+    pub per_second: f32,
+}
+
+/// The visibility of the tiles of a tile map, stored as R8Unorm mask with one pixel per tile, which can be used by
+/// a shader or multiplied with the tile map. 0 means unexplored and 255 visible. The tile at a position is centered
+/// at the position multiplied with the tile size in the world, like sprites placed at these coordinates.
+/// Revealed tiles stay visible, unless a decay is set. Then they fade to the explored visibility over time,
+/// so the visible area has to be revealed every frame.
+#[derive(Clone, Debug)]
+pub struct FogOfWar {
+    /// The position of the bottom left tile
+    origin: Position,
+    /// The size of a tile in world units
+    tile_size: Vec2,
+    mask: Image,
+    decay: Option<FogDecay>,
+    /// The part of the decay which could not be applied yet, as the visibility only has whole steps
+    pending_decay: f32,
+}
+
+impl FogOfWar {
+    /// Create a completely unexplored fog of war for the tiles in the given area.
+    /// Both components of the tile size must be positive and finite.
+    pub fn new(origin: Position, (width, height): (usize, usize), tile_size: Vec2, options: ImageOptions) -> Result<Self, TextureUtilsError> {
+        if !tile_size.is_finite() || tile_size.min_element() <= 0.0 {
+            return Err(TextureUtilsError::OutOfRange { name: "tile size", value: tile_size.min_element() as f64, expected: "positive and finite" });
+        }
+
+        Ok(Self {
+            origin,
+            tile_size,
+            mask: options.create_image((width, height), vec![0; width * height], TextureFormat::R8Unorm),
+            decay: None,
+            pending_decay: 0.0,
+        })
+    }
+
+    /// Let revealed tiles fade to the explored visibility with the given speed.
+    pub fn with_decay(mut self, decay: FogDecay) -> Self {
+        self.decay = Some(decay);
+        self
+    }
+
+    /// The mask with one pixel per tile, where the top left pixel belongs to the top left tile.
+    pub fn mask(&self) -> &Image {
+        &self.mask
+    }
+
+    /// The visibility of the tile at the given position, or None if the position is outside of the fog.
+    pub fn visibility(&self, position: Position) -> Option<u8> {
+        self.index(position).map(|index| self.mask.data[index])
+    }
+
+    /// Make the tile at the given position fully visible.
+    pub fn reveal_tile(&mut self, position: Position) -> Result<(), TextureUtilsError> {
+        let index = self.index(position).ok_or(TextureUtilsError::PositionOutOfBounds { position })?;
+        self.mask.data[index] = 255;
+
+        Ok(())
+    }
+
+    /// Make all tiles fully visible whose center is inside of the given circle in world coordinates.
+    /// Tiles outside of the fog are ignored.
+    pub fn reveal_circle(&mut self, world_pos: Vec2, radius: f32) {
+        // only the tiles of the fog are visited, no matter how large the circle is
+        let first = Vec2::new(self.origin.x as f32, self.origin.y as f32);
+        let last = first + Vec2::new(self.mask.width() as f32, self.mask.height() as f32) - 1.0;
+        let min = ((world_pos - radius) / self.tile_size).floor().clamp(first, last);
+        let max = ((world_pos + radius) / self.tile_size).ceil().clamp(first, last);
+
+        for y in min.y as isize..=max.y as isize {
+            for x in min.x as isize..=max.x as isize {
+                let center = Vec2::new(x as f32, y as f32) * self.tile_size;
+
+                if center.distance(world_pos) > radius {
+                    continue;
+                }
+
+                if let Some(index) = self.index(p!(x, y)) {
+                    self.mask.data[index] = 255;
+                }
+            }
+        }
+    }
+
+    /// Let the revealed tiles fade by the time which passed since the last update, if a decay is set.
+    pub fn update(&mut self, delta_seconds: f32) {
+        let decay = match self.decay {
+            Some(decay) => decay,
+            None => return
+        };
+
+        self.pending_decay += decay.per_second * delta_seconds;
+        let steps = self.pending_decay.floor().min(255.0);
+        self.pending_decay -= steps;
+
+        for visibility in self.mask.data.iter_mut().filter(|visibility| **visibility > decay.explored) {
+            *visibility = visibility.saturating_sub(steps as u8).max(decay.explored);
+        }
+    }
+
+    /// Copy the mask into the given texture, which must have been created from it, for example every frame
+    /// after the fog was updated.
+    pub fn update_texture(&self, images: &mut Assets<Image>, texture: &Handle<Image>) -> Result<(), TextureUtilsError> {
+        let image = images
+            .get_mut(texture)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: texture.id().untyped() })?;
+
+        if image.data.len() != self.mask.data.len() {
+            return Err(TextureUtilsError::SizeMismatch);
+        }
+
+        image.data.copy_from_slice(&self.mask.data);
+
+        Ok(())
+    }
+
+    /// The index of the pixel of the tile at the given position in the mask.
+    fn index(&self, position: Position) -> Option<usize> {
+        let (width, height) = (self.mask.width() as isize, self.mask.height() as isize);
+        let (x, y) = (position.x - self.origin.x, position.y - self.origin.y);
+
+        match x >= 0 && y >= 0 && x < width && y < height {
+            true => Some(((height - 1 - y) * width + x) as usize),
+            false => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Vec2;
+    use pad::p;
+
+    use crate::error::TextureUtilsError;
+    use crate::fog_of_war::{FogDecay, FogOfWar};
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn reveal_works() {
+        // arrange
+        let mut fog = FogOfWar::new(p!(-1, 0), (4, 3), Vec2::splat(16.0), ImageOptions::default()).unwrap();
+
+        // act
+        fog.reveal_circle(Vec2::new(16.0, 16.0), 16.0);
+        fog.reveal_tile(p!(-1, 0)).unwrap();
+        let result = fog.reveal_tile(p!(3, 0));
+
+        // assert
+        assert_eq!(
+            vec![
+                0, 0, 255, 0,
+                0, 255, 255, 255,
+                255, 0, 255, 0,
+            ],
+            fog.mask().data
+        );
+        assert!(matches!(result, Err(TextureUtilsError::PositionOutOfBounds { position }) if position == p!(3, 0)));
+    }
+
+    #[test]
+    fn revealed_tiles_decay_to_explored() {
+        // arrange
+        let mut fog = FogOfWar::new(p!(0, 0), (2, 1), Vec2::ONE, ImageOptions::default())
+            .unwrap()
+            .with_decay(FogDecay { explored: 100, per_second: 100.0 });
+        fog.reveal_tile(p!(0, 0)).unwrap();
+
+        // act
+        fog.update(0.505);
+        let visibility_after_half_second = fog.visibility(p!(0, 0));
+        fog.update(10.0);
+
+        // assert
+        assert_eq!(Some(205), visibility_after_half_second);
+        assert_eq!(Some(100), fog.visibility(p!(0, 0)));
+        assert_eq!(Some(0), fog.visibility(p!(1, 0)));
+    }
+
+    /// Huge circles only visit the tiles of the fog, so revealing them finishes quickly.
+    #[test]
+    fn reveal_huge_circle_works() {
+        // arrange
+        let mut fog = FogOfWar::new(p!(0, 0), (2, 2), Vec2::ONE, ImageOptions::default()).unwrap();
+
+        // act
+        fog.reveal_circle(Vec2::new(1e12, -1e12), 1e13);
+
+        // assert
+        assert_eq!(vec![255; 4], fog.mask().data);
+    }
+
+    #[test]
+    fn new_with_zero_tile_size_fails() {
+        // act
+        let result = FogOfWar::new(p!(0, 0), (2, 2), Vec2::new(16.0, 0.0), ImageOptions::default());
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::OutOfRange { name: "tile size", .. })));
+    }
+}
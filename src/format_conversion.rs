@@ -0,0 +1,161 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_render::texture::TextureFormatPixelInfo;
+use half::f16;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::error::TextureUtilsError;
+
+/// The uncompressed texture formats [convert_format] supports.
+const SUPPORTED_FORMATS: [TextureFormat; 8] = [
+    TextureFormat::Rgba8UnormSrgb,
+    TextureFormat::Rgba8Unorm,
+    TextureFormat::Bgra8UnormSrgb,
+    TextureFormat::Bgra8Unorm,
+    TextureFormat::R8Unorm,
+    TextureFormat::Rg8Unorm,
+    TextureFormat::Rgba16Float,
+    TextureFormat::Rgba32Float,
+];
+
+/// Create a copy of the given image with the given texture format, for example to combine images from different sources.
+/// Supports Rgba8UnormSrgb, Rgba8Unorm, Bgra8UnormSrgb, Bgra8Unorm, R8Unorm, Rg8Unorm, Rgba16Float and Rgba32Float.
+/// The colors are converted between sRGB and linear encoding where required, alpha is always linear.
+/// Like on the GPU, R8Unorm and Rg8Unorm are expanded with zeros for the missing color channels and an opaque alpha,
+/// and converting to them keeps only their channels. Float colors outside of 0..=1 are clamped for the 8-bit formats.
+pub fn convert_format(image: &Image, target: TextureFormat) -> Result<Image, TextureUtilsError> {
+    let source = image.texture_descriptor.format;
+
+    for format in [source, target] {
+        if !SUPPORTED_FORMATS.contains(&format) {
+            return Err(TextureUtilsError::UnsupportedFormat { format });
+        }
+    }
+
+    let mut converted = image.clone();
+
+    if source == target {
+        return Ok(converted);
+    }
+
+    let source_size = source.pixel_size();
+
+    if !image.data.len().is_multiple_of(source_size) {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let mut data = Vec::with_capacity(image.data.len() / source_size * target.pixel_size());
+
+    for pixel in image.data.chunks_exact(source_size) {
+        encode(target, decode(source, pixel), &mut data);
+    }
+
+    converted.texture_descriptor.format = target;
+    converted.data = data;
+    Ok(converted)
+}
+
+/// Get the linear RGBA color of the given pixel bytes.
+fn decode(format: TextureFormat, pixel: &[u8]) -> [f32; 4] {
+    let unorm = |value: u8| value as f32 / 255.0;
+    let half = |i: usize| f16::from_le_bytes([pixel[i * 2], pixel[i * 2 + 1]]).to_f32();
+    let float = |i: usize| f32::from_le_bytes([pixel[i * 4], pixel[i * 4 + 1], pixel[i * 4 + 2], pixel[i * 4 + 3]]);
+
+    match format {
+        TextureFormat::Rgba8UnormSrgb => [srgb_to_linear(pixel[0]), srgb_to_linear(pixel[1]), srgb_to_linear(pixel[2]), unorm(pixel[3])],
+        TextureFormat::Rgba8Unorm => [unorm(pixel[0]), unorm(pixel[1]), unorm(pixel[2]), unorm(pixel[3])],
+        TextureFormat::Bgra8UnormSrgb => [srgb_to_linear(pixel[2]), srgb_to_linear(pixel[1]), srgb_to_linear(pixel[0]), unorm(pixel[3])],
+        TextureFormat::Bgra8Unorm => [unorm(pixel[2]), unorm(pixel[1]), unorm(pixel[0]), unorm(pixel[3])],
+        TextureFormat::R8Unorm => [unorm(pixel[0]), 0.0, 0.0, 1.0],
+        TextureFormat::Rg8Unorm => [unorm(pixel[0]), unorm(pixel[1]), 0.0, 1.0],
+        TextureFormat::Rgba16Float => [half(0), half(1), half(2), half(3)],
+        TextureFormat::Rgba32Float => [float(0), float(1), float(2), float(3)],
+        _ => unreachable!("The format was checked before")
+    }
+}
+
+/// Append the bytes of the given linear RGBA color in the given format.
+fn encode(format: TextureFormat, [r, g, b, a]: [f32; 4], data: &mut Vec<u8>) {
+    let unorm = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    match format {
+        TextureFormat::Rgba8UnormSrgb => data.extend([linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), unorm(a)]),
+        TextureFormat::Rgba8Unorm => data.extend([unorm(r), unorm(g), unorm(b), unorm(a)]),
+        TextureFormat::Bgra8UnormSrgb => data.extend([linear_to_srgb(b), linear_to_srgb(g), linear_to_srgb(r), unorm(a)]),
+        TextureFormat::Bgra8Unorm => data.extend([unorm(b), unorm(g), unorm(r), unorm(a)]),
+        TextureFormat::R8Unorm => data.push(unorm(r)),
+        TextureFormat::Rg8Unorm => data.extend([unorm(r), unorm(g)]),
+        TextureFormat::Rgba16Float => data.extend([r, g, b, a].into_iter().flat_map(|c| f16::from_f32(c).to_le_bytes())),
+        TextureFormat::Rgba32Float => data.extend([r, g, b, a].into_iter().flat_map(f32::to_le_bytes)),
+        _ => unreachable!("The format was checked before")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::format_conversion::convert_format;
+
+    /// Converting to a format with at least the same precision and back keeps every possible pixel value.
+    #[test]
+    fn convert_format_roundtrip_works() {
+        // arrange
+        let colors = (0..=255).map(|v| Color::rgba_u8(v, 255 - v, v / 2, v));
+        let image = create_image((256, 1), TextureFormat::Rgba8UnormSrgb, colors);
+
+        for format in [TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba32Float, TextureFormat::Rgba16Float] {
+            // act
+            let converted = convert_format(&image, format).unwrap();
+            let back = convert_format(&converted, TextureFormat::Rgba8UnormSrgb).unwrap();
+
+            // assert
+            assert_eq!(format, converted.texture_descriptor.format);
+            assert_eq!(image.data, back.data, "Converting to {:?} and back should not change the pixels, but did.", format);
+        }
+    }
+
+    #[test]
+    fn convert_format_applies_gamma() {
+        // arrange
+        let image = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::rgba_u8(188, 0, 255, 128)]);
+
+        // act
+        let converted = convert_format(&image, TextureFormat::Bgra8Unorm).unwrap();
+
+        // assert
+        assert_eq!(vec![255, 0, 128, 128], converted.data);
+    }
+
+    #[test]
+    fn convert_format_expands_r8() {
+        // arrange
+        let mut image = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::NONE]);
+        image.texture_descriptor.format = TextureFormat::R8Unorm;
+        image.data = vec![51];
+
+        // act
+        let converted = convert_format(&image, TextureFormat::Rgba32Float).unwrap();
+
+        // assert
+        let expected = [0.2f32, 0.0, 0.0, 1.0].into_iter().flat_map(f32::to_le_bytes).collect::<Vec<_>>();
+
+        assert_eq!(expected, converted.data);
+    }
+
+    #[test]
+    fn convert_to_unsupported_format_fails() {
+        // arrange
+        let image = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+
+        // act
+        let result = convert_format(&image, TextureFormat::Depth32Float);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Depth32Float })));
+    }
+}
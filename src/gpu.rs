@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_asset::AssetId;
+use bevy_ecs::prelude::*;
+use bevy_render::graph::CameraDriverLabel;
+use bevy_render::prelude::*;
+use bevy_render::render_asset::RenderAssets;
+use bevy_render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, RenderLabel};
+use bevy_render::render_resource::*;
+use bevy_render::render_resource::binding_types::{texture_2d, texture_storage_2d};
+use bevy_render::renderer::{RenderContext, RenderDevice};
+use bevy_render::{Extract, ExtractSchedule, Render, RenderApp, RenderSet};
+
+use crate::image_options::ImageOptions;
+use crate::stable_hash::StableHasher;
+
+/// The width and height of the workgroups of the compute shaders
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Runs the operations of all [GpuTextureModifier]s as compute passes every frame.
+/// Requires the RenderPlugin.
+pub struct GpuTextureModifierPlugin;
+
+impl Plugin for GpuTextureModifierPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GpuOperationShaders>()
+            .add_systems(PostUpdate, create_operation_shaders);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return
+        };
+
+        render_app
+            .init_resource::<ExtractedModifiers>()
+            .init_resource::<GpuTextureModifierBindGroups>()
+            .add_systems(ExtractSchedule, extract_modifiers)
+            .add_systems(Render, (
+                queue_pipelines.in_set(RenderSet::Queue),
+                prepare_bind_groups.in_set(RenderSet::PrepareBindGroups),
+            ));
+
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(GpuTextureModifierLabel, GpuTextureModifierNode);
+        render_graph.add_node_edge(GpuTextureModifierLabel, CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // the render device is not available before
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<GpuTextureModifierPipelines>();
+        }
+    }
+}
+
+/// Writes the pixels of the input texture, modified by the operation, to the output texture
+/// on the GPU every frame, like [crate::texture_modification::modify_texture] does on the CPU.
+/// Remove the component to stop the modification.
+/// The output texture must have the size of the input texture and be created with [create_gpu_output_image],
+/// as the shader writes linear colors to it.
+#[derive(Component, Clone, Debug)]
+pub struct GpuTextureModifier {
+    pub input: Handle<Image>,
+    pub output: Handle<Image>,
+    pub operation: GpuOperation,
+}
+
+impl GpuTextureModifier {
+    pub fn new(input: Handle<Image>, output: Handle<Image>, operation: GpuOperation) -> Self {
+        Self { input, output, operation }
+    }
+}
+
+/// An operation which is applied to every pixel by a [GpuTextureModifier].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GpuOperation {
+    /// Inverts the color, but keeps the alpha
+    Invert,
+    /// Replaces the color with its luminance
+    Grayscale,
+    /// The body of the WGSL function `fn modify(pixel: vec4<f32>, position: vec2<u32>) -> vec4<f32>`,
+    /// which gets the linear color and the position of a pixel and returns its new color.
+    /// For example: `return vec4<f32>(pixel.r, 0.0, 0.0, pixel.a);`
+    Custom(String),
+}
+
+impl GpuOperation {
+    fn body(&self) -> &str {
+        match self {
+            GpuOperation::Invert => "return vec4<f32>(1.0 - pixel.rgb, pixel.a);",
+            GpuOperation::Grayscale => "return vec4<f32>(vec3<f32>(dot(pixel.rgb, vec3<f32>(0.2126, 0.7152, 0.0722))), pixel.a);",
+            GpuOperation::Custom(body) => body,
+        }
+    }
+
+    /// The path the shader of the operation is registered under, so every operation can be told apart
+    /// in import resolution and error messages. Custom operations are named by the hash of their body.
+    fn shader_path(&self) -> String {
+        match self {
+            GpuOperation::Invert => "bevy_texture_utils/gpu/invert.wgsl".to_string(),
+            GpuOperation::Grayscale => "bevy_texture_utils/gpu/grayscale.wgsl".to_string(),
+            GpuOperation::Custom(body) => {
+                let mut hasher = StableHasher::new();
+                hasher.write_str(body);
+                format!("bevy_texture_utils/gpu/custom_{:016x}.wgsl", hasher.finish())
+            }
+        }
+    }
+
+    fn shader_source(&self) -> String {
+        format!(r#"
+@group(0) @binding(0) var input: texture_2d<f32>;
+@group(0) @binding(1) var output: texture_storage_2d<rgba8unorm, write>;
+
+fn modify(pixel: vec4<f32>, position: vec2<u32>) -> vec4<f32> {{
+    {}
+}}
+
+@compute @workgroup_size({WORKGROUP_SIZE}, {WORKGROUP_SIZE}, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+    let size = min(textureDimensions(input), textureDimensions(output));
+
+    if (id.x >= size.x || id.y >= size.y) {{
+        return;
+    }}
+
+    let pixel = textureLoad(input, vec2<i32>(id.xy), 0);
+    textureStore(output, vec2<i32>(id.xy), modify(pixel, id.xy));
+}}
+"#, self.body())
+    }
+}
+
+/// Create an image a [GpuTextureModifier] can write to, with the given size.
+pub fn create_gpu_output_image(size: (usize, usize), options: ImageOptions) -> Image {
+    let mut image = options.create_image(size, vec![0; size.0 * size.1 * 4], TextureFormat::Rgba8Unorm);
+    image.texture_descriptor.usage |= TextureUsages::STORAGE_BINDING;
+    image
+}
+
+/// The shader of every used operation, so every operation is only compiled once.
+#[derive(Resource, Default)]
+struct GpuOperationShaders(HashMap<GpuOperation, Handle<Shader>>);
+
+/// The shader of the operation of a [GpuTextureModifier]
+#[derive(Component)]
+struct GpuOperationShader(Handle<Shader>);
+
+fn create_operation_shaders(
+    mut commands: Commands,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut operation_shaders: ResMut<GpuOperationShaders>,
+    modifiers: Query<(Entity, &GpuTextureModifier), Changed<GpuTextureModifier>>,
+) {
+    for (entity, modifier) in &modifiers {
+        let shader = operation_shaders.0
+            .entry(modifier.operation.clone())
+            .or_insert_with(|| shaders.add(Shader::from_wgsl(modifier.operation.shader_source(), modifier.operation.shader_path())))
+            .clone();
+
+        commands.entity(entity).insert(GpuOperationShader(shader));
+    }
+}
+
+struct ExtractedModifier {
+    input: AssetId<Image>,
+    output: AssetId<Image>,
+    shader: Handle<Shader>,
+}
+
+#[derive(Resource, Default)]
+struct ExtractedModifiers(Vec<ExtractedModifier>);
+
+fn extract_modifiers(
+    mut extracted: ResMut<ExtractedModifiers>,
+    modifiers: Extract<Query<(&GpuTextureModifier, &GpuOperationShader)>>,
+) {
+    extracted.0 = modifiers
+        .iter()
+        .map(|(modifier, shader)| ExtractedModifier {
+            input: modifier.input.id(),
+            output: modifier.output.id(),
+            shader: shader.0.clone(),
+        })
+        .collect();
+}
+
+#[derive(Resource)]
+struct GpuTextureModifierPipelines {
+    layout: BindGroupLayout,
+    pipelines: HashMap<AssetId<Shader>, CachedComputePipelineId>,
+}
+
+impl FromWorld for GpuTextureModifierPipelines {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "gpu_texture_modifier_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_storage_2d(TextureFormat::Rgba8Unorm, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+
+        Self { layout, pipelines: HashMap::new() }
+    }
+}
+
+fn queue_pipelines(
+    mut pipelines: ResMut<GpuTextureModifierPipelines>,
+    pipeline_cache: Res<PipelineCache>,
+    modifiers: Res<ExtractedModifiers>,
+) {
+    for modifier in &modifiers.0 {
+        if pipelines.pipelines.contains_key(&modifier.shader.id()) {
+            continue;
+        }
+
+        let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("gpu_texture_modifier_pipeline".into()),
+            layout: vec![pipelines.layout.clone()],
+            push_constant_ranges: vec![],
+            shader: modifier.shader.clone(),
+            shader_defs: vec![],
+            entry_point: "main".into(),
+        });
+        pipelines.pipelines.insert(modifier.shader.id(), pipeline);
+    }
+}
+
+/// The bind group, pipeline and amount of workgroups of every modifier whose textures are ready.
+#[derive(Resource, Default)]
+struct GpuTextureModifierBindGroups(Vec<(BindGroup, CachedComputePipelineId, (u32, u32))>);
+
+fn prepare_bind_groups(
+    mut bind_groups: ResMut<GpuTextureModifierBindGroups>,
+    pipelines: Res<GpuTextureModifierPipelines>,
+    modifiers: Res<ExtractedModifiers>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    bind_groups.0 = modifiers.0
+        .iter()
+        .filter_map(|modifier| {
+            let input = gpu_images.get(modifier.input)?;
+            let output = gpu_images.get(modifier.output)?;
+            let pipeline = pipelines.pipelines.get(&modifier.shader.id())?;
+
+            let bind_group = render_device.create_bind_group(
+                "gpu_texture_modifier_bind_group",
+                &pipelines.layout,
+                &BindGroupEntries::sequential((&input.texture_view, &output.texture_view)),
+            );
+            let size = input.size.min(output.size).as_uvec2();
+
+            Some((bind_group, *pipeline, (size.x.div_ceil(WORKGROUP_SIZE), size.y.div_ceil(WORKGROUP_SIZE))))
+        })
+        .collect();
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuTextureModifierLabel;
+
+struct GpuTextureModifierNode;
+
+impl Node for GpuTextureModifierNode {
+    fn run<'w>(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let bind_groups = world.resource::<GpuTextureModifierBindGroups>();
+
+        let mut pass = render_context
+            .command_encoder()
+            .begin_compute_pass(&ComputePassDescriptor::default());
+
+        for (bind_group, pipeline, (x, y)) in &bind_groups.0 {
+            // skip the pipelines which are not compiled yet
+            if let Some(pipeline) = pipeline_cache.get_compute_pipeline(*pipeline) {
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(*x, *y, 1);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy_render::render_resource::{TextureFormat, TextureUsages};
+
+    use crate::gpu::{create_gpu_output_image, GpuOperation};
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn create_gpu_output_image_works() {
+        // act
+        let image = create_gpu_output_image((4, 2), ImageOptions::default());
+
+        // assert
+        assert_eq!(TextureFormat::Rgba8Unorm, image.texture_descriptor.format);
+        assert!(image.texture_descriptor.usage.contains(TextureUsages::STORAGE_BINDING));
+        assert_eq!(4 * 2 * 4, image.data.len());
+    }
+
+    #[test]
+    fn custom_operation_is_part_of_the_shader() {
+        // arrange
+        let operation = GpuOperation::Custom("return pixel.bgra;".to_string());
+
+        // act
+        let source = operation.shader_source();
+
+        // assert
+        assert!(source.contains("fn modify(pixel: vec4<f32>, position: vec2<u32>) -> vec4<f32> {\n    return pixel.bgra;\n}"));
+    }
+
+    /// Every operation needs its own shader path, so their shaders can be told apart.
+    #[test]
+    fn shader_paths_are_unique() {
+        // arrange
+        let operations = [
+            GpuOperation::Invert,
+            GpuOperation::Grayscale,
+            GpuOperation::Custom("return pixel.bgra;".to_string()),
+            GpuOperation::Custom("return pixel.rgba;".to_string()),
+        ];
+
+        // act
+        let paths = operations.iter().map(GpuOperation::shader_path).collect::<HashSet<_>>();
+
+        // assert
+        assert_eq!(operations.len(), paths.len());
+        assert_eq!(
+            GpuOperation::Custom("return pixel.bgra;".to_string()).shader_path(),
+            operations[2].shader_path(),
+            "The path of a custom operation should only depend on its body, but didn't."
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::image_options::ImageOptions;
+
+/// Generate an Rgba8UnormSrgb image with a linear gradient from one color to another, for example as a mask or fog texture.
+/// The angle is the direction of the gradient in radians, where 0 goes from left to right and PI / 2 from top to bottom.
+/// The gradient is stretched so the pixels in the corners it starts and ends at get exactly the given colors.
+pub fn generate_linear_gradient(
+    (width, height): (usize, usize),
+    from: Color,
+    to: Color,
+    angle: f32,
+    options: ImageOptions,
+) -> Image {
+    let direction = (angle.cos(), angle.sin());
+    let center = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+    // the distance from the center to the pixel which is the furthest along the direction
+    let extent = center.0 * direction.0.abs() + center.1 * direction.1.abs();
+
+    generate((width, height), options, |x, y| {
+        let distance = (x - center.0) * direction.0 + (y - center.1) * direction.1;
+
+        match extent > f32::EPSILON {
+            true => lerp(from, to, (distance / extent + 1.0) / 2.0),
+            false => from
+        }
+    })
+}
+
+/// Generate an Rgba8UnormSrgb image with a radial gradient around the given pixel, like a vignette or a light cone.
+/// The center gets the inner color, which fades into the outer color at the pixel the furthest away from the center.
+/// The center may be outside of the image.
+pub fn generate_radial_gradient(
+    (width, height): (usize, usize),
+    center: (f32, f32),
+    inner: Color,
+    outer: Color,
+    options: ImageOptions,
+) -> Image {
+    let distance = |x: f32, y: f32| ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
+    let (right, bottom) = (width.saturating_sub(1) as f32, height.saturating_sub(1) as f32);
+    let radius = [(0.0, 0.0), (right, 0.0), (0.0, bottom), (right, bottom)]
+        .into_iter()
+        .map(|(x, y)| distance(x, y))
+        .fold(0.0, f32::max);
+
+    generate((width, height), options, |x, y| match radius > f32::EPSILON {
+        true => lerp(inner, outer, distance(x, y) / radius),
+        false => inner
+    })
+}
+
+/// Create an image by getting the color of every pixel from its coordinates.
+fn generate((width, height): (usize, usize), options: ImageOptions, color_at: impl Fn(f32, f32) -> Color) -> Image {
+    let data = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| color_at(x as f32, y as f32).as_rgba_u8())
+        .collect();
+
+    options.create_image((width, height), data, TextureFormat::Rgba8UnormSrgb)
+}
+
+/// Interpolate between both colors, where t is clamped to 0.0 to 1.0.
+fn lerp(from: Color, to: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+
+    Color::rgba(
+        from.r() + (to.r() - from.r()) * t,
+        from.g() + (to.g() - from.g()) * t,
+        from.b() + (to.b() - from.b()) * t,
+        from.a() + (to.a() - from.a()) * t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::gradient::{generate_linear_gradient, generate_radial_gradient};
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn generate_linear_gradient_works() {
+        // arrange
+        let gray = Color::rgb(0.5, 0.5, 0.5);
+
+        // act
+        let horizontal = generate_linear_gradient((3, 2), Color::BLACK, Color::WHITE, 0.0, ImageOptions::default());
+        let vertical = generate_linear_gradient((1, 3), Color::BLACK, Color::WHITE, PI / 2.0, ImageOptions::default());
+
+        // assert
+        let expected_horizontal = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::BLACK, gray, Color::WHITE,
+                Color::BLACK, gray, Color::WHITE,
+            ],
+        );
+        let expected_vertical = create_image((1, 3), TextureFormat::Rgba8UnormSrgb, [Color::BLACK, gray, Color::WHITE]);
+
+        assert_eq!(expected_horizontal.data, horizontal.data);
+        assert_eq!(expected_vertical.data, vertical.data);
+    }
+
+    #[test]
+    fn generate_radial_gradient_works() {
+        // arrange
+        let transparent = Color::rgba(1.0, 1.0, 1.0, 0.0);
+
+        // act
+        let gradient = generate_radial_gradient((3, 3), (1.0, 1.0), Color::WHITE, transparent, ImageOptions::default());
+
+        // assert
+        let alphas = gradient.data.chunks_exact(4).map(|pixel| pixel[3]).collect::<Vec<_>>();
+
+        assert_eq!(vec![0, 74, 0, 74, 255, 74, 0, 74, 0], alphas);
+    }
+}
@@ -0,0 +1,215 @@
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use pad::Position;
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::tile_map_layout::{composite_tiles, tile_bounds};
+
+/// How the hexagons of a [HexTileMapTextureCreator] are oriented.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HexOrientation {
+    /// The hexagons have a corner at the top. The tiles form rows, and every odd row is shifted right by half a tile.
+    PointyTop,
+    /// The hexagons have an edge at the top. The tiles form columns, and every odd column is shifted up by half a tile.
+    FlatTop,
+}
+
+/// Creates tile map textures from hexagonal tiles. Every tile is a rectangular sprite containing a hexagon,
+/// and the sprites overlap where the hexagons interlock, so they are blended over each other by their alpha.
+/// The tiles further up are drawn first, so tiles with some depth, like mountains, are covered by the tiles in front of them.
+/// Only works with 8-bit RGBA and BGRA textures.
+#[derive(Clone, Debug)]
+pub struct HexTileMapTextureCreator {
+    orientation: HexOrientation,
+    /// The expected texture format of every image
+    texture_format: TextureFormat,
+    /// The expected width of each tile texture
+    tile_width: usize,
+    /// The expected height of each tile texture
+    tile_height: usize,
+    /// The distance in pixels between neighbouring rows of pointy-top maps or columns of flat-top maps
+    stagger_step: usize,
+    /// The options for the created tile map textures
+    options: ImageOptions,
+}
+
+impl HexTileMapTextureCreator {
+    /// Create a creator for tiles with the given size. The rows or columns of the tiles are three quarters of a tile apart,
+    /// which fits regular hexagons touching the borders of their sprites.
+    pub fn new(orientation: HexOrientation, texture_format: TextureFormat, tile_width: usize, tile_height: usize) -> Self {
+        let stagger_step = match orientation {
+            HexOrientation::PointyTop => tile_height * 3 / 4,
+            HexOrientation::FlatTop => tile_width * 3 / 4,
+        };
+
+        Self { orientation, texture_format, tile_width, tile_height, stagger_step, options: ImageOptions::default() }
+    }
+
+    /// Set the distance in pixels between neighbouring rows of pointy-top maps or columns of flat-top maps,
+    /// for hexagons which are not regular, like the squashed hexagons of pixel art tilesets.
+    pub fn with_stagger_step(mut self, stagger_step: usize) -> Self {
+        self.stagger_step = stagger_step;
+        self
+    }
+
+    /// Set the options for the created tile map textures.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The pixel coordinates of the bottom left corner of the tile at the given position, relative to the one at (0, 0),
+    /// where y points upwards. The tiles use offset coordinates, so (0, 0) is the bottom left tile like in square tile maps.
+    pub fn tile_offset(&self, pos: Position) -> (isize, isize) {
+        let (width, height, step) = (self.tile_width as isize, self.tile_height as isize, self.stagger_step as isize);
+
+        match self.orientation {
+            HexOrientation::PointyTop => (pos.x * width + pos.y.rem_euclid(2) * width / 2, pos.y * step),
+            HexOrientation::FlatTop => (pos.x * step, pos.y * height + pos.x.rem_euclid(2) * height / 2),
+        }
+    }
+
+    /// Combine the given hexagonal tiles to a tile map texture. The texture just fits all tiles, so its bottom left corner
+    /// is at the smallest [HexTileMapTextureCreator::tile_offset] of the tiles.
+    pub fn create_tile_map_texture(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let tile_map = self.create_tile_map_image(images, positions_and_textures)?;
+        Ok(images.add(tile_map))
+    }
+
+    /// Like [HexTileMapTextureCreator::create_tile_map_texture], but only reads the images and returns
+    /// the tile map texture instead of adding it to the images.
+    pub fn create_tile_map_image(
+        &self,
+        images: &Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+    ) -> Result<Image, TextureUtilsError> {
+        is_srgb_rgba8(self.texture_format)?;
+
+        let mut tiles = positions_and_textures
+            .into_iter()
+            .map(|(pos, handle)| match images.get(&handle) {
+                Some(tile) => self.check_tile(tile, pos).map(|_| (self.tile_offset(pos), tile)),
+                None => Err(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if tiles.is_empty() {
+            return Err(TextureUtilsError::NoTilesProvided);
+        }
+
+        // back to front, so the lower tiles cover the ones above them
+        tiles.sort_by_key(|((x, y), _)| (-y, *x));
+
+        composite_tiles(&tiles, tile_bounds(&tiles), self.texture_format, &self.options)
+    }
+
+    fn check_tile(&self, tile: &Image, position: Position) -> Result<(), TextureUtilsError> {
+        if tile.texture_descriptor.format != self.texture_format {
+            return Err(TextureUtilsError::FormatMismatch {
+                expected: self.texture_format,
+                actual: tile.texture_descriptor.format,
+                position: Some(position),
+            });
+        }
+
+        if tile.width() as usize != self.tile_width || tile.height() as usize != self.tile_height {
+            return Err(TextureUtilsError::TileSizeMismatch {
+                expected: (self.tile_width, self.tile_height),
+                actual: (tile.width() as usize, tile.height() as usize),
+                position: Some(position),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::hex_tile_map::{HexOrientation, HexTileMapTextureCreator};
+
+    fn pixel(image: &Image, x: usize, y: usize) -> Color {
+        let index = (y * image.width() as usize + x) * 4;
+        let data = &image.data[index..index + 4];
+        Color::rgba_u8(data[0], data[1], data[2], data[3])
+    }
+
+    #[test]
+    fn create_pointy_top_tile_map_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let mut red_pixels = [Color::RED; 16];
+        red_pixels[2] = Color::NONE;
+        let red = images.add(create_image((4, 4), TextureFormat::Rgba8UnormSrgb, red_pixels));
+        let blue = images.add(create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 16]));
+        let green = images.add(create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 16]));
+        let creator = HexTileMapTextureCreator::new(HexOrientation::PointyTop, TextureFormat::Rgba8UnormSrgb, 4, 4);
+
+        // act
+        let handle = creator
+            .create_tile_map_texture(&mut images, [(p!(0, 0), red), (p!(1, 0), blue), (p!(0, 1), green)])
+            .unwrap();
+
+        // assert
+        let tile_map = images.get(handle).unwrap();
+
+        assert_eq!((8, 7), (tile_map.width(), tile_map.height()));
+        // the odd row is shifted by half a tile and overlaps the row below by a quarter of a tile
+        assert_eq!(Color::NONE, pixel(tile_map, 0, 0));
+        assert_eq!(Color::GREEN, pixel(tile_map, 2, 0));
+        assert_eq!(Color::RED, pixel(tile_map, 3, 3));
+        assert_eq!(Color::BLUE, pixel(tile_map, 4, 3));
+        // the transparent pixel of the red tile shows the green tile behind it
+        assert_eq!(Color::GREEN, pixel(tile_map, 2, 3));
+        assert_eq!(Color::NONE, pixel(tile_map, 7, 0));
+    }
+
+    #[test]
+    fn create_flat_top_tile_map_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::RED; 16]));
+        let blue = images.add(create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 16]));
+        let creator = HexTileMapTextureCreator::new(HexOrientation::FlatTop, TextureFormat::Rgba8UnormSrgb, 4, 4)
+            .with_stagger_step(2);
+
+        // act
+        let tile_map = creator.create_tile_map_image(&images, [(p!(0, 0), red), (p!(1, 0), blue)]).unwrap();
+
+        // assert
+        assert_eq!((6, 6), (tile_map.width(), tile_map.height()));
+        assert_eq!((2, 2), creator.tile_offset(p!(1, 0)));
+        assert_eq!(Color::BLUE, pixel(&tile_map, 2, 0));
+        assert_eq!(Color::NONE, pixel(&tile_map, 0, 0));
+        // the red tile is further down, so it covers the blue one
+        assert_eq!(Color::RED, pixel(&tile_map, 3, 3));
+    }
+
+    #[test]
+    fn create_tile_map_with_wrong_tile_size_fails() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tile = images.add(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]));
+        let creator = HexTileMapTextureCreator::new(HexOrientation::PointyTop, TextureFormat::Rgba8UnormSrgb, 4, 4);
+
+        // act
+        let result = creator.create_tile_map_image(&images, [(p!(1, 1), tile)]);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::TileSizeMismatch { expected: (4, 4), actual: (2, 2), position }) if position == Some(p!(1, 1))));
+    }
+}
@@ -0,0 +1,354 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// The size of the square windows SSIM compares the images in
+const SSIM_WINDOW_SIZE: usize = 8;
+
+/// Calculate the peak signal-to-noise ratio of two images in decibels. The higher the value,
+/// the more similar the images are. Identical images have an infinite ratio, while values
+/// above 40 dB are usually indistinguishable for the eye.
+/// Every byte of the image data is compared, so it works with any texture format with 8 bits per channel.
+pub fn psnr(a: &Image, b: &Image) -> Result<f32, TextureUtilsError> {
+    check_comparable(a, b)?;
+
+    let squared_error_sum = a.data
+        .iter()
+        .zip(&b.data)
+        .map(|(a, b)| (*a as f64 - *b as f64).powi(2))
+        .sum::<f64>();
+    let mean_squared_error = squared_error_sum / a.data.len().max(1) as f64;
+
+    if mean_squared_error == 0.0 {
+        return Ok(f32::INFINITY);
+    }
+
+    Ok((10.0 * (255.0 * 255.0 / mean_squared_error).log10()) as f32)
+}
+
+/// Calculate the structural similarity of two images, which matches the perceived similarity
+/// better than [psnr]. The result ranges from -1.0 to 1.0, where 1.0 means identical.
+/// The luminance of the images is compared in windows of 8x8 pixels, which overlap by half.
+/// Supports R8Unorm images and the 8-bit RGBA and BGRA formats.
+pub fn ssim(a: &Image, b: &Image) -> Result<f32, TextureUtilsError> {
+    check_comparable(a, b)?;
+
+    let width = a.width() as usize;
+    let height = a.height() as usize;
+
+    let luminance_a = luminance(a)?;
+    let luminance_b = luminance(b)?;
+
+    let window_width = SSIM_WINDOW_SIZE.min(width);
+    let window_height = SSIM_WINDOW_SIZE.min(height);
+    let window_starts = |size: usize, window: usize| (0..=size - window)
+        .step_by((window / 2).max(1))
+        .collect::<Vec<_>>();
+
+    let mut sum = 0.0;
+    let mut windows = 0;
+
+    for y in window_starts(height, window_height) {
+        for x in window_starts(width, window_width) {
+            let indices = (y..y + window_height)
+                .flat_map(|wy| (x..x + window_width).map(move |wx| wy * width + wx))
+                .collect::<Vec<_>>();
+
+            sum += window_ssim(
+                indices.iter().map(|i| luminance_a[*i]),
+                indices.iter().map(|i| luminance_b[*i]),
+            );
+            windows += 1;
+        }
+    }
+
+    Ok((sum / windows as f64) as f32)
+}
+
+/// The differences between two images, created by [diff_textures].
+#[derive(Clone, Debug)]
+pub struct DiffReport {
+    /// The amount of pixels with at least one different byte
+    pub differing_pixels: usize,
+    /// The biggest difference of a single byte
+    pub max_channel_delta: u8,
+    /// The position of the first differing pixel, row by row from the top left
+    pub first_difference: Option<(usize, usize)>,
+    /// An opaque grayscale image with the format Rgba8Unorm which shows where the images differ,
+    /// where every pixel is as bright as the biggest byte difference of the pixel. So identical pixels are black.
+    pub diff_image: Image,
+}
+
+impl DiffReport {
+    /// Tells if the images are identical.
+    pub fn is_identical(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+/// Compare both images byte by byte, for example to find out why a generated texture doesn't match the expected one.
+/// Both images must have the same size and texture format, which can be any uncompressed format.
+/// Every byte is treated as a channel, so the deltas are only meaningful for formats with 8 bits per channel.
+pub fn diff_textures(a: &Image, b: &Image) -> Result<DiffReport, TextureUtilsError> {
+    check_comparable(a, b)?;
+
+    let width = a.width() as usize;
+    let format = a.texture_descriptor.format;
+    // pixel_size panics for compressed and combined depth-stencil formats
+    let bytes_per_pixel = match (format.block_dimensions(), format.block_copy_size(None)) {
+        ((1, 1), Some(size)) => size as usize,
+        _ => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+    let mut differing_pixels = 0;
+    let mut max_channel_delta = 0;
+    let mut first_difference = None;
+    let mut data = Vec::with_capacity(a.data.len() / bytes_per_pixel * 4);
+
+    for (i, (pixel_a, pixel_b)) in a.data.chunks_exact(bytes_per_pixel).zip(b.data.chunks_exact(bytes_per_pixel)).enumerate() {
+        let delta = pixel_a
+            .iter()
+            .zip(pixel_b)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or_default();
+
+        if delta > 0 {
+            differing_pixels += 1;
+            first_difference = first_difference.or(Some((i % width, i / width)));
+        }
+
+        max_channel_delta = max_channel_delta.max(delta);
+        data.extend([delta, delta, delta, 255]);
+    }
+
+    Ok(DiffReport {
+        differing_pixels,
+        max_channel_delta,
+        first_difference,
+        diff_image: ImageOptions::default().create_image((width, a.height() as usize), data, TextureFormat::Rgba8Unorm),
+    })
+}
+
+/// Assert that both images have the same size and format and that no byte differs by more than the given tolerance,
+/// so tests don't have to compare the raw data and get a readable message if the images differ.
+/// Panics with the amount of differing pixels, the biggest difference and the first differing pixel otherwise.
+#[track_caller]
+pub fn assert_images_eq_within(a: &Image, b: &Image, tolerance: u8) {
+    let report = match diff_textures(a, b) {
+        Ok(report) => report,
+        Err(e) => panic!("The images can't be compared: {e}")
+    };
+
+    if report.max_channel_delta > tolerance {
+        panic!(
+            "The images differ by up to {} (tolerance {}) in {} of {} pixels, the first one at {:?}.",
+            report.max_channel_delta,
+            tolerance,
+            report.differing_pixels,
+            a.width() * a.height(),
+            report.first_difference.unwrap_or_default()
+        );
+    }
+}
+
+fn check_comparable(a: &Image, b: &Image) -> Result<(), TextureUtilsError> {
+    if a.width() != b.width() || a.height() != b.height() {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    if a.texture_descriptor.format != b.texture_descriptor.format {
+        return Err(TextureUtilsError::FormatMismatch {
+            expected: a.texture_descriptor.format,
+            actual: b.texture_descriptor.format,
+            position: None,
+        });
+    }
+
+    if a.data.len() != b.data.len() {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    if a.data.is_empty() {
+        return Err(TextureUtilsError::ZeroSize { size: (a.width() as usize, a.height() as usize) });
+    }
+
+    Ok(())
+}
+
+fn luminance(image: &Image) -> Result<Vec<f64>, TextureUtilsError> {
+    let (bytes_per_pixel, [r, g, b]) = match image.texture_descriptor.format {
+        TextureFormat::R8Unorm => (1, [0, 0, 0]),
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (4, [0, 1, 2]),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => (4, [2, 1, 0]),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    if image.data.len() != (image.width() * image.height()) as usize * bytes_per_pixel {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    Ok(image.data
+        .chunks_exact(bytes_per_pixel)
+        .map(|p| 0.299 * p[r] as f64 + 0.587 * p[g] as f64 + 0.114 * p[b] as f64)
+        .collect())
+}
+
+fn window_ssim(a: impl Iterator<Item=f64> + Clone, b: impl Iterator<Item=f64> + Clone) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let count = a.clone().count() as f64;
+    let mean_a = a.clone().sum::<f64>() / count;
+    let mean_b = b.clone().sum::<f64>() / count;
+
+    let (variance_a, variance_b, covariance) = a
+        .zip(b)
+        .fold((0.0, 0.0, 0.0), |(va, vb, cov), (a, b)| {
+            let (da, db) = (a - mean_a, b - mean_b);
+            (va + da * da, vb + db * db, cov + da * db)
+        });
+    let (variance_a, variance_b, covariance) = (variance_a / count, variance_b / count, covariance / count);
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::{checkerboard_image, create_image};
+    use crate::error::TextureUtilsError;
+    use crate::image_comparison::{assert_images_eq_within, diff_textures, psnr, ssim};
+    use crate::image_options::ImageOptions;
+    use crate::texture_modification::map_to_new_texture;
+
+    fn create_checkerboard() -> Image {
+        checkerboard_image((16, 16), 1, Color::WHITE, Color::BLACK)
+    }
+
+    #[test]
+    fn psnr_works() {
+        // arrange
+        let image = create_checkerboard();
+        let slightly_changed = map_to_new_texture(&image, |_, _, p| [p[0].saturating_sub(1), p[1], p[2], p[3]]);
+        let inverted = map_to_new_texture(&image, |_, _, p| [255 - p[0], 255 - p[1], 255 - p[2], p[3]]);
+
+        // act
+        let identical_psnr = psnr(&image, &image).unwrap();
+        let slightly_changed_psnr = psnr(&image, &slightly_changed).unwrap();
+        let inverted_psnr = psnr(&image, &inverted).unwrap();
+
+        // assert
+        assert_eq!(f32::INFINITY, identical_psnr);
+        assert!(slightly_changed_psnr > 40.0, "A tiny change should have a high PSNR, but was {}.", slightly_changed_psnr);
+        assert!(inverted_psnr < 10.0, "An inverted image should have a low PSNR, but was {}.", inverted_psnr);
+    }
+
+    #[test]
+    fn ssim_works() {
+        // arrange
+        let image = create_checkerboard();
+        let brightened = map_to_new_texture(&image, |_, _, p| [p[0].max(5), p[1].max(5), p[2].max(5), p[3]]);
+        let gray = create_image((16, 16), TextureFormat::Rgba8UnormSrgb, [Color::rgb(0.5, 0.5, 0.5); 256]);
+
+        // act
+        let identical_ssim = ssim(&image, &image).unwrap();
+        let brightened_ssim = ssim(&image, &brightened).unwrap();
+        let gray_ssim = ssim(&image, &gray).unwrap();
+
+        // assert
+        assert!((identical_ssim - 1.0).abs() < 0.0001);
+        assert!(brightened_ssim > 0.95, "A slightly brightened image should be similar, but was {}.", brightened_ssim);
+        assert!(gray_ssim < 0.1, "A flat gray image should not be similar to a checkerboard, but was {}.", gray_ssim);
+    }
+
+    #[test]
+    fn ssim_with_unsupported_format_fails() {
+        // arrange
+        let image = ImageOptions::default().create_image((2, 2), vec![0; 8], TextureFormat::R16Unorm);
+
+        // act
+        let result = ssim(&image, &image);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R16Unorm })));
+    }
+
+    #[test]
+    fn psnr_with_different_sizes_fails() {
+        // arrange
+        let a = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let b = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]);
+
+        // act
+        let result = psnr(&a, &b);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::SizeMismatch)));
+    }
+
+    #[test]
+    fn diff_textures_works() {
+        // arrange
+        let a = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK, Color::rgba_u8(10, 20, 30, 255), Color::WHITE]);
+        let b = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK, Color::rgba_u8(13, 20, 25, 255), Color::WHITE]);
+
+        // act
+        let report = diff_textures(&a, &b).unwrap();
+
+        // assert
+        assert_eq!(1, report.differing_pixels);
+        assert_eq!(5, report.max_channel_delta);
+        assert_eq!(Some((1, 0)), report.first_difference);
+        assert_eq!(vec![0, 0, 0, 255, 5, 5, 5, 255, 0, 0, 0, 255], report.diff_image.data);
+        assert!(!report.is_identical());
+    }
+
+    #[test]
+    fn diff_textures_with_compressed_format_fails() {
+        // arrange
+        let mut image = create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::RED; 16]);
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnormSrgb;
+
+        // act
+        let result = diff_textures(&image, &image);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Bc7RgbaUnormSrgb })));
+    }
+
+    /// Images whose data has different lengths can't be compared completely, so they must be rejected.
+    #[test]
+    fn diff_textures_with_different_data_lengths_fails() {
+        // arrange
+        let a = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]);
+        let mut b = a.clone();
+        b.data.truncate(4);
+
+        // act
+        let result = diff_textures(&a, &b);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedPixelSize)));
+    }
+
+    #[test]
+    fn assert_images_eq_within_works() {
+        // arrange
+        let image = create_checkerboard();
+        let slightly_changed = map_to_new_texture(&image, |_, _, p| [p[0].saturating_sub(1), p[1], p[2], p[3]]);
+
+        // act
+        let within_tolerance = std::panic::catch_unwind(|| assert_images_eq_within(&image, &slightly_changed, 1));
+        let outside_tolerance = std::panic::catch_unwind(|| assert_images_eq_within(&image, &slightly_changed, 0));
+
+        // assert
+        assert!(within_tolerance.is_ok());
+        assert!(outside_tolerance.is_err());
+    }
+}
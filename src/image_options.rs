@@ -0,0 +1,96 @@
+use bevy_render::prelude::*;
+use bevy_render::render_asset::RenderAssetUsages;
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_render::texture::ImageSampler;
+
+/// Options for the images created by this crate.
+#[derive(Clone, Debug, Default)]
+pub struct ImageOptions {
+    /// Tells in which worlds the image is kept. Use only RENDER_WORLD to drop the CPU copy of
+    /// the image after it was uploaded to the GPU, or keep MAIN_WORLD to edit it further on the CPU.
+    /// Both are kept by default.
+    pub asset_usage: RenderAssetUsages,
+    /// The sampler of the image, like nearest filtering for pixel art or the address modes.
+    /// The default sampler configured in the ImagePlugin is used by default.
+    pub sampler: ImageSampler,
+    /// If set, the images are only kept in the render world, no matter the configured asset usage.
+    /// So bevy drops the CPU copy of an image, including its pixel data, once it was uploaded to the GPU,
+    /// which saves a lot of memory for big atlases and maps that are not edited anymore.
+    pub discard_cpu_data: bool,
+}
+
+impl ImageOptions {
+    pub fn new(asset_usage: RenderAssetUsages) -> Self {
+        Self { asset_usage, sampler: ImageSampler::Default, discard_cpu_data: false }
+    }
+
+    /// Set the sampler of the created images.
+    pub fn with_sampler(mut self, sampler: ImageSampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Discard the CPU copy of the created images after they were uploaded to the GPU.
+    pub fn with_discarded_cpu_data(mut self) -> Self {
+        self.discard_cpu_data = true;
+        self
+    }
+
+    /// The asset usage the created images actually get.
+    pub fn effective_asset_usage(&self) -> RenderAssetUsages {
+        match self.discard_cpu_data {
+            true => RenderAssetUsages::RENDER_WORLD,
+            false => self.asset_usage
+        }
+    }
+
+    /// Create a 2D image with these options from the given size, data and texture format.
+    pub(crate) fn create_image(
+        &self,
+        (width, height): (usize, usize),
+        mut data: Vec<u8>,
+        texture_format: TextureFormat,
+    ) -> Image {
+        // the image keeps the buffer until it is dropped, so don't retain more than needed
+        data.shrink_to_fit();
+
+        let mut image = Image::new(
+            Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            texture_format,
+            self.effective_asset_usage(),
+        );
+        image.sampler = self.sampler.clone();
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::render_asset::RenderAssetUsages;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::image_options::ImageOptions;
+
+    #[test]
+    fn create_image_with_discarded_cpu_data_works() {
+        // arrange
+        let options = ImageOptions::new(RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD)
+            .with_discarded_cpu_data();
+        let mut data = Vec::with_capacity(64);
+        data.extend([0; 4]);
+
+        // act
+        let image = options.create_image((1, 1), data, TextureFormat::Rgba8UnormSrgb);
+
+        // assert
+        assert_eq!(RenderAssetUsages::RENDER_WORLD, image.asset_usage, "The image should only be kept in the render world, but wasn't.");
+        assert_eq!(4, image.data.capacity(), "The image should not retain unused capacity, but did.");
+    }
+}
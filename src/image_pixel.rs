@@ -0,0 +1,137 @@
+use bevy_render::prelude::*;
+
+use crate::color::{color_to_pixel_bytes, pixel_bytes_to_color};
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+/// Access single pixels of an image by their coordinates, where (0, 0) is the top left pixel.
+/// The colors are encoded and decoded according to the texture format of the image,
+/// which must be one of the formats supported by [color_to_pixel_bytes].
+pub trait ImagePixelExt {
+    /// The width of the image in pixels
+    fn width_usize(&self) -> usize;
+
+    /// The height of the image in pixels
+    fn height_usize(&self) -> usize;
+
+    /// Get the color of the pixel at the given coordinates. Returns None if the coordinates are outside
+    /// of the image or its texture format is not supported.
+    fn get_pixel(&self, x: usize, y: usize) -> Option<Color>;
+
+    /// Set the color of the pixel at the given coordinates.
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) -> Result<(), TextureUtilsError>;
+
+    /// Set the color of every pixel.
+    fn fill(&mut self, color: Color) -> Result<(), TextureUtilsError>;
+}
+
+impl ImagePixelExt for Image {
+    fn width_usize(&self) -> usize {
+        self.width() as usize
+    }
+
+    fn height_usize(&self) -> usize {
+        self.height() as usize
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x >= self.width_usize() || y >= self.height_usize() {
+            return None;
+        }
+
+        // pixel_size panics for compressed and depth formats, so check the format first
+        let bytes_per_pixel = color_to_pixel_bytes(Color::NONE, self.texture_descriptor.format).ok()?.len();
+        let index = (y * self.width_usize() + x) * bytes_per_pixel;
+
+        pixel_bytes_to_color(self.data.get(index..index + bytes_per_pixel)?, self.texture_descriptor.format).ok()
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) -> Result<(), TextureUtilsError> {
+        if x >= self.width_usize() || y >= self.height_usize() {
+            return Err(TextureUtilsError::RectOutOfBounds { rect: PixelRect::new(x, y, 1, 1) });
+        }
+
+        let bytes = color_to_pixel_bytes(color, self.texture_descriptor.format)?;
+        let index = (y * self.width_usize() + x) * bytes.len();
+        self.data[index..index + bytes.len()].copy_from_slice(&bytes);
+
+        Ok(())
+    }
+
+    fn fill(&mut self, color: Color) -> Result<(), TextureUtilsError> {
+        let bytes = color_to_pixel_bytes(color, self.texture_descriptor.format)?;
+
+        for pixel in self.data.chunks_exact_mut(bytes.len()) {
+            pixel.copy_from_slice(&bytes);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_pixel::ImagePixelExt;
+    use crate::pixel_rect::PixelRect;
+
+    #[test]
+    fn get_and_set_pixel_works() {
+        // arrange
+        let mut image = create_image((2, 2), TextureFormat::Bgra8UnormSrgb, [Color::NONE; 4]);
+
+        // act
+        image.set_pixel(1, 0, Color::RED).unwrap();
+
+        // assert
+        assert_eq!(Some(Color::RED), image.get_pixel(1, 0));
+        assert_eq!(Some(Color::rgba_u8(0, 0, 0, 0)), image.get_pixel(0, 1));
+        assert_eq!([0, 0, 255, 255], image.data[4..8]);
+        assert_eq!(None, image.get_pixel(2, 0));
+    }
+
+    #[test]
+    fn fill_works() {
+        // arrange
+        let mut image = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::NONE; 2]);
+
+        // act
+        image.fill(Color::BLUE).unwrap();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 2]);
+
+        assert_eq!(expected.data, image.data);
+    }
+
+    #[test]
+    fn set_pixel_outside_fails() {
+        // arrange
+        let mut image = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::NONE]);
+
+        // act
+        let result = image.set_pixel(1, 0, Color::RED);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::RectOutOfBounds { rect }) if rect == PixelRect::new(1, 0, 1, 1)));
+    }
+
+    /// Compressed formats are not supported, so there is no pixel instead of a panic.
+    #[test]
+    fn get_pixel_with_compressed_format_returns_none() {
+        // arrange
+        let mut image = create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::RED; 16]);
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnormSrgb;
+
+        // act
+        let pixel = image.get_pixel(0, 0);
+
+        // assert
+        assert_eq!(None, pixel);
+    }
+}
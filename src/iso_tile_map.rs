@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use pad::{p, Position};
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::tile_map_layout::{composite_tiles, tile_bounds};
+
+/// A tile image with the pixel coordinates of its bottom left corner.
+type PlacedTile<'a> = ((isize, isize), &'a Image);
+
+/// A chunk of an isometric tile map, created by [IsoTileMapTextureCreator::create_tile_map_texture_chunks].
+#[derive(Clone, Debug)]
+pub struct IsoChunk {
+    /// The position of the bottom tile of the chunk
+    pub position: Position,
+    /// The pixel coordinates of the bottom left corner of the chunk texture, relative to the tile at (0, 0)
+    pub offset: (isize, isize),
+    pub texture: Handle<Image>,
+}
+
+/// Creates tile map textures from isometric tiles, whose ground is a diamond twice as wide as high. The x axis of the
+/// positions runs to the top right and the y axis to the top left, so (0, 0) is the bottom corner of the map.
+/// The bottom of every tile sprite is the bottom of its diamond, so sprites can be higher than the diamond, like walls or trees.
+/// The sprites overlap, so they are blended over each other by their alpha from back to front.
+/// Only works with 8-bit RGBA and BGRA textures.
+#[derive(Clone, Debug)]
+pub struct IsoTileMapTextureCreator {
+    /// The expected texture format of every image
+    texture_format: TextureFormat,
+    /// The expected width of each tile texture, which is also the width of the diamonds
+    tile_width: usize,
+    /// The expected height of each tile texture
+    tile_height: usize,
+    /// The options for the created tile map textures
+    options: ImageOptions,
+}
+
+impl IsoTileMapTextureCreator {
+    pub fn new(texture_format: TextureFormat, tile_width: usize, tile_height: usize) -> Self {
+        Self { texture_format, tile_width, tile_height, options: ImageOptions::default() }
+    }
+
+    /// Set the options for the created tile map textures.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The pixel coordinates of the bottom left corner of the tile at the given position, relative to the one at (0, 0),
+    /// where y points upwards. Neighbouring tiles are half a tile width apart horizontally and half a diamond height vertically.
+    pub fn tile_offset(&self, pos: Position) -> (isize, isize) {
+        let half_width = self.tile_width as isize / 2;
+        let half_diamond_height = self.tile_width as isize / 4;
+
+        ((pos.x - pos.y) * half_width, (pos.x + pos.y) * half_diamond_height)
+    }
+
+    /// Combine the given isometric tiles to a tile map texture. The texture just fits all tiles, so its bottom left corner
+    /// is at the smallest [IsoTileMapTextureCreator::tile_offset] of the tiles.
+    pub fn create_tile_map_texture(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let tile_map = self.create_tile_map_image(images, positions_and_textures)?;
+        Ok(images.add(tile_map))
+    }
+
+    /// Like [IsoTileMapTextureCreator::create_tile_map_texture], but only reads the images and returns
+    /// the tile map texture instead of adding it to the images.
+    pub fn create_tile_map_image(
+        &self,
+        images: &Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+    ) -> Result<Image, TextureUtilsError> {
+        let tiles = self.collect_tiles(images, positions_and_textures)?;
+
+        if tiles.is_empty() {
+            return Err(TextureUtilsError::NoTilesProvided);
+        }
+
+        self.composite(tiles.into_iter().map(|(_, tile)| tile).collect(), None)
+    }
+
+    /// Like [IsoTileMapTextureCreator::create_tile_map_texture], but splits the tile map into chunks with the given
+    /// amount of tiles in x and y direction, for large maps which exceed the texture size limit of the GPU or are streamed.
+    /// All chunk textures have the same size, so the chunks can be placed at their offsets next to each other,
+    /// where they overlap like the tiles do. Chunks without tiles are skipped.
+    pub fn create_tile_map_texture_chunks(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+        (chunk_width, chunk_height): (usize, usize),
+    ) -> Result<Vec<IsoChunk>, TextureUtilsError> {
+        if chunk_width == 0 || chunk_height == 0 {
+            return Err(TextureUtilsError::ZeroSize { size: (chunk_width, chunk_height) });
+        }
+
+        let (chunk_width, chunk_height) = (chunk_width as isize, chunk_height as isize);
+        let mut chunks = HashMap::<Position, Vec<_>>::new();
+
+        for (pos, tile) in self.collect_tiles(images, positions_and_textures)? {
+            let chunk = p!(pos.x.div_euclid(chunk_width) * chunk_width, pos.y.div_euclid(chunk_height) * chunk_height);
+            chunks.entry(chunk).or_default().push(tile);
+        }
+
+        if chunks.is_empty() {
+            return Err(TextureUtilsError::NoTilesProvided);
+        }
+
+        let chunks = chunks
+            .into_iter()
+            .map(|(chunk, tiles)| {
+                // the left, right and top corners of the chunk are the tiles at its other corners
+                let (left, bottom) = (self.tile_offset(p!(chunk.x, chunk.y + chunk_height - 1)).0, self.tile_offset(chunk).1);
+                let right = self.tile_offset(p!(chunk.x + chunk_width - 1, chunk.y)).0 + self.tile_width as isize;
+                let top = self.tile_offset(p!(chunk.x + chunk_width - 1, chunk.y + chunk_height - 1)).1 + self.tile_height as isize;
+                let bounds = ((left, bottom), ((right - left) as usize, (top - bottom) as usize));
+
+                self.composite(tiles, Some(bounds)).map(|image| (chunk, bounds.0, image))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(chunks
+            .into_iter()
+            .map(|(position, offset, image)| IsoChunk { position, offset, texture: images.add(image) })
+            .collect())
+    }
+
+    /// Get the images of the given tiles with their offsets and check if they match the tile size and format.
+    fn collect_tiles<'a>(
+        &self,
+        images: &'a Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+    ) -> Result<Vec<(Position, PlacedTile<'a>)>, TextureUtilsError> {
+        is_srgb_rgba8(self.texture_format)?;
+
+        positions_and_textures
+            .into_iter()
+            .map(|(pos, handle)| match images.get(&handle) {
+                Some(tile) => self.check_tile(tile, pos).map(|_| (pos, (self.tile_offset(pos), tile))),
+                None => Err(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+            })
+            .collect()
+    }
+
+    /// Blend the tiles from back to front into an image with the given bounds, or into one which just fits them.
+    fn composite(
+        &self,
+        mut tiles: Vec<PlacedTile>,
+        bounds: Option<((isize, isize), (usize, usize))>,
+    ) -> Result<Image, TextureUtilsError> {
+        // the tiles further up are further away and get covered by the ones in front of them
+        tiles.sort_by_key(|((x, y), _)| (-y, *x));
+        let bounds = bounds.unwrap_or_else(|| tile_bounds(&tiles));
+
+        composite_tiles(&tiles, bounds, self.texture_format, &self.options)
+    }
+
+    fn check_tile(&self, tile: &Image, position: Position) -> Result<(), TextureUtilsError> {
+        if tile.texture_descriptor.format != self.texture_format {
+            return Err(TextureUtilsError::FormatMismatch {
+                expected: self.texture_format,
+                actual: tile.texture_descriptor.format,
+                position: Some(position),
+            });
+        }
+
+        if tile.width() as usize != self.tile_width || tile.height() as usize != self.tile_height {
+            return Err(TextureUtilsError::TileSizeMismatch {
+                expected: (self.tile_width, self.tile_height),
+                actual: (tile.width() as usize, tile.height() as usize),
+                position: Some(position),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::iso_tile_map::IsoTileMapTextureCreator;
+
+    fn pixel(image: &Image, x: usize, y: usize) -> Color {
+        let index = (y * image.width() as usize + x) * 4;
+        let data = &image.data[index..index + 4];
+        Color::rgba_u8(data[0], data[1], data[2], data[3])
+    }
+
+    fn add_tiles(images: &mut Assets<Image>) -> Vec<(pad::Position, Handle<Image>)> {
+        [(p!(0, 0), Color::RED), (p!(1, 0), Color::BLUE), (p!(0, 1), Color::GREEN), (p!(1, 1), Color::YELLOW)]
+            .into_iter()
+            .map(|(pos, color)| (pos, images.add(create_image((4, 2), TextureFormat::Rgba8UnormSrgb, [color; 8]))))
+            .collect()
+    }
+
+    #[test]
+    fn create_tile_map_texture_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tiles = add_tiles(&mut images);
+        let creator = IsoTileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 4, 2);
+
+        // act
+        let handle = creator.create_tile_map_texture(&mut images, tiles).unwrap();
+
+        // assert
+        let tile_map = images.get(handle).unwrap();
+
+        assert_eq!((8, 4), (tile_map.width(), tile_map.height()));
+        assert_eq!((-2, 1), creator.tile_offset(p!(0, 1)));
+        assert_eq!(Color::YELLOW, pixel(tile_map, 3, 0));
+        // the tiles in front cover the ones behind them
+        assert_eq!(Color::GREEN, pixel(tile_map, 3, 1));
+        assert_eq!(Color::BLUE, pixel(tile_map, 6, 1));
+        assert_eq!(Color::RED, pixel(tile_map, 4, 2));
+        assert_eq!(Color::NONE, pixel(tile_map, 0, 3));
+    }
+
+    #[test]
+    fn create_tile_map_texture_chunks_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tiles = add_tiles(&mut images);
+        let creator = IsoTileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 4, 2);
+
+        // act
+        let mut chunks = creator.create_tile_map_texture_chunks(&mut images, tiles, (2, 1)).unwrap();
+        chunks.sort_by_key(|chunk| chunk.position.y);
+
+        // assert
+        let front = images.get(chunks[0].texture.clone()).unwrap();
+        let back = images.get(chunks[1].texture.clone()).unwrap();
+
+        assert_eq!(2, chunks.len());
+        assert_eq!((p!(0, 0), (0, 0)), (chunks[0].position, chunks[0].offset));
+        assert_eq!((p!(0, 1), (-2, 1)), (chunks[1].position, chunks[1].offset));
+        assert_eq!((6, 3), (front.width(), front.height()));
+        assert_eq!((6, 3), (back.width(), back.height()));
+        assert_eq!(Color::BLUE, pixel(front, 5, 0));
+        assert_eq!(Color::RED, pixel(front, 3, 1));
+        assert_eq!(Color::YELLOW, pixel(back, 2, 0));
+        assert_eq!(Color::GREEN, pixel(back, 2, 1));
+    }
+
+    #[test]
+    fn create_tile_map_texture_with_unsupported_format_fails() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let creator = IsoTileMapTextureCreator::new(TextureFormat::R8Unorm, 4, 2);
+
+        // act
+        let result = creator.create_tile_map_texture(&mut images, []);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+}
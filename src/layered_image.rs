@@ -0,0 +1,315 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// Tells how the colors of a layer are combined with the colors below it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BlendMode {
+    /// The layer is simply drawn over the layers below.
+    Normal,
+    /// Multiplies the colors, which darkens the result.
+    Multiply,
+    /// Inverted multiplication of the inverted colors, which lightens the result.
+    Screen,
+    /// Adds the colors, which lightens the result.
+    Add,
+}
+
+impl BlendMode {
+    fn blend(&self, below: f32, above: f32) -> f32 {
+        match self {
+            BlendMode::Normal => above,
+            BlendMode::Multiply => below * above,
+            BlendMode::Screen => 1.0 - (1.0 - below) * (1.0 - above),
+            BlendMode::Add => (below + above).min(1.0),
+        }
+    }
+}
+
+/// A single named layer of a [LayeredImage].
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub image: Image,
+    /// The position of the top left pixel of the layer on the canvas. Layers can be
+    /// partially or completely outside of the canvas.
+    pub offset: (isize, isize),
+    /// The opacity (0.0 to 1.0) of the whole layer
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// Hidden layers are ignored when flattening
+    pub visible: bool,
+}
+
+impl Layer {
+    /// Create a visible, fully opaque layer with normal blending at the given offset.
+    pub fn new(name: impl Into<String>, image: Image, offset: (isize, isize)) -> Self {
+        Self {
+            name: name.into(),
+            image,
+            offset,
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
+            visible: true,
+        }
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+}
+
+/// An image consisting of multiple layers on a canvas with a fixed size, like in an image editor.
+/// The first layer is the bottom one and the last layer the top one.
+pub struct LayeredImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Layer>,
+    options: ImageOptions,
+}
+
+impl LayeredImage {
+    /// Create a layered image without layers with the given canvas size.
+    pub fn new((width, height): (usize, usize)) -> Self {
+        Self { width, height, layers: vec![], options: ImageOptions::default() }
+    }
+
+    /// Set the options for the flattened images.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Get the layer with the given name.
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Add a layer on top of all other layers. Layer names must be unique and the image of the layer
+    /// must have an 8-bit RGBA or BGRA format.
+    pub fn add_layer(&mut self, layer: Layer) -> Result<(), TextureUtilsError> {
+        is_srgb_rgba8(layer.image.texture_descriptor.format)?;
+
+        if self.layers.iter().any(|l| l.name == layer.name) {
+            return Err(TextureUtilsError::DuplicateName { kind: "layer", name: layer.name });
+        }
+
+        if layer.image.data.len() != (layer.image.width() * layer.image.height() * 4) as usize {
+            return Err(TextureUtilsError::UnsupportedPixelSize);
+        }
+
+        self.layers.push(layer);
+        Ok(())
+    }
+
+    /// Remove the layer with the given name and return it.
+    pub fn remove_layer(&mut self, name: &str) -> Option<Layer> {
+        let index = self.index_of(name)?;
+        Some(self.layers.remove(index))
+    }
+
+    /// Move the layer with the given name to the given index, where 0 is the bottom.
+    /// Indices after the top layer move the layer to the top.
+    pub fn move_layer(&mut self, name: &str, index: usize) -> Result<(), TextureUtilsError> {
+        let current = self.index_of(name).ok_or(TextureUtilsError::NotFound { kind: "layer", name: name.to_string() })?;
+        let layer = self.layers.remove(current);
+        self.layers.insert(index.min(self.layers.len()), layer);
+
+        Ok(())
+    }
+
+    /// Combine all visible layers to a single image with the size of the canvas.
+    pub fn flatten(&self) -> Image {
+        let mut data = vec![0.0f32; self.width * self.height * 4];
+
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            let channels = match layer.image.texture_descriptor.format {
+                TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => [2, 1, 0],
+                _ => [0, 1, 2]
+            };
+            let layer_width = layer.image.width() as usize;
+            let layer_height = layer.image.height() as usize;
+
+            for ly in 0..layer_height {
+                for lx in 0..layer_width {
+                    let x = layer.offset.0 + lx as isize;
+                    let y = layer.offset.1 + ly as isize;
+
+                    if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+                        continue;
+                    }
+
+                    let source_index = (ly * layer_width + lx) * 4;
+                    let source = &layer.image.data[source_index..source_index + 4];
+                    let target_index = (y as usize * self.width + x as usize) * 4;
+                    let target = &mut data[target_index..target_index + 4];
+
+                    let alpha = source[3] as f32 / 255.0 * layer.opacity.clamp(0.0, 1.0);
+                    let target_alpha = target[3];
+                    let result_alpha = alpha + target_alpha * (1.0 - alpha);
+
+                    if result_alpha <= 0.0 {
+                        continue;
+                    }
+
+                    for (i, channel) in channels.into_iter().enumerate() {
+                        let color = source[channel] as f32 / 255.0;
+                        // the blend mode only applies where there is something below the layer
+                        let blended = color * (1.0 - target_alpha) + layer.blend_mode.blend(target[i], color) * target_alpha;
+                        target[i] = (blended * alpha + target[i] * target_alpha * (1.0 - alpha)) / result_alpha;
+                    }
+
+                    target[3] = result_alpha;
+                }
+            }
+        }
+
+        let data = data
+            .into_iter()
+            .map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8)
+            .collect();
+
+        self.options.create_image((self.width, self.height), data, TextureFormat::Rgba8UnormSrgb)
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::layered_image::{BlendMode, Layer, LayeredImage};
+
+    #[test]
+    fn flatten_works() {
+        // arrange
+        let mut layered = LayeredImage::new((3, 1));
+        layered.add_layer(Layer::new("background", create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 3]), (0, 0))).unwrap();
+        layered.add_layer(Layer::new("blue", create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 2]), (2, 0))).unwrap();
+        layered.add_layer(
+            Layer::new("white", create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 2]), (-1, 0))
+                .with_blend_mode(BlendMode::Multiply)
+        ).unwrap();
+
+        // act
+        let flattened = layered.flatten();
+
+        // assert
+        let expected = create_image(
+            (3, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::RED, Color::RED, Color::BLUE],
+        );
+
+        assert_eq!(expected.data, flattened.data);
+    }
+
+    #[test]
+    fn flatten_with_opacity_works() {
+        // arrange
+        let mut layered = LayeredImage::new((1, 1));
+        layered.add_layer(Layer::new("black", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK]), (0, 0))).unwrap();
+        layered.add_layer(Layer::new("white", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE]), (0, 0)).with_opacity(0.5)).unwrap();
+
+        // act
+        let flattened = layered.flatten();
+
+        // assert
+        assert_eq!(vec![128, 128, 128, 255], flattened.data);
+    }
+
+    /// A semi-transparent layer on an empty canvas must keep its color and only be transparent.
+    #[test]
+    fn flatten_semi_transparent_layer_onto_empty_canvas_works() {
+        // arrange
+        let mut layered = LayeredImage::new((1, 1));
+        layered.add_layer(Layer::new("red", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]), (0, 0)).with_opacity(0.5)).unwrap();
+
+        // act
+        let flattened = layered.flatten();
+
+        // assert
+        assert_eq!(vec![255, 0, 0, 128], flattened.data);
+    }
+
+    #[test]
+    fn move_and_remove_layer_work() {
+        // arrange
+        let mut layered = LayeredImage::new((1, 1));
+        layered.add_layer(Layer::new("red", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]), (0, 0))).unwrap();
+        layered.add_layer(Layer::new("green", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]), (0, 0))).unwrap();
+        layered.add_layer(Layer::new("blue", create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]), (0, 0))).unwrap();
+
+        // act
+        layered.move_layer("red", 10).unwrap();
+        let removed = layered.remove_layer("green");
+
+        // assert
+        assert!(removed.is_some());
+        assert_eq!(vec!["blue", "red"], layered.layers().iter().map(|l| l.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(Color::RED.as_rgba_u8().to_vec(), layered.flatten().data);
+    }
+
+    #[test]
+    fn add_layer_with_existing_name_fails() {
+        // arrange
+        let mut layered = LayeredImage::new((1, 1));
+        let image = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        layered.add_layer(Layer::new("layer", image.clone(), (0, 0))).unwrap();
+
+        // act
+        let result = layered.add_layer(Layer::new("layer", image, (0, 0)));
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::DuplicateName { kind: "layer", name }) if name == "layer"));
+    }
+
+    #[test]
+    fn flatten_with_bgra_layer_works() {
+        // arrange
+        let mut layered = LayeredImage::new((1, 1));
+        let mut image = create_image((1, 1), TextureFormat::Bgra8UnormSrgb, [Color::RED]);
+        image.data.swap(0, 2);
+        layered.add_layer(Layer::new("layer", image, (0, 0))).unwrap();
+
+        // act
+        let flattened = layered.flatten();
+
+        // assert
+        assert_eq!(Color::RED.as_rgba_u8().to_vec(), flattened.data);
+    }
+
+    #[test]
+    fn add_layer_with_unsupported_format_fails() {
+        // arrange
+        let mut layered = LayeredImage::new((2, 2));
+        let image = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let result = layered.add_layer(Layer::new("layer", image, (0, 0)));
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+}
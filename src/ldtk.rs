@@ -0,0 +1,401 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy_app::prelude::*;
+use bevy_asset::{Asset, AssetApp, AssetLoader, AsyncReadExt, LoadContext};
+use bevy_asset::io::Reader;
+use bevy_asset::prelude::*;
+use bevy_reflect::TypePath;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_utils::BoxedFuture;
+use pad::{p, Position};
+use serde::Deserialize;
+
+use crate::blit::blit;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+/// Registers the [LdtkLoader], so .ldtk files can be loaded as [LdtkProject]s.
+pub struct LdtkPlugin;
+
+impl Plugin for LdtkPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<LdtkProject>()
+            .register_asset_loader(LdtkLoader);
+    }
+}
+
+/// Loads LDtk projects and the images of their tilesets.
+pub struct LdtkLoader;
+
+impl AssetLoader for LdtkLoader {
+    type Asset = LdtkProject;
+    type Settings = ();
+    type Error = TextureUtilsError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = vec![];
+            reader
+                .read_to_end(&mut bytes)
+                .await?;
+
+            // the tileset paths are relative to the project file
+            let directory = load_context.path().parent().map(Path::to_path_buf).unwrap_or_default();
+            LdtkProject::from_bytes(&bytes, |path| load_context.load(directory.join(path)))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtk"]
+    }
+}
+
+/// The levels of an LDtk project together with the images of its tilesets.
+/// Only levels stored in the project file itself are supported.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct LdtkProject {
+    pub levels: Vec<LdtkLevel>,
+    /// The image of every tileset by its uid
+    pub tilesets: HashMap<i64, Handle<Image>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LdtkLevel {
+    pub identifier: String,
+    /// The width of the level in pixels
+    pub width: usize,
+    /// The height of the level in pixels
+    pub height: usize,
+    /// The layers with tiles from bottom to top
+    pub layers: Vec<LdtkLayer>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LdtkLayer {
+    pub identifier: String,
+    /// The width and height of a tile in pixels
+    pub grid_size: usize,
+    /// The width of the layer in tiles
+    pub columns: usize,
+    /// The height of the layer in tiles
+    pub rows: usize,
+    /// The uid of the tileset of the tiles
+    pub tileset: i64,
+    pub tiles: Vec<LdtkTile>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LdtkTile {
+    /// The top left pixel of the tile in the layer
+    pub position: (usize, usize),
+    /// The top left pixel of the tile in the tileset
+    pub source: (usize, usize),
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+/// The stitched textures of the layers of a level.
+#[derive(Clone, Debug)]
+pub struct BakedLdtkLevel {
+    /// The width of the level in pixels
+    pub width: usize,
+    /// The height of the level in pixels
+    pub height: usize,
+    /// The identifier and texture of every layer with tiles, from bottom to top
+    pub layers: Vec<(String, Handle<Image>)>,
+}
+
+impl LdtkProject {
+    /// Parse the JSON of an LDtk project. The given function provides the image handle for the
+    /// relative path of every tileset.
+    pub fn from_bytes(bytes: &[u8], mut tileset_image: impl FnMut(&str) -> Handle<Image>) -> Result<Self, TextureUtilsError> {
+        let json = serde_json::from_slice::<LdtkJson>(bytes)
+            .map_err(|e| TextureUtilsError::Decoding(format!("Could not parse the LDtk project: {e}")))?;
+
+        let tilesets = json.defs.tilesets
+            .iter()
+            .filter_map(|tileset| Some((tileset.uid, tileset_image(tileset.rel_path.as_ref()?))))
+            .collect();
+
+        let levels = json.levels
+            .into_iter()
+            .map(|level| Ok(LdtkLevel {
+                layers: level.layer_instances
+                    .ok_or(TextureUtilsError::InvalidParameter(format!("The level '{}' is stored in a separate file, which is not supported.", level.identifier)))?
+                    .into_iter()
+                    // LDtk lists the layers from top to bottom
+                    .rev()
+                    .filter_map(|layer| Some(LdtkLayer {
+                        tileset: layer.tileset_def_uid?,
+                        identifier: layer.identifier,
+                        grid_size: layer.grid_size,
+                        columns: layer.columns,
+                        rows: layer.rows,
+                        tiles: layer.grid_tiles
+                            .into_iter()
+                            .chain(layer.auto_layer_tiles)
+                            .map(|tile| LdtkTile {
+                                position: (tile.px[0], tile.px[1]),
+                                source: (tile.src[0], tile.src[1]),
+                                flip_x: tile.f & 1 != 0,
+                                flip_y: tile.f & 2 != 0,
+                            })
+                            .collect(),
+                    }))
+                    .filter(|layer| !layer.tiles.is_empty())
+                    .collect(),
+                identifier: level.identifier,
+                width: level.px_wid,
+                height: level.px_hei,
+            }))
+            .collect::<Result<_, TextureUtilsError>>()?;
+
+        Ok(Self { levels, tilesets })
+    }
+
+    pub fn level(&self, identifier: &str) -> Option<&LdtkLevel> {
+        self.levels.iter().find(|level| level.identifier == identifier)
+    }
+
+    /// Stitch the tiles of every layer of the level with the given identifier to a texture.
+    /// The tileset images must be loaded and have the format Rgba8UnormSrgb. If multiple tiles
+    /// are at the same position of a layer, the last one is used.
+    pub fn bake_level(
+        &self,
+        identifier: &str,
+        images: &mut Assets<Image>,
+        options: ImageOptions,
+    ) -> Result<BakedLdtkLevel, TextureUtilsError> {
+        let level = self.level(identifier).ok_or(TextureUtilsError::NotFound { kind: "level", name: identifier.to_string() })?;
+
+        let layers = level.layers
+            .iter()
+            .map(|layer| Ok((layer.identifier.clone(), self.bake_layer(layer, images, options.clone())?)))
+            .collect::<Result<Vec<_>, TextureUtilsError>>()?;
+
+        Ok(BakedLdtkLevel {
+            width: level.width,
+            height: level.height,
+            layers: layers
+                .into_iter()
+                .map(|(identifier, texture)| (identifier, images.add(texture)))
+                .collect(),
+        })
+    }
+
+    fn bake_layer(&self, layer: &LdtkLayer, images: &Assets<Image>, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+        let handle = self.tilesets
+            .get(&layer.tileset)
+            .ok_or(TextureUtilsError::NotFound { kind: "tileset", name: layer.tileset.to_string() })?;
+        let tileset = images
+            .get(handle)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })?;
+
+        let size = layer.grid_size;
+        let mut tile_images = HashMap::new();
+
+        for tile in &layer.tiles {
+            if let Entry::Vacant(entry) = tile_images.entry((tile.source, tile.flip_x, tile.flip_y)) {
+                entry.insert(extract_tile(tileset, tile, size)?);
+            }
+        }
+
+        // LDtk positions start at the top left, but tile map positions at the bottom left
+        let mut positions_and_images = layer.tiles
+            .iter()
+            .map(|tile| (
+                p!(tile.position.0 / size, layer.rows - 1 - tile.position.1 / size),
+                &tile_images[&(tile.source, tile.flip_x, tile.flip_y)]
+            ))
+            .collect::<HashMap<Position, &Image>>();
+
+        // place empty tiles in the corners, so the texture covers the whole layer
+        let empty = ImageOptions::default().create_image((size, size), vec![0; size * size * 4], TextureFormat::Rgba8UnormSrgb);
+        positions_and_images.entry(p!(0, 0)).or_insert(&empty);
+        positions_and_images.entry(p!(layer.columns - 1, layer.rows - 1)).or_insert(&empty);
+
+        TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, size, size)
+            .with_options(options)
+            .create_tile_map_image_from_images(positions_and_images)
+    }
+}
+
+/// Copy the given tile out of the tileset, flipped if required.
+fn extract_tile(tileset: &Image, tile: &LdtkTile, size: usize) -> Result<Image, TextureUtilsError> {
+    let mut image = ImageOptions::default().create_image((size, size), vec![0; size * size * 4], tileset.texture_descriptor.format);
+    blit(tileset, PixelRect::new(tile.source.0, tile.source.1, size, size), &mut image, (0, 0))?;
+
+    if tile.flip_y {
+        let rows = image.data.chunks_exact(size * 4).rev().flatten().copied().collect();
+        image.data = rows;
+    }
+
+    if tile.flip_x {
+        for row in image.data.chunks_exact_mut(size * 4) {
+            let mut pixels = row.chunks_exact(4).rev().flatten().copied().collect::<Vec<_>>();
+            row.swap_with_slice(&mut pixels);
+        }
+    }
+
+    Ok(image)
+}
+
+#[derive(Deserialize)]
+struct LdtkJson {
+    defs: LdtkJsonDefs,
+    levels: Vec<LdtkJsonLevel>,
+}
+
+#[derive(Deserialize)]
+struct LdtkJsonDefs {
+    tilesets: Vec<LdtkJsonTileset>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkJsonTileset {
+    uid: i64,
+    rel_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkJsonLevel {
+    identifier: String,
+    px_wid: usize,
+    px_hei: usize,
+    layer_instances: Option<Vec<LdtkJsonLayer>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LdtkJsonLayer {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__gridSize")]
+    grid_size: usize,
+    #[serde(rename = "__cWid")]
+    columns: usize,
+    #[serde(rename = "__cHei")]
+    rows: usize,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_def_uid: Option<i64>,
+    grid_tiles: Vec<LdtkJsonTile>,
+    auto_layer_tiles: Vec<LdtkJsonTile>,
+}
+
+#[derive(Deserialize)]
+struct LdtkJsonTile {
+    px: [usize; 2],
+    src: [usize; 2],
+    f: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::ldtk::LdtkProject;
+
+    const PROJECT: &str = r#"{
+        "defs": { "tilesets": [{ "uid": 7, "relPath": "tiles.png" }] },
+        "levels": [{
+            "identifier": "Level_0",
+            "pxWid": 3,
+            "pxHei": 2,
+            "layerInstances": [
+                {
+                    "__identifier": "Decoration",
+                    "__gridSize": 1,
+                    "__cWid": 3,
+                    "__cHei": 2,
+                    "__tilesetDefUid": 7,
+                    "gridTiles": [{ "px": [1, 1], "src": [1, 0], "f": 0 }],
+                    "autoLayerTiles": []
+                },
+                {
+                    "__identifier": "Ground",
+                    "__gridSize": 1,
+                    "__cWid": 3,
+                    "__cHei": 2,
+                    "__tilesetDefUid": 7,
+                    "gridTiles": [],
+                    "autoLayerTiles": [{ "px": [0, 0], "src": [0, 0], "f": 0 }, { "px": [2, 0], "src": [0, 0], "f": 0 }]
+                },
+                {
+                    "__identifier": "Entities",
+                    "__gridSize": 1,
+                    "__cWid": 3,
+                    "__cHei": 2,
+                    "__tilesetDefUid": null,
+                    "gridTiles": [],
+                    "autoLayerTiles": []
+                }
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn bake_level_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tileset = images.add(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN, Color::RED]));
+        let project = LdtkProject::from_bytes(PROJECT.as_bytes(), |_| tileset.clone()).unwrap();
+
+        // act
+        let level = project.bake_level("Level_0", &mut images, ImageOptions::default()).unwrap();
+
+        // assert
+        let expected_ground = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::GREEN, Color::NONE, Color::GREEN,
+                Color::NONE, Color::NONE, Color::NONE,
+            ],
+        );
+        let expected_decoration = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::NONE, Color::NONE, Color::NONE,
+                Color::NONE, Color::RED, Color::NONE,
+            ],
+        );
+
+        assert_eq!((3, 2), (level.width, level.height));
+        assert_eq!(vec!["Ground", "Decoration"], level.layers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>());
+        assert_eq!(expected_ground.data, images.get(&level.layers[0].1).unwrap().data);
+        assert_eq!(expected_decoration.data, images.get(&level.layers[1].1).unwrap().data);
+    }
+
+    #[test]
+    fn bake_unknown_level_fails() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let project = LdtkProject::from_bytes(PROJECT.as_bytes(), |_| Handle::default()).unwrap();
+
+        // act
+        let result = project.bake_level("Level_1", &mut images, ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::NotFound { kind: "level", name }) if name == "Level_1"));
+    }
+}
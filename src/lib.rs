@@ -1,6 +1,77 @@
 pub mod tile_map_texture;
 pub mod texture_modification;
 pub mod texture_mashup;
+pub mod animation_export;
+pub mod channel_packing;
+pub mod normal_maps;
+pub mod erosion;
+pub mod splat_map;
+pub mod light_baking;
+pub mod tile_transitions;
+pub mod image_options;
+pub mod double_buffer;
+pub mod plugin;
+pub mod work_queue;
+pub mod pending_tile_maps;
+pub mod tile_map_build_task;
+pub mod pixel_rect;
+pub mod edit_history;
+pub mod selection;
+pub mod layered_image;
+pub mod sprite_atlas;
+pub mod tile_registry;
+pub mod planet;
+pub mod color;
+pub mod image_comparison;
+pub mod error;
+pub mod blit;
+pub mod atlas_packer;
+pub mod image_pixel;
+pub mod export;
+pub mod resize;
+pub mod format_conversion;
+pub mod nine_slice;
+pub mod bitmap_font;
+pub mod draw;
+pub mod gradient;
+pub mod outline;
+pub mod trim;
+pub mod transform;
+pub mod upscale;
+pub mod tileable;
+pub mod placeholder;
+pub mod fog_of_war;
+pub mod minimap;
+pub mod adjustments;
+pub mod filters;
+pub mod dithering;
+pub mod palette;
+pub mod morphology;
+pub mod collision_mask;
+pub mod contours;
+pub mod sdf;
+pub mod slicing;
+pub mod autotile;
+pub mod wang;
+pub mod hex_tile_map;
+pub mod iso_tile_map;
+pub mod lut;
+#[cfg(feature = "aseprite")]
+pub mod aseprite;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "gpu")]
+pub mod dirty_regions;
+#[cfg(feature = "recipe")]
+pub mod tile_map_recipe;
+#[cfg(feature = "ldtk")]
+pub mod ldtk;
+#[cfg(feature = "noise")]
+pub mod noise;
 
-#[cfg(test)]
-mod test_utils;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod builders;
+
+mod tile_map_layout;
+mod random;
+mod stable_hash;
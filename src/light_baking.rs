@@ -0,0 +1,341 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy_render::prelude::*;
+use pad::{p, Position};
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::tile_map_layout::TileMapLayout;
+
+/// Configures the shadows baked by [bake_tile_shadows].
+#[derive(Copy, Clone, Debug)]
+pub struct ShadowParams {
+    /// The direction the shadows are cast to. Like tile positions, x points
+    /// right and y points up, so (1.0, -1.0) casts shadows to the bottom right.
+    pub direction: (f32, f32),
+    /// The length of a shadow in pixels for each unit of tile height.
+    pub length: f32,
+    /// How much (0.0 to 1.0) a fully shadowed pixel gets darkened.
+    pub strength: f32,
+    /// The width in pixels of the soft shadow edges. 0.0 creates hard shadows.
+    pub softness: f32,
+}
+
+impl Default for ShadowParams {
+    fn default() -> Self {
+        Self {
+            direction: (1.0, -1.0),
+            length: 8.0,
+            strength: 0.4,
+            softness: 2.0,
+        }
+    }
+}
+
+/// Bake simple projected shadows of tall tiles onto the ground of an assembled tile map.
+/// The tile heights tell how tall the tile at a position is. Tiles without a height or with
+/// a height of 0 or less are ground tiles, and only those receive shadows.
+/// The origin is the bottom left position of the tile map and the tile size is the width and height
+/// of a single tile in pixels, like they were used to create the tile map. The tile map must have an 8-bit RGBA or BGRA format.
+pub fn bake_tile_shadows(
+    tile_map: &mut Image,
+    origin: Position,
+    tile_size: (usize, usize),
+    tile_heights: impl IntoIterator<Item=(Position, f32)>,
+    params: ShadowParams,
+) -> Result<(), TextureUtilsError> {
+    is_srgb_rgba8(tile_map.texture_descriptor.format)?;
+
+    let layout = TileMapLayout::new(tile_map, origin, tile_size)?;
+    let heights = tile_heights
+        .into_iter()
+        .filter(|(_, height)| *height > 0.0)
+        .collect::<HashMap<_, _>>();
+
+    let direction_length = (params.direction.0 * params.direction.0 + params.direction.1 * params.direction.1).sqrt();
+
+    if direction_length == 0.0 {
+        return Err(TextureUtilsError::OutOfRange { name: "length of the shadow direction", value: 0.0, expected: "positive" });
+    }
+
+    // the direction towards the light in pixel space, where y points down
+    let to_light = (-params.direction.0 / direction_length, params.direction.1 / direction_length);
+    let perpendicular = (-to_light.1, to_light.0);
+    let max_distance = heights.values().fold(0.0f32, |max, h| max.max(*h)) * params.length;
+
+    let ray_offsets = match params.softness > 0.0 {
+        true => (0..5).map(|i| (i as f32 / 4.0 - 0.5) * params.softness).collect::<Vec<_>>(),
+        false => vec![0.0]
+    };
+
+    let height_at = |x: f32, y: f32| layout
+        .position_at(x, y)
+        .and_then(|pos| heights.get(&pos))
+        .copied()
+        .unwrap_or(0.0);
+
+    for py in 0..layout.height {
+        for px in 0..layout.width {
+            let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+
+            if height_at(x, y) > 0.0 {
+                continue;
+            }
+
+            let shadow = ray_offsets
+                .iter()
+                .map(|offset| {
+                    // rays starting outside of the map would never hit a tile, so they are kept inside
+                    let start = (
+                        (x + perpendicular.0 * offset).clamp(0.0, layout.width as f32 - 0.5),
+                        (y + perpendicular.1 * offset).clamp(0.0, layout.height as f32 - 0.5)
+                    );
+
+                    (1..=max_distance.ceil() as usize)
+                        .map(|step| {
+                            let distance = step as f32;
+                            let height = height_at(start.0 + to_light.0 * distance, start.1 + to_light.1 * distance);
+                            let shadow_length = height * params.length;
+
+                            match (distance <= shadow_length, params.softness > 0.0) {
+                                (false, _) => 0.0,
+                                (true, false) => 1.0,
+                                (true, true) => ((shadow_length - distance) / params.softness + 0.5).clamp(0.0, 1.0)
+                            }
+                        })
+                        .fold(0.0f32, f32::max)
+                })
+                .sum::<f32>() / ray_offsets.len() as f32;
+
+            layout.darken(&mut tile_map.data, px, py, shadow * params.strength);
+        }
+    }
+
+    Ok(())
+}
+
+/// Configures the ambient occlusion baked by [bake_ambient_occlusion].
+#[derive(Copy, Clone, Debug)]
+pub struct AmbientOcclusionParams {
+    /// The distance in pixels from a wall in which floor pixels get darkened.
+    pub radius: f32,
+    /// How much (0.0 to 1.0) a floor pixel directly next to a wall gets darkened.
+    pub strength: f32,
+}
+
+impl Default for AmbientOcclusionParams {
+    fn default() -> Self {
+        Self {
+            radius: 4.0,
+            strength: 0.5,
+        }
+    }
+}
+
+/// Bake cheap ambient occlusion into an assembled tile map by darkening the pixels of floor
+/// tiles that are near wall tiles. The wall classifier tells if a tile is a wall. Every
+/// other tile, including missing ones, is a floor tile. The darkening fades out linearly
+/// with the distance to the closest wall.
+/// The origin is the bottom left position of the tile map and the tile size is the width and height
+/// of a single tile in pixels, like they were used to create the tile map. The tile map must have an 8-bit RGBA or BGRA format.
+pub fn bake_ambient_occlusion<T>(
+    tile_map: &mut Image,
+    origin: Position,
+    tile_size: (usize, usize),
+    positions_and_tiles: impl IntoIterator<Item=(Position, T)>,
+    wall_classifier: impl Fn(&T) -> bool,
+    params: AmbientOcclusionParams,
+) -> Result<(), TextureUtilsError> {
+    is_srgb_rgba8(tile_map.texture_descriptor.format)?;
+
+    let layout = TileMapLayout::new(tile_map, origin, tile_size)?;
+
+    if params.radius <= 0.0 {
+        return Ok(());
+    }
+
+    let walls = positions_and_tiles
+        .into_iter()
+        .filter(|(_, tile)| wall_classifier(tile))
+        .map(|(pos, _)| pos)
+        .collect::<HashSet<_>>();
+
+    let range_x = (params.radius / layout.tile_width as f32).ceil() as isize;
+    let range_y = (params.radius / layout.tile_height as f32).ceil() as isize;
+
+    for py in 0..layout.height {
+        for px in 0..layout.width {
+            let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+            let position = match layout.position_at(x, y) {
+                Some(pos) if !walls.contains(&pos) => pos,
+                _ => continue
+            };
+
+            let distance = (-range_y..=range_y)
+                .flat_map(|dy| (-range_x..=range_x).map(move |dx| p!(position.x + dx, position.y + dy)))
+                .filter(|pos| walls.contains(pos))
+                .map(|pos| layout.distance_to_tile(x, y, pos))
+                .fold(f32::MAX, f32::min);
+
+            if distance < params.radius {
+                layout.darken(&mut tile_map.data, px, py, (1.0 - distance / params.radius) * params.strength);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::light_baking::{AmbientOcclusionParams, bake_ambient_occlusion, bake_tile_shadows, ShadowParams};
+
+    #[test]
+    fn bake_tile_shadows_works() {
+        // arrange
+        let mut tile_map = create_image((8, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 8]);
+
+        // act
+        let result = bake_tile_shadows(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 1),
+            [(p!(0, 0), 1.0)],
+            ShadowParams { direction: (1.0, 0.0), length: 2.0, strength: 0.5, softness: 0.0 },
+        );
+
+        // assert
+        assert!(result.is_ok());
+
+        let expected = create_image(
+            (8, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::WHITE, Color::WHITE,
+                Color::rgb_u8(128, 128, 128), Color::rgb_u8(128, 128, 128),
+                Color::WHITE, Color::WHITE,
+                Color::WHITE, Color::WHITE,
+            ],
+        );
+
+        assert_eq!(expected.data, tile_map.data, "Only the two pixels next to the tall tile should be shadowed.");
+    }
+
+    /// Soft shadows fade out at the end of the shadow instead of stopping abruptly.
+    #[test]
+    fn bake_tile_shadows_with_softness_works() {
+        // arrange
+        let mut tile_map = create_image((16, 4), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 64]);
+
+        // act
+        bake_tile_shadows(
+            &mut tile_map,
+            p!(0, 0),
+            (4, 4),
+            [(p!(0, 0), 1.0)],
+            ShadowParams { direction: (1.0, 0.0), length: 6.0, strength: 1.0, softness: 4.0 },
+        ).unwrap();
+
+        // assert
+        let red = |x: usize| tile_map.data[(32 + x) * 4];
+
+        assert_eq!(0, red(4));
+        assert!(red(4) < red(8), "The shadow should get lighter with distance.");
+        assert!(red(8) < red(9), "The shadow should get lighter with distance.");
+        assert_eq!(255, red(10));
+    }
+
+    #[test]
+    fn bake_tile_shadows_with_wrong_tile_size_fails() {
+        // arrange
+        let mut tile_map = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 3]);
+
+        // act
+        let result = bake_tile_shadows(&mut tile_map, p!(0, 0), (2, 1), [], ShadowParams::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::InvalidCellSize { size: (3, 1), cell_size: (2, 1) })));
+    }
+
+    #[test]
+    fn bake_tile_shadows_with_unsupported_format_fails() {
+        // arrange
+        let mut tile_map = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let result = bake_tile_shadows(&mut tile_map, p!(0, 0), (1, 1), [], ShadowParams::default());
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+
+    #[test]
+    fn bake_ambient_occlusion_works() {
+        // arrange
+        let mut tile_map = create_image((6, 2), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 12]);
+
+        // act
+        let result = bake_ambient_occlusion(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 2),
+            [(p!(0, 0), true), (p!(1, 0), false), (p!(2, 0), false)],
+            |is_wall| *is_wall,
+            AmbientOcclusionParams { radius: 2.0, strength: 1.0 },
+        );
+
+        // assert
+        assert!(result.is_ok());
+        let red = |x: usize| tile_map.data[x * 4];
+
+        assert_eq!(vec![255, 255, 64, 191, 255, 255], (0..6).map(red).collect::<Vec<_>>(), "Only the floor next to the wall should be darkened.");
+    }
+
+    /// Wall positions outside of the tile map still darken the floor next to them.
+    #[test]
+    fn bake_ambient_occlusion_with_walls_outside_of_map_works() {
+        // arrange
+        let mut tile_map = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 2]);
+
+        // act
+        bake_ambient_occlusion(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 1),
+            [(p!(-1, 0), true)],
+            |is_wall| *is_wall,
+            AmbientOcclusionParams { radius: 1.0, strength: 1.0 },
+        ).unwrap();
+
+        // assert
+        assert_eq!(128, tile_map.data[0]);
+        assert_eq!(255, tile_map.data[4]);
+    }
+
+    #[test]
+    fn bake_ambient_occlusion_with_unsupported_format_fails() {
+        // arrange
+        let mut tile_map = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let result = bake_ambient_occlusion(
+            &mut tile_map,
+            p!(0, 0),
+            (1, 1),
+            [(p!(0, 0), true)],
+            |is_wall| *is_wall,
+            AmbientOcclusionParams::default(),
+        );
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+}
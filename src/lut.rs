@@ -0,0 +1,304 @@
+use std::fs;
+use std::path::Path;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::export::load_image_png;
+use crate::image_options::ImageOptions;
+use crate::texture_modification::modify_texture_colors;
+
+/// A 3D lookup table which maps every color to a new one, usually created in an image editor to color grade
+/// a game. Colors between the entries of the table are interpolated trilinearly.
+/// Like in image editors, the table works with the perceived (sRGB encoded) colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut3d {
+    /// The amount of entries along every axis
+    size: usize,
+    /// The output colors, where red changes fastest and blue slowest
+    entries: Vec<[f32; 3]>,
+    /// The input colors which are mapped to the first and last entries along every axis
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+impl Lut3d {
+    /// Create a table with the given size from its entries, where red changes fastest and blue slowest,
+    /// like in .cube files. At least two entries along every axis are required.
+    pub fn new(size: usize, entries: Vec<[f32; 3]>) -> Result<Self, TextureUtilsError> {
+        if size < 2 {
+            return Err(TextureUtilsError::OutOfRange { name: "LUT size", value: size as f64, expected: "at least 2" });
+        }
+
+        if entries.len() != size * size * size {
+            return Err(TextureUtilsError::CountMismatch { kind: "LUT entries", expected: size * size * size, actual: entries.len() });
+        }
+
+        Ok(Self { size, entries, domain_min: [0.0; 3], domain_max: [1.0; 3] })
+    }
+
+    /// Create a table which maps every color to itself, as starting point to create a new one.
+    pub fn identity(size: usize) -> Result<Self, TextureUtilsError> {
+        let step = 1.0 / size.saturating_sub(1).max(1) as f32;
+        let entries = (0..size * size * size)
+            .map(|i| [(i % size) as f32 * step, (i / size % size) as f32 * step, (i / (size * size)) as f32 * step])
+            .collect();
+
+        Self::new(size, entries)
+    }
+
+    /// Parse the content of a .cube file with a 3D LUT, like the ones exported by most image and video editors.
+    pub fn from_cube(content: &str) -> Result<Self, TextureUtilsError> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut entries = Vec::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with("TITLE") {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().expect("The line is not empty");
+
+            match keyword {
+                "LUT_3D_SIZE" => size = Some(
+                    words
+                        .next()
+                        .and_then(|size| size.parse::<usize>().ok())
+                        .ok_or(cube_error(number, line))?
+                ),
+                "DOMAIN_MIN" => domain_min = parse_triple(words).ok_or(cube_error(number, line))?,
+                "DOMAIN_MAX" => domain_max = parse_triple(words).ok_or(cube_error(number, line))?,
+                "LUT_1D_SIZE" => return Err(TextureUtilsError::Decoding("1D LUTs are not supported.".to_string())),
+                _ => entries.push(parse_triple(line.split_whitespace()).ok_or(cube_error(number, line))?)
+            }
+        }
+
+        let size = size.ok_or(TextureUtilsError::Decoding("The .cube file does not define LUT_3D_SIZE.".to_string()))?;
+
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(TextureUtilsError::Decoding(format!("The domain from {domain_min:?} to {domain_max:?} is empty.")));
+        }
+
+        let lut = Self::new(size, entries)?;
+
+        Ok(Self { domain_min, domain_max, ..lut })
+    }
+
+    /// Read the .cube file at the given path, see [Lut3d::from_cube].
+    pub fn load_cube(path: impl AsRef<Path>) -> Result<Self, TextureUtilsError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        Self::from_cube(&content)
+    }
+
+    /// Read the table from a strip image, which consists of one square slice per blue value, placed from left to right.
+    /// In every slice, red increases to the right and green downwards. So a table with the size 16 is stored in a
+    /// 256x16 image, which is the common layout for LUTs used by game engines. The image must have one of the
+    /// 8-bit RGBA or BGRA formats.
+    pub fn from_strip_image(image: &Image) -> Result<Self, TextureUtilsError> {
+        let format = image.texture_descriptor.format;
+        is_srgb_rgba8(format)?;
+
+        let (width, size) = (image.width() as usize, image.height() as usize);
+
+        if width != size * size {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "A LUT strip with a height of {size} must be {} pixels wide, but it is {width}.",
+                size * size
+            )));
+        }
+
+        let (red, blue) = match format {
+            TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => (2, 0),
+            _ => (0, 2)
+        };
+
+        // the entries are the stored values, no matter how the format encodes them
+        let entries = (0..size * size * size)
+            .map(|i| {
+                let (r, g, b) = (i % size, i / size % size, i / (size * size));
+                let index = (g * width + b * size + r) * 4;
+                let pixel = &image.data[index..index + 3];
+
+                [pixel[red] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[blue] as f32 / 255.0]
+            })
+            .collect();
+
+        Self::new(size, entries)
+    }
+
+    /// Read the strip PNG at the given path, see [Lut3d::from_strip_image].
+    pub fn load_strip_png(path: impl AsRef<Path>) -> Result<Self, TextureUtilsError> {
+        Self::from_strip_image(&load_image_png(path, TextureFormat::Rgba8UnormSrgb, ImageOptions::default())?)
+    }
+
+    /// Create a strip image with the format Rgba8UnormSrgb from the table, see [Lut3d::from_strip_image].
+    pub fn to_strip_image(&self, options: ImageOptions) -> Image {
+        let size = self.size;
+        let width = size * size;
+        let mut data = vec![255; width * size * 4];
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let (r, g, b) = (i % size, i / size % size, i / (size * size));
+            let index = (g * width + b * size + r) * 4;
+
+            for (channel, value) in entry.iter().enumerate() {
+                data[index + channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+
+        options.create_image((width, size), data, TextureFormat::Rgba8UnormSrgb)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Get the color the given color is mapped to.
+    pub fn map(&self, color: Color) -> Color {
+        let [r, g, b, a] = color.as_rgba_f32();
+        let max = (self.size - 1) as f32;
+
+        // the position of the color in the table, the indices of the entries before it and its distance to them
+        let position = [0, 1, 2].map(|i| {
+            let value = [r, g, b][i];
+            ((value - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i])).clamp(0.0, 1.0) * max
+        });
+        let lower = position.map(|p| (p.floor() as usize).min(self.size - 2));
+        let fraction = [0, 1, 2].map(|i| position[i] - lower[i] as f32);
+
+        let mut result = [0.0; 3];
+
+        for corner in 0..8 {
+            let offset = [corner & 1, corner >> 1 & 1, corner >> 2 & 1];
+            let weight = (0..3)
+                .map(|i| match offset[i] {
+                    1 => fraction[i],
+                    _ => 1.0 - fraction[i]
+                })
+                .product::<f32>();
+            let entry = self.entry(lower[0] + offset[0], lower[1] + offset[1], lower[2] + offset[2]);
+
+            for (value, entry_value) in result.iter_mut().zip(entry) {
+                *value += entry_value * weight;
+            }
+        }
+
+        Color::rgba(result[0], result[1], result[2], a)
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.entries[(b * self.size + g) * self.size + r]
+    }
+}
+
+/// Create the strip image of a LUT which maps every color to itself, see [Lut3d::from_strip_image].
+/// Color grade a screenshot of the game and this image together in an image editor, and the graded strip
+/// can be loaded as LUT for the game.
+pub fn identity_lut(size: usize, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+    Ok(Lut3d::identity(size)?.to_strip_image(options))
+}
+
+/// Map the colors of all pixels of the texture with the given LUT, keeping their alpha.
+/// Works with the 8-bit RGBA and BGRA formats.
+pub fn apply_lut(texture: &mut Image, lut: &Lut3d) -> Result<(), TextureUtilsError> {
+    modify_texture_colors(texture, |_, _, color| lut.map(color))
+}
+
+fn parse_triple<'a>(mut words: impl Iterator<Item=&'a str>) -> Option<[f32; 3]> {
+    let mut next = || words.next().and_then(|word| word.parse::<f32>().ok());
+    Some([next()?, next()?, next()?])
+}
+
+fn cube_error(number: usize, line: &str) -> TextureUtilsError {
+    TextureUtilsError::Decoding(format!("Could not parse line {} of the .cube file: '{line}'", number + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::lut::{apply_lut, identity_lut, Lut3d};
+
+    #[test]
+    fn strip_image_round_trip_works() {
+        // act
+        let strip = identity_lut(4, ImageOptions::default()).unwrap();
+        let lut = Lut3d::from_strip_image(&strip).unwrap();
+
+        // assert
+        assert_eq!((16, 4), (strip.width(), strip.height()));
+        // the entry for red 1, green 2 and blue 3 is in the fourth slice
+        assert_eq!([85, 170, 255, 255], strip.data[(2 * 16 + 3 * 4 + 1) * 4..][..4]);
+        assert_eq!(4, lut.size());
+        assert_eq!(
+            Lut3d::identity(4).unwrap().to_strip_image(ImageOptions::default()).data,
+            lut.to_strip_image(ImageOptions::default()).data
+        );
+    }
+
+    #[test]
+    fn apply_lut_works() {
+        // arrange
+        let cube = "
+            # inverts all colors
+            TITLE \"Invert\"
+            LUT_3D_SIZE 2
+
+            1.0 1.0 1.0
+            0.0 1.0 1.0
+            1.0 0.0 1.0
+            0.0 0.0 1.0
+            1.0 1.0 0.0
+            0.0 1.0 0.0
+            1.0 0.0 0.0
+            0.0 0.0 0.0
+        ";
+        let lut = Lut3d::from_cube(cube).unwrap();
+        let mut texture = create_image(
+            (2, 1),
+            TextureFormat::Bgra8UnormSrgb,
+            [Color::RED, Color::rgba_u8(51, 102, 204, 50)],
+        );
+        let mut identity_texture = texture.clone();
+
+        // act
+        apply_lut(&mut texture, &lut).unwrap();
+        apply_lut(&mut identity_texture, &Lut3d::identity(3).unwrap()).unwrap();
+
+        // assert
+        let expected = create_image(
+            (2, 1),
+            TextureFormat::Bgra8UnormSrgb,
+            [Color::CYAN, Color::rgba_u8(204, 153, 51, 50)],
+        );
+
+        assert_eq!(expected.data, texture.data);
+        assert_eq!(create_image((2, 1), TextureFormat::Bgra8UnormSrgb, [Color::RED, Color::rgba_u8(51, 102, 204, 50)]).data, identity_texture.data);
+    }
+
+    #[test]
+    fn from_cube_with_invalid_content_fails() {
+        // act
+        let missing_size = Lut3d::from_cube("0 0 0");
+        let invalid_line = Lut3d::from_cube("LUT_3D_SIZE 2\n0 zero 0");
+        let missing_entries = Lut3d::from_cube("LUT_3D_SIZE 2\n0 0 0");
+
+        // assert
+        assert_eq!("The .cube file does not define LUT_3D_SIZE.", missing_size.unwrap_err().to_string());
+        assert_eq!("Could not parse line 2 of the .cube file: '0 zero 0'", invalid_line.unwrap_err().to_string());
+        assert!(matches!(missing_entries, Err(TextureUtilsError::CountMismatch { kind: "LUT entries", expected: 8, actual: 1 })));
+    }
+}
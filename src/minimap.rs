@@ -0,0 +1,133 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::{Extent3d, TextureDescriptor};
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::color::{average_pixel_bytes, is_srgb_rgba8};
+use crate::error::TextureUtilsError;
+
+/// How the pixels of a minimap are taken from the tiles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MinimapMode {
+    /// The average color of the area of the tile, which works best for natural tiles like grass or water.
+    /// Only works with 8-bit RGBA and BGRA formats.
+    AverageColor,
+    /// The pixel in the center of the area of the tile, which keeps the exact colors of the tiles
+    CenterPixel,
+}
+
+/// Create an overview of the given tile map, where every tile with the given size becomes a square of the given
+/// amount of pixels in each direction, like a single pixel. The tile size must be divisible by this amount, and the
+/// size of the tile map by the tile size.
+/// Besides its size, the minimap keeps all properties of the tile map, like its format and sampler.
+pub fn generate_minimap(
+    tile_map: &Image,
+    (tile_width, tile_height): (usize, usize),
+    pixels_per_tile: usize,
+    mode: MinimapMode,
+) -> Result<Image, TextureUtilsError> {
+    let (width, height) = (tile_map.width() as usize, tile_map.height() as usize);
+
+    if tile_width == 0 || tile_height == 0 {
+        return Err(TextureUtilsError::ZeroSize { size: (tile_width, tile_height) });
+    }
+
+    if !width.is_multiple_of(tile_width) || !height.is_multiple_of(tile_height) {
+        return Err(TextureUtilsError::InvalidCellSize { size: (width, height), cell_size: (tile_width, tile_height) });
+    }
+
+    if pixels_per_tile == 0 || !tile_width.is_multiple_of(pixels_per_tile) || !tile_height.is_multiple_of(pixels_per_tile) {
+        return Err(TextureUtilsError::InvalidCellSize { size: (tile_width, tile_height), cell_size: (pixels_per_tile, pixels_per_tile) });
+    }
+
+    let bytes_per_pixel = tile_map.texture_descriptor.format.pixel_size();
+    let (area_width, area_height) = (tile_width / pixels_per_tile, tile_height / pixels_per_tile);
+    let (new_width, new_height) = (width / area_width, height / area_height);
+    let pixel = |x: usize, y: usize| {
+        let index = (y * width + x) * bytes_per_pixel;
+        &tile_map.data[index..index + bytes_per_pixel]
+    };
+
+    let mut data = Vec::with_capacity(new_width * new_height * bytes_per_pixel);
+
+    match mode {
+        MinimapMode::AverageColor => {
+            let srgb = is_srgb_rgba8(tile_map.texture_descriptor.format)?;
+
+            for y in 0..new_height {
+                for x in 0..new_width {
+                    let area = (0..area_height)
+                        .flat_map(|dy| (0..area_width).map(move |dx| (x * area_width + dx, y * area_height + dy)))
+                        .map(|(x, y)| pixel(x, y));
+
+                    data.extend(average_pixel_bytes(area, srgb));
+                }
+            }
+        }
+        MinimapMode::CenterPixel => for y in 0..new_height {
+            for x in 0..new_width {
+                data.extend_from_slice(pixel(x * area_width + area_width / 2, y * area_height + area_height / 2));
+            }
+        }
+    }
+
+    Ok(Image {
+        data,
+        texture_descriptor: TextureDescriptor {
+            size: Extent3d { width: new_width as u32, height: new_height as u32, depth_or_array_layers: 1 },
+            ..tile_map.texture_descriptor.clone()
+        },
+        sampler: tile_map.sampler.clone(),
+        texture_view_descriptor: tile_map.texture_view_descriptor.clone(),
+        asset_usage: tile_map.asset_usage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::minimap::{generate_minimap, MinimapMode};
+
+    const R: Color = Color::RED;
+    const B: Color = Color::BLUE;
+    const W: Color = Color::WHITE;
+    const K: Color = Color::BLACK;
+
+    /// A tile map with two tiles of 2x2 pixels, a red one with a white center and a black and blue one.
+    fn create_tile_map() -> Image {
+        create_image(
+            (4, 2),
+            TextureFormat::Rgba8Unorm,
+            [
+                R, R, K, B,
+                R, W, B, K,
+            ],
+        )
+    }
+
+    #[test]
+    fn generate_minimap_works() {
+        // act
+        let average = generate_minimap(&create_tile_map(), (2, 2), 1, MinimapMode::AverageColor).unwrap();
+        let center = generate_minimap(&create_tile_map(), (2, 2), 1, MinimapMode::CenterPixel).unwrap();
+        let full_size = generate_minimap(&create_tile_map(), (2, 2), 2, MinimapMode::CenterPixel).unwrap();
+
+        // assert
+        assert_eq!((2, 1), (average.width(), average.height()));
+        assert_eq!(vec![255, 64, 64, 255, 0, 0, 128, 255], average.data);
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8Unorm, [W, K]).data, center.data);
+        assert_eq!(create_tile_map().data, full_size.data);
+    }
+
+    #[test]
+    fn generate_minimap_with_invalid_tile_size_fails() {
+        // act
+        let result = generate_minimap(&create_tile_map(), (3, 2), 1, MinimapMode::CenterPixel);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::InvalidCellSize { size: (4, 2), cell_size: (3, 2) })));
+    }
+}
@@ -0,0 +1,198 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+
+// The operations work on the alpha channel of the 8-bit RGBA and BGRA formats, or on the only channel of R8Unorm masks.
+// The colors of the pixels are not changed, so dilated pixels keep the color they had while they were transparent.
+// A radius of 0 keeps the image unchanged.
+
+/// The area around a pixel which is considered by the morphological operations.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KernelShape {
+    /// All pixels whose horizontal and vertical distance is at most the radius, which keeps sharp corners
+    Square,
+    /// All pixels whose distance is at most the radius, which rounds corners
+    Circle,
+    /// All pixels in the same row or column which are at most the radius away
+    Cross,
+}
+
+impl KernelShape {
+    fn offsets(&self, radius: u32) -> Vec<(isize, isize)> {
+        let radius = radius as isize;
+
+        (-radius..=radius)
+            .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| match self {
+                KernelShape::Square => true,
+                KernelShape::Circle => dx * dx + dy * dy <= radius * radius,
+                KernelShape::Cross => *dx == 0 || *dy == 0
+            })
+            .collect()
+    }
+}
+
+/// Grow the opaque areas of the mask: every pixel gets the highest value around it.
+pub fn dilate(image: &mut Image, radius: u32, shape: KernelShape) -> Result<(), TextureUtilsError> {
+    apply_kernel(image, radius, shape, |values| values.max())
+}
+
+/// Shrink the opaque areas of the mask: every pixel gets the lowest value around it.
+pub fn erode(image: &mut Image, radius: u32, shape: KernelShape) -> Result<(), TextureUtilsError> {
+    apply_kernel(image, radius, shape, |values| values.min())
+}
+
+/// Erode and then dilate the mask, which removes opaque areas smaller than the kernel,
+/// like single stray pixels, while keeping the size of the other areas.
+pub fn open(image: &mut Image, radius: u32, shape: KernelShape) -> Result<(), TextureUtilsError> {
+    erode(image, radius, shape)?;
+    dilate(image, radius, shape)
+}
+
+/// Dilate and then erode the mask, which fills holes and gaps smaller than the kernel,
+/// while keeping the size of the opaque areas.
+pub fn close(image: &mut Image, radius: u32, shape: KernelShape) -> Result<(), TextureUtilsError> {
+    dilate(image, radius, shape)?;
+    erode(image, radius, shape)
+}
+
+/// Replace the mask value of every pixel with the value reduced from the values of the pixels in the kernel around it.
+/// Pixels outside of the image are ignored.
+fn apply_kernel(
+    image: &mut Image,
+    radius: u32,
+    shape: KernelShape,
+    reduce: impl Fn(&mut dyn Iterator<Item=u8>) -> Option<u8>,
+) -> Result<(), TextureUtilsError> {
+    let (pixel_size, channel) = mask_channel(image.texture_descriptor.format)?;
+    let (width, height) = (image.width() as isize, image.height() as isize);
+    let offsets = shape.offsets(radius);
+    let values = image.data.iter().skip(channel).step_by(pixel_size).copied().collect::<Vec<_>>();
+
+    for (i, pixel) in image.data.chunks_exact_mut(pixel_size).enumerate() {
+        let (x, y) = (i as isize % width, i as isize / width);
+        let mut neighbours = offsets
+            .iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|(nx, ny)| *nx >= 0 && *ny >= 0 && *nx < width && *ny < height)
+            .map(|(nx, ny)| values[(ny * width + nx) as usize]);
+
+        pixel[channel] = reduce(&mut neighbours).expect("The kernel contains at least the pixel itself");
+    }
+
+    Ok(())
+}
+
+/// The size of the pixels of the given format and the index of the channel which is used as mask.
+pub(crate) fn mask_channel(format: TextureFormat) -> Result<(usize, usize), TextureUtilsError> {
+    match format {
+        TextureFormat::R8Unorm => Ok((1, 0)),
+        // both the RGBA and BGRA formats store the alpha last
+        format => is_srgb_rgba8(format).map(|_| (4, 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::morphology::{close, dilate, erode, open, KernelShape};
+
+    fn create_mask(values: Vec<u8>) -> Image {
+        ImageOptions::default().create_image((5, 5), values, TextureFormat::R8Unorm)
+    }
+
+    #[test]
+    fn dilate_and_erode_work() {
+        // arrange
+        let mut mask = create_mask(vec![
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 255, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]);
+        let mut square = mask.clone();
+
+        // act
+        dilate(&mut mask, 1, KernelShape::Cross).unwrap();
+        let dilated = mask.data.clone();
+        erode(&mut mask, 1, KernelShape::Cross).unwrap();
+        dilate(&mut square, 1, KernelShape::Square).unwrap();
+
+        // assert
+        assert_eq!(
+            vec![
+                0, 0, 0, 0, 0,
+                0, 0, 255, 0, 0,
+                0, 255, 255, 255, 0,
+                0, 0, 255, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            dilated
+        );
+        assert_eq!(
+            vec![
+                0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+                0, 0, 255, 0, 0,
+                0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0,
+            ],
+            mask.data
+        );
+        assert_eq!(9, square.data.iter().filter(|value| **value == 255).count());
+    }
+
+    #[test]
+    fn open_and_close_work() {
+        // arrange
+        let n = Color::NONE;
+        let w = Color::WHITE;
+        let mut noisy = create_image((4, 3), TextureFormat::Rgba8UnormSrgb, [
+            w, n, n, n,
+            n, n, w, w,
+            n, n, w, w,
+        ]);
+        let mut holey = create_image((3, 3), TextureFormat::Rgba8UnormSrgb, [
+            w, w, w,
+            w, n, w,
+            w, w, w,
+        ]);
+
+        // act
+        open(&mut noisy, 1, KernelShape::Square).unwrap();
+        close(&mut holey, 1, KernelShape::Circle).unwrap();
+
+        // assert
+        let alpha = |image: &Image| image.data.chunks_exact(4).map(|pixel| pixel[3]).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                0, 0, 0, 0,
+                0, 0, 255, 255,
+                0, 0, 255, 255,
+            ],
+            alpha(&noisy)
+        );
+        assert_eq!(vec![255; 9], alpha(&holey));
+    }
+
+    #[test]
+    fn morphology_with_unsupported_format_fails() {
+        // arrange
+        let mut image = ImageOptions::default().create_image((1, 1), vec![0; 2], TextureFormat::Rg8Unorm);
+
+        // act
+        let result = dilate(&mut image, 1, KernelShape::Square);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rg8Unorm })));
+    }
+}
@@ -0,0 +1,214 @@
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// Tells how the edges and the center of a [NineSlice] fill the space between the corners.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SliceMode {
+    /// The slices are stretched with nearest filtering
+    #[default]
+    Stretch,
+    /// The slices are repeated, starting at the corners they are next to
+    Tile,
+}
+
+/// Creates panels of arbitrary size from a source image, like UI windows or buttons. The source is split into
+/// nine slices by the border insets: the corners keep their size, the edges grow along one axis and the
+/// center grows along both.
+#[derive(Clone, Debug)]
+pub struct NineSlice {
+    /// The width of the left border in pixels
+    left: usize,
+    /// The width of the right border in pixels
+    right: usize,
+    /// The height of the top border in pixels
+    top: usize,
+    /// The height of the bottom border in pixels
+    bottom: usize,
+    mode: SliceMode,
+    /// The options for the created panels
+    options: ImageOptions,
+}
+
+impl NineSlice {
+    pub fn new(left: usize, right: usize, top: usize, bottom: usize) -> Self {
+        Self { left, right, top, bottom, mode: SliceMode::default(), options: ImageOptions::default() }
+    }
+
+    /// Create a nine slice where all borders have the same size.
+    pub fn uniform(border: usize) -> Self {
+        Self::new(border, border, border, border)
+    }
+
+    /// Set how the edges and the center fill the panel.
+    pub fn with_mode(mut self, mode: SliceMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the options for the created panels.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Like [NineSlice::create_panel], but gets the source from the images and adds the panel to them.
+    pub fn create_panel_texture(
+        &self,
+        images: &mut Assets<Image>,
+        source: &Handle<Image>,
+        size: (usize, usize),
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let source_image = images
+            .get(source)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: source.id().untyped() })?;
+        let panel = self.create_panel(source_image, size)?;
+
+        Ok(images.add(panel))
+    }
+
+    /// Create a panel with the given size and the format of the source. The panel must be at least as big as the
+    /// borders, and the source must have a center if the panel is bigger than the borders.
+    pub fn create_panel(&self, source: &Image, (width, height): (usize, usize)) -> Result<Image, TextureUtilsError> {
+        let (source_width, source_height) = (source.width() as usize, source.height() as usize);
+        let columns = self.map_axis(width, source_width, self.left, self.right, "width")?;
+        let rows = self.map_axis(height, source_height, self.top, self.bottom, "height")?;
+
+        let format = source.texture_descriptor.format;
+        let bytes_per_pixel = format.pixel_size();
+        let mut data = Vec::with_capacity(width * height * bytes_per_pixel);
+
+        for source_y in rows {
+            for source_x in &columns {
+                let index = (source_y * source_width + source_x) * bytes_per_pixel;
+                data.extend_from_slice(&source.data[index..index + bytes_per_pixel]);
+            }
+        }
+
+        Ok(self.options.create_image((width, height), data, format))
+    }
+
+    /// Get the source pixel of every panel pixel along one axis. The dimension is only used for errors.
+    fn map_axis(
+        &self,
+        length: usize,
+        source_length: usize,
+        start: usize,
+        end: usize,
+        dimension: &str,
+    ) -> Result<Vec<usize>, TextureUtilsError> {
+        if start + end > source_length {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "The borders ({start} and {end}) are bigger than the source {dimension} {source_length}."
+            )));
+        }
+
+        if start + end > length {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "The borders ({start} and {end}) are bigger than the panel {dimension} {length}."
+            )));
+        }
+
+        let source_center = source_length - start - end;
+        let center = length - start - end;
+
+        if source_center == 0 && center > 0 {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "The source has no center to fill the panel {dimension} {length}."
+            )));
+        }
+
+        Ok((0..length)
+            .map(|i| match i {
+                i if i < start => i,
+                i if i >= start + center => source_length - (length - i),
+                i => start + match self.mode {
+                    SliceMode::Stretch => (i - start) * source_center / center,
+                    SliceMode::Tile => (i - start) % source_center,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::nine_slice::{NineSlice, SliceMode};
+
+    fn create_source() -> Image {
+        create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::WHITE, Color::BLACK, Color::RED,
+                Color::WHITE, Color::GREEN, Color::BLUE, Color::WHITE,
+                Color::RED, Color::WHITE, Color::BLACK, Color::RED,
+            ],
+        )
+    }
+
+    #[test]
+    fn create_panel_with_stretch_works() {
+        // arrange
+        let nine_slice = NineSlice::uniform(1);
+
+        // act
+        let panel = nine_slice.create_panel(&create_source(), (6, 4)).unwrap();
+
+        // assert
+        let expected = create_image(
+            (6, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::WHITE, Color::WHITE, Color::BLACK, Color::BLACK, Color::RED,
+                Color::WHITE, Color::GREEN, Color::GREEN, Color::BLUE, Color::BLUE, Color::WHITE,
+                Color::WHITE, Color::GREEN, Color::GREEN, Color::BLUE, Color::BLUE, Color::WHITE,
+                Color::RED, Color::WHITE, Color::WHITE, Color::BLACK, Color::BLACK, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, panel.data);
+    }
+
+    #[test]
+    fn create_panel_with_tiles_works() {
+        // arrange
+        let nine_slice = NineSlice::uniform(1).with_mode(SliceMode::Tile);
+
+        // act
+        let panel = nine_slice.create_panel(&create_source(), (7, 3)).unwrap();
+
+        // assert
+        let expected = create_image(
+            (7, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::WHITE, Color::BLACK, Color::WHITE, Color::BLACK, Color::WHITE, Color::RED,
+                Color::WHITE, Color::GREEN, Color::BLUE, Color::GREEN, Color::BLUE, Color::GREEN, Color::WHITE,
+                Color::RED, Color::WHITE, Color::BLACK, Color::WHITE, Color::BLACK, Color::WHITE, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, panel.data);
+    }
+
+    #[test]
+    fn create_panel_smaller_than_borders_fails() {
+        // arrange
+        let nine_slice = NineSlice::new(1, 2, 1, 1);
+
+        // act
+        let result = nine_slice.create_panel(&create_source(), (2, 3));
+
+        // assert
+        assert!(result.is_err());
+        assert_eq!("The borders (1 and 2) are bigger than the panel width 2.", result.unwrap_err().to_string());
+    }
+}
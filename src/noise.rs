@@ -0,0 +1,247 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// The kind of noise generated by [generate_noise_texture].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum NoiseKind {
+    /// Gradient noise on a square grid, the classic smooth noise for clouds or terrain
+    #[default]
+    Perlin,
+    /// Gradient noise on a triangular grid, which has less visible grid artifacts than Perlin noise
+    Simplex,
+    /// The distance to the closest of randomly scattered points, which looks like cells, stones or scales
+    Worley,
+}
+
+/// Configures the noise generated by [generate_noise_texture].
+#[derive(Clone, Debug)]
+pub struct NoiseParams {
+    pub kind: NoiseKind,
+    /// The same seed always generates the same noise
+    pub seed: u64,
+    /// The amount of noise cells along the width of the texture
+    pub frequency: f32,
+    /// The amount of layers of fractal brownian motion (fBM). Every octave adds finer details,
+    /// a single octave generates plain noise.
+    pub octaves: usize,
+    /// The factor by which the frequency of every octave grows
+    pub lacunarity: f32,
+    /// The factor by which the influence of every octave shrinks
+    pub persistence: f32,
+}
+
+impl Default for NoiseParams {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::default(),
+            seed: 0,
+            frequency: 4.0,
+            octaves: 1,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+}
+
+/// Generate a texture of the given size filled with noise, like a heightmap or a mask.
+/// Every pixel gets a value from 0 (black) to 255 (white). Supports the formats R8Unorm, which stores only the value,
+/// and Rgba8Unorm and Rgba8UnormSrgb, which store the value in the color channels and are opaque.
+pub fn generate_noise_texture(
+    (width, height): (usize, usize),
+    params: &NoiseParams,
+    texture_format: TextureFormat,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let bytes_per_pixel = match texture_format {
+        TextureFormat::R8Unorm => 1,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => 4,
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    if params.frequency <= 0.0 {
+        return Err(TextureUtilsError::OutOfRange { name: "noise frequency", value: params.frequency as f64, expected: "positive" });
+    }
+
+    let scale = params.frequency / width.max(1) as f32;
+    let mut data = Vec::with_capacity(width * height * bytes_per_pixel);
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = (sample_noise(params, (x as f32 + 0.5) * scale, (y as f32 + 0.5) * scale) * 255.0).round() as u8;
+
+            match bytes_per_pixel {
+                1 => data.push(value),
+                _ => data.extend([value, value, value, 255])
+            }
+        }
+    }
+
+    Ok(options.create_image((width, height), data, texture_format))
+}
+
+/// Get the noise value (0.0 to 1.0) at the given point, where one unit is one noise cell.
+pub fn sample_noise(params: &NoiseParams, x: f32, y: f32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+    let mut frequency = 1.0;
+
+    for octave in 0..params.octaves.max(1) {
+        let seed = params.seed.wrapping_add(octave as u64);
+        let (x, y) = (x * frequency, y * frequency);
+
+        value += amplitude * match params.kind {
+            NoiseKind::Perlin => perlin(x, y, seed),
+            NoiseKind::Simplex => simplex(x, y, seed),
+            NoiseKind::Worley => worley(x, y, seed),
+        };
+        total_amplitude += amplitude;
+        amplitude *= params.persistence;
+        frequency *= params.lacunarity;
+    }
+
+    (value / total_amplitude).clamp(0.0, 1.0)
+}
+
+/// Perlin noise, normalized to 0.0 to 1.0.
+fn perlin(x: f32, y: f32, seed: u64) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let corner = |dx: i64, dy: i64| dot_gradient(hash(x0 + dx, y0 + dy, seed), fx - dx as f32, fy - dy as f32);
+
+    let (u, v) = (fade(fx), fade(fy));
+    let value = lerp(lerp(corner(0, 0), corner(1, 0), u), lerp(corner(0, 1), corner(1, 1), u), v);
+
+    // with unit gradients, 2D Perlin noise is within -sqrt(0.5) to sqrt(0.5)
+    value * std::f32::consts::FRAC_1_SQRT_2 + 0.5
+}
+
+/// Simplex noise, normalized to 0.0 to 1.0.
+fn simplex(x: f32, y: f32, seed: u64) -> f32 {
+    const SKEW: f32 = 0.366_025_42;
+    const UNSKEW: f32 = 0.211_324_87;
+
+    // find the triangle which contains the point
+    let skew = (x + y) * SKEW;
+    let (i, j) = ((x + skew).floor(), (y + skew).floor());
+    let unskew = (i + j) * UNSKEW;
+    let (x0, y0) = (x - (i - unskew), y - (j - unskew));
+    let (i1, j1) = match x0 > y0 {
+        true => (1, 0),
+        false => (0, 1)
+    };
+    let (i, j) = (i as i64, j as i64);
+
+    let corners = [
+        (0, 0, x0, y0),
+        (i1, j1, x0 - i1 as f32 + UNSKEW, y0 - j1 as f32 + UNSKEW),
+        (1, 1, x0 - 1.0 + 2.0 * UNSKEW, y0 - 1.0 + 2.0 * UNSKEW),
+    ];
+
+    let value = corners
+        .into_iter()
+        .map(|(di, dj, dx, dy)| {
+            let falloff = 0.5 - dx * dx - dy * dy;
+
+            match falloff > 0.0 {
+                true => falloff.powi(4) * dot_gradient(hash(i + di, j + dj, seed), dx, dy),
+                false => 0.0
+            }
+        })
+        .sum::<f32>();
+
+    // scales the maximum of about 0.01 with unit gradients to 0.5
+    value * 49.0 + 0.5
+}
+
+/// The distance to the closest feature point, where every noise cell contains one feature point. Clamped to 0.0 to 1.0.
+fn worley(x: f32, y: f32, seed: u64) -> f32 {
+    let (cell_x, cell_y) = (x.floor() as i64, y.floor() as i64);
+    let mut closest = f32::MAX;
+
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (cx, cy) = (cell_x + dx, cell_y + dy);
+            let hash = hash(cx, cy, seed);
+            let point_x = cx as f32 + (hash & 0xFFFF) as f32 / 65536.0;
+            let point_y = cy as f32 + ((hash >> 16) & 0xFFFF) as f32 / 65536.0;
+
+            closest = closest.min(((point_x - x).powi(2) + (point_y - y).powi(2)).sqrt());
+        }
+    }
+
+    closest.min(1.0)
+}
+
+/// The dot product of the given offset with one of eight unit gradients, chosen by the hash.
+fn dot_gradient(hash: u64, x: f32, y: f32) -> f32 {
+    let angle = (hash % 8) as f32 * std::f32::consts::FRAC_PI_4;
+    angle.cos() * x + angle.sin() * y
+}
+
+fn hash(x: i64, y: i64, seed: u64) -> u64 {
+    let mut hash = seed.wrapping_mul(0x165667B19E3779F9)
+        ^ (x as u64).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51AFD7ED558CCD);
+    hash ^= hash >> 33;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::noise::{generate_noise_texture, NoiseKind, NoiseParams};
+
+    #[test]
+    fn generate_noise_texture_is_deterministic() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex, NoiseKind::Worley] {
+            // arrange
+            let params = NoiseParams { kind, seed: 42, octaves: 3, ..NoiseParams::default() };
+            let other_seed = NoiseParams { seed: 43, ..params.clone() };
+
+            // act
+            let first = generate_noise_texture((16, 8), &params, TextureFormat::R8Unorm, ImageOptions::default()).unwrap();
+            let second = generate_noise_texture((16, 8), &params, TextureFormat::R8Unorm, ImageOptions::default()).unwrap();
+            let other = generate_noise_texture((16, 8), &other_seed, TextureFormat::R8Unorm, ImageOptions::default()).unwrap();
+
+            // assert
+            assert_eq!(16 * 8, first.data.len());
+            assert_eq!(first.data, second.data, "{:?} noise with the same seed should be equal, but wasn't.", kind);
+            assert_ne!(first.data, other.data, "{:?} noise with different seeds should differ, but didn't.", kind);
+            assert!(first.data.iter().any(|v| *v != first.data[0]), "{:?} noise should not be uniform, but was.", kind);
+        }
+    }
+
+    #[test]
+    fn generate_noise_texture_as_rgba_is_opaque_gray() {
+        // arrange
+        let params = NoiseParams { kind: NoiseKind::Simplex, ..NoiseParams::default() };
+
+        // act
+        let texture = generate_noise_texture((4, 4), &params, TextureFormat::Rgba8UnormSrgb, ImageOptions::default()).unwrap();
+
+        // assert
+        assert!(texture.data.chunks_exact(4).all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2] && pixel[3] == 255));
+    }
+
+    #[test]
+    fn generate_noise_texture_with_unsupported_format_fails() {
+        // act
+        let result = generate_noise_texture((1, 1), &NoiseParams::default(), TextureFormat::Rgba32Float, ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rgba32Float })));
+    }
+}
@@ -0,0 +1,290 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::selection::Selection;
+
+/// The method used to combine two tangent-space normal maps.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NormalBlendMethod {
+    /// Reoriented normal mapping: rotates the detail normal onto the base normal.
+    /// Gives the most accurate results.
+    Reoriented,
+    /// Unreal derivative normal blending, which adds the detail slopes (xy) to the base normal
+    /// and keeps the base z. Cheap but flattens strong details.
+    Udn,
+    /// Adds both normals and normalizes the result. Flattens both maps.
+    Linear,
+}
+
+/// Combine a base and a detail tangent-space normal map, like detail normals on baked terrain.
+/// Both maps must have the same size and store their normals in the red, green and blue
+/// channels, mapped from [-1, 1] to [0, 255], so both need an 8-bit RGBA format. The alpha channel
+/// and texture format are taken from the base map.
+/// If a selection is given, only the selected pixels are blended. All others keep the base normal.
+pub fn blend_normal_maps(
+    base: &Image,
+    detail: &Image,
+    method: NormalBlendMethod,
+    selection: Option<&Selection>,
+) -> Result<Image, TextureUtilsError> {
+    for format in [base.texture_descriptor.format, detail.texture_descriptor.format] {
+        if !matches!(format, TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb) {
+            return Err(TextureUtilsError::UnsupportedFormat { format });
+        }
+    }
+
+    if base.width() != detail.width() || base.height() != detail.height() {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    if base.data.len() != (base.width() * base.height() * 4) as usize || base.data.len() != detail.data.len() {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let width = base.width() as usize;
+    let mut blended = base.clone();
+
+    for (i, (target, detail_pixel)) in blended.data.chunks_exact_mut(4).zip(detail.data.chunks_exact(4)).enumerate() {
+        if selection.is_some_and(|s| !s.is_selected(i % width, i / width)) {
+            continue;
+        }
+
+        let n1 = decode_normal(target);
+        let n2 = decode_normal(detail_pixel);
+
+        let normal = match method {
+            NormalBlendMethod::Reoriented => {
+                let t = [n1[0], n1[1], n1[2] + 1.0];
+                let u = [-n2[0], -n2[1], n2[2]];
+                let factor = dot(t, u) / t[2];
+
+                [t[0] * factor - u[0], t[1] * factor - u[1], t[2] * factor - u[2]]
+            }
+            NormalBlendMethod::Udn => [n1[0] + n2[0], n1[1] + n2[1], n1[2]],
+            NormalBlendMethod::Linear => [n1[0] + n2[0], n1[1] + n2[1], n1[2] + n2[2]],
+        };
+
+        target[..3].copy_from_slice(&encode_normal(normalize(normal)));
+    }
+
+    Ok(blended)
+}
+
+/// Create a tangent-space normal map with the format Rgba8Unorm from the given heightmap, like a generated noise texture.
+/// The heights are read from the only channel of an R8Unorm image or the red channel of an 8-bit RGBA or BGRA image,
+/// like a grayscale one. The strength scales the slopes, so higher values create more pronounced bumps.
+/// Positive green values point to the top of the image, like bevy expects it. Pixels outside of
+/// the heightmap get the height of the closest edge pixel. The normal map is created with the given options.
+pub fn heightmap_to_normal_map(height: &Image, strength: f32, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+    let (bytes_per_pixel, height_channel) = match height.texture_descriptor.format {
+        TextureFormat::R8Unorm => (1, 0),
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => (4, 0),
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm => (4, 2),
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+
+    let (width, rows) = (height.width() as usize, height.height() as usize);
+
+    if height.data.len() != width * rows * bytes_per_pixel {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let height_at = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, rows as isize - 1) as usize;
+        height.data[(y * width + x) * bytes_per_pixel + height_channel] as f32 / 255.0
+    };
+
+    let mut data = Vec::with_capacity(width * rows * 4);
+
+    for y in 0..rows as isize {
+        for x in 0..width as isize {
+            // central differences, where the y-axis of the image points down, but the one of the normal up
+            let slope_x = (height_at(x + 1, y) - height_at(x - 1, y)) / 2.0 * strength;
+            let slope_y = (height_at(x, y + 1) - height_at(x, y - 1)) / 2.0 * strength;
+
+            data.extend(encode_normal(normalize([-slope_x, slope_y, 1.0])));
+            data.push(255);
+        }
+    }
+
+    Ok(options.create_image((width, rows), data, TextureFormat::Rgba8Unorm))
+}
+
+fn decode_normal(pixel: &[u8]) -> [f32; 3] {
+    [
+        pixel[0] as f32 / 255.0 * 2.0 - 1.0,
+        pixel[1] as f32 / 255.0 * 2.0 - 1.0,
+        pixel[2] as f32 / 255.0 * 2.0 - 1.0,
+    ]
+}
+
+fn encode_normal(normal: [f32; 3]) -> [u8; 3] {
+    normal.map(|v| ((v * 0.5 + 0.5) * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+
+    match length > 0.0 {
+        true => v.map(|c| c / length),
+        false => [0.0, 0.0, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_asset::RenderAssetUsages;
+    use bevy_render::render_resource::TextureFormat;
+    use bevy_render::texture::ImageSampler;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::normal_maps::{blend_normal_maps, heightmap_to_normal_map, NormalBlendMethod};
+    use crate::pixel_rect::PixelRect;
+    use crate::selection::Selection;
+
+    fn assert_pixels_close(expected: &[u8], actual: &[u8]) {
+        assert_eq!(expected.len(), actual.len());
+
+        for (e, a) in expected.iter().zip(actual) {
+            assert!(e.abs_diff(*a) <= 1, "Expected {:?}, but got {:?}", expected, actual);
+        }
+    }
+
+    /// Blending a detail map onto a flat base map must keep the detail unchanged when using reoriented
+    /// normal mapping. UDN flattens the detail, but keeps its direction.
+    #[test]
+    fn blend_normal_maps_on_flat_base_keeps_detail() {
+        // arrange
+        let flat = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(128, 128, 255, 255)]);
+        let detail = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(204, 128, 230, 255)]);
+
+        // act
+        let reoriented = blend_normal_maps(&flat, &detail, NormalBlendMethod::Reoriented, None).unwrap();
+        let udn = blend_normal_maps(&flat, &detail, NormalBlendMethod::Udn, None).unwrap();
+
+        // assert
+        assert_pixels_close(&detail.data, &reoriented.data);
+        assert_eq!(detail.data[0] > 128, udn.data[0] > 128);
+    }
+
+    /// A flat detail map must not change the base map when using reoriented normal mapping.
+    #[test]
+    fn blend_normal_maps_with_flat_detail_keeps_base() {
+        // arrange
+        let base = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(128, 51, 230, 100)]);
+        let flat = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(128, 128, 255, 255)]);
+
+        // act
+        let result = blend_normal_maps(&base, &flat, NormalBlendMethod::Reoriented, None).unwrap();
+
+        // assert
+        assert_pixels_close(&base.data, &result.data);
+    }
+
+    #[test]
+    fn blend_normal_maps_linear_works() {
+        // arrange
+        let left = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(51, 128, 230, 255)]);
+        let right = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(204, 128, 230, 255)]);
+
+        // act
+        let result = blend_normal_maps(&left, &right, NormalBlendMethod::Linear, None).unwrap();
+
+        // assert
+        assert_pixels_close(&[128, 128, 255, 255], &result.data);
+    }
+
+    #[test]
+    fn blend_normal_maps_with_different_sizes_fails() {
+        // arrange
+        let small = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::BLUE]);
+        let big = create_image((2, 1), TextureFormat::Rgba8Unorm, [Color::BLUE, Color::BLUE]);
+
+        // act
+        let result = blend_normal_maps(&small, &big, NormalBlendMethod::Udn, None);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::SizeMismatch)));
+    }
+
+    /// Only selected pixels are blended.
+    #[test]
+    fn blend_normal_maps_with_selection_works() {
+        // arrange
+        let flat = create_image((2, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(128, 128, 255, 255); 2]);
+        let detail = create_image((2, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(204, 128, 230, 255); 2]);
+        let selection = Selection::from_rect((2, 1), PixelRect::new(1, 0, 1, 1));
+
+        // act
+        let result = blend_normal_maps(&flat, &detail, NormalBlendMethod::Reoriented, Some(&selection)).unwrap();
+
+        // assert
+        assert_eq!(&flat.data[0..4], &result.data[0..4], "The unselected pixel should not change, but did.");
+        assert_pixels_close(&detail.data[4..8], &result.data[4..8]);
+    }
+
+    /// BGRA maps would swap the x and z axes of the normals, so they are rejected.
+    #[test]
+    fn blend_normal_maps_with_unsupported_format_fails() {
+        // arrange
+        let flat = create_image((1, 1), TextureFormat::Rgba8Unorm, [Color::rgba_u8(128, 128, 255, 255)]);
+        let bgra = create_image((1, 1), TextureFormat::Bgra8Unorm, [Color::rgba_u8(128, 128, 255, 255)]);
+
+        // act
+        let result = blend_normal_maps(&flat, &bgra, NormalBlendMethod::Udn, None);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Bgra8Unorm })));
+    }
+
+    #[test]
+    fn heightmap_to_normal_map_works() {
+        // arrange
+        // rising to the right
+        let heightmap = ImageOptions::default().create_image(
+            (3, 2),
+            vec![
+                0, 51, 102,
+                0, 51, 102,
+            ],
+            TextureFormat::R8Unorm,
+        );
+        // rising to the bottom
+        let rising_down = create_image((1, 2), TextureFormat::Rgba8Unorm, [Color::BLACK, Color::WHITE]);
+
+        // act
+        let normal_map = heightmap_to_normal_map(&heightmap, 10.0, ImageOptions::default()).unwrap();
+        let normal_map_down = heightmap_to_normal_map(&rising_down, 1.0, ImageOptions::default()).unwrap();
+
+        // assert
+        assert_eq!(TextureFormat::Rgba8Unorm, normal_map.texture_descriptor.format);
+        assert_pixels_close(&[13, 128, 185, 255], &normal_map.data[4..8]);
+        assert!(normal_map_down.data[1] > 128, "The normal should point up, away from the rising slope, but didn't.");
+    }
+
+    /// The configured options must be applied to the created normal map.
+    #[test]
+    fn heightmap_to_normal_map_with_options_works() {
+        // arrange
+        let heightmap = ImageOptions::default().create_image((2, 1), vec![0, 255], TextureFormat::R8Unorm);
+        let options = ImageOptions::new(RenderAssetUsages::RENDER_WORLD).with_sampler(ImageSampler::linear());
+
+        // act
+        let normal_map = heightmap_to_normal_map(&heightmap, 1.0, options).unwrap();
+
+        // assert
+        assert_eq!(RenderAssetUsages::RENDER_WORLD, normal_map.asset_usage);
+        assert!(matches!(normal_map.sampler, ImageSampler::Descriptor(_)), "The sampler should be the configured one, but wasn't.");
+    }
+}
@@ -0,0 +1,160 @@
+use bevy_render::prelude::*;
+
+use crate::color::color_to_pixel_bytes;
+use crate::error::TextureUtilsError;
+
+/// Paint an outline with the given color and thickness in pixels around all non-transparent pixels of the image,
+/// like for pixel art characters or selection highlights. The outline is drawn onto the transparent pixels close
+/// to the opaque ones, so the image needs a transparent margin of the thickness to fit the whole outline.
+/// A thickness of 1 only outlines horizontally and vertically, bigger thicknesses create round corners.
+/// Supports the same formats as [color_to_pixel_bytes].
+pub fn add_outline(image: &mut Image, color: Color, thickness: u32) -> Result<(), TextureUtilsError> {
+    let bytes = color_to_pixel_bytes(color, image.texture_descriptor.format)?;
+    let rim = outline_mask(image, thickness)?;
+
+    for (pixel, _) in image.data.chunks_exact_mut(4).zip(rim).filter(|(_, is_rim)| *is_rim) {
+        pixel.copy_from_slice(&bytes);
+    }
+
+    Ok(())
+}
+
+/// Like [add_outline], but creates a new image with the size and format of the given one which only contains
+/// the outline, so it can be shown and hidden separately.
+pub fn create_outline(image: &Image, color: Color, thickness: u32) -> Result<Image, TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+    let bytes = color_to_pixel_bytes(color, format)?;
+    let transparent = color_to_pixel_bytes(Color::NONE, format)?;
+    let rim = outline_mask(image, thickness)?;
+
+    let mut outline = image.clone();
+    outline.data = rim
+        .into_iter()
+        .flat_map(|is_rim| match is_rim {
+            true => bytes.clone(),
+            false => transparent.clone()
+        })
+        .collect();
+
+    Ok(outline)
+}
+
+/// Tells for every pixel if it is transparent and at most thickness pixels away from a non-transparent one.
+fn outline_mask(image: &Image, thickness: u32) -> Result<Vec<bool>, TextureUtilsError> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    if image.data.len() != width * height * 4 {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    // both the RGBA and BGRA formats store the alpha last
+    let opaque = image.data
+        .chunks_exact(4)
+        .map(|pixel| pixel[3] > 0)
+        .collect::<Vec<_>>();
+    let radius = thickness as isize;
+    let offsets = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= radius * radius)
+        .collect::<Vec<_>>();
+
+    Ok((0..width * height)
+        .map(|i| {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+
+            !opaque[i] && offsets.iter().any(|(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0 && ny >= 0 && nx < width as isize && ny < height as isize && opaque[ny as usize * width + nx as usize]
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::outline::{add_outline, create_outline};
+
+    const N: Color = Color::NONE;
+    const W: Color = Color::WHITE;
+    const R: Color = Color::RED;
+
+    fn create_sprite() -> Image {
+        create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, N, N,
+                N, N, N, N, N,
+                N, N, W, N, N,
+                N, N, N, N, N,
+                N, N, N, N, N,
+            ],
+        )
+    }
+
+    #[test]
+    fn add_outline_works() {
+        // arrange
+        let mut thin = create_sprite();
+        let mut thick = create_sprite();
+
+        // act
+        add_outline(&mut thin, R, 1).unwrap();
+        add_outline(&mut thick, R, 2).unwrap();
+
+        // assert
+        let expected_thin = create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, N, N,
+                N, N, R, N, N,
+                N, R, W, R, N,
+                N, N, R, N, N,
+                N, N, N, N, N,
+            ],
+        );
+        let expected_thick = create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, R, N, N,
+                N, R, R, R, N,
+                R, R, W, R, R,
+                N, R, R, R, N,
+                N, N, R, N, N,
+            ],
+        );
+
+        assert_eq!(expected_thin.data, thin.data);
+        assert_eq!(expected_thick.data, thick.data);
+    }
+
+    #[test]
+    fn create_outline_works() {
+        // arrange
+        let sprite = create_sprite();
+
+        // act
+        let outline = create_outline(&sprite, R, 1).unwrap();
+
+        // assert
+        let expected = create_image(
+            (5, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, N, N,
+                N, N, R, N, N,
+                N, R, N, R, N,
+                N, N, R, N, N,
+                N, N, N, N, N,
+            ],
+        );
+
+        assert_eq!(expected.data, outline.data);
+    }
+}
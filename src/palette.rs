@@ -0,0 +1,120 @@
+use bevy_render::prelude::*;
+
+use crate::dithering::{dither, nearest, rgb, with_rgb, DitherMethod};
+use crate::error::TextureUtilsError;
+use crate::texture_modification::modify_texture_colors;
+
+/// The palettes of classic consoles and graphics cards, to give generated textures a consistent retro look.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RetroPalette {
+    /// The four shades of green of the original Game Boy
+    GameBoy,
+    /// The 16 colors of the PICO-8 fantasy console
+    Pico8,
+    /// The 55 distinct colors of the common NES palette
+    Nes,
+    /// The 16 colors of the CGA graphics card
+    Cga,
+}
+
+impl RetroPalette {
+    pub fn colors(&self) -> Vec<Color> {
+        let hex_colors: &[u32] = match self {
+            RetroPalette::GameBoy => &[0x0F380F, 0x306230, 0x8BAC0F, 0x9BBC0F],
+            RetroPalette::Pico8 => &[
+                0x000000, 0x1D2B53, 0x7E2553, 0x008751, 0xAB5236, 0x5F574F, 0xC2C3C7, 0xFFF1E8,
+                0xFF004D, 0xFFA300, 0xFFEC27, 0x00E436, 0x29ADFF, 0x83769C, 0xFF77A8, 0xFFCCAA,
+            ],
+            RetroPalette::Nes => &[
+                0x7C7C7C, 0x0000FC, 0x0000BC, 0x4428BC, 0x940084, 0xA80020, 0xA81000, 0x881400,
+                0x503000, 0x007800, 0x006800, 0x005800, 0x004058, 0x000000,
+                0xBCBCBC, 0x0078F8, 0x0058F8, 0x6844FC, 0xD800CC, 0xE40058, 0xF83800, 0xE45C10,
+                0xAC7C00, 0x00B800, 0x00A800, 0x00A844, 0x008888,
+                0xF8F8F8, 0x3CBCFC, 0x6888FC, 0x9878F8, 0xF878F8, 0xF85898, 0xF87858, 0xFCA044,
+                0xF8B800, 0xB8F818, 0x58D854, 0x58F898, 0x00E8D8, 0x787878,
+                0xFCFCFC, 0xA4E4FC, 0xB8B8F8, 0xD8B8F8, 0xF8B8F8, 0xF8A4C0, 0xF0D0B0, 0xFCE0A8,
+                0xF8D878, 0xD8F878, 0xB8F8B8, 0xB8F8D8, 0x00FCFC, 0xF8D8F8,
+            ],
+            RetroPalette::Cga => &[
+                0x000000, 0x0000AA, 0x00AA00, 0x00AAAA, 0xAA0000, 0xAA00AA, 0xAA5500, 0xAAAAAA,
+                0x555555, 0x5555FF, 0x55FF55, 0x55FFFF, 0xFF5555, 0xFF55FF, 0xFFFF55, 0xFFFFFF,
+            ],
+        };
+
+        hex_colors
+            .iter()
+            .map(|hex| Color::rgb_u8((hex >> 16) as u8, (hex >> 8) as u8, *hex as u8))
+            .collect()
+    }
+}
+
+/// Replace the color of every pixel with the closest color of the given palette, like a [RetroPalette].
+/// The alpha of the pixels is kept. With dithering, the missing colors are simulated with
+/// [DitherMethod::FloydSteinberg], otherwise large gradients become flat areas.
+/// Works with the 8-bit RGBA and BGRA formats.
+pub fn quantize_to_palette(texture: &mut Image, palette: &[Color], dithering: bool) -> Result<(), TextureUtilsError> {
+    if dithering {
+        return dither(texture, palette, DitherMethod::FloydSteinberg);
+    }
+
+    if palette.is_empty() {
+        return Err(TextureUtilsError::NoColorsProvided);
+    }
+
+    let palette = palette.iter().map(|color| rgb(*color)).collect::<Vec<_>>();
+
+    modify_texture_colors(texture, |_, _, color| with_rgb(color, nearest(&palette, rgb(color))))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::palette::{quantize_to_palette, RetroPalette};
+
+    #[test]
+    fn retro_palettes_have_distinct_colors() {
+        for (palette, expected_len) in [(RetroPalette::GameBoy, 4), (RetroPalette::Pico8, 16), (RetroPalette::Nes, 55), (RetroPalette::Cga, 16)] {
+            // act
+            let colors = palette.colors();
+
+            // assert
+            assert_eq!(expected_len, colors.len());
+            assert!(
+                colors.iter().enumerate().all(|(i, color)| !colors[..i].contains(color)),
+                "The colors of {palette:?} should be distinct, but weren't."
+            );
+        }
+    }
+
+    #[test]
+    fn quantize_to_palette_works() {
+        // arrange
+        let palette = RetroPalette::GameBoy.colors();
+        let mut texture = create_image(
+            (3, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::BLACK, Color::rgba_u8(50, 100, 50, 100), Color::WHITE],
+        );
+        let mut dithered = texture.clone();
+
+        // act
+        quantize_to_palette(&mut texture, &palette, false).unwrap();
+        quantize_to_palette(&mut dithered, &palette, true).unwrap();
+
+        // assert
+        let expected = create_image(
+            (3, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [palette[0], palette[1].with_a(100.0 / 255.0), palette[3]],
+        );
+
+        assert_eq!(expected.data, texture.data);
+        assert!(
+            dithered.data.chunks_exact(4).all(|pixel| palette.contains(&Color::rgb_u8(pixel[0], pixel[1], pixel[2]))),
+            "All dithered pixels should have a color of the palette, but didn't."
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use bevy_asset::prelude::*;
+use bevy_asset::LoadState;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+use pad::Position;
+
+use crate::plugin::TileMapTextureCreated;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+/// Where the texture of a [PendingTileMaps] request ends up when it is created.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BuildTarget {
+    /// Insert the handle of the texture as component into the entity, like a sprite
+    Entity(Entity),
+    /// Send a [TileMapTextureCreated] event with the given id
+    Event(u64),
+}
+
+/// A tile map which is created when all of its tiles are loaded.
+#[derive(Clone, Debug)]
+struct PendingTileMap {
+    creator: TileMapTextureCreator,
+    positions_and_textures: Vec<(Position, Handle<Image>)>,
+    target: BuildTarget,
+}
+
+/// Tile maps which wait for their tiles to be loaded. Each frame, the load state of the tiles is checked
+/// and the tile maps whose tiles are all loaded are created and delivered to their [BuildTarget].
+/// So handles which were just returned by the AssetServer can be used right away.
+/// If a tile fails to load, its tile map is dropped with a warning.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct PendingTileMaps {
+    requests: Vec<PendingTileMap>,
+}
+
+impl PendingTileMaps {
+    /// Add a tile map which gets created as soon as all given tiles are loaded.
+    pub fn push(
+        &mut self,
+        creator: TileMapTextureCreator,
+        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
+        target: BuildTarget,
+    ) {
+        self.requests.push(PendingTileMap {
+            creator,
+            positions_and_textures: positions_and_textures.into_iter().collect(),
+            target,
+        })
+    }
+
+    /// The amount of tile maps which were not created yet.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+pub(crate) fn build_pending_tile_maps(
+    mut commands: Commands,
+    mut pending: ResMut<PendingTileMaps>,
+    mut created: EventWriter<TileMapTextureCreated>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    if pending.is_empty() {
+        return;
+    }
+
+    let mut not_ready = Vec::new();
+
+    for request in pending.requests.drain(..) {
+        let states = request
+            .positions_and_textures
+            .iter()
+            .map(|(_, tile)| load_state(tile, &images, asset_server.as_deref()))
+            .collect::<Vec<_>>();
+
+        if states.contains(&LoadState::Failed) {
+            bevy_log::warn!("Could not create the tile map texture for {:?}, as some of its tiles failed to load", request.target);
+            continue;
+        }
+
+        if states.iter().any(|state| *state != LoadState::Loaded) {
+            not_ready.push(request);
+            continue;
+        }
+
+        let handle = match request.creator.create_tile_map_texture(&mut images, request.positions_and_textures) {
+            Ok(handle) => handle,
+            Err(e) => {
+                bevy_log::warn!("Could not create the tile map texture for {:?}: {e}", request.target);
+                continue;
+            }
+        };
+
+        match request.target {
+            BuildTarget::Entity(entity) => match commands.get_entity(entity) {
+                Some(mut entity_commands) => { entity_commands.insert(handle); }
+                None => bevy_log::warn!("The target {entity:?} of the created tile map texture does not exist")
+            },
+            BuildTarget::Event(id) => { created.send(TileMapTextureCreated { id, handle }); }
+        }
+    }
+
+    pending.requests = not_ready;
+}
+
+/// A tile counts as loaded as soon as its image exists, even if it was not loaded by the AssetServer.
+pub(crate) fn load_state(tile: &Handle<Image>, images: &Assets<Image>, asset_server: Option<&AssetServer>) -> LoadState {
+    match (images.contains(tile), asset_server) {
+        (true, _) => LoadState::Loaded,
+        (false, Some(asset_server)) => match asset_server.load_state(tile) {
+            // the image was removed again
+            LoadState::Loaded => LoadState::NotLoaded,
+            state => state
+        },
+        (false, None) => LoadState::NotLoaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_asset::prelude::*;
+    use bevy_ecs::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::pending_tile_maps::{BuildTarget, PendingTileMaps};
+    use crate::plugin::{TextureUtilsPlugin, TileMapTextureCreated};
+    use crate::tile_map_texture::TileMapTextureCreator;
+
+    /// The tile maps are created when their tiles are loaded and delivered to their target.
+    #[test]
+    fn pending_tile_maps_are_built_when_loaded() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.reserve_handle();
+        let entity = app.world.spawn_empty().id();
+
+        let mut pending = app.world.resource_mut::<PendingTileMaps>();
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        pending.push(creator.clone(), [(p!(0, 0), red.clone()), (p!(1, 0), blue.clone())], BuildTarget::Entity(entity));
+        pending.push(creator, [(p!(0, 0), red)], BuildTarget::Event(3));
+
+        // act
+        app.update();
+        let pending_before_loading = app.world.resource::<PendingTileMaps>().len();
+
+        app.world.resource_mut::<Assets<Image>>().insert(&blue, create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        app.update();
+
+        // assert
+        assert_eq!(1, pending_before_loading);
+        assert!(app.world.resource::<PendingTileMaps>().is_empty());
+
+        let handle = app.world.get::<Handle<Image>>(entity).expect("The texture should have been attached, but wasn't.");
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        assert_eq!(expected.data, app.world.resource::<Assets<Image>>().get(handle).unwrap().data);
+
+        let events = app.world.resource::<Events<TileMapTextureCreated>>();
+
+        assert_eq!(vec![3], events.get_reader().read(events).map(|e| e.id).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,35 @@
+/// A rectangle of pixels in an image. (x, y) is the top left pixel of the rectangle,
+/// like the first pixel of an image is the top left one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PixelRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl PixelRect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Tells if the rectangle fits into an image of the given size.
+    pub fn fits_into(&self, (width, height): (usize, usize)) -> bool {
+        self.x + self.width <= width && self.y + self.height <= height
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &PixelRect) -> PixelRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        PixelRect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Tells if the given pixel is inside of the rectangle.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
@@ -0,0 +1,91 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::bitmap_font::{BitmapFont, draw_text};
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// Generate an Rgba8UnormSrgb checkerboard of square cells with the given size in pixels, where the top left cell has
+/// the first color. Useful as a fallback for textures which could not be loaded.
+pub fn generate_placeholder(
+    (width, height): (usize, usize),
+    cell_size: usize,
+    color_a: Color,
+    color_b: Color,
+    options: ImageOptions,
+) -> Image {
+    let cell_size = cell_size.max(1);
+    let data = (0..width * height)
+        .flat_map(|i| match (i % width / cell_size + i / width / cell_size) % 2 {
+            0 => color_a.as_rgba_u8(),
+            _ => color_b.as_rgba_u8()
+        })
+        .collect();
+
+    options.create_image((width, height), data, TextureFormat::Rgba8UnormSrgb)
+}
+
+/// Generate the classic magenta and black "missing texture" checkerboard with cells of 8 pixels.
+pub fn generate_missing_texture(size: (usize, usize), options: ImageOptions) -> Image {
+    generate_placeholder(size, 8, Color::FUCHSIA, Color::BLACK, options)
+}
+
+/// Like [generate_missing_texture], but with the given text written over the checkerboard in white, like the path
+/// of the asset which is missing. The text starts one pixel from the top left corner and is cut off at the edges.
+/// The atlas of the font must have the format Rgba8UnormSrgb.
+pub fn generate_missing_texture_with_text(
+    size: (usize, usize),
+    font: &BitmapFont,
+    text: &str,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let mut placeholder = generate_missing_texture(size, options);
+    draw_text(&mut placeholder, font, text, (1, 1), Color::WHITE)?;
+
+    Ok(placeholder)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::bitmap_font::BitmapFont;
+    use crate::builders::{checkerboard_image, create_image};
+    use crate::image_options::ImageOptions;
+    use crate::placeholder::{generate_missing_texture, generate_missing_texture_with_text};
+
+    const M: Color = Color::FUCHSIA;
+    const K: Color = Color::BLACK;
+
+    #[test]
+    fn generate_missing_texture_works() {
+        // act
+        let placeholder = generate_missing_texture((20, 10), ImageOptions::default());
+
+        // assert
+        let pixel_at = |x: usize, y: usize| placeholder.data[(y * 20 + x) * 4..(y * 20 + x + 1) * 4].to_vec();
+
+        assert_eq!(TextureFormat::Rgba8UnormSrgb, placeholder.texture_descriptor.format);
+        assert_eq!(M.as_rgba_u8().to_vec(), pixel_at(7, 7));
+        assert_eq!(K.as_rgba_u8().to_vec(), pixel_at(8, 7));
+        assert_eq!(K.as_rgba_u8().to_vec(), pixel_at(7, 8));
+        assert_eq!(M.as_rgba_u8().to_vec(), pixel_at(15, 9));
+    }
+
+    #[test]
+    fn generate_missing_texture_with_text_works() {
+        // arrange
+        // a font with a single glyph, which is a filled square
+        let font = BitmapFont::from_grid(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE]), (1, 1), "x").unwrap();
+
+        // act
+        let placeholder = generate_missing_texture_with_text((10, 9), &font, "xx", ImageOptions::default()).unwrap();
+
+        // assert
+        let mut expected = checkerboard_image((10, 9), 8, M, K);
+        expected.data[(10 + 1) * 4..(10 + 3) * 4].copy_from_slice(&create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 2]).data);
+
+        assert_eq!(expected.data, placeholder.data);
+    }
+}
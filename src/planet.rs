@@ -0,0 +1,274 @@
+use std::f32::consts::PI;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::layered_image::{Layer, LayeredImage};
+
+/// Parameters for generating an equirectangular planet texture.
+#[derive(Clone, Debug)]
+pub struct PlanetParams {
+    /// Different seeds create different planets
+    pub seed: u64,
+    /// The amount of noise layers with increasing detail
+    pub octaves: usize,
+    /// The size of the continents. Higher values create smaller ones.
+    pub frequency: f32,
+    /// Map the height (0.0 to 1.0) to a color. Each stop is a height and the color at this
+    /// height. Colors between stops are interpolated. Must be sorted by height.
+    pub color_stops: Vec<(f32, Color)>,
+    /// An optional cloud layer drawn over the surface
+    pub clouds: Option<CloudParams>,
+}
+
+impl Default for PlanetParams {
+    /// An earth-like planet with oceans, beaches, grassland, mountains and clouds.
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            octaves: 5,
+            frequency: 1.5,
+            color_stops: vec![
+                (0.0, Color::rgb(0.02, 0.05, 0.3)),
+                (0.5, Color::rgb(0.1, 0.3, 0.7)),
+                (0.52, Color::rgb(0.8, 0.75, 0.5)),
+                (0.6, Color::rgb(0.2, 0.55, 0.2)),
+                (0.8, Color::rgb(0.45, 0.4, 0.35)),
+                (0.9, Color::WHITE),
+            ],
+            clouds: Some(CloudParams::default()),
+        }
+    }
+}
+
+/// Parameters of the cloud layer of a planet.
+#[derive(Clone, Debug)]
+pub struct CloudParams {
+    pub seed: u64,
+    /// The size of the clouds. Higher values create smaller ones.
+    pub frequency: f32,
+    /// The portion (0.0 to 1.0) of the planet covered by clouds
+    pub coverage: f32,
+    /// The opacity (0.0 to 1.0) of the clouds
+    pub opacity: f32,
+}
+
+impl Default for CloudParams {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            frequency: 3.0,
+            coverage: 0.4,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// Generate an equirectangular planet texture (like for a UV sphere), where the x-axis is the
+/// longitude and the y-axis the latitude. A width of twice the height avoids stretching.
+/// The noise is sampled on the surface of a sphere instead of the flat texture, so the texture
+/// has no seam between the left and right edge and is not distorted at the poles.
+pub fn generate_planet_texture(
+    (width, height): (usize, usize),
+    params: &PlanetParams,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    if width == 0 || height == 0 {
+        return Err(TextureUtilsError::ZeroSize { size: (width, height) });
+    }
+
+    if params.color_stops.is_empty() {
+        return Err(TextureUtilsError::NoColorsProvided);
+    }
+
+    let surface = sample_sphere((width, height), |point| {
+        let height = fbm(point, params.seed, params.octaves, params.frequency);
+        map_to_gradient(height, &params.color_stops).as_rgba_u8()
+    });
+
+    let mut planet = LayeredImage::new((width, height)).with_options(options);
+    planet.add_layer(Layer::new("surface", create_rgba_image((width, height), surface), (0, 0)))?;
+
+    if let Some(clouds) = &params.clouds {
+        let cloud_data = sample_sphere((width, height), |point| {
+            let density = fbm(point, clouds.seed, 4, clouds.frequency);
+            let threshold = 1.0 - clouds.coverage.clamp(0.0, 1.0);
+            // fade the clouds in over a small band above the threshold to get soft edges
+            let alpha = ((density - threshold) / 0.1).clamp(0.0, 1.0);
+
+            [255, 255, 255, (alpha * 255.0) as u8]
+        });
+
+        planet.add_layer(
+            Layer::new("clouds", create_rgba_image((width, height), cloud_data), (0, 0))
+                .with_opacity(clouds.opacity)
+        )?;
+    }
+
+    Ok(planet.flatten())
+}
+
+fn create_rgba_image(size: (usize, usize), data: Vec<u8>) -> Image {
+    ImageOptions::default().create_image(size, data, TextureFormat::Rgba8UnormSrgb)
+}
+
+/// Map every pixel of an equirectangular texture to its point on the unit sphere and create the pixel from it.
+fn sample_sphere(
+    (width, height): (usize, usize),
+    pixel_creator: impl Fn([f32; 3]) -> [u8; 4],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(width * height * 4);
+
+    for y in 0..height {
+        let latitude = PI / 2.0 - (y as f32 + 0.5) / height as f32 * PI;
+
+        for x in 0..width {
+            let longitude = (x as f32 + 0.5) / width as f32 * 2.0 * PI;
+
+            let point = [
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                latitude.cos() * longitude.sin(),
+            ];
+
+            data.extend(pixel_creator(point));
+        }
+    }
+
+    data
+}
+
+/// Get the color of the given height (0.0 to 1.0) by interpolating between the given color stops.
+fn map_to_gradient(height: f32, color_stops: &[(f32, Color)]) -> Color {
+    let upper = color_stops.iter().position(|(stop, _)| *stop >= height);
+
+    match upper {
+        None => color_stops[color_stops.len() - 1].1,
+        Some(0) => color_stops[0].1,
+        Some(i) => {
+            let (start, from) = color_stops[i - 1];
+            let (end, to) = color_stops[i];
+            let t = (height - start) / (end - start);
+
+            Color::rgba(
+                from.r() + (to.r() - from.r()) * t,
+                from.g() + (to.g() - from.g()) * t,
+                from.b() + (to.b() - from.b()) * t,
+                from.a() + (to.a() - from.a()) * t,
+            )
+        }
+    }
+}
+
+/// Fractal 3D value noise, normalized to 0.0 to 1.0.
+fn fbm(point: [f32; 3], seed: u64, octaves: usize, frequency: f32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+    let mut frequency = frequency;
+
+    for octave in 0..octaves.max(1) {
+        let scaled = point.map(|v| v * frequency);
+        value += value_noise(scaled, seed.wrapping_add(octave as u64)) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    value / total_amplitude
+}
+
+/// Smoothly interpolated random values (0.0 to 1.0) on a 3D integer lattice.
+fn value_noise([x, y, z]: [f32; 3], seed: u64) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+    let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let corner = |dx: i64, dy: i64, dz: i64| lattice_value(x0 + dx, y0 + dy, z0 + dz, seed);
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), tx);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), tx);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), tx);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), tx);
+
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+fn lattice_value(x: i64, y: i64, z: i64, seed: u64) -> f32 {
+    let mut hash = seed
+        ^ (x as u64).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2AE3D27D4EB4F)
+        ^ (z as u64).wrapping_mul(0x165667B19E3779F9);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51AFD7ED558CCD);
+    hash ^= hash >> 33;
+
+    (hash % 10_000) as f32 / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::planet::{generate_planet_texture, PlanetParams};
+
+    /// The left and right edge of the texture meet on the sphere, so they must have similar colors.
+    #[test]
+    fn generate_planet_texture_has_no_seam() {
+        // arrange
+        let params = PlanetParams {
+            octaves: 1,
+            color_stops: vec![(0.0, Color::BLACK), (1.0, Color::WHITE)],
+            clouds: None,
+            ..PlanetParams::default()
+        };
+
+        // act
+        let planet = generate_planet_texture((64, 32), &params, ImageOptions::default()).unwrap();
+
+        // assert
+        for y in 0..32 {
+            let left = &planet.data[(y * 64) * 4..(y * 64) * 4 + 4];
+            let right = &planet.data[(y * 64 + 63) * 4..(y * 64 + 63) * 4 + 4];
+            let difference = left.iter().zip(right).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+
+            assert!(difference < 32, "The edges in row {} differ by {}.", y, difference);
+        }
+    }
+
+    /// Without clouds, every pixel must have a color from the gradient.
+    #[test]
+    fn generate_planet_texture_uses_color_stops() {
+        // arrange
+        let params = PlanetParams {
+            color_stops: vec![(0.5, Color::BLUE), (0.5, Color::GREEN)],
+            clouds: None,
+            ..PlanetParams::default()
+        };
+
+        // act
+        let planet = generate_planet_texture((16, 8), &params, ImageOptions::default()).unwrap();
+
+        // assert
+        assert!(planet.data.chunks_exact(4).all(|p| p == Color::BLUE.as_rgba_u8() || p == Color::GREEN.as_rgba_u8()));
+    }
+
+    #[test]
+    fn generate_planet_texture_without_color_stops_fails() {
+        // arrange
+        let params = PlanetParams { color_stops: vec![], ..PlanetParams::default() };
+
+        // act
+        let result = generate_planet_texture((16, 8), &params, ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::NoColorsProvided)));
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy_app::prelude::*;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_asset::LoadState;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_render::texture::TextureFormatPixelInfo;
+use pad::{p, Position};
+
+use crate::double_buffer::present_dynamic_canvases;
+use crate::error::TextureUtilsError;
+use crate::pending_tile_maps::{build_pending_tile_maps, load_state, BuildTarget, PendingTileMaps};
+use crate::tile_map_build_task::finish_tile_map_build_tasks;
+use crate::tile_map_layout::TileMapLayout;
+use crate::tile_map_texture::TileMapTextureCreator;
+use crate::tile_registry::TileRegistry;
+use crate::work_queue::{run_texture_work_queue, TextureWorkQueue};
+
+/// Adds the systems and events of this crate to a bevy app.
+pub struct TextureUtilsPlugin;
+
+impl Plugin for TextureUtilsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<TileChanged>()
+            .add_event::<CreateTileMapTexture>()
+            .add_event::<TileMapTextureCreated>()
+            .init_resource::<TextureWorkQueue>()
+            .init_resource::<PendingTileMaps>()
+            .init_resource::<TileRegistry>()
+            .add_systems(Update, (
+                queue_tile_map_requests.before(build_pending_tile_maps),
+                build_pending_tile_maps,
+                finish_tile_map_build_tasks,
+                update_changed_tiles,
+                run_texture_work_queue,
+            ))
+            .add_systems(PostUpdate, present_dynamic_canvases);
+    }
+}
+
+/// A tile map whose baked texture gets updated when [TileChanged] events for it are sent.
+#[derive(Component, Clone, Debug)]
+pub struct DynamicTileMap {
+    /// The baked tile map texture
+    pub texture: Handle<Image>,
+    /// The bottom left position of the tile map
+    pub origin: Position,
+    /// The width and height of a single tile in pixels
+    pub tile_size: (usize, usize),
+}
+
+/// Tells that the tile at the given position of the given [DynamicTileMap] entity changed.
+/// All changes of a frame are baked into the tile map texture at once. Changes whose tile is not loaded yet are
+/// applied in a later frame. Changes whose tile failed to load or does not fit the tile map are dropped with a warning.
+#[derive(Event, Clone, Debug)]
+pub struct TileChanged {
+    /// The entity with the [DynamicTileMap]
+    pub map: Entity,
+    /// The position of the changed tile
+    pub position: Position,
+    /// The texture of the new tile
+    pub new_tile: Handle<Image>,
+}
+
+/// Requests the creation of a tile map texture. The texture is created as soon as all
+/// tile textures are loaded, which is announced with a [TileMapTextureCreated] event.
+/// The request is handled by [PendingTileMaps], so it is dropped with a warning if a tile fails to load.
+#[derive(Event, Clone, Debug)]
+pub struct CreateTileMapTexture {
+    /// Chosen by the sender to identify the [TileMapTextureCreated] event of this request
+    pub id: u64,
+    pub creator: TileMapTextureCreator,
+    pub positions_and_textures: Vec<(Position, Handle<Image>)>,
+}
+
+/// Tells that the tile map texture of a [CreateTileMapTexture] request was created.
+#[derive(Event, Clone, Debug)]
+pub struct TileMapTextureCreated {
+    /// The id of the request
+    pub id: u64,
+    pub handle: Handle<Image>,
+}
+
+/// A rectangle of tiles, from the min position to the max position (inclusive).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct DirtyRect {
+    min: Position,
+    max: Position,
+}
+
+/// Hand the requested tile maps over to the [PendingTileMaps], which wait for their tiles to be loaded.
+fn queue_tile_map_requests(
+    mut requests: EventReader<CreateTileMapTexture>,
+    mut pending: ResMut<PendingTileMaps>,
+) {
+    for request in requests.read().cloned() {
+        pending.push(request.creator, request.positions_and_textures, BuildTarget::Event(request.id));
+    }
+}
+
+fn update_changed_tiles(
+    mut events: EventReader<TileChanged>,
+    mut pending: Local<Vec<TileChanged>>,
+    maps: Query<&DynamicTileMap>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    // the latest change of a position in a frame wins
+    let mut changes_per_map = HashMap::<Entity, HashMap<Position, Handle<Image>>>::new();
+
+    for event in pending.drain(..).chain(events.read().cloned()) {
+        changes_per_map
+            .entry(event.map)
+            .or_default()
+            .insert(event.position, event.new_tile);
+    }
+
+    for (entity, changes) in changes_per_map {
+        let map = match maps.get(entity) {
+            Ok(map) => map,
+            Err(_) => continue
+        };
+
+        let mut tiles = HashMap::new();
+
+        for (position, new_tile) in changes {
+            match load_state(&new_tile, &images, asset_server.as_deref()) {
+                LoadState::Loaded => match images.get(&new_tile) {
+                    Some(tile) => { tiles.insert(position, tile.clone()); }
+                    None => pending.push(TileChanged { map: entity, position, new_tile })
+                },
+                LoadState::Failed => bevy_log::warn!("Dropped the change of the tile at {position:?}, as its tile failed to load"),
+                // tiles which are not loaded yet are applied in a later frame
+                _ => pending.push(TileChanged { map: entity, position, new_tile })
+            }
+        }
+
+        if tiles.is_empty() {
+            continue;
+        }
+
+        if let Err(e) = patch_tile_map(&mut images, map, tiles) {
+            bevy_log::warn!("Could not update the tile map texture: {e}");
+        }
+    }
+}
+
+/// Merge the given tile positions to as few rectangles as possible. First, horizontally
+/// adjacent positions are merged to runs, then runs which cover the same columns in
+/// adjacent rows are merged to rectangles.
+fn merge_dirty_rects(positions: impl IntoIterator<Item=Position>) -> Vec<DirtyRect> {
+    let mut rows = BTreeMap::<isize, Vec<isize>>::new();
+
+    for pos in positions {
+        rows.entry(pos.y).or_default().push(pos.x);
+    }
+
+    let mut rects = Vec::<DirtyRect>::new();
+
+    for (y, mut xs) in rows {
+        xs.sort();
+        xs.dedup();
+
+        let mut runs = Vec::<(isize, isize)>::new();
+
+        for x in xs {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == x => *end = x,
+                _ => runs.push((x, x))
+            }
+        }
+
+        for (start, end) in runs {
+            match rects.iter_mut().find(|r| r.min.x == start && r.max.x == end && r.max.y + 1 == y) {
+                Some(rect) => rect.max.y = y,
+                None => rects.push(DirtyRect { min: p!(start, y), max: p!(end, y) })
+            }
+        }
+    }
+
+    rects
+}
+
+/// Copy the given tiles into the tile map texture, touching it only once. All tiles are checked before anything is written,
+/// and the tiles which don't fit the tile map, as they have another format or size or are outside of it, are dropped with a warning.
+fn patch_tile_map(
+    images: &mut Assets<Image>,
+    map: &DynamicTileMap,
+    mut tiles: HashMap<Position, Image>,
+) -> Result<(), TextureUtilsError> {
+    let texture = images
+        .get(&map.texture)
+        .ok_or(TextureUtilsError::ImageNotLoaded { handle: map.texture.id().untyped() })?;
+    let format = texture.texture_descriptor.format;
+    let pixel_size = format.pixel_size();
+    let layout = TileMapLayout::with_pixel_size(texture, map.origin, map.tile_size, pixel_size)?;
+
+    tiles.retain(|pos, tile| match check_changed_tile(&layout, format, *pos, tile) {
+        Ok(_) => true,
+        Err(e) => {
+            bevy_log::warn!("Dropped the change of the tile at {pos:?}: {e}");
+            false
+        }
+    });
+
+    if tiles.is_empty() {
+        return Ok(());
+    }
+
+    let texture = images
+        .get_mut(&map.texture)
+        .ok_or(TextureUtilsError::ImageNotLoaded { handle: map.texture.id().untyped() })?;
+    let row_length = layout.tile_width * pixel_size;
+
+    for rect in merge_dirty_rects(tiles.keys().copied()) {
+        for y in rect.min.y..=rect.max.y {
+            for x in rect.min.x..=rect.max.x {
+                let pos = p!(x, y);
+                let tile = &tiles[&pos];
+                let (tile_x, tile_y) = layout.tile_origin(pos).expect("The position was checked");
+
+                for row in 0..layout.tile_height {
+                    let map_index = ((tile_y + row) * layout.width + tile_x) * pixel_size;
+                    let tile_index = row * row_length;
+
+                    texture.data[map_index..map_index + row_length].copy_from_slice(&tile.data[tile_index..tile_index + row_length]);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the given tile can be copied into the tile map with the given layout and format.
+fn check_changed_tile(layout: &TileMapLayout, format: TextureFormat, pos: Position, tile: &Image) -> Result<(), TextureUtilsError> {
+    if tile.texture_descriptor.format != format {
+        return Err(TextureUtilsError::FormatMismatch { expected: format, actual: tile.texture_descriptor.format, position: Some(pos) });
+    }
+
+    if tile.width() as usize != layout.tile_width || tile.height() as usize != layout.tile_height {
+        return Err(TextureUtilsError::TileSizeMismatch {
+            expected: (layout.tile_width, layout.tile_height),
+            actual: (tile.width() as usize, tile.height() as usize),
+            position: Some(pos),
+        });
+    }
+
+    if tile.data.len() != layout.tile_width * layout.tile_height * format.pixel_size() {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    match layout.tile_origin(pos) {
+        Some(_) => Ok(()),
+        None => Err(TextureUtilsError::PositionOutOfBounds { position: pos })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_asset::prelude::*;
+    use bevy_ecs::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::image_options::ImageOptions;
+    use crate::plugin::{CreateTileMapTexture, DirtyRect, DynamicTileMap, merge_dirty_rects, TextureUtilsPlugin, TileChanged, TileMapTextureCreated};
+    use crate::tile_map_texture::TileMapTextureCreator;
+
+    #[test]
+    fn tile_changed_updates_tile_map_texture() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let texture = images.add(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let green = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]));
+
+        let map = app.world.spawn(DynamicTileMap {
+            texture: texture.clone(),
+            origin: p!(0, 0),
+            tile_size: (1, 1),
+        }).id();
+
+        // act
+        app.world.send_event(TileChanged { map, position: p!(0, 0), new_tile: green });
+        app.world.send_event(TileChanged { map, position: p!(1, 1), new_tile: blue.clone() });
+        app.world.send_event(TileChanged { map, position: p!(0, 0), new_tile: blue });
+        app.update();
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::BLUE,
+                Color::BLUE, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, app.world.resource::<Assets<Image>>().get(&texture).unwrap().data);
+    }
+
+    /// Changes which don't fit the tile map are dropped without affecting the others, and maps of any format can be patched.
+    #[test]
+    fn tile_changed_drops_invalid_tiles() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let texture = images.add(ImageOptions::default().create_image((3, 1), vec![1, 2, 3], TextureFormat::R8Unorm));
+        let valid = images.add(ImageOptions::default().create_image((1, 1), vec![9], TextureFormat::R8Unorm));
+        let wrong_format = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+
+        let map = app.world.spawn(DynamicTileMap {
+            texture: texture.clone(),
+            origin: p!(0, 0),
+            tile_size: (1, 1),
+        }).id();
+
+        // act
+        app.world.send_event(TileChanged { map, position: p!(0, 0), new_tile: wrong_format });
+        app.world.send_event(TileChanged { map, position: p!(1, 0), new_tile: valid.clone() });
+        app.world.send_event(TileChanged { map, position: p!(5, 0), new_tile: valid });
+        app.update();
+
+        // assert
+        assert_eq!(vec![1, 9, 3], app.world.resource::<Assets<Image>>().get(&texture).unwrap().data);
+    }
+
+    /// The texture must only be created once all tiles are loaded.
+    #[test]
+    fn create_tile_map_texture_waits_for_tiles() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.reserve_handle();
+
+        app.world.send_event(CreateTileMapTexture {
+            id: 7,
+            creator: TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1),
+            positions_and_textures: vec![(p!(0, 0), red), (p!(1, 0), blue.clone())],
+        });
+
+        // act
+        app.update();
+        let created_before_loading = app.world.resource::<Events<TileMapTextureCreated>>().len();
+
+        app.world.resource_mut::<Assets<Image>>().insert(&blue, create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        app.update();
+
+        // assert
+        assert_eq!(0, created_before_loading);
+
+        let events = app.world.resource::<Events<TileMapTextureCreated>>();
+        let created = events.iter_current_update_events().next().expect("The texture should have been created, but wasn't.");
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        assert_eq!(7, created.id);
+        assert_eq!(expected.data, app.world.resource::<Assets<Image>>().get(&created.handle).unwrap().data);
+    }
+
+    #[test]
+    fn merge_dirty_rects_works() {
+        // act
+        let rects = merge_dirty_rects([
+            p!(0, 0), p!(1, 0), p!(0, 1), p!(1, 1),
+            p!(3, 0),
+            p!(0, 2),
+        ]);
+
+        // assert
+        assert_eq!(
+            vec![
+                DirtyRect { min: p!(0, 0), max: p!(1, 1) },
+                DirtyRect { min: p!(3, 0), max: p!(3, 0) },
+                DirtyRect { min: p!(0, 2), max: p!(0, 2) },
+            ],
+            rects
+        );
+    }
+}
@@ -0,0 +1,57 @@
+/// Small xorshift generator, so random results like eroded heightmaps or tilings are reproducible with a seed.
+pub(crate) struct Random(u64);
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Self {
+        const SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+        // xorshift must not start with 0, which happens for the seed equal to the mix
+        match seed ^ SEED_MIX {
+            0 => Self(SEED_MIX),
+            state => Self(state)
+        }
+    }
+
+    /// Create a generator for the given position, so every position gets its own random values,
+    /// independent of the order in which the positions are visited.
+    pub(crate) fn for_position(seed: u64, x: isize, y: isize) -> Self {
+        // the finalizer of splitmix64, so neighbouring positions get unrelated values
+        let mut hash = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        Self::new(hash ^ (hash >> 31))
+    }
+
+    /// A random value from 0.0 (inclusive) to 1.0 (exclusive).
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A random index into a collection with the given length, which must not be 0.
+    pub(crate) fn next_index(&mut self, len: usize) -> usize {
+        ((self.next_f32() * len as f32) as usize).min(len - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::Random;
+
+    /// Every seed must produce random values, as xorshift gets stuck at a state of 0.
+    #[test]
+    fn seed_equal_to_the_mix_is_not_stuck() {
+        // arrange
+        let mut random = Random::new(0x9E37_79B9_7F4A_7C15);
+
+        // act
+        let values = (0..4).map(|_| random.next_f32()).collect::<Vec<_>>();
+
+        // assert
+        assert!(values.iter().any(|value| *value > 0.0), "The values should be random, but were {values:?}.");
+    }
+}
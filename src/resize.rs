@@ -0,0 +1,273 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::{Extent3d, TextureFormat};
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::error::TextureUtilsError;
+
+/// How the pixels of a resized texture are sampled from the original one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FilterMode {
+    /// Every pixel is a copy of the closest original pixel, which keeps pixel art crisp
+    Nearest,
+    /// Every pixel is interpolated from the closest original pixels
+    Bilinear,
+    /// Every pixel is interpolated from the original pixels in a radius of three with a windowed sinc,
+    /// which is sharper than bilinear filtering, but can add slight halos at hard edges
+    Lanczos3,
+}
+
+impl FilterMode {
+    /// The radius of the filter kernel in pixels
+    fn radius(&self) -> f32 {
+        match self {
+            FilterMode::Nearest => 0.0,
+            FilterMode::Bilinear => 1.0,
+            FilterMode::Lanczos3 => 3.0,
+        }
+    }
+
+    /// The weight of a pixel at the given distance
+    fn weight(&self, distance: f32) -> f32 {
+        let distance = distance.abs();
+
+        match self {
+            FilterMode::Nearest => 1.0,
+            FilterMode::Bilinear => (1.0 - distance).max(0.0),
+            FilterMode::Lanczos3 => match distance < 3.0 {
+                true => sinc(distance) * sinc(distance / 3.0),
+                false => 0.0
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    match x == 0.0 {
+        true => 1.0,
+        false => (std::f32::consts::PI * x).sin() / (std::f32::consts::PI * x)
+    }
+}
+
+/// Create a copy of the given texture with the given size. Besides the size, the copy keeps
+/// all properties of the texture, like its format and sampler.
+/// Nearest filtering works with every uncompressed format. Bilinear and Lanczos3 filtering only work with
+/// 8-bit RGBA and BGRA formats, where the sRGB formats are filtered in linear space. Colors are weighted
+/// by their alpha, so transparent pixels don't darken their neighbours.
+pub fn resize(texture: &Image, new_width: usize, new_height: usize, filter: FilterMode) -> Result<Image, TextureUtilsError> {
+    if new_width == 0 || new_height == 0 {
+        return Err(TextureUtilsError::ZeroSize { size: (new_width, new_height) });
+    }
+
+    let data = match filter {
+        FilterMode::Nearest => resize_nearest(texture, new_width, new_height),
+        _ => resize_filtered(texture, new_width, new_height, filter)?
+    };
+
+    let mut resized = texture.clone();
+    resized.texture_descriptor.size = Extent3d { width: new_width as u32, height: new_height as u32, depth_or_array_layers: 1 };
+    resized.data = data;
+    Ok(resized)
+}
+
+fn resize_nearest(texture: &Image, new_width: usize, new_height: usize) -> Vec<u8> {
+    let bytes_per_pixel = texture.texture_descriptor.format.pixel_size();
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+    let mut data = Vec::with_capacity(new_width * new_height * bytes_per_pixel);
+
+    for y in 0..new_height {
+        let src_y = y * height / new_height;
+
+        for x in 0..new_width {
+            let src_x = x * width / new_width;
+            let index = (src_y * width + src_x) * bytes_per_pixel;
+            data.extend_from_slice(&texture.data[index..index + bytes_per_pixel]);
+        }
+    }
+
+    data
+}
+
+fn resize_filtered(texture: &Image, new_width: usize, new_height: usize, filter: FilterMode) -> Result<Vec<u8>, TextureUtilsError> {
+    let srgb = match texture.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm | TextureFormat::Bgra8Unorm => false,
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+
+    // linear colors premultiplied with alpha
+    let pixels = texture.data
+        .chunks_exact(4)
+        .map(|pixel| {
+            let alpha = pixel[3] as f32 / 255.0;
+            let channel = |value: u8| match srgb {
+                true => srgb_to_linear(value),
+                false => value as f32 / 255.0
+            } * alpha;
+
+            [channel(pixel[0]), channel(pixel[1]), channel(pixel[2]), alpha]
+        })
+        .collect::<Vec<_>>();
+
+    // the filter is separable, so the rows are resized first and the columns afterwards
+    let horizontal = weights(width, new_width, filter);
+    let pixels = &pixels;
+    let rows = (0..height)
+        .flat_map(|y| horizontal.iter().map(move |(start, weights)| weighted_sum(weights, |i| pixels[y * width + start + i])))
+        .collect::<Vec<_>>();
+
+    let vertical = weights(height, new_height, filter);
+    let rows = &rows;
+    let resized = vertical
+        .iter()
+        .flat_map(|(start, weights)| (0..new_width).map(move |x| (start, weights, x)))
+        .map(|(start, weights, x)| weighted_sum(weights, |i| rows[(start + i) * new_width + x]));
+
+    Ok(resized
+        .flat_map(|[r, g, b, a]| {
+            let alpha = a.clamp(0.0, 1.0);
+            let channel = |value: f32| {
+                let value = match alpha > 0.0 {
+                    true => value / alpha,
+                    false => 0.0
+                };
+
+                match srgb {
+                    true => linear_to_srgb(value),
+                    false => (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                }
+            };
+
+            [channel(r), channel(g), channel(b), (alpha * 255.0).round() as u8]
+        })
+        .collect())
+}
+
+/// The first original pixel and the weights of all original pixels which contribute to every new pixel along one axis.
+fn weights(length: usize, new_length: usize, filter: FilterMode) -> Vec<(usize, Vec<f32>)> {
+    let scale = length as f32 / new_length as f32;
+    // when shrinking, the kernel is stretched so every original pixel contributes
+    let filter_scale = scale.max(1.0);
+    let support = filter.radius() * filter_scale;
+
+    (0..new_length)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let start = ((center - support).floor().max(0.0) as usize).min(length - 1);
+            let end = ((center + support).ceil() as usize).clamp(start + 1, length);
+
+            let weights = (start..end)
+                .map(|j| filter.weight((j as f32 + 0.5 - center) / filter_scale))
+                .collect::<Vec<_>>();
+            let sum = weights.iter().sum::<f32>();
+
+            match sum.abs() > f32::EPSILON {
+                true => (start, weights.into_iter().map(|w| w / sum).collect()),
+                // no pixel is in reach of the kernel, so use the closest one
+                false => ((center as usize).min(length - 1), vec![1.0])
+            }
+        })
+        .collect()
+}
+
+fn weighted_sum(weights: &[f32], pixel: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    weights
+        .iter()
+        .enumerate()
+        .fold([0.0; 4], |mut sum, (i, weight)| {
+            let pixel = pixel(i);
+
+            for c in 0..4 {
+                sum[c] += pixel[c] * weight;
+            }
+
+            sum
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::resize::{FilterMode, resize};
+
+    #[test]
+    fn resize_nearest_works() {
+        // arrange
+        let texture = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        // act
+        let resized = resize(&texture, 4, 2, FilterMode::Nearest).unwrap();
+
+        // assert
+        let expected = create_image(
+            (4, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::RED, Color::BLUE, Color::BLUE,
+                Color::RED, Color::RED, Color::BLUE, Color::BLUE,
+            ],
+        );
+
+        assert_eq!((4, 2), (resized.width(), resized.height()));
+        assert_eq!(expected.data, resized.data);
+    }
+
+    /// Shrinking averages the pixels in linear space, where transparent pixels don't contribute any color.
+    #[test]
+    fn resize_filtered_averages_pixels() {
+        // arrange
+        let texture = create_image(
+            (2, 2),
+            TextureFormat::Rgba8Unorm,
+            [
+                Color::WHITE, Color::BLACK,
+                Color::rgba(0.0, 1.0, 0.0, 0.0), Color::WHITE,
+            ],
+        );
+
+        for filter in [FilterMode::Bilinear, FilterMode::Lanczos3] {
+            // act
+            let resized = resize(&texture, 1, 1, filter).unwrap();
+
+            // assert
+            let [r, g, b, a] = [0, 1, 2, 3].map(|i| resized.data[i]);
+
+            assert_eq!((170, 170, 170), (r, g, b), "{:?} should average the visible colors, but didn't.", filter);
+            assert_eq!(191, a, "{:?} should average the alpha, but didn't.", filter);
+        }
+    }
+
+    #[test]
+    fn resize_filtered_keeps_uniform_colors() {
+        // arrange
+        let texture = create_image((3, 3), TextureFormat::Rgba8UnormSrgb, [Color::rgb(0.2, 0.4, 0.6); 9]);
+
+        for filter in [FilterMode::Bilinear, FilterMode::Lanczos3] {
+            // act
+            let resized = resize(&texture, 7, 5, filter).unwrap();
+
+            // assert
+            let expected = create_image((7, 5), TextureFormat::Rgba8UnormSrgb, [Color::rgb(0.2, 0.4, 0.6); 35]);
+
+            assert_eq!(expected.data, resized.data, "{:?} should keep the uniform color, but didn't.", filter);
+        }
+    }
+
+    #[test]
+    fn resize_to_zero_fails() {
+        // arrange
+        let texture = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+
+        // act
+        let result = resize(&texture, 0, 1, FilterMode::Nearest);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TextureUtilsError::ZeroSize { size: (0, 1) }));
+    }
+}
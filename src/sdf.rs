@@ -0,0 +1,190 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use half::f16;
+
+use crate::collision_mask::{extract_collision_mask, BitGrid};
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// The format of the signed distance field created by [generate_sdf].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SdfFormat {
+    /// R8Unorm, where 0.5 is the edge and the distances from -spread to spread are mapped to 0.0 to 1.0
+    R8,
+    /// R16Float with the distance in pixels, clamped to -spread to spread
+    R16Float,
+}
+
+/// Create a signed distance field from the alpha of the image, for example to render crisp icons at any scale or to add
+/// outlines and glows in shaders. The distances are measured in pixels from the edge between the pixels with an alpha of
+/// at least 128 and the others, and are positive inside and negative outside. The spread is the largest distance which
+/// is stored. See [extract_collision_mask] for the supported formats. The field is created with the given options,
+/// which usually should have a linear sampler.
+pub fn generate_sdf(image: &Image, spread: f32, format: SdfFormat, options: ImageOptions) -> Result<Image, TextureUtilsError> {
+    if spread <= 0.0 {
+        return Err(TextureUtilsError::OutOfRange { name: "spread", value: spread as f64, expected: "positive" });
+    }
+
+    let mask = extract_collision_mask(image, 128)?;
+    let (width, height) = (mask.width(), mask.height());
+    let distance_to_outside = distance_transform(&mask, false);
+    let distance_to_inside = distance_transform(&mask, true);
+
+    // the distances are measured between the pixel centers, but the edge is half a pixel away from them
+    let distances = (0..width * height).map(|i| match mask.get(i % width, i / width) {
+        true => distance_to_outside[i].sqrt() - 0.5,
+        false => 0.5 - distance_to_inside[i].sqrt()
+    }.clamp(-spread, spread));
+
+    let (data, texture_format) = match format {
+        SdfFormat::R8 => (
+            distances.map(|distance| ((0.5 + distance / (2.0 * spread)) * 255.0).round() as u8).collect(),
+            TextureFormat::R8Unorm
+        ),
+        SdfFormat::R16Float => (
+            distances.flat_map(|distance| f16::from_f32(distance).to_le_bytes()).collect(),
+            TextureFormat::R16Float
+        ),
+    };
+
+    Ok(options.create_image((width, height), data, texture_format))
+}
+
+/// The squared distance of every pixel to the center of the closest pixel whose solidity is the given one,
+/// computed exactly with the algorithm of Felzenszwalb and Huttenlocher.
+fn distance_transform(mask: &BitGrid, solid: bool) -> Vec<f32> {
+    // large enough to be infinite, but small enough to not overflow when squared distances are added
+    const INFINITY: f32 = 1e20;
+
+    let (width, height) = (mask.width(), mask.height());
+    let mut distances = (0..width * height)
+        .map(|i| match mask.get(i % width, i / width) == solid {
+            true => 0.0,
+            false => INFINITY
+        })
+        .collect::<Vec<_>>();
+
+    for x in 0..width {
+        let column = (0..height).map(|y| distances[y * width + x]).collect::<Vec<_>>();
+
+        for (y, distance) in distance_transform_1d(&column).into_iter().enumerate() {
+            distances[y * width + x] = distance;
+        }
+    }
+
+    for row in distances.chunks_exact_mut(width.max(1)) {
+        let transformed = distance_transform_1d(row);
+        row.copy_from_slice(&transformed);
+    }
+
+    distances
+}
+
+/// The lower envelope of the parabolas rooted at the given squared distances.
+fn distance_transform_1d(values: &[f32]) -> Vec<f32> {
+    let parabola_intersection = |q: usize, p: usize| {
+        let (q_f, p_f) = (q as f32, p as f32);
+        ((values[q] + q_f * q_f) - (values[p] + p_f * p_f)) / (2.0 * q_f - 2.0 * p_f)
+    };
+
+    // the positions of the parabolas in the envelope and where they start to be the lowest
+    let mut parabolas = vec![0];
+    let mut boundaries = vec![f32::NEG_INFINITY];
+
+    for q in 1..values.len() {
+        let mut intersection = parabola_intersection(q, *parabolas.last().expect("The envelope is never empty"));
+
+        while intersection <= *boundaries.last().expect("The envelope is never empty") {
+            parabolas.pop();
+            boundaries.pop();
+            intersection = parabola_intersection(q, *parabolas.last().expect("The envelope is never empty"));
+        }
+
+        parabolas.push(q);
+        boundaries.push(intersection);
+    }
+
+    let mut current = 0;
+
+    (0..values.len())
+        .map(|q| {
+            while current + 1 < parabolas.len() && boundaries[current + 1] < q as f32 {
+                current += 1;
+            }
+
+            let p = parabolas[current];
+            (q as f32 - p as f32).powi(2) + values[p]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_asset::RenderAssetUsages;
+    use bevy_render::render_resource::TextureFormat;
+    use bevy_render::texture::ImageSampler;
+    use half::f16;
+
+    use crate::builders::create_image;
+    use crate::image_options::ImageOptions;
+    use crate::sdf::{generate_sdf, SdfFormat};
+
+    #[test]
+    fn generate_sdf_works() {
+        // arrange
+        let n = Color::NONE;
+        let w = Color::WHITE;
+        let image = create_image((5, 1), TextureFormat::Rgba8UnormSrgb, [n, n, w, n, n]);
+
+        // act
+        let r8 = generate_sdf(&image, 2.0, SdfFormat::R8, ImageOptions::default()).unwrap();
+        let r16 = generate_sdf(&image, 1.0, SdfFormat::R16Float, ImageOptions::default()).unwrap();
+
+        // assert
+        let distances = r16.data
+            .chunks_exact(2)
+            .map(|bytes| f16::from_le_bytes([bytes[0], bytes[1]]).to_f32())
+            .collect::<Vec<_>>();
+
+        assert_eq!(TextureFormat::R8Unorm, r8.texture_descriptor.format);
+        assert_eq!(vec![32, 96, 159, 96, 32], r8.data);
+        assert_eq!(TextureFormat::R16Float, r16.texture_descriptor.format);
+        assert_eq!(vec![-1.0, -0.5, 0.5, -0.5, -1.0], distances);
+    }
+
+    #[test]
+    fn generate_sdf_measures_euclidean_distances() {
+        // arrange
+        let mut mask = vec![0; 16];
+        mask[0] = 255;
+        let image = ImageOptions::default().create_image((4, 4), mask, TextureFormat::R8Unorm);
+
+        // act
+        let sdf = generate_sdf(&image, 10.0, SdfFormat::R16Float, ImageOptions::default()).unwrap();
+
+        // assert
+        let distance = |x: usize, y: usize| {
+            let index = (y * 4 + x) * 2;
+            f16::from_le_bytes([sdf.data[index], sdf.data[index + 1]]).to_f32()
+        };
+
+        assert!((distance(3, 3) - (0.5 - 18f32.sqrt())).abs() < 0.01);
+        assert!((distance(2, 1) - (0.5 - 5f32.sqrt())).abs() < 0.01);
+    }
+
+    /// The configured options must be applied to the created field.
+    #[test]
+    fn generate_sdf_with_options_works() {
+        // arrange
+        let image = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE, Color::NONE]);
+        let options = ImageOptions::new(RenderAssetUsages::RENDER_WORLD).with_sampler(ImageSampler::linear());
+
+        // act
+        let sdf = generate_sdf(&image, 1.0, SdfFormat::R8, options).unwrap();
+
+        // assert
+        assert_eq!(RenderAssetUsages::RENDER_WORLD, sdf.asset_usage);
+        assert!(matches!(sdf.sampler, ImageSampler::Descriptor(_)), "The sampler should be the configured one, but wasn't.");
+    }
+}
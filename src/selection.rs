@@ -0,0 +1,247 @@
+use bevy_render::prelude::*;
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+use crate::texture_modification::PixelBytes;
+
+/// A selection of pixels of an image, stored as a bit mask with one bit per pixel.
+/// Edits can be restricted to the selected pixels.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Selection {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl Selection {
+    /// Create an empty selection for an image of the given size.
+    pub fn new((width, height): (usize, usize)) -> Self {
+        Self { width, height, bits: vec![0; (width * height).div_ceil(64)] }
+    }
+
+    /// Create a selection for an image of the given size with all pixels in the given rectangle selected.
+    pub fn from_rect(size: (usize, usize), rect: PixelRect) -> Self {
+        let mut selection = Self::new(size);
+        selection.add_rect(rect);
+        selection
+    }
+
+    /// Select all pixels of the given image whose alpha is at least the given threshold.
+    /// The image must have an 8-bit RGBA or BGRA format.
+    pub fn from_alpha(image: &Image, threshold: u8) -> Result<Self, TextureUtilsError> {
+        is_srgb_rgba8(image.texture_descriptor.format)?;
+
+        let mut selection = Self::new((image.width() as usize, image.height() as usize));
+
+        for (i, pixel) in image.data.chunks_exact(4).enumerate() {
+            if pixel[3] >= threshold {
+                selection.set(i % selection.width, i / selection.width, true);
+            }
+        }
+
+        Ok(selection)
+    }
+
+    /// Select the area of similar colors connected to the given pixel, like a magic wand tool.
+    /// A pixel is similar if none of its channels differs by more than the tolerance from the
+    /// given pixel. Only horizontally and vertically adjacent pixels are connected.
+    /// The image must have an 8-bit RGBA or BGRA format.
+    pub fn magic_wand(image: &Image, (x, y): (usize, usize), tolerance: u8) -> Result<Self, TextureUtilsError> {
+        is_srgb_rgba8(image.texture_descriptor.format)?;
+
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let mut selection = Self::new((width, height));
+
+        if image.data.len() != width * height * 4 {
+            return Err(TextureUtilsError::UnsupportedPixelSize);
+        }
+
+        if x >= width || y >= height {
+            return Ok(selection);
+        }
+
+        let pixel_at = |x: usize, y: usize| -> &[u8] {
+            let index = (y * width + x) * 4;
+            &image.data[index..index + 4]
+        };
+        let start = pixel_at(x, y);
+        let is_similar = |pixel: &[u8]| pixel
+            .iter()
+            .zip(start)
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance);
+
+        let mut stack = vec![(x, y)];
+        selection.set(x, y, true);
+
+        while let Some((x, y)) = stack.pop() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+
+            for (nx, ny) in neighbours {
+                if nx < width && ny < height && !selection.is_selected(nx, ny) && is_similar(pixel_at(nx, ny)) {
+                    selection.set(nx, ny, true);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        Ok(selection)
+    }
+
+    /// Add all pixels in the given rectangle to the selection. Parts outside of the image are ignored.
+    pub fn add_rect(&mut self, rect: PixelRect) {
+        for y in rect.y..(rect.y + rect.height).min(self.height) {
+            for x in rect.x..(rect.x + rect.width).min(self.width) {
+                self.set(x, y, true);
+            }
+        }
+    }
+
+    /// Add all pixels selected by the other selection to this one.
+    pub fn union(&mut self, other: &Selection) {
+        self.bits.iter_mut().zip(&other.bits).for_each(|(a, b)| *a |= b);
+    }
+
+    /// Select all pixels that were not selected and deselect all that were.
+    pub fn invert(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, !self.is_selected(x, y));
+            }
+        }
+    }
+
+    /// Select or deselect the given pixel.
+    pub fn set(&mut self, x: usize, y: usize, selected: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = y * self.width + x;
+
+        match selected {
+            true => self.bits[index / 64] |= 1 << (index % 64),
+            false => self.bits[index / 64] &= !(1 << (index % 64))
+        }
+    }
+
+    /// Tells if the given pixel is selected. Pixels outside of the image never are.
+    pub fn is_selected(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let index = y * self.width + x;
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Restrict the given pixel mapper to the selected pixels. Every other pixel stays unchanged.
+    pub fn restrict<'a>(
+        &'a self,
+        pixel_mapper: impl Fn(usize, usize, PixelBytes) -> PixelBytes + 'a,
+    ) -> impl Fn(usize, usize, PixelBytes) -> PixelBytes + 'a {
+        move |x, y, pixel| match self.is_selected(x, y) {
+            true => pixel_mapper(x, y, pixel),
+            false => pixel
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::pixel_rect::PixelRect;
+    use crate::selection::Selection;
+    use crate::texture_modification::modify_texture;
+
+    #[test]
+    fn magic_wand_works() {
+        // arrange
+        let image = create_image(
+            (3, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::RED, Color::BLUE,
+                Color::BLUE, Color::RED, Color::BLUE,
+                Color::RED, Color::BLUE, Color::RED,
+            ],
+        );
+
+        // act
+        let selection = Selection::magic_wand(&image, (0, 0), 10).unwrap();
+
+        // assert
+        let selected = (0..9).map(|i| selection.is_selected(i % 3, i / 3)).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                true, true, false,
+                false, true, false,
+                false, false, false,
+            ],
+            selected,
+            "Only the red pixels connected to the start should be selected, but weren't."
+        );
+    }
+
+    #[test]
+    fn from_alpha_works() {
+        // arrange
+        let image = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::NONE]);
+
+        // act
+        let selection = Selection::from_alpha(&image, 128).unwrap();
+
+        // assert
+        assert!(selection.is_selected(0, 0));
+        assert!(!selection.is_selected(1, 0));
+    }
+
+    #[test]
+    fn magic_wand_and_from_alpha_with_unsupported_format_fail() {
+        // arrange
+        let image = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let magic_wand = Selection::magic_wand(&image, (0, 0), 10);
+        let from_alpha = Selection::from_alpha(&image, 128);
+
+        // assert
+        assert!(matches!(magic_wand, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+        assert!(matches!(from_alpha, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+
+    /// A mapper restricted to a selection must only change the selected pixels.
+    #[test]
+    fn modify_texture_with_selection_works() {
+        // arrange
+        let mut image = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]);
+        let selection = Selection::from_rect((2, 2), PixelRect::new(1, 0, 5, 1));
+
+        // act
+        modify_texture(&mut image, selection.restrict(|_, _, _| Color::GREEN.as_rgba_u8()));
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN,
+                Color::RED, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, image.data);
+    }
+}
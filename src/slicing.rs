@@ -0,0 +1,177 @@
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+
+use crate::collision_mask::extract_collision_mask;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+use crate::transform::crop;
+
+/// Find all sprites of a sheet which are separated by transparency, like Aseprite's auto slice, and crop each of them.
+/// A sprite consists of all pixels with an alpha of at least the given threshold which are connected horizontally,
+/// vertically or diagonally. Every sprite is returned with its bounds in the sheet, ordered row by row by its topmost
+/// pixel. If the bounds of sprites overlap, the cropped images contain parts of the other sprites.
+/// See [extract_collision_mask] for the supported formats.
+pub fn auto_slice(sheet: &Image, alpha_threshold: u8) -> Result<Vec<(PixelRect, Image)>, TextureUtilsError> {
+    let mut mask = extract_collision_mask(sheet, alpha_threshold)?;
+    let (width, height) = (mask.width(), mask.height());
+    let mut bounds = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            if !mask.get(x, y) {
+                continue;
+            }
+
+            // flood fill the sprite and remove it from the mask, so every pixel is visited once
+            let mut rect = PixelRect::new(x, y, 1, 1);
+            let mut stack = vec![(x, y)];
+            mask.set(x, y, false);
+
+            while let Some((px, py)) = stack.pop() {
+                rect = rect.union(&PixelRect::new(px, py, 1, 1));
+
+                for ny in py.saturating_sub(1)..=(py + 1).min(height - 1) {
+                    for nx in px.saturating_sub(1)..=(px + 1).min(width - 1) {
+                        if mask.get(nx, ny) {
+                            mask.set(nx, ny, false);
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+
+            bounds.push(rect);
+        }
+    }
+
+    bounds
+        .into_iter()
+        .map(|rect| Ok((rect, crop(sheet, rect)?)))
+        .collect()
+}
+
+/// Cut a sheet with a regular grid of tiles, like a tileset, into one image per tile, ordered row by row.
+/// The margin is the amount of pixels between the border of the sheet and the first tiles, and the spacing the amount of
+/// pixels between neighbouring tiles. Tiles which don't fit completely into the sheet are ignored.
+/// The inverse of [TileMapTextureCreator](crate::tile_map_texture::TileMapTextureCreator), which works with every
+/// uncompressed texture format.
+pub fn slice_grid(
+    sheet: &Image,
+    tile_width: usize,
+    tile_height: usize,
+    margin: usize,
+    spacing: usize,
+) -> Result<Vec<Image>, TextureUtilsError> {
+    if tile_width == 0 || tile_height == 0 {
+        return Err(TextureUtilsError::ZeroSize { size: (tile_width, tile_height) });
+    }
+
+    let tiles_along = |sheet_length: usize, tile_length: usize| (sheet_length + spacing).saturating_sub(2 * margin) / (tile_length + spacing);
+    let columns = tiles_along(sheet.width() as usize, tile_width);
+    let rows = tiles_along(sheet.height() as usize, tile_height);
+
+    (0..columns * rows)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            let rect = PixelRect::new(
+                margin + column * (tile_width + spacing),
+                margin + row * (tile_height + spacing),
+                tile_width,
+                tile_height,
+            );
+
+            crop(sheet, rect)
+        })
+        .collect()
+}
+
+/// Like [slice_grid], but the sheet is taken from the images and every tile is added to them.
+pub fn slice_grid_into_assets(
+    images: &mut Assets<Image>,
+    sheet: &Handle<Image>,
+    tile_width: usize,
+    tile_height: usize,
+    margin: usize,
+    spacing: usize,
+) -> Result<Vec<Handle<Image>>, TextureUtilsError> {
+    let sheet_image = images
+        .get(sheet)
+        .ok_or(TextureUtilsError::ImageNotLoaded { handle: sheet.id().untyped() })?;
+
+    Ok(slice_grid(sheet_image, tile_width, tile_height, margin, spacing)?
+        .into_iter()
+        .map(|tile| images.add(tile))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::pixel_rect::PixelRect;
+    use crate::slicing::{auto_slice, slice_grid, slice_grid_into_assets};
+
+    const N: Color = Color::NONE;
+    const R: Color = Color::RED;
+    const B: Color = Color::BLUE;
+
+    #[test]
+    fn auto_slice_works() {
+        // arrange
+        let sheet = create_image(
+            (5, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, B, N,
+                R, N, N, N, B,
+                N, R, N, N, N,
+                R, R, N, B, B,
+            ],
+        );
+
+        // act
+        let sprites = auto_slice(&sheet, 128).unwrap();
+
+        // assert
+        let rects = sprites.iter().map(|(rect, _)| *rect).collect::<Vec<_>>();
+
+        assert_eq!(vec![PixelRect::new(3, 0, 2, 2), PixelRect::new(0, 1, 2, 3), PixelRect::new(3, 3, 2, 1)], rects);
+        assert_eq!(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [B, N, N, B]).data, sprites[0].1.data);
+        assert_eq!(create_image((2, 3), TextureFormat::Rgba8UnormSrgb, [R, N, N, R, R, R]).data, sprites[1].1.data);
+    }
+
+    #[test]
+    fn slice_grid_works() {
+        // arrange
+        const G: Color = Color::GREEN;
+        const Y: Color = Color::YELLOW;
+
+        // a margin of one pixel, a spacing of one pixel and 2x1 tiles, where the last column is cut off
+        let sheet = create_image(
+            (9, 5),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, N, N, N, N, N, N,
+                N, R, R, N, B, B, N, R, N,
+                N, N, N, N, N, N, N, N, N,
+                N, G, G, N, Y, Y, N, R, N,
+                N, N, N, N, N, N, N, N, N,
+            ],
+        );
+        let mut images = Assets::<Image>::default();
+        let handle = images.add(sheet.clone());
+
+        // act
+        let tiles = slice_grid(&sheet, 2, 1, 1, 1).unwrap();
+        let handles = slice_grid_into_assets(&mut images, &handle, 2, 1, 1, 1).unwrap();
+
+        // assert
+        let expected = [R, B, G, Y].map(|color| create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [color; 2]).data);
+
+        assert_eq!(expected.to_vec(), tiles.iter().map(|tile| tile.data.clone()).collect::<Vec<_>>());
+        assert_eq!(expected.to_vec(), handles.iter().map(|handle| images.get(handle).unwrap().data.clone()).collect::<Vec<_>>());
+    }
+}
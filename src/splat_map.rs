@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use pad::{p, Position};
+
+use crate::channel_packing::Channel;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+
+/// Creates splat-weight textures from a logical grid of terrain types, for terrain
+/// rendered with splat-map shaders.
+pub struct SplatMapCreator {
+    /// The width and height in pixels each tile covers in the splat map
+    pixels_per_tile: usize,
+    /// The distance in pixels over which the weights of neighbouring tiles fade into each other
+    feathering: usize,
+    /// The options for the created splat maps
+    options: ImageOptions,
+}
+
+impl SplatMapCreator {
+    pub fn new(pixels_per_tile: usize, feathering: usize) -> Self {
+        Self { pixels_per_tile, feathering, options: ImageOptions::default() }
+    }
+
+    /// Set the options for the created splat maps.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Create a splat map from the given terrain types. The channel mapper tells to which channel
+    /// of the splat map a terrain type is written, so up to four terrain types are supported.
+    /// Terrain types that are mapped to no channel are ignored.
+    /// The positions are interpreted like a mathematical coordinate system: position (0, 0) is bottom
+    /// left and position (m, n) is top right.
+    /// The weights of every pixel sum up to 255, except for pixels that are not near any tile.
+    /// The rounding remainder of the weights is given to the largest one.
+    pub fn create_splat_map<T>(
+        &self,
+        positions_and_terrains: impl IntoIterator<Item=(Position, T)>,
+        channel_mapper: impl Fn(&T) -> Option<Channel>,
+    ) -> Result<Image, TextureUtilsError> {
+        if self.pixels_per_tile == 0 {
+            return Err(TextureUtilsError::OutOfRange { name: "amount of pixels per tile", value: 0.0, expected: "at least 1" });
+        }
+
+        let position_channel_map = positions_and_terrains
+            .into_iter()
+            .filter_map(|(pos, terrain)| channel_mapper(&terrain).map(|channel| (pos, channel)))
+            .collect::<HashMap<_, _>>();
+
+        let min_x = position_channel_map.keys().map(|pos| pos.x).min().ok_or(TextureUtilsError::NoTilesProvided)?;
+        let max_x = position_channel_map.keys().map(|pos| pos.x).max().ok_or(TextureUtilsError::NoTilesProvided)?;
+        let min_y = position_channel_map.keys().map(|pos| pos.y).min().ok_or(TextureUtilsError::NoTilesProvided)?;
+        let max_y = position_channel_map.keys().map(|pos| pos.y).max().ok_or(TextureUtilsError::NoTilesProvided)?;
+
+        let width = (max_x - min_x + 1) as usize * self.pixels_per_tile;
+        let height = (max_y - min_y + 1) as usize * self.pixels_per_tile;
+        let tile_range = self.feathering.div_ceil(self.pixels_per_tile) as isize;
+
+        let mut data = vec![0u8; width * height * 4];
+
+        for py in 0..height {
+            for px in 0..width {
+                // the pixel center in tile units, with y pointing up
+                let x = (px as f32 + 0.5) / self.pixels_per_tile as f32;
+                let y = (height - py) as f32 / self.pixels_per_tile as f32 - 0.5 / self.pixels_per_tile as f32;
+                let tile_x = min_x + x as isize;
+                let tile_y = min_y + y as isize;
+
+                let mut weights = [0.0f32; 4];
+
+                for ty in tile_y - tile_range..=tile_y + tile_range {
+                    for tx in tile_x - tile_range..=tile_x + tile_range {
+                        let channel = match position_channel_map.get(&p!(tx, ty)) {
+                            Some(channel) => channel,
+                            None => continue
+                        };
+
+                        let distance = self.distance_to_tile(x, y, (tx - min_x) as f32, (ty - min_y) as f32);
+                        weights[channel.index()] += self.weight(distance);
+                    }
+                }
+
+                let sum = weights.iter().sum::<f32>();
+
+                if sum == 0.0 {
+                    continue;
+                }
+
+                let index = (py * width + px) * 4;
+                let pixel = &mut data[index..index + 4];
+
+                for (i, weight) in weights.iter().enumerate() {
+                    pixel[i] = (weight / sum * 255.0).round() as u8;
+                }
+
+                // every weight is rounded on its own, so they can sum up to slightly more or less than 255
+                let remainder = 255 - pixel.iter().map(|w| *w as i32).sum::<i32>();
+                let largest = (0..4).max_by(|a, b| weights[*a].total_cmp(&weights[*b])).unwrap_or_default();
+                pixel[largest] = (pixel[largest] as i32 + remainder) as u8;
+            }
+        }
+
+        Ok(self.options.create_image(
+            (width, height),
+            data,
+            TextureFormat::Rgba8Unorm,
+        ))
+    }
+
+    /// The distance in pixels from the given point to the closest point of the given tile.
+    fn distance_to_tile(&self, x: f32, y: f32, tile_x: f32, tile_y: f32) -> f32 {
+        let dx = (tile_x - x).max(x - (tile_x + 1.0)).max(0.0);
+        let dy = (tile_y - y).max(y - (tile_y + 1.0)).max(0.0);
+
+        (dx * dx + dy * dy).sqrt() * self.pixels_per_tile as f32
+    }
+
+    fn weight(&self, distance: f32) -> f32 {
+        if distance == 0.0 {
+            1.0
+        } else if self.feathering == 0 {
+            0.0
+        } else {
+            (1.0 - distance / self.feathering as f32).max(0.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pad::p;
+
+    use crate::channel_packing::Channel;
+    use crate::error::TextureUtilsError;
+    use crate::splat_map::SplatMapCreator;
+
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    enum Terrain {
+        Grass,
+        Sand,
+        Lava,
+    }
+
+    fn channel(terrain: &Terrain) -> Option<Channel> {
+        match terrain {
+            Terrain::Grass => Some(Channel::R),
+            Terrain::Sand => Some(Channel::G),
+            Terrain::Lava => None,
+        }
+    }
+
+    #[test]
+    fn create_splat_map_without_feathering_works() {
+        // arrange
+        let creator = SplatMapCreator::new(2, 0);
+
+        // act
+        let result = creator.create_splat_map(
+            [
+                (p!(0, 0), Terrain::Grass),
+                (p!(1, 0), Terrain::Sand),
+                (p!(1, 1), Terrain::Lava),
+            ],
+            channel,
+        );
+
+        // assert
+        assert!(result.is_ok());
+        let splat_map = result.unwrap();
+
+        assert_eq!((4, 2), (splat_map.width(), splat_map.height()), "Ignored terrain types should not be part of the map.");
+        assert_eq!(
+            vec![
+                255, 0, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0,
+                255, 0, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0,
+            ],
+            splat_map.data
+        );
+    }
+
+    /// With feathering, pixels next to a border between two terrain types get weights from both,
+    /// while pixels far away from the border only get the weight of their own terrain.
+    #[test]
+    fn create_splat_map_with_feathering_works() {
+        // arrange
+        let creator = SplatMapCreator::new(4, 2);
+
+        // act
+        let result = creator.create_splat_map(
+            [
+                (p!(0, 0), Terrain::Grass),
+                (p!(1, 0), Terrain::Sand),
+            ],
+            channel,
+        );
+
+        // assert
+        let splat_map = result.unwrap();
+        let pixel = |x: usize| &splat_map.data[x * 4..x * 4 + 4];
+
+        assert_eq!(&[255, 0, 0, 0], pixel(0));
+        assert!(pixel(3)[0] > pixel(3)[1] && pixel(3)[1] > 0, "The border pixel should mostly be grass with some sand.");
+        assert!(pixel(4)[1] > pixel(4)[0] && pixel(4)[0] > 0, "The border pixel should mostly be sand with some grass.");
+        assert_eq!(255, pixel(3)[0] as u16 + pixel(3)[1] as u16);
+        assert_eq!(&[0, 255, 0, 0], pixel(7));
+    }
+
+    /// Equal weights are rounded up on their own, so the remainder must be corrected to keep the sum at 255.
+    #[test]
+    fn create_splat_map_weights_sum_up_to_255() {
+        // arrange
+        let creator = SplatMapCreator::new(1, 2);
+
+        // act
+        let result = creator.create_splat_map(
+            [
+                (p!(0, 0), Terrain::Grass),
+                (p!(1, 0), Terrain::Lava),
+                (p!(2, 0), Terrain::Sand),
+            ],
+            channel,
+        );
+
+        // assert
+        let splat_map = result.unwrap();
+        let sums = splat_map.data
+            .chunks_exact(4)
+            .map(|pixel| pixel.iter().map(|w| *w as u16).sum::<u16>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![255; 3], sums);
+    }
+
+    #[test]
+    fn create_splat_map_without_tiles_fails() {
+        // arrange
+        let creator = SplatMapCreator::new(2, 0);
+
+        // act
+        let result = creator.create_splat_map([(p!(0, 0), Terrain::Lava)], channel);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::NoTilesProvided)));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use bevy_asset::{AssetPath, LoadedFolder};
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
+
+/// An atlas of uniformly sized sprites, whose sprites can be referenced by name.
+/// The sprites are arranged in a grid, ordered by name from the top left to the bottom right.
+#[derive(Clone, Debug)]
+pub struct SpriteAtlas {
+    /// The atlas texture
+    pub texture: Handle<Image>,
+    /// The amount of sprites per row
+    pub columns: usize,
+    /// The width and height of a single sprite in pixels
+    pub sprite_size: (usize, usize),
+    indices: HashMap<String, usize>,
+}
+
+impl SpriteAtlas {
+    /// Get the index of the sprite with the given name.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    /// Get the area of the sprite with the given name in the atlas texture.
+    pub fn rect(&self, name: &str) -> Option<PixelRect> {
+        let index = self.index(name)?;
+        let (width, height) = self.sprite_size;
+
+        Some(PixelRect::new((index % self.columns) * width, (index / self.columns) * height, width, height))
+    }
+
+    /// The names of all sprites in the atlas.
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.indices.keys().map(String::as_str)
+    }
+}
+
+/// Name a sprite after the file name of its image without the extension.
+pub fn file_stem_name(path: &AssetPath) -> String {
+    path.path()
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Pack all images of a loaded folder (like an asset pack) into a single atlas texture.
+/// The given naming function determines the name of each sprite from its asset path,
+/// for example [file_stem_name]. Assets which are not images are ignored.
+pub fn pack_directory(
+    folder: &LoadedFolder,
+    images: &mut Assets<Image>,
+    naming: impl Fn(&AssetPath) -> String,
+    options: ImageOptions,
+) -> Result<SpriteAtlas, TextureUtilsError> {
+    let named_images = folder.handles
+        .iter()
+        .filter_map(|handle| {
+            let image = images.get(handle.clone().try_typed::<Image>().ok()?.id())?;
+            Some((handle.path().map(&naming), image))
+        })
+        .map(|(name, image)| name
+            .map(|name| (name, image))
+            .ok_or(TextureUtilsError::InvalidParameter("An image of the folder has no path.".to_string()))
+        )
+        .collect::<Result<Vec<_>, TextureUtilsError>>()?;
+
+    let (texture, atlas) = pack_images(named_images, options)?;
+    let handle = images.add(texture);
+
+    Ok(SpriteAtlas { texture: handle, ..atlas })
+}
+
+/// Pack the given named images into a single atlas texture. All images must have the same size and format.
+/// The returned atlas references the default handle, as the texture is not added to the assets yet.
+pub fn pack_images<'a>(
+    named_images: impl IntoIterator<Item=(String, &'a Image)>,
+    options: ImageOptions,
+) -> Result<(Image, SpriteAtlas), TextureUtilsError> {
+    let mut named_images = named_images.into_iter().collect::<Vec<_>>();
+    named_images.sort_by(|(name_0, _), (name_1, _)| name_0.cmp(name_1));
+
+    let (_, first) = named_images.first().ok_or(TextureUtilsError::NoImagesProvided)?;
+    let format = first.texture_descriptor.format;
+    let width = first.width() as usize;
+    let height = first.height() as usize;
+
+    if named_images.iter().any(|(_, image)| image.width() as usize != width || image.height() as usize != height) {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    if let Some((_, image)) = named_images.iter().find(|(_, image)| image.texture_descriptor.format != format) {
+        return Err(TextureUtilsError::FormatMismatch { expected: format, actual: image.texture_descriptor.format, position: None });
+    }
+
+    let mut indices = HashMap::new();
+
+    for (index, (name, _)) in named_images.iter().enumerate() {
+        if indices.insert(name.clone(), index).is_some() {
+            return Err(TextureUtilsError::DuplicateName { kind: "sprite", name: name.clone() });
+        }
+    }
+
+    let columns = (named_images.len() as f32).sqrt().ceil() as usize;
+    let rows = named_images.len().div_ceil(columns);
+    let row_length = first.data.len() / height;
+    let mut data = vec![0; row_length * columns * height * rows];
+
+    for (index, (_, image)) in named_images.iter().enumerate() {
+        let column = index % columns;
+        let row = index / columns;
+
+        for y in 0..height {
+            let target = ((row * height + y) * columns + column) * row_length;
+            data[target..target + row_length].copy_from_slice(&image.data[y * row_length..(y + 1) * row_length]);
+        }
+    }
+
+    let texture = options.create_image((columns * width, rows * height), data, format);
+    let atlas = SpriteAtlas { texture: Handle::default(), columns, sprite_size: (width, height), indices };
+
+    Ok((texture, atlas))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::pixel_rect::PixelRect;
+    use crate::sprite_atlas::pack_images;
+
+    #[test]
+    fn pack_images_works() {
+        // arrange
+        let red = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let green = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]);
+        let blue = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]);
+
+        // act
+        let (texture, atlas) = pack_images(
+            [("red".to_string(), &red), ("blue".to_string(), &blue), ("green".to_string(), &green)],
+            ImageOptions::default(),
+        ).unwrap();
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::BLUE, Color::GREEN,
+                Color::RED, Color::NONE,
+            ],
+        );
+
+        assert_eq!(expected.data, texture.data);
+        assert_eq!(Some(2), atlas.index("red"));
+        assert_eq!(Some(PixelRect::new(1, 0, 1, 1)), atlas.rect("green"));
+        assert_eq!(None, atlas.rect("yellow"));
+    }
+
+    #[test]
+    fn pack_images_with_different_sizes_fails() {
+        // arrange
+        let small = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let big = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]);
+
+        // act
+        let result = pack_images([("small".to_string(), &small), ("big".to_string(), &big)], ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::SizeMismatch)));
+    }
+}
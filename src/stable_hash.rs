@@ -0,0 +1,51 @@
+/// The 64-bit FNV-1a hash. Unlike the hashers of the standard library, its values don't change between Rust releases
+/// or platforms, so they can be stored, like in the names of cached files. Values are written as explicit bytes
+/// instead of through the Hash trait, whose output is not guaranteed to be stable either.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Write the length before the bytes, so consecutive strings can't be confused.
+    pub(crate) fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stable_hash::StableHasher;
+
+    /// The values must never change, as they are stored in the names of cached files.
+    #[test]
+    fn stable_hasher_matches_fnv_1a() {
+        // arrange
+        let mut empty = StableHasher::new();
+        let mut hasher = StableHasher::new();
+
+        // act
+        empty.write(&[]);
+        hasher.write(b"a");
+
+        // assert
+        assert_eq!(0xCBF2_9CE4_8422_2325, empty.finish());
+        assert_eq!(0xAF63_DC4C_8601_EC8C, hasher.finish());
+    }
+}
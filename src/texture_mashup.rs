@@ -1,95 +1,253 @@
 use bevy_asset::prelude::*;
 use bevy_render::prelude::*;
-use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_render::render_resource::TextureFormat;
+
+use crate::blit::{blit, blit_tinted};
+use crate::color::color_to_pixel_bytes;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
 
 /// The x, y and z offset of a texture. Tells
 /// where to put the texture relative to (0, 0) and
-/// on which layer.
+/// on which layer. Also tells how the texture is colored.
+/// Negative offsets place the texture partially or completely left of or above the mashed texture.
 #[derive(Copy, Clone)]
 pub struct Offset {
-    x: usize,
-    y: usize,
+    x: i32,
+    y: i32,
     z: isize,
+    /// Multiplied with the color of every pixel
+    tint: Color,
+    /// The opacity (0.0 to 1.0) of the whole texture
+    opacity: f32,
 }
 
 impl Offset {
-    pub fn new(x: usize, y: usize, z: isize) -> Self {
-        Self { x, y, z }
+    pub fn new(x: i32, y: i32, z: isize) -> Self {
+        Self { x, y, z, tint: Color::WHITE, opacity: 1.0 }
+    }
+
+    /// Multiply the colors of the texture with the given color, so the same texture can be used
+    /// in different color variations. The alpha of the tint is multiplied with the opacity.
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Set the opacity (0.0 to 1.0) of the whole texture.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Untinted, opaque textures replace the pixels below, all others are blended over them.
+    fn is_plain(&self) -> bool {
+        self.tint == Color::WHITE && self.opacity >= 1.0
     }
 }
 
 // TODO find a better name
-// TODO only works for 4 byte pixel images
+// TODO only works for Rgba8UnormSrgb images
 pub fn mash_textures(
     images: &mut Assets<Image>,
     offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
-) -> Result<Handle<Image>, String> {
-    let offsets_textures_opt = offsets_handles
+) -> Result<Handle<Image>, TextureUtilsError> {
+    mash_textures_with_options(images, offsets_handles, ImageOptions::default())
+}
+
+/// Like [mash_textures], but the mashed texture is created with the given options.
+pub fn mash_textures_with_options(
+    images: &mut Assets<Image>,
+    offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
+    options: ImageOptions,
+) -> Result<Handle<Image>, TextureUtilsError> {
+    let image = mash_textures_image(images, offsets_handles, options)?;
+    Ok(images.add(image))
+}
+
+/// Like [mash_textures], but only reads the images and returns the mashed texture
+/// instead of adding it to the images.
+pub fn mash_textures_image(
+    images: &Assets<Image>,
+    offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let offsets_textures = offsets_handles
         .into_iter()
-        .map(|(offset, handle)| images.get(handle).map(|t| (offset, t)))
-        .collect::<Option<Vec<(Offset, &Image)>>>();
+        .map(|(offset, handle)| images
+            .get(&handle)
+            .map(|t| (offset, t))
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+        )
+        .collect::<Result<Vec<(Offset, &Image)>, TextureUtilsError>>()?;
 
-    let mut offsets_textures = match offsets_textures_opt {
-        Some(ots) => ots,
-        None => return Err("Some textures could not be retrieved. Maybe they aren't loaded yet".to_string())
-    };
+    mash_images(offsets_textures, options)
+}
 
-    offsets_textures.sort_by(|(offset_0, _), (offset_1, _)| offset_0.z.cmp(&offset_1.z));
+/// Like [mash_textures_image], but takes the images directly, so it can be used without bevy's asset storage.
+pub fn mash_images<'a>(
+    offsets_images: impl IntoIterator<Item=(Offset, &'a Image)>,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let offsets_textures = offsets_images.into_iter().collect::<Vec<_>>();
 
+    // the mashed texture starts at (0, 0), so everything left of or above it is clipped
     let image_width = offsets_textures
         .iter()
-        .map(|(ofs, txt)| ofs.x + txt.width() as usize)
+        .map(|(ofs, txt)| ofs.x as isize + txt.width() as isize)
         .max()
-        .ok_or("No texture handles were provided")?;
+        .ok_or(TextureUtilsError::NoImagesProvided)?;
 
     let image_height = offsets_textures
         .iter()
-        .map(|(ofs, txt)| ofs.y + txt.height() as usize)
+        .map(|(ofs, txt)| ofs.y as isize + txt.height() as isize)
         .max()
-        .ok_or("No texture handles were provided")?;
+        .ok_or(TextureUtilsError::NoImagesProvided)?;
+
+    if image_width <= 0 || image_height <= 0 {
+        return Err(TextureUtilsError::InvalidParameter("All textures are left of or above the mashed texture.".to_string()));
+    }
+
+    mash_images_onto((image_width as usize, image_height as usize), Color::NONE, offsets_textures, options)
+}
 
-    let mut image_data = vec![0; image_width * image_height * 4];
+/// Like [mash_textures], but the mashed texture has the given size and background color instead of growing
+/// to fit every texture, for example for HUD elements or portraits. Textures are clipped at the borders.
+pub fn mash_textures_onto(
+    images: &mut Assets<Image>,
+    canvas_size: (usize, usize),
+    background: Color,
+    offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
+    options: ImageOptions,
+) -> Result<Handle<Image>, TextureUtilsError> {
+    let offsets_textures = offsets_handles
+        .into_iter()
+        .map(|(offset, handle)| images
+            .get(&handle)
+            .map(|t| (offset, t))
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+        )
+        .collect::<Result<Vec<(Offset, &Image)>, TextureUtilsError>>()?;
+
+    let image = mash_images_onto(canvas_size, background, offsets_textures, options)?;
+    Ok(images.add(image))
+}
+
+/// Like [mash_textures_onto], but takes the images directly and returns the mashed texture
+/// instead of adding it to the images.
+pub fn mash_images_onto<'a>(
+    (width, height): (usize, usize),
+    background: Color,
+    offsets_images: impl IntoIterator<Item=(Offset, &'a Image)>,
+    options: ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    if width == 0 || height == 0 {
+        return Err(TextureUtilsError::ZeroSize { size: (width, height) });
+    }
+
+    let background = color_to_pixel_bytes(background, TextureFormat::Rgba8UnormSrgb)?;
+    let mut image = options.create_image((width, height), background.repeat(width * height), TextureFormat::Rgba8UnormSrgb);
+    draw_textures(&mut image, offsets_images)?;
+
+    Ok(image)
+}
+
+/// Like [mash_textures], but draws the textures onto the existing target image instead of creating a new one,
+/// for compositions which change often. The target keeps its size and the textures are clipped at its borders.
+/// Returns the area of the target which was drawn to, so only this part has to be processed further,
+/// or None if no texture overlaps the target. The target cannot be one of the textures.
+pub fn mash_onto_existing(
+    images: &mut Assets<Image>,
+    target: &Handle<Image>,
+    offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
+) -> Result<Option<PixelRect>, TextureUtilsError> {
+    // the target is taken out of the images while drawing, so the textures can be borrowed at the same time
+    let mut target_image = images
+        .remove_untracked(target)
+        .ok_or(TextureUtilsError::ImageNotLoaded { handle: target.id().untyped() })?;
+
+    let result = draw_onto_existing(images, &mut target_image, offsets_handles);
+    images.insert(target, target_image);
+
+    result
+}
+
+fn draw_onto_existing(
+    images: &Assets<Image>,
+    target: &mut Image,
+    offsets_handles: impl IntoIterator<Item=(Offset, Handle<Image>)>,
+) -> Result<Option<PixelRect>, TextureUtilsError> {
+    let offsets_textures = offsets_handles
+        .into_iter()
+        .map(|(offset, handle)| images
+            .get(&handle)
+            .map(|t| (offset, t))
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })
+        )
+        .collect::<Result<Vec<(Offset, &Image)>, TextureUtilsError>>()?;
+
+    let target_size = (target.width() as isize, target.height() as isize);
+    let dirty_rect = offsets_textures
+        .iter()
+        .filter_map(|(offset, texture)| drawn_area(*offset, texture, target_size))
+        .reduce(|a, b| a.union(&b));
+
+    draw_textures(target, offsets_textures)?;
+    Ok(dirty_rect)
+}
+
+/// The part of the target with the given size which is covered by the texture at the given offset.
+fn drawn_area(offset: Offset, texture: &Image, (width, height): (isize, isize)) -> Option<PixelRect> {
+    let min_x = (offset.x as isize).max(0);
+    let min_y = (offset.y as isize).max(0);
+    let max_x = (offset.x as isize + texture.width() as isize).min(width);
+    let max_y = (offset.y as isize + texture.height() as isize).min(height);
+
+    (max_x > min_x && max_y > min_y).then(|| PixelRect::new(
+        min_x as usize,
+        min_y as usize,
+        (max_x - min_x) as usize,
+        (max_y - min_y) as usize,
+    ))
+}
+
+/// Draw the given textures onto the image, ordered by their z offset.
+fn draw_textures<'a>(
+    image: &mut Image,
+    offsets_images: impl IntoIterator<Item=(Offset, &'a Image)>,
+) -> Result<(), TextureUtilsError> {
+    let mut offsets_textures = offsets_images.into_iter().collect::<Vec<_>>();
+    offsets_textures.sort_by_key(|(offset, _)| offset.z);
 
     for (offset, texture) in offsets_textures {
-        let data = &texture.data;
-        let part_width = texture.width() as usize;
-        let part_height = texture.height() as usize;
-
-        for y in 0..part_height {
-            for x in 0..part_width {
-                let mash_texture_index = image_width * 4 * (y + offset.y) + (x + offset.x) * 4;
-                let part_texture_index = part_width * 4 * y + x * 4;
-
-                image_data[mash_texture_index] = data[part_texture_index];
-                image_data[mash_texture_index + 1] = data[part_texture_index + 1];
-                image_data[mash_texture_index + 2] = data[part_texture_index + 2];
-                image_data[mash_texture_index + 3] = data[part_texture_index + 3];
+        let rect = PixelRect::new(0, 0, texture.width() as usize, texture.height() as usize);
+        let position = (offset.x as isize, offset.y as isize);
+
+        match offset.is_plain() {
+            true => blit(texture, rect, image, position)?,
+            false => {
+                let tint = offset.tint.with_a(offset.tint.a() * offset.opacity.clamp(0.0, 1.0));
+                blit_tinted(texture, rect, image, position, tint)?;
             }
         }
     }
 
-    let image = Image::new(
-        Extent3d {
-            width: image_width as u32,
-            height: image_height as u32,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        image_data,
-        TextureFormat::Rgba8UnormSrgb,
-    );
-
-    Ok(images.add(image))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use bevy_asset::prelude::*;
     use bevy_render::prelude::*;
+    use bevy_render::render_asset::RenderAssetUsages;
     use bevy_render::render_resource::TextureFormat;
 
-    use crate::test_utils::create_image;
-    use crate::texture_mashup::{mash_textures, Offset};
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::pixel_rect::PixelRect;
+    use crate::texture_mashup::{mash_images, mash_onto_existing, mash_textures, mash_textures_onto, mash_textures_with_options, Offset};
 
     #[test]
     fn mash_textures_works() {
@@ -131,7 +289,7 @@ mod tests {
                 (Offset::new(0, 0, -1), red),
                 (Offset::new(1, 1, 1), green),
                 (Offset::new(2, 2, 0), blue),
-            ]
+            ],
         );
 
         // assert
@@ -151,4 +309,189 @@ mod tests {
 
         assert_eq!(expected.data, created_image.unwrap().data);
     }
+
+    /// The configured options must be applied to the mashed texture.
+    #[test]
+    fn mash_textures_with_options_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+
+        // act
+        let result = mash_textures_with_options(
+            &mut images,
+            [(Offset::new(0, 0, 0), red)],
+            ImageOptions::new(RenderAssetUsages::RENDER_WORLD),
+        );
+
+        // assert
+        let mashed = images.get(result.unwrap()).unwrap();
+
+        assert_eq!(RenderAssetUsages::RENDER_WORLD, mashed.asset_usage);
+        assert_eq!(Color::RED.as_rgba_u8().to_vec(), mashed.data);
+    }
+
+    #[test]
+    fn mash_images_works() {
+        // arrange
+        let red = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]);
+        let green = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]);
+
+        // act
+        let result = mash_images(
+            [
+                (Offset::new(0, 0, 0), &red),
+                (Offset::new(1, 0, 1), &green),
+            ],
+            ImageOptions::default(),
+        );
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::GREEN]);
+
+        assert_eq!(expected.data, result.unwrap().data);
+    }
+
+    #[test]
+    fn mash_images_with_tint_and_opacity_works() {
+        // arrange
+        let white = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE; 2]);
+        let small_white = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE]);
+        let background = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK; 2]);
+
+        // act
+        let result = mash_images(
+            [
+                (Offset::new(0, 0, 0), &background),
+                (Offset::new(0, 0, 1).with_tint(Color::RED), &white),
+                (Offset::new(1, 0, 2).with_tint(Color::BLUE).with_opacity(0.6), &small_white),
+            ],
+            ImageOptions::default(),
+        );
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::rgb_u8(102, 0, 153)]);
+
+        assert_eq!(expected.data, result.unwrap().data);
+    }
+
+    /// Textures with negative offsets are clipped at the left and top of the mashed texture.
+    #[test]
+    fn mash_images_with_negative_offsets_works() {
+        // arrange
+        let red = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN,
+                Color::BLUE, Color::WHITE,
+            ],
+        );
+        let black = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK; 2]);
+
+        // act
+        let result = mash_images(
+            [
+                (Offset::new(-1, -1, 0), &red),
+                (Offset::new(-1, 1, 0), &black),
+            ],
+            ImageOptions::default(),
+        );
+
+        // assert
+        let expected = create_image((1, 2), TextureFormat::Rgba8UnormSrgb, [Color::WHITE, Color::BLACK]);
+        let image = result.unwrap();
+
+        assert_eq!((1, 2), (image.width(), image.height()));
+        assert_eq!(expected.data, image.data);
+    }
+
+    #[test]
+    fn mash_images_outside_of_the_texture_fails() {
+        // arrange
+        let red = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+
+        // act
+        let result = mash_images([(Offset::new(-1, 0, 0), &red)], ImageOptions::default());
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TextureUtilsError::InvalidParameter(_)));
+    }
+
+    #[test]
+    fn mash_textures_onto_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]));
+
+        // act
+        let result = mash_textures_onto(
+            &mut images,
+            (3, 2),
+            Color::BLUE,
+            [(Offset::new(2, 1, 0), red)],
+            ImageOptions::default(),
+        );
+
+        // assert
+        let expected = create_image(
+            (3, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::BLUE, Color::BLUE, Color::BLUE,
+                Color::BLUE, Color::BLUE, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, images.get(result.unwrap()).unwrap().data);
+    }
+
+    #[test]
+    fn mash_onto_existing_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let target = images.add(create_image((3, 3), TextureFormat::Rgba8UnormSrgb, [Color::BLACK; 9]));
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let green = images.add(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 4]));
+
+        // act
+        let result = mash_onto_existing(
+            &mut images,
+            &target,
+            [
+                (Offset::new(0, 0, 0), red),
+                (Offset::new(2, 1, 0), green),
+            ],
+        );
+
+        // assert
+        let expected = create_image(
+            (3, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::BLACK, Color::BLACK,
+                Color::BLACK, Color::BLACK, Color::GREEN,
+                Color::BLACK, Color::BLACK, Color::GREEN,
+            ],
+        );
+
+        assert_eq!(Some(PixelRect::new(0, 0, 3, 3)), result.unwrap());
+        assert_eq!(expected.data, images.get(&target).unwrap().data);
+    }
+
+    /// The target is kept, even if drawing onto it fails.
+    #[test]
+    fn mash_onto_existing_with_missing_texture_fails() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let target = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLACK]));
+
+        // act
+        let result = mash_onto_existing(&mut images, &target, [(Offset::new(0, 0, 0), Handle::default())]);
+
+        // assert
+        assert!(result.is_err());
+        assert!(images.get(&target).is_some());
+    }
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
 use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::color::{color_to_pixel_bytes, is_srgb_rgba8, pixel_bytes_to_color};
+use crate::error::TextureUtilsError;
 
 pub type PixelBytes = [u8; 4];
 
@@ -34,6 +38,25 @@ pub fn modify_texture(
     }
 }
 
+/// Like [modify_texture], but the mapper gets and returns the colors of the pixels, so it works with every
+/// 8-bit RGBA and BGRA format and doesn't need to care about their channel order and encoding.
+pub fn modify_texture_colors(
+    texture: &mut Image,
+    color_mapper: impl Fn(usize, usize, Color) -> Color,
+) -> Result<(), TextureUtilsError> {
+    let format = texture.texture_descriptor.format;
+    is_srgb_rgba8(format)?;
+
+    modify_texture(texture, |x, y, pixel| {
+        let color = color_mapper(x, y, pixel_bytes_to_color(&pixel, format).expect("The format was checked before"));
+        let bytes = color_to_pixel_bytes(color, format).expect("The format was checked before");
+
+        [bytes[0], bytes[1], bytes[2], bytes[3]]
+    });
+
+    Ok(())
+}
+
 /// Takes a texture and a pixel mapper and creates a new texture from if.
 /// TODO: Currently only works with 4-byte-pixel-images, will crash if something else is provided.
 pub fn map_to_new_texture(
@@ -69,12 +92,260 @@ pub fn map_to_texture_pixels<'a>(
     }
 }
 
+/// Tells which pixels a kernel sees at the edges of a texture.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EdgeMode {
+    /// Pixels outside of the texture repeat the nearest edge pixel.
+    Clamp,
+    /// The texture repeats, like for tileable textures.
+    Wrap,
+    /// The texture is mirrored at its edges.
+    Mirror,
+}
+
+impl EdgeMode {
+    /// Map a coordinate which might be outside of 0..size into it.
+    fn apply(&self, coordinate: isize, size: usize) -> usize {
+        let size = size as isize;
+
+        let mapped = match self {
+            EdgeMode::Clamp => coordinate.clamp(0, size - 1),
+            EdgeMode::Wrap => coordinate.rem_euclid(size),
+            EdgeMode::Mirror => {
+                let period = coordinate.rem_euclid(2 * size);
+
+                match period < size {
+                    true => period,
+                    false => 2 * size - 1 - period
+                }
+            }
+        };
+
+        mapped as usize
+    }
+}
+
+/// Apply the given kernel to the color channels of every pixel, which allows neighbourhood effects like
+/// blurring or sharpening. The kernel is centered on the pixel, so its size should be odd. Alpha stays unchanged.
+/// The texture must have an 8-bit RGBA or BGRA format.
+pub fn convolve<const N: usize>(texture: &Image, kernel: &[[f32; N]; N], edge_mode: EdgeMode) -> Result<Image, TextureUtilsError> {
+    is_srgb_rgba8(texture.texture_descriptor.format)?;
+
+    let width = texture.width() as usize;
+    let height = texture.height() as usize;
+    let center = (N / 2) as isize;
+
+    Ok(map_to_new_texture(texture, |x, y, pixel| {
+        let mut sum = [0.0f32; 3];
+
+        for (ky, row) in kernel.iter().enumerate() {
+            for (kx, weight) in row.iter().enumerate() {
+                let sx = edge_mode.apply(x as isize + kx as isize - center, width);
+                let sy = edge_mode.apply(y as isize + ky as isize - center, height);
+                let index = (sy * width + sx) * 4;
+
+                for (i, channel) in sum.iter_mut().enumerate() {
+                    *channel += texture.data[index + i] as f32 * weight;
+                }
+            }
+        }
+
+        let [r, g, b] = sum.map(|v| v.round().clamp(0.0, 255.0) as u8);
+        [r, g, b, pixel[3]]
+    }))
+}
+
+/// Highlight the edges of the given texture with the sobel operator. The result is a grayscale
+/// texture, where brighter pixels are stronger edges. Alpha stays unchanged.
+/// The texture must have an 8-bit RGBA or BGRA format.
+pub fn detect_edges(texture: &Image, edge_mode: EdgeMode) -> Result<Image, TextureUtilsError> {
+    let channels = color_channels(texture.texture_descriptor.format)?;
+    let gradient_x = convolve_luminance(texture, channels, &SOBEL_X, edge_mode);
+    let gradient_y = convolve_luminance(texture, channels, &SOBEL_Y, edge_mode);
+    let width = texture.width() as usize;
+
+    Ok(map_to_new_texture(texture, |x, y, pixel| {
+        let index = y * width + x;
+        let magnitude = (gradient_x[index] * gradient_x[index] + gradient_y[index] * gradient_y[index]).sqrt();
+        let value = magnitude.round().clamp(0.0, 255.0) as u8;
+
+        [value, value, value, pixel[3]]
+    }))
+}
+
+/// The indices of the red, green and blue channel in the pixels of the given format.
+fn color_channels(format: TextureFormat) -> Result<[usize; 3], TextureUtilsError> {
+    match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Ok([0, 1, 2]),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Ok([2, 1, 0]),
+        format => Err(TextureUtilsError::UnsupportedFormat { format })
+    }
+}
+
+fn convolve_luminance<const N: usize>(texture: &Image, [r, g, b]: [usize; 3], kernel: &[[f32; N]; N], edge_mode: EdgeMode) -> Vec<f32> {
+    let width = texture.width() as usize;
+    let height = texture.height() as usize;
+    let center = (N / 2) as isize;
+    let luminance = |x: usize, y: usize| {
+        let index = (y * width + x) * 4;
+        let p = &texture.data[index..index + 3];
+        0.299 * p[r] as f32 + 0.587 * p[g] as f32 + 0.114 * p[b] as f32
+    };
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| kernel
+            .iter()
+            .enumerate()
+            .flat_map(|(ky, row)| row.iter().enumerate().map(move |(kx, weight)| (kx, ky, weight)))
+            .map(|(kx, ky, weight)| {
+                let sx = edge_mode.apply(x as isize + kx as isize - center, width);
+                let sy = edge_mode.apply(y as isize + ky as isize - center, height);
+                luminance(sx, sy) * weight
+            })
+            .sum())
+        .collect()
+}
+
+/// The amount of pixels with each value of the channels of a texture, and of their luminance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Histogram {
+    pub red: [u32; 256],
+    pub green: [u32; 256],
+    pub blue: [u32; 256],
+    pub alpha: [u32; 256],
+    pub luminance: [u32; 256],
+}
+
+/// Count the values of every channel of the given texture, to analyze its brightness and contrast.
+/// The texture must have an 8-bit RGBA or BGRA format.
+pub fn histogram(texture: &Image) -> Result<Histogram, TextureUtilsError> {
+    let channels = color_channels(texture.texture_descriptor.format)?;
+    let [r, g, b] = channels;
+    let mut histogram = Histogram {
+        red: [0; 256],
+        green: [0; 256],
+        blue: [0; 256],
+        alpha: [0; 256],
+        luminance: [0; 256],
+    };
+
+    for pixel in texture.data.chunks_exact(4) {
+        histogram.red[pixel[r] as usize] += 1;
+        histogram.green[pixel[g] as usize] += 1;
+        histogram.blue[pixel[b] as usize] += 1;
+        histogram.alpha[pixel[3] as usize] += 1;
+        histogram.luminance[luminance(pixel, channels) as usize] += 1;
+    }
+
+    Ok(histogram)
+}
+
+/// Spread the values of the given texture over the whole range, so every brightness is used about equally often,
+/// which brings out details in dark or washed out textures. The mapping is computed from the luminance of the pixels
+/// and applied to every color channel. Alpha stays unchanged. The texture must have an 8-bit RGBA or BGRA format.
+pub fn equalize(texture: &Image) -> Result<Image, TextureUtilsError> {
+    let counts = histogram(texture)?.luminance;
+    let mut cdf = [0u32; 256];
+    let mut sum = 0;
+
+    for (value, count) in counts.iter().enumerate() {
+        sum += count;
+        cdf[value] = sum;
+    }
+
+    let cdf_min = cdf.iter().copied().find(|count| *count > 0).unwrap_or(0);
+    let total = sum;
+
+    let mapping = match total > cdf_min {
+        true => cdf.map(|count| (count.saturating_sub(cdf_min) as f32 / (total - cdf_min) as f32 * 255.0).round() as u8),
+        false => std::array::from_fn(|value| value as u8)
+    };
+
+    Ok(map_to_new_texture(texture, |_, _, [r, g, b, a]| [mapping[r as usize], mapping[g as usize], mapping[b as usize], a]))
+}
+
+/// Stretch the color values of the given texture linearly, so the darkest value becomes 0 and the brightest 255.
+/// All color channels are stretched the same, so the hues are kept. Alpha stays unchanged.
+/// The texture must have an 8-bit RGBA or BGRA format.
+pub fn auto_contrast(texture: &Image) -> Result<Image, TextureUtilsError> {
+    is_srgb_rgba8(texture.texture_descriptor.format)?;
+
+    let (min, max) = texture.data
+        .chunks_exact(4)
+        .flat_map(|pixel| &pixel[..3])
+        .fold((u8::MAX, u8::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+
+    if min >= max {
+        return Ok(texture.clone());
+    }
+
+    let stretch = |value: u8| ((value - min) as f32 * 255.0 / (max - min) as f32).round() as u8;
+
+    Ok(map_to_new_texture(texture, |_, _, [r, g, b, a]| [stretch(r), stretch(g), stretch(b), a]))
+}
+
+/// The luminance of the given pixel, with the same weights as the edge detection.
+fn luminance(pixel: &[u8], [r, g, b]: [usize; 3]) -> u8 {
+    (0.299 * pixel[r] as f32 + 0.587 * pixel[g] as f32 + 0.114 * pixel[b] as f32).round() as u8
+}
+
+/// A kernel which blurs by averaging all pixels in a square of size N.
+pub fn box_blur_kernel<const N: usize>() -> [[f32; N]; N] {
+    [[1.0 / (N * N) as f32; N]; N]
+}
+
+/// A kernel which blurs with a gaussian distribution with the given standard deviation.
+/// A size of about six times the deviation covers the whole distribution.
+pub fn gaussian_blur_kernel<const N: usize>(sigma: f32) -> [[f32; N]; N] {
+    let center = (N / 2) as f32;
+    let mut kernel = [[0.0; N]; N];
+
+    for (y, row) in kernel.iter_mut().enumerate() {
+        for (x, weight) in row.iter_mut().enumerate() {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            *weight = (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+
+    let sum = kernel.iter().flatten().sum::<f32>();
+    kernel.map(|row| row.map(|weight| weight / sum))
+}
+
+pub const SHARPEN: [[f32; 3]; 3] = [
+    [0.0, -1.0, 0.0],
+    [-1.0, 5.0, -1.0],
+    [0.0, -1.0, 0.0],
+];
+
+pub const EMBOSS: [[f32; 3]; 3] = [
+    [-2.0, -1.0, 0.0],
+    [-1.0, 1.0, 1.0],
+    [0.0, 1.0, 2.0],
+];
+
+/// Detects horizontal changes, see [detect_edges].
+pub const SOBEL_X: [[f32; 3]; 3] = [
+    [-1.0, 0.0, 1.0],
+    [-2.0, 0.0, 2.0],
+    [-1.0, 0.0, 1.0],
+];
+
+/// Detects vertical changes, see [detect_edges].
+pub const SOBEL_Y: [[f32; 3]; 3] = [
+    [-1.0, -2.0, -1.0],
+    [0.0, 0.0, 0.0],
+    [1.0, 2.0, 1.0],
+];
+
 #[cfg(test)]
 mod tests {
     use bevy_render::prelude::*;
     use bevy_render::render_resource::TextureFormat;
-    use crate::test_utils::create_image;
-    use crate::texture_modification::{map_to_new_texture, map_to_texture_pixels, modify_texture};
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::texture_modification::{auto_contrast, box_blur_kernel, convolve, detect_edges, EdgeMode, equalize, gaussian_blur_kernel, histogram, map_to_new_texture, map_to_texture_pixels, modify_texture, SHARPEN};
 
     #[test]
     fn modify_texture_works() {
@@ -183,4 +454,155 @@ mod tests {
 
         assert_eq!(expected.data, red_blue.data, "The red-blue texture should now be red-green-yellow, but wasn't.")
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn convolve_with_box_blur_works() {
+        // arrange
+        let texture = create_image(
+            (3, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::BLACK, Color::WHITE, Color::BLACK],
+        );
+
+        // act
+        let clamped = convolve(&texture, &box_blur_kernel::<3>(), EdgeMode::Clamp).unwrap();
+        let wrapped = convolve(&texture, &box_blur_kernel::<3>(), EdgeMode::Wrap).unwrap();
+
+        // assert
+        // the rows above and below are the row itself, so each pixel is the average of itself and its neighbours
+        assert_eq!(vec![85, 85, 85, 255], clamped.data[0..4].to_vec());
+        assert_eq!(vec![85, 85, 85, 255], clamped.data[4..8].to_vec());
+        assert_eq!(vec![85, 85, 85, 255], wrapped.data[8..12].to_vec());
+    }
+
+    /// Blurring and sharpening a single colored texture must not change it.
+    #[test]
+    fn convolve_keeps_single_colored_textures() {
+        // arrange
+        let texture = create_image((4, 4), TextureFormat::Rgba8UnormSrgb, [Color::rgb(0.2, 0.4, 0.6); 16]);
+
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::Wrap, EdgeMode::Mirror] {
+            // act
+            let blurred = convolve(&texture, &gaussian_blur_kernel::<5>(1.0), edge_mode).unwrap();
+            let sharpened = convolve(&texture, &SHARPEN, edge_mode).unwrap();
+
+            // assert
+            assert_eq!(texture.data, blurred.data);
+            assert_eq!(texture.data, sharpened.data);
+        }
+    }
+
+    #[test]
+    fn detect_edges_works() {
+        // arrange
+        let texture = create_image(
+            (4, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::BLACK, Color::BLACK, Color::WHITE, Color::WHITE],
+        );
+
+        // act
+        let edges = detect_edges(&texture, EdgeMode::Clamp).unwrap();
+
+        // assert
+        let values = edges.data.chunks_exact(4).map(|p| p[0]).collect::<Vec<_>>();
+
+        assert_eq!(vec![0, 255, 255, 0], values);
+    }
+
+    /// The luminance of BGRA textures is computed from their channels in the right order.
+    #[test]
+    fn detect_edges_with_bgra_texture_works() {
+        // arrange
+        let mut texture = create_image(
+            (4, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::BLACK, Color::BLACK, Color::RED, Color::RED],
+        );
+        let rgba_edges = detect_edges(&texture, EdgeMode::Clamp).unwrap();
+        texture.data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        texture.texture_descriptor.format = TextureFormat::Bgra8UnormSrgb;
+
+        // act
+        let bgra_edges = detect_edges(&texture, EdgeMode::Clamp).unwrap();
+
+        // assert
+        assert_eq!(rgba_edges.data, bgra_edges.data);
+    }
+
+    #[test]
+    fn convolve_and_detect_edges_with_unsupported_format_fail() {
+        // arrange
+        let texture = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let convolved = convolve(&texture, &SHARPEN, EdgeMode::Clamp);
+        let edges = detect_edges(&texture, EdgeMode::Clamp);
+
+        // assert
+        assert!(matches!(convolved, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+        assert!(matches!(edges, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+
+    #[test]
+    fn histogram_works() {
+        // arrange
+        let texture = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::RED, Color::WHITE]);
+
+        // act
+        let histogram = histogram(&texture).unwrap();
+
+        // assert
+        assert_eq!(3, histogram.red[255]);
+        assert_eq!((1, 2), (histogram.green[255], histogram.green[0]));
+        assert_eq!(3, histogram.alpha[255]);
+        assert_eq!((2, 1), (histogram.luminance[76], histogram.luminance[255]));
+    }
+
+    #[test]
+    fn equalize_and_auto_contrast_works() {
+        // arrange
+        let gray = |value: u8| Color::rgb_u8(value, value, value);
+        let texture = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [gray(100), gray(110), gray(150)]);
+
+        // act
+        let equalized = equalize(&texture).unwrap();
+        let stretched = auto_contrast(&texture).unwrap();
+
+        // assert
+        assert_eq!(create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [gray(0), gray(128), gray(255)]).data, equalized.data);
+        assert_eq!(create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [gray(0), gray(51), gray(255)]).data, stretched.data);
+    }
+
+    #[test]
+    fn histogram_with_bgra_texture_works() {
+        // arrange
+        let mut texture = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::RED, Color::WHITE]);
+        texture.data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        texture.texture_descriptor.format = TextureFormat::Bgra8UnormSrgb;
+
+        // act
+        let histogram = histogram(&texture).unwrap();
+
+        // assert
+        assert_eq!(3, histogram.red[255]);
+        assert_eq!((1, 2), (histogram.blue[255], histogram.blue[0]));
+        assert_eq!((2, 1), (histogram.luminance[76], histogram.luminance[255]));
+    }
+
+    #[test]
+    fn histogram_equalize_and_auto_contrast_with_unsupported_format_fail() {
+        // arrange
+        let texture = ImageOptions::default().create_image((2, 2), vec![0; 4], TextureFormat::R8Unorm);
+
+        // act
+        let histogram = histogram(&texture);
+        let equalized = equalize(&texture);
+        let stretched = auto_contrast(&texture);
+
+        // assert
+        assert!(matches!(histogram, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+        assert!(matches!(equalized, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+        assert!(matches!(stretched, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R8Unorm })));
+    }
+}
@@ -0,0 +1,81 @@
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+use bevy_tasks::{block_on, poll_once, Task};
+
+use crate::error::TextureUtilsError;
+
+/// A tile map texture which is assembled in the background, created with
+/// [TileMapTextureCreator::create_tile_map_build_task](crate::tile_map_texture::TileMapTextureCreator::create_tile_map_build_task).
+/// Insert it into an entity: when the task is finished, the texture is added to the images and its handle
+/// replaces this component. If the tile map could not be created, the component is removed with a warning.
+#[derive(Component, Debug)]
+pub struct TileMapBuildTask(pub(crate) Task<Result<Image, TextureUtilsError>>);
+
+pub(crate) fn finish_tile_map_build_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut TileMapBuildTask)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (entity, mut task) in &mut tasks {
+        let result = match block_on(poll_once(&mut task.0)) {
+            Some(result) => result,
+            None => continue
+        };
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<TileMapBuildTask>();
+
+        match result {
+            Ok(image) => { entity_commands.insert(images.add(image)); }
+            Err(e) => bevy_log::warn!("Could not build the tile map texture of {entity:?}: {e}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use bevy_tasks::{AsyncComputeTaskPool, TaskPool};
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::plugin::TextureUtilsPlugin;
+    use crate::tile_map_build_task::TileMapBuildTask;
+    use crate::tile_map_texture::TileMapTextureCreator;
+
+    #[test]
+    fn tile_map_build_task_inserts_texture() {
+        // arrange
+        AsyncComputeTaskPool::get_or_init(TaskPool::default);
+
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(TextureUtilsPlugin);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let task = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+            .create_tile_map_build_task(&images, [(p!(0, 0), red), (p!(1, 0), blue.clone())])
+            .unwrap();
+
+        // the tiles were copied, so removing them doesn't affect the task
+        images.remove(&blue);
+        let entity = app.world.spawn(task).id();
+
+        // act
+        while app.world.get::<TileMapBuildTask>(entity).is_some() {
+            app.update();
+        }
+
+        // assert
+        let handle = app.world.get::<Handle<Image>>(entity).expect("The texture should have been inserted, but wasn't.");
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+
+        assert_eq!(expected.data, app.world.resource::<Assets<Image>>().get(handle).unwrap().data);
+    }
+}
@@ -0,0 +1,131 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_render::texture::TextureFormatPixelInfo;
+use pad::{p, Position};
+
+use crate::blit::blit_tinted;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
+
+/// Describes where the tiles of an assembled tile map are.
+pub(crate) struct TileMapLayout {
+    pub origin: Position,
+    pub tile_width: usize,
+    pub tile_height: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl TileMapLayout {
+    pub fn new(tile_map: &Image, origin: Position, tile_size: (usize, usize)) -> Result<Self, TextureUtilsError> {
+        Self::with_pixel_size(tile_map, origin, tile_size, 4)
+    }
+
+    /// Like [TileMapLayout::new], but for tile maps whose pixels have the given amount of bytes.
+    pub fn with_pixel_size(
+        tile_map: &Image,
+        origin: Position,
+        (tile_width, tile_height): (usize, usize),
+        pixel_size: usize,
+    ) -> Result<Self, TextureUtilsError> {
+        let width = tile_map.width() as usize;
+        let height = tile_map.height() as usize;
+
+        if tile_map.data.len() != width * height * pixel_size {
+            return Err(TextureUtilsError::UnsupportedPixelSize);
+        }
+
+        if tile_width == 0 || tile_height == 0 {
+            return Err(TextureUtilsError::ZeroSize { size: (tile_width, tile_height) });
+        }
+
+        if !width.is_multiple_of(tile_width) || !height.is_multiple_of(tile_height) {
+            return Err(TextureUtilsError::InvalidCellSize { size: (width, height), cell_size: (tile_width, tile_height) });
+        }
+
+        Ok(Self { origin, tile_width, tile_height, width, height })
+    }
+
+    /// Get the tile position at the given pixel coordinates, if they are inside the tile map.
+    pub fn position_at(&self, x: f32, y: f32) -> Option<Position> {
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+
+        let column = x as usize / self.tile_width;
+        let row_from_bottom = (self.height - 1 - y as usize) / self.tile_height;
+
+        Some(p!(self.origin.x + column as isize, self.origin.y + row_from_bottom as isize))
+    }
+
+    /// Get the pixel coordinates of the top left corner of the tile at the given position,
+    /// if the tile is inside the tile map.
+    pub fn tile_origin(&self, pos: Position) -> Option<(usize, usize)> {
+        let column = pos.x - self.origin.x;
+        let row_from_bottom = pos.y - self.origin.y;
+        let columns = (self.width / self.tile_width) as isize;
+        let rows = (self.height / self.tile_height) as isize;
+
+        if column < 0 || row_from_bottom < 0 || column >= columns || row_from_bottom >= rows {
+            return None;
+        }
+
+        Some((
+            column as usize * self.tile_width,
+            (rows - 1 - row_from_bottom) as usize * self.tile_height
+        ))
+    }
+
+    /// The distance in pixels from the given pixel coordinates to the closest point of the tile at the given position.
+    pub fn distance_to_tile(&self, x: f32, y: f32, pos: Position) -> f32 {
+        let left = ((pos.x - self.origin.x) * self.tile_width as isize) as f32;
+        let bottom = self.height as f32 - ((pos.y - self.origin.y) * self.tile_height as isize) as f32;
+        let top = bottom - self.tile_height as f32;
+
+        let dx = (left - x).max(x - (left + self.tile_width as f32)).max(0.0);
+        let dy = (top - y).max(y - bottom).max(0.0);
+
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Darken the color of the given pixel by the given factor (0.0 to 1.0).
+    pub fn darken(&self, data: &mut [u8], x: usize, y: usize, factor: f32) {
+        let index = (y * self.width + x) * 4;
+
+        for value in &mut data[index..index + 3] {
+            *value = (*value as f32 * (1.0 - factor)).round() as u8;
+        }
+    }
+}
+
+/// The pixel coordinates of the bottom left corner and the size of the area which just fits all given tiles.
+/// Every tile is given with the pixel coordinates of its bottom left corner, where y points upwards.
+pub(crate) fn tile_bounds(tiles: &[((isize, isize), &Image)]) -> ((isize, isize), (usize, usize)) {
+    let left = tiles.iter().map(|((x, _), _)| *x).min().unwrap_or_default();
+    let bottom = tiles.iter().map(|((_, y), _)| *y).min().unwrap_or_default();
+    let right = tiles.iter().map(|((x, _), tile)| x + tile.width() as isize).max().unwrap_or_default();
+    let top = tiles.iter().map(|((_, y), tile)| y + tile.height() as isize).max().unwrap_or_default();
+
+    ((left, bottom), ((right - left) as usize, (top - bottom) as usize))
+}
+
+/// Blend the given tiles over each other by their alpha, in the given order, into a new transparent image with the given size,
+/// whose bottom left corner is at the given pixel coordinates. Every tile is given with the pixel coordinates of its
+/// bottom left corner, where y points upwards. The tiles must be 8-bit RGBA or BGRA images with the given format.
+pub(crate) fn composite_tiles(
+    tiles: &[((isize, isize), &Image)],
+    ((left, bottom), (width, height)): ((isize, isize), (usize, usize)),
+    texture_format: TextureFormat,
+    options: &ImageOptions,
+) -> Result<Image, TextureUtilsError> {
+    let top = bottom + height as isize;
+    let mut image = options.create_image((width, height), vec![0; width * height * texture_format.pixel_size()], texture_format);
+
+    for ((x, y), tile) in tiles {
+        let rect = PixelRect::new(0, 0, tile.width() as usize, tile.height() as usize);
+        blit_tinted(tile, rect, &mut image, (x - left, top - y - tile.height() as isize), Color::WHITE)?;
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,212 @@
+use bevy_app::prelude::*;
+use bevy_asset::{Asset, AssetApp, AssetLoader, AsyncReadExt, LoadContext};
+use bevy_asset::io::Reader;
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_reflect::TypePath;
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use bevy_utils::BoxedFuture;
+use pad::{p, Position};
+use serde::Deserialize;
+
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+/// Registers the [TileMapRecipeLoader], so .tilemap.ron files can be loaded as [TileMapRecipe]s,
+/// and bakes the recipes of [BakeTileMapRecipe] components.
+pub struct TileMapRecipePlugin;
+
+impl Plugin for TileMapRecipePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<TileMapRecipe>()
+            .register_asset_loader(TileMapRecipeLoader)
+            .add_systems(Update, bake_tile_map_recipes);
+    }
+}
+
+/// Loads tile map recipes and all their tile textures.
+pub struct TileMapRecipeLoader;
+
+impl AssetLoader for TileMapRecipeLoader {
+    type Asset = TileMapRecipe;
+    type Settings = ();
+    type Error = TextureUtilsError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = vec![];
+            reader
+                .read_to_end(&mut bytes)
+                .await?;
+
+            let file = ron::de::from_bytes::<RecipeFile>(&bytes)
+                .map_err(|e| TextureUtilsError::Decoding(format!("Could not parse the tile map recipe: {e}")))?;
+
+            Ok(TileMapRecipe {
+                tile_size: file.tile_size,
+                tiles: file.tiles
+                    .into_iter()
+                    .map(|tile| (p!(tile.position.0, tile.position.1), load_context.load(tile.texture)))
+                    .collect(),
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tilemap.ron"]
+    }
+}
+
+/// A tile map stored in a file instead of code, which lists the tile textures and their positions:
+/// ```ron
+/// (
+///     tile_size: (16, 16),
+///     tiles: [
+///         (texture: "tiles/grass.png", position: (0, 0)),
+///         (texture: "tiles/water.png", position: (1, 0)),
+///     ],
+/// )
+/// ```
+/// The textures are asset paths and are loaded together with the recipe. Their positions
+/// are interpreted like in [TileMapTextureCreator::create_tile_map_texture].
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct TileMapRecipe {
+    /// The width and height of every tile texture
+    pub tile_size: (usize, usize),
+    pub tiles: Vec<(Position, Handle<Image>)>,
+}
+
+impl TileMapRecipe {
+    /// Create the tile map texture of this recipe. All tile textures must be loaded and have the format Rgba8UnormSrgb.
+    pub fn bake(&self, images: &mut Assets<Image>, options: ImageOptions) -> Result<Handle<Image>, TextureUtilsError> {
+        TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, self.tile_size.0, self.tile_size.1)
+            .with_options(options)
+            .create_tile_map_texture(images, self.tiles.iter().cloned())
+    }
+}
+
+#[derive(Deserialize)]
+struct RecipeFile {
+    tile_size: (usize, usize),
+    tiles: Vec<RecipeFileTile>,
+}
+
+#[derive(Deserialize)]
+struct RecipeFileTile {
+    texture: String,
+    position: (isize, isize),
+}
+
+/// Bakes the recipe to a tile map texture as soon as the recipe and all its tiles are loaded.
+/// The component is then replaced by the handle of the texture, so it can be used for a sprite.
+#[derive(Component, Clone, Debug)]
+pub struct BakeTileMapRecipe {
+    pub recipe: Handle<TileMapRecipe>,
+    pub options: ImageOptions,
+}
+
+impl BakeTileMapRecipe {
+    pub fn new(recipe: Handle<TileMapRecipe>) -> Self {
+        Self { recipe, options: ImageOptions::default() }
+    }
+
+    /// Set the options for the baked texture.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+fn bake_tile_map_recipes(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    recipes: Res<Assets<TileMapRecipe>>,
+    bakes: Query<(Entity, &BakeTileMapRecipe)>,
+) {
+    for (entity, bake) in &bakes {
+        let recipe = match recipes.get(&bake.recipe) {
+            Some(recipe) => recipe,
+            None => continue
+        };
+
+        if recipe.tiles.iter().any(|(_, tile)| !images.contains(tile)) {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<BakeTileMapRecipe>();
+
+        match recipe.bake(&mut images, bake.options.clone()) {
+            Ok(texture) => { entity_commands.insert(texture); }
+            Err(e) => bevy_log::warn!("Could not bake the tile map recipe: {e}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::prelude::*;
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::tile_map_recipe::{bake_tile_map_recipes, BakeTileMapRecipe, RecipeFile, TileMapRecipe};
+
+    #[test]
+    fn recipe_file_can_be_parsed() {
+        // arrange
+        let ron = r#"(
+            tile_size: (16, 8),
+            tiles: [
+                (texture: "tiles/grass.png", position: (0, 0)),
+                (texture: "tiles/water.png", position: (1, -1)),
+            ],
+        )"#;
+
+        // act
+        let file = ron::de::from_str::<RecipeFile>(ron).unwrap();
+
+        // assert
+        assert_eq!((16, 8), file.tile_size);
+        assert_eq!("tiles/water.png", file.tiles[1].texture);
+        assert_eq!((1, -1), file.tiles[1].position);
+    }
+
+    #[test]
+    fn bake_tile_map_recipes_works() {
+        // arrange
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.init_resource::<Assets<TileMapRecipe>>();
+        app.add_systems(Update, bake_tile_map_recipes);
+
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let recipe = app.world.resource_mut::<Assets<TileMapRecipe>>().add(TileMapRecipe {
+            tile_size: (1, 1),
+            tiles: vec![(p!(0, 0), red), (p!(1, 0), blue)],
+        });
+        let entity = app.world.spawn(BakeTileMapRecipe::new(recipe)).id();
+
+        // act
+        app.update();
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+        let texture = app.world.get::<Handle<Image>>(entity).unwrap();
+
+        assert!(app.world.get::<BakeTileMapRecipe>(entity).is_none());
+        assert_eq!(expected.data, app.world.resource::<Assets<Image>>().get(texture).unwrap().data);
+    }
+}
@@ -1,12 +1,245 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 use bevy_asset::prelude::*;
+use bevy_math::{Rect, Vec2};
 use bevy_render::prelude::*;
-use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_render::render_resource::{Extent3d, TextureFormat};
 use bevy_render::texture::TextureFormatPixelInfo;
+use bevy_sprite::TextureAtlasLayout;
+use bevy_tasks::AsyncComputeTaskPool;
 use pad::{p, Position};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::blit::{blit_data, blit_within};
+use crate::color::color_to_pixel_bytes;
+use crate::error::TextureUtilsError;
+use crate::export::{load_image_png, save_image_png};
+use crate::format_conversion::convert_format;
+use crate::image_options::ImageOptions;
+use crate::pixel_rect::PixelRect;
+use crate::random::Random;
+use crate::stable_hash::StableHasher;
+use crate::tile_map_build_task::TileMapBuildTask;
+use crate::tile_registry::TileRegistry;
+use crate::transform::crop;
+
+/// A tile map texture together with the area of every tile in it, to render single tiles as sprites.
+#[derive(Clone, Debug)]
+pub struct TileMapTextureWithLayout {
+    pub texture: Handle<Image>,
+    /// Contains the area of every tile in pixels, where (0, 0) is the top left corner of the texture
+    pub layout: TextureAtlasLayout,
+    /// The index of every tile position in the layout
+    pub indices: HashMap<Position, usize>,
+}
+
+impl TileMapTextureWithLayout {
+    /// Get the area of the tile at the given position in the texture.
+    pub fn rect(&self, pos: Position) -> Option<Rect> {
+        self.indices.get(&pos).map(|index| self.layout.textures[*index])
+    }
+}
+
+/// A rotation by a multiple of a quarter turn, clockwise.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Quarter {
+    #[default]
+    Zero,
+    One,
+    Two,
+    Three,
+}
+
+/// A tile texture and how it is transformed when placed in the tile map, so the same texture
+/// can be used in different orientations. The tile is flipped first and rotated afterwards.
+/// Tiles can only be rotated by an odd amount of quarters if they are square.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TilePlacement {
+    pub handle: Handle<Image>,
+    /// Mirror the tile horizontally
+    pub flip_x: bool,
+    /// Mirror the tile vertically
+    pub flip_y: bool,
+    pub rotation: Quarter,
+    /// If set, the texture is picked from the variants instead of using the handle
+    pub variants: Option<VariantSet>,
+}
+
+impl TilePlacement {
+    pub fn new(handle: Handle<Image>) -> Self {
+        Self { handle, ..Default::default() }
+    }
+
+    pub fn with_flip_x(mut self, flip_x: bool) -> Self {
+        self.flip_x = flip_x;
+        self
+    }
+
+    pub fn with_flip_y(mut self, flip_y: bool) -> Self {
+        self.flip_y = flip_y;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quarter) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_variants(mut self, variants: VariantSet) -> Self {
+        self.variants = Some(variants);
+        self
+    }
+
+    fn transform(&self) -> TileTransform {
+        TileTransform { flip_x: self.flip_x, flip_y: self.flip_y, rotation: self.rotation }
+    }
+}
+
+impl From<Handle<Image>> for TilePlacement {
+    fn from(handle: Handle<Image>) -> Self {
+        Self::new(handle)
+    }
+}
+
+impl From<VariantSet> for TilePlacement {
+    fn from(variants: VariantSet) -> Self {
+        Self { variants: Some(variants), ..Default::default() }
+    }
+}
+
+/// Alternative textures of a tile with their weights, like different grass tiles, so large tile maps look less repetitive.
+/// The creator picks one texture per position, where a texture with twice the weight is picked twice as often.
+/// The picked textures only depend on the positions and the seed of the [TileMapTextureCreator].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VariantSet {
+    pub variants: Vec<(Handle<Image>, f32)>,
+}
+
+impl VariantSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_variant(mut self, texture: Handle<Image>, weight: f32) -> Self {
+        self.variants.push((texture, weight));
+        self
+    }
+
+    /// Pick the texture for the given position with the given seed. Fails if no variant has a positive weight.
+    pub fn pick(&self, seed: u64, pos: Position) -> Result<&Handle<Image>, TextureUtilsError> {
+        let total = self.variants.iter().map(|(_, weight)| weight.max(0.0)).sum::<f32>();
+
+        if total <= 0.0 {
+            return Err(TextureUtilsError::InvalidParameter("A variant set requires at least one variant with a positive weight.".to_string()));
+        }
+
+        let mut remaining = Random::for_position(seed, pos.x, pos.y).next_f32() * total;
+
+        for (texture, weight) in self.variants.iter().filter(|(_, weight)| *weight > 0.0) {
+            if remaining < *weight {
+                return Ok(texture);
+            }
+
+            remaining -= weight;
+        }
+
+        // rounding errors can leave a tiny remainder
+        Ok(&self.variants.iter().rev().find(|(_, weight)| *weight > 0.0).expect("The total weight is positive").0)
+    }
+}
+
+/// The transformation of a placed tile, without its texture.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct TileTransform {
+    flip_x: bool,
+    flip_y: bool,
+    rotation: Quarter,
+}
+
+impl TileTransform {
+    fn is_identity(&self) -> bool {
+        *self == TileTransform::default()
+    }
+
+    /// Get the pixel of the untransformed tile which ends up at the given pixel of the transformed tile.
+    fn source_pixel(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        // undo the rotation, odd rotations only happen with square tiles
+        let (x, y) = match self.rotation {
+            Quarter::Zero => (x, y),
+            Quarter::One => (y, height - 1 - x),
+            Quarter::Two => (width - 1 - x, height - 1 - y),
+            Quarter::Three => (width - 1 - y, x),
+        };
+
+        (
+            if self.flip_x { width - 1 - x } else { x },
+            if self.flip_y { height - 1 - y } else { y },
+        )
+    }
+}
+
+/// Tells what happens if the image of a tile is not loaded when a tile map is created.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MissingTilePolicy {
+    /// Fail with [TextureUtilsError::ImageNotLoaded]
+    #[default]
+    Error,
+    /// Leave the area of the tile empty, like positions without a tile. The tile map keeps its size,
+    /// so the tile can be added later with [TileMapTextureCreator::replace_tile].
+    Skip,
+    /// Use the given tile instead, like a generated missing texture. It must be loaded and match the tile size and format.
+    Placeholder(Handle<Image>),
+}
+
+/// The images of tiles by position, together with their transformation.
+type Tiles<'a> = HashMap<Position, (Cow<'a, Image>, TileTransform)>;
+
+/// What empty positions of a tile map are filled with.
+#[derive(Clone, Debug)]
+enum TileFill {
+    Color(Color),
+    Tile(Handle<Image>),
+}
+
+/// The area covered by tiles, from the min to the max position (inclusive).
+#[derive(Copy, Clone, Debug)]
+struct TileBounds {
+    min_x: usize,
+    max_x: usize,
+    min_y: usize,
+    max_y: usize,
+}
+
+impl TileBounds {
+    fn of<'a>(positions: impl IntoIterator<Item=&'a Position> + Clone) -> Result<Self, TextureUtilsError> {
+        Ok(Self {
+            min_x: TileMapTextureCreator::get_min_x(positions.clone())?,
+            max_x: TileMapTextureCreator::get_max_x(positions.clone())?,
+            min_y: TileMapTextureCreator::get_min_y(positions.clone())?,
+            max_y: TileMapTextureCreator::get_max_y(positions)?,
+        })
+    }
+
+    /// The width in tiles
+    fn width(&self) -> usize {
+        self.max_x - self.min_x + 1
+    }
+
+    /// The height in tiles
+    fn height(&self) -> usize {
+        self.max_y - self.min_y + 1
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        (self.min_x..=self.max_x).contains(&(pos.x as usize)) && (self.min_y..=self.max_y).contains(&(pos.y as usize))
+    }
+}
 
 /// Creates tile map textures.
+#[derive(Clone, Debug)]
 pub struct TileMapTextureCreator {
     /// The expected texture format of every image
     texture_format: TextureFormat,
@@ -16,11 +249,79 @@ pub struct TileMapTextureCreator {
     tile_width: usize,
     /// The expected height of each tile texture
     tile_height: usize,
+    /// The options for the created tile map textures
+    options: ImageOptions,
+    /// What empty positions are filled with. If not set, they stay transparent.
+    fill: Option<TileFill>,
+    /// The amount of pixels between a tile and the border of its area in the tile map
+    padding: usize,
+    /// If the padding is filled with the border pixels of the tile instead of staying transparent
+    extrude: bool,
+    /// If tiles with another texture format are converted instead of rejected
+    auto_convert: bool,
+    /// What happens with tiles whose image is not loaded
+    missing_tile_policy: MissingTilePolicy,
+    /// The seed for picking the textures of [VariantSet]s
+    seed: u64,
 }
 
 impl TileMapTextureCreator {
     pub fn new(texture_format: TextureFormat, tile_width: usize, tile_height: usize) -> Self {
-        Self { texture_format, bytes_per_pixel: texture_format.pixel_size(), tile_width, tile_height }
+        Self { texture_format, bytes_per_pixel: texture_format.pixel_size(), tile_width, tile_height, options: ImageOptions::default(), fill: None, padding: 0, extrude: false, auto_convert: false, missing_tile_policy: MissingTilePolicy::default(), seed: 0 }
+    }
+
+    /// Set the options for the created tile map textures.
+    pub fn with_options(mut self, options: ImageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Surround every tile with the given amount of pixels, so neighbouring tiles don't bleed into each other
+    /// when the tile map is sampled with linear filtering or mipmaps. Every tile area in the tile map
+    /// grows by twice the padding in width and height.
+    pub fn with_padding(mut self, padding: usize) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Fill the padding around every tile by repeating its border pixels instead of leaving it transparent.
+    pub fn with_extrusion(mut self, extrude: bool) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Convert tiles and fill tiles with another texture format to the configured one with
+    /// [crate::format_conversion::convert_format] instead of failing with a format mismatch.
+    /// The conversion only works for the formats supported by it and copies the converted tiles.
+    pub fn with_auto_convert(mut self, auto_convert: bool) -> Self {
+        self.auto_convert = auto_convert;
+        self
+    }
+
+    /// Set what happens with tiles whose image is not loaded yet, so partially loaded tile maps can be created.
+    /// By default, creating the tile map fails.
+    pub fn with_missing_tile_policy(mut self, policy: MissingTilePolicy) -> Self {
+        self.missing_tile_policy = policy;
+        self
+    }
+
+    /// Set the seed for picking the textures of [VariantSet]s, so the same tiles get other variants.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Fill the empty positions of the created tile maps with the given color.
+    pub fn with_fill(mut self, color: Color) -> Self {
+        self.fill = Some(TileFill::Color(color));
+        self
+    }
+
+    /// Fill the empty positions of the created tile maps with the given tile, like a placeholder.
+    /// The tile must match the tile size and format.
+    pub fn with_fill_tile(mut self, tile: Handle<Image>) -> Self {
+        self.fill = Some(TileFill::Tile(tile));
+        self
     }
 
     /// Combine multiple given textures to a single one, forming
@@ -30,212 +331,1380 @@ impl TileMapTextureCreator {
     /// positions_and_textures tells at which position in the tile map each texture should be. The positions
     /// are interpreted like a mathematical coordinate system: position (0, 0) is bottom left and position
     /// (m, n) is top right, where m >= 0 and n >= 0.
+    /// The textures are either plain handles, [VariantSet]s or [TilePlacement]s, if they should be flipped or rotated.
     pub fn create_tile_map_texture(
         &self,
         images: &mut Assets<Image>,
-        positions_and_textures: impl IntoIterator<Item=(Position, Handle<Image>)>,
-    ) -> Result<Handle<Image>, String> {
-        // TODO Performance: it might be possible to only iterate once here using fold
-        let position_texture_map = positions_and_textures
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let tiles_texture = self.create_tile_map_image(images, positions_and_textures)?;
+        Ok(images.add(tiles_texture))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but only reads the images and returns
+    /// the tile map texture instead of adding it to the images.
+    pub fn create_tile_map_image(
+        &self,
+        images: &Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<Image, TextureUtilsError> {
+        let position_texture_map = self.collect_tiles(images, positions_and_textures)?;
+        let fill = self.fill_data(Some(images))?;
+        self.create_image_from_tiles(&position_texture_map, fill.as_deref())
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_image], but takes the tile images directly,
+    /// so it can be used without bevy's asset storage. Fails if a fill tile is set, as it cannot be resolved.
+    pub fn create_tile_map_image_from_images<'a>(
+        &self,
+        positions_and_images: impl IntoIterator<Item=(Position, &'a Image)>,
+    ) -> Result<Image, TextureUtilsError> {
+        let position_texture_map = self.validate_tiles(positions_and_images
+            .into_iter()
+            .map(|(pos, image)| (pos, image, TileTransform::default()))
+        )?;
+        let fill = self.fill_data(None)?;
+        self.create_image_from_tiles(&position_texture_map, fill.as_deref())
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_image], but writes the pixel data of the tile map into the given buffer
+    /// instead of allocating a new one, and returns the width and height of the tile map in pixels. The buffer only grows
+    /// if it is too small, so reusing it avoids the allocations of tile maps which are rebuilt often.
+    /// If the tile map can't be created, the buffer stays unchanged.
+    pub fn create_tile_map_data_into(
+        &self,
+        images: &Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+        data: &mut Vec<u8>,
+    ) -> Result<(usize, usize), TextureUtilsError> {
+        let tiles = self.collect_tiles(images, positions_and_textures)?;
+        let fill = self.fill_data(Some(images))?;
+        let bounds = TileBounds::of(tiles.keys())?;
+        self.stitch_tiles_into(&tiles, bounds, fill.as_deref(), data);
+
+        Ok((bounds.width() * self.cell_width(), bounds.height() * self.cell_height()))
+    }
+
+    /// Rebuild an existing tile map texture from the given tiles, reusing the buffer of its pixel data,
+    /// for example for dynamic maps which change every frame. The size of the texture changes to the
+    /// size of the new tile map, while its other properties are kept.
+    pub fn rebuild_tile_map_texture(
+        &self,
+        images: &mut Assets<Image>,
+        tile_map: &Handle<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<(), TextureUtilsError> {
+        let not_loaded = || TextureUtilsError::ImageNotLoaded { handle: tile_map.id().untyped() };
+        let mut data = std::mem::take(&mut images.get_mut(tile_map).ok_or_else(not_loaded)?.data);
+        let result = self.create_tile_map_data_into(images, positions_and_textures, &mut data);
+
+        // the data is put back even if the tile map could not be created, as it is unchanged then
+        let texture = images.get_mut(tile_map).ok_or_else(not_loaded)?;
+        texture.data = data;
+        let (width, height) = result?;
+        texture.texture_descriptor.size = Extent3d { width: width as u32, height: height as u32, depth_or_array_layers: 1 };
+
+        Ok(())
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but creates a tile map for each of the given requests,
+    /// like all chunks of a streamed world which changed in a frame. The handles are returned in the order of the requests.
+    /// Work shared by the requests is only done once: the fill is resolved once, and every tile is only validated and
+    /// converted the first time it is used. If a request fails, no tile map is added at all.
+    pub fn create_many<R, P>(
+        &self,
+        images: &mut Assets<Image>,
+        requests: impl IntoIterator<Item=R>,
+    ) -> Result<Vec<Handle<Image>>, TextureUtilsError>
+    where
+        R: IntoIterator<Item=(Position, P)>,
+        P: Into<TilePlacement>,
+    {
+        let tile_maps = {
+            let images = &*images;
+            let fill = self.fill_data(Some(images))?;
+            let mut cache = HashMap::new();
+
+            requests
+                .into_iter()
+                .map(|positions_and_textures| {
+                    let tiles = self.collect_tiles_with_cache(images, &mut cache, positions_and_textures)?;
+                    self.create_image_from_tiles(&tiles, fill.as_deref())
+                })
+                .collect::<Result<Vec<_>, TextureUtilsError>>()?
+        };
+
+        Ok(tile_maps
+            .into_iter()
+            .map(|tile_map| images.add(tile_map))
+            .collect())
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_image], but only collects the tiles right away and assembles
+    /// the tile map on the [AsyncComputeTaskPool], so big tile maps don't block the schedule.
+    /// The tiles are copied, so they can change or be unloaded while the task runs.
+    pub fn create_tile_map_build_task(
+        &self,
+        images: &Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<TileMapBuildTask, TextureUtilsError> {
+        let tiles = self
+            .collect_tiles(images, positions_and_textures)?
+            .into_iter()
+            .map(|(pos, (tile, transform))| (pos, (Cow::Owned(tile.into_owned()), transform)))
+            .collect::<Tiles<'static>>();
+        let fill = self.fill_data(Some(images))?.map(Cow::into_owned);
+        let creator = self.clone();
+
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            creator.create_image_from_tiles(&tiles, fill.as_deref())
+        });
+
+        Ok(TileMapBuildTask(task))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but creates an array texture with one layer
+    /// per z-index, for example for maps with ground, decoration and overhang layers. The layers are
+    /// ordered by ascending z-index and all have the size of the area covered by all tiles.
+    /// Only the lowest layer is filled, so the layers above stay transparent where they have no tiles.
+    pub fn create_tile_map_array_texture(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, isize, impl Into<TilePlacement>)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let mut layers = BTreeMap::<isize, Vec<(Position, TilePlacement)>>::new();
+
+        for (pos, z, placement) in positions_and_textures {
+            layers.entry(z).or_default().push((pos, placement.into()));
+        }
+
+        let layer_tiles = layers
+            .into_values()
+            .map(|tiles| self.collect_tiles(images, tiles))
+            .collect::<Result<Vec<_>, TextureUtilsError>>()?;
+        let bounds = TileBounds::of(layer_tiles.iter().flat_map(|tiles| tiles.keys()))?;
+        let fill = self.fill_data(Some(images))?;
+
+        // the layers are stacked vertically, which is the memory layout of an array texture
+        let data = layer_tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(i, tiles)| self.stitch_tiles(tiles, bounds, fill.as_deref().filter(|_| i == 0)))
+            .collect();
+
+        let mut array_texture = self.create_image_from_data(bounds.width(), bounds.height() * layer_tiles.len(), data);
+        array_texture.reinterpret_stacked_2d_as_array(layer_tiles.len() as u32);
+
+        Ok(images.add(array_texture))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but splits the tile map into a grid of chunk textures
+    /// which are at most max_dimension pixels wide and high, for maps exceeding the texture size limit of the GPU.
+    /// Every chunk is returned with the position of its bottom left tile. Chunks without tiles are skipped,
+    /// unless a fill is set.
+    pub fn create_tile_map_texture_chunks(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+        max_dimension: usize,
+    ) -> Result<Vec<(Position, Handle<Image>)>, TextureUtilsError> {
+        let chunk_width = max_dimension / self.cell_width();
+        let chunk_height = max_dimension / self.cell_height();
+
+        if chunk_width == 0 || chunk_height == 0 {
+            return Err(TextureUtilsError::InvalidParameter(format!(
+                "A single tile does not fit into a chunk with the maximum dimension {}.",
+                max_dimension
+            )));
+        }
+
+        let tiles = self.collect_tiles(images, positions_and_textures)?;
+        let fill = self.fill_data(Some(images))?;
+        let bounds = TileBounds::of(tiles.keys())?;
+
+        let chunks = (bounds.min_y..=bounds.max_y)
+            .step_by(chunk_height)
+            .flat_map(|min_y| (bounds.min_x..=bounds.max_x).step_by(chunk_width).map(move |min_x| TileBounds {
+                min_x,
+                max_x: (min_x + chunk_width - 1).min(bounds.max_x),
+                min_y,
+                max_y: (min_y + chunk_height - 1).min(bounds.max_y),
+            }))
+            .filter(|chunk| fill.is_some() || tiles.keys().any(|pos| chunk.contains(pos)))
+            .map(|chunk| {
+                let data = self.stitch_tiles(&tiles, chunk, fill.as_deref());
+                (p!(chunk.min_x, chunk.min_y), self.create_image_from_data(chunk.width(), chunk.height(), data))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(chunks
             .into_iter()
-            .map(|(pos, handle)| {
-                let texture = match images.get(handle.id()) {
-                    Some(t) => t,
-                    None => {
-                        return Err("Not all textures are loaded yet.".to_string())
-                    }
-                };
-
-                match texture.texture_descriptor.format == self.texture_format {
-                    true => Ok((pos, texture)),
-                    false => Err(format!("Not all textures have the configured texture format '{:?}'.", self.texture_format))
+            .map(|(pos, chunk)| (pos, images.add(chunk)))
+            .collect())
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but the tiles are given by their names
+    /// in the given registry.
+    pub fn create_tile_map_texture_from_names<'a>(
+        &self,
+        images: &mut Assets<Image>,
+        registry: &TileRegistry,
+        positions_and_names: impl IntoIterator<Item=(Position, &'a str)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let positions_and_textures = registry.resolve(positions_and_names)?;
+        self.create_tile_map_texture(images, positions_and_textures)
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_image_from_images], but the tiles are given by their index in a
+    /// tileset image, like in Tiled or LDtk. The tileset is a grid of tiles with the tile size of this creator and without
+    /// spacing, where the indices start with 0 at the top left tile and go row by row.
+    pub fn create_tile_map_image_from_indices(
+        &self,
+        tileset: &Image,
+        positions_and_indices: impl IntoIterator<Item=(Position, u32)>,
+    ) -> Result<Image, TextureUtilsError> {
+        let columns = tileset.width() as usize / self.tile_width.max(1);
+        let rows = tileset.height() as usize / self.tile_height.max(1);
+        let positions_and_indices = positions_and_indices.into_iter().collect::<Vec<_>>();
+        // only the referenced tiles are cut out of the tileset, every one of them once
+        let mut tiles = HashMap::new();
+
+        for (_, index) in &positions_and_indices {
+            let i = *index as usize;
+
+            if tiles.contains_key(index) {
+                continue;
+            }
+
+            if i >= columns * rows {
+                return Err(TextureUtilsError::NotFound { kind: "tile index", name: index.to_string() });
+            }
+
+            let rect = PixelRect::new(i % columns * self.tile_width, i / columns * self.tile_height, self.tile_width, self.tile_height);
+            tiles.insert(*index, crop(tileset, rect)?);
+        }
+
+        self.create_tile_map_image_from_images(positions_and_indices.iter().map(|(pos, index)| (*pos, &tiles[index])))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but the tiles are drawn as characters in a multi-line string,
+    /// like for prototypes and tests. Every character is looked up in the legend, and spaces are empty positions.
+    /// The last line is the bottom row of the tile map. Empty lines at the start and end of the string, trailing
+    /// whitespace and the indentation all lines have in common are ignored, so the string can be indented like code.
+    pub fn create_tile_map_texture_from_str(
+        &self,
+        images: &mut Assets<Image>,
+        legend: &HashMap<char, Handle<Image>>,
+        map: &str,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let lines = map
+            .lines()
+            .map(str::trim_end)
+            .skip_while(|line| line.is_empty())
+            .collect::<Vec<_>>();
+        let lines = &lines[..lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1)];
+        let indentation = lines
+            .iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        let mut positions_and_textures = vec![];
+
+        for (row, line) in lines.iter().rev().enumerate() {
+            for (column, character) in line.chars().skip(indentation).enumerate() {
+                if character == ' ' {
+                    continue;
                 }
+
+                let texture = legend
+                    .get(&character)
+                    .ok_or(TextureUtilsError::NotFound { kind: "legend character", name: character.to_string() })?;
+                positions_and_textures.push((p!(column, row), texture.clone()));
+            }
+        }
+
+        self.create_tile_map_texture(images, positions_and_textures)
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_image_from_indices], but the tileset is taken from the images
+    /// and the tile map is added to them.
+    pub fn create_tile_map_texture_from_indices(
+        &self,
+        images: &mut Assets<Image>,
+        tileset: &Handle<Image>,
+        positions_and_indices: impl IntoIterator<Item=(Position, u32)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        let tileset_image = images
+            .get(tileset)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: tileset.id().untyped() })?;
+        let tile_map = self.create_tile_map_image_from_indices(tileset_image, positions_and_indices)?;
+
+        Ok(images.add(tile_map))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but caches the tile map as PNG in the given directory,
+    /// so the stitching is skipped on subsequent runs. The cached file is named after the key and a hash of the pixels,
+    /// sizes and formats of the tiles, their positions and the settings of this creator, so it is only loaded if nothing
+    /// changed. Otherwise the tile map is baked and written to the cache, replacing older versions with the same key.
+    /// The hash is stable across Rust releases. The key becomes part of a file name, so it must not be empty or contain
+    /// path separators. All tiles must be loaded, even if the cached tile map is used, and the texture format must be
+    /// supported by [save_image_png].
+    /// Failing to write the cache only logs a warning, as the baked tile map can be used anyway.
+    pub fn create_or_load_cached(
+        &self,
+        key: &str,
+        cache_dir: impl AsRef<Path>,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<Handle<Image>, TextureUtilsError> {
+        if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\']) {
+            return Err(TextureUtilsError::InvalidParameter(format!("The cache key '{key}' must be a valid file name.")));
+        }
+
+        let cache_dir = cache_dir.as_ref();
+        let placements = positions_and_textures
+            .into_iter()
+            .map(|(pos, placement)| (pos, placement.into()))
+            .collect::<Vec<(Position, TilePlacement)>>();
+        let path = cache_dir.join(format!("{key}-{:016x}.png", self.cache_hash(images, &placements)?));
+
+        if path.exists() {
+            match load_image_png(&path, self.texture_format, self.options.clone()) {
+                Ok(tile_map) => return Ok(images.add(tile_map)),
+                Err(e) => bevy_log::warn!("Could not load the cached tile map '{}', baking it again: {e}", path.display())
+            }
+        }
+
+        let tile_map = self.create_tile_map_image(images, placements)?;
+
+        if let Err(e) = Self::write_cache(key, cache_dir, &path, &tile_map) {
+            bevy_log::warn!("Could not cache the tile map '{}': {e}", path.display());
+        }
+
+        Ok(images.add(tile_map))
+    }
+
+    /// Like [TileMapTextureCreator::create_tile_map_texture], but also returns a [TextureAtlasLayout]
+    /// with the area of every tile, ordered by position. The areas don't include the padding.
+    pub fn create_tile_map_texture_with_layout(
+        &self,
+        images: &mut Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<TileMapTextureWithLayout, TextureUtilsError> {
+        let positions_and_textures = positions_and_textures.into_iter().collect::<HashMap<_, _>>();
+        let mut positions = positions_and_textures.keys().copied().collect::<Vec<_>>();
+        positions.sort();
+
+        let bounds = TileBounds::of(&positions)?;
+
+        let texture = self.create_tile_map_texture(images, positions_and_textures)?;
+        let size = images.get(&texture).map(|image| image.size_f32()).unwrap_or_default();
+
+        let mut layout = TextureAtlasLayout::new_empty(size);
+        let indices = positions
+            .into_iter()
+            .map(|pos| {
+                let min = Vec2::new(
+                    ((pos.x as usize - bounds.min_x) * self.cell_width() + self.padding) as f32,
+                    ((bounds.max_y - pos.y as usize) * self.cell_height() + self.padding) as f32,
+                );
+                let max = min + Vec2::new(self.tile_width as f32, self.tile_height as f32);
+
+                (pos, layout.add_texture(Rect::from_corners(min, max)))
             })
-            .collect::<Result<HashMap<Position, &Image>, String>>()?;
+            .collect();
+
+        Ok(TileMapTextureWithLayout { texture, layout, indices })
+    }
+
+    /// Replace a single tile of an existing tile map texture created by this creator, without
+    /// rebuilding the whole texture. The position is relative to the bottom left tile of the tile map.
+    pub fn replace_tile(
+        &self,
+        images: &mut Assets<Image>,
+        tile_map: &Handle<Image>,
+        position: Position,
+        new_tile: &Handle<Image>,
+    ) -> Result<(), TextureUtilsError> {
+        let tile = images
+            .get(new_tile)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: new_tile.id().untyped() })?
+            .clone();
+        let texture = images
+            .get_mut(tile_map)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: tile_map.id().untyped() })?;
+
+        self.replace_tile_in_image(texture, position, &tile).map(|_| ())
+    }
+
+    /// Like [TileMapTextureCreator::replace_tile], but works with the images directly.
+    /// Returns the area of the tile map which was written, including the padding of the tile.
+    /// The tile map must have the format of this creator.
+    pub fn replace_tile_in_image(&self, tile_map: &mut Image, position: Position, new_tile: &Image) -> Result<PixelRect, TextureUtilsError> {
+        let tile_map_format = tile_map.texture_descriptor.format;
+
+        if tile_map_format != self.texture_format {
+            return Err(TextureUtilsError::FormatMismatch {
+                expected: self.texture_format,
+                actual: tile_map_format,
+                position: None,
+            });
+        }
+
+        if tile_map.data.len() != tile_map.width() as usize * tile_map.height() as usize * self.bytes_per_pixel {
+            return Err(TextureUtilsError::SizeMismatch);
+        }
+
+        let tile = self.prepare_tile(new_tile, Some(position))?;
+        let width = tile_map.width() as usize / self.cell_width();
+        let height = tile_map.height() as usize / self.cell_height();
+
+        if position.x < 0 || position.y < 0 || position.x as usize >= width || position.y as usize >= height {
+            return Err(TextureUtilsError::PositionOutOfBounds { position });
+        }
+
+        let relative_pos = p!(position.x, height as isize - 1 - position.y);
+        self.add_data_from_tile_image_at_position(width, &mut tile_map.data, &relative_pos, &tile.data, TileTransform::default());
+
+        Ok(PixelRect::new(
+            relative_pos.x as usize * self.cell_width(),
+            relative_pos.y as usize * self.cell_height(),
+            self.cell_width(),
+            self.cell_height(),
+        ))
+    }
+
+    /// Hash everything the tile map created from the given tiles depends on.
+    fn cache_hash(&self, images: &Assets<Image>, placements: &[(Position, TilePlacement)]) -> Result<u64, TextureUtilsError> {
+        let mut hasher = StableHasher::new();
+        hasher.write_str(&format!("{:?}", self.texture_format));
+
+        for value in [self.tile_width, self.tile_height, self.padding, self.extrude as usize] {
+            hasher.write_u64(value as u64);
+        }
+
+        hasher.write_str(&format!("{:?}", self.options));
+
+        match &self.fill {
+            Some(TileFill::Color(color)) => hasher.write(&color.as_rgba_u8()),
+            Some(TileFill::Tile(tile)) => Self::hash_tile(&mut hasher, images, tile)?,
+            None => hasher.write(&[0])
+        }
+
+        // the order of the tiles only matters for tiles at the same position
+        let mut sorted = placements.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|(pos, _)| (pos.x, pos.y));
+
+        for (pos, placement) in sorted {
+            hasher.write(&(pos.x as i64).to_le_bytes());
+            hasher.write(&(pos.y as i64).to_le_bytes());
+            hasher.write(&[placement.flip_x as u8, placement.flip_y as u8]);
+            hasher.write_str(&format!("{:?}", placement.rotation));
+            Self::hash_tile(&mut hasher, images, self.placed_texture(*pos, placement)?)?;
+        }
+
+        Ok(hasher.finish())
+    }
+
+    /// Hash the pixels, size and format of the tile, so the hash changes when the file of a tile was edited.
+    fn hash_tile(hasher: &mut StableHasher, images: &Assets<Image>, tile: &Handle<Image>) -> Result<(), TextureUtilsError> {
+        let image = images
+            .get(tile)
+            .ok_or(TextureUtilsError::ImageNotLoaded { handle: tile.id().untyped() })?;
+
+        hasher.write_str(&format!("{:?}", image.texture_descriptor.format));
+        hasher.write_u64(image.width() as u64);
+        hasher.write_u64(image.height() as u64);
+        hasher.write_u64(image.data.len() as u64);
+        hasher.write(&image.data);
 
-        let max_x = Self::get_max_x(position_texture_map.keys())?;
-        let min_x = Self::get_min_x(position_texture_map.keys())?;
-        let max_y = Self::get_max_y(position_texture_map.keys())?;
-        let min_y = Self::get_min_y(position_texture_map.keys())?;
+        Ok(())
+    }
+
+    /// Write the tile map to the given path and remove the older tile maps cached with the same key.
+    fn write_cache(key: &str, cache_dir: &Path, path: &Path, tile_map: &Image) -> Result<(), TextureUtilsError> {
+        std::fs::create_dir_all(cache_dir)?;
+
+        let outdated = std::fs::read_dir(cache_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|entry| entry.as_path() != path && Self::is_cached_with_key(entry, key));
+
+        for entry in outdated {
+            std::fs::remove_file(&entry)?;
+        }
+
+        save_image_png(tile_map, path)
+    }
+
+    /// Tells if the file at the given path is a cached tile map with the given key, followed by the 16 digits of its hash.
+    fn is_cached_with_key(path: &Path, key: &str) -> bool {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name
+                .strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix('-'))
+                .and_then(|rest| rest.strip_suffix(".png"))
+                .map(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                .unwrap_or(false),
+            None => false
+        }
+    }
+
+    /// Get the images of the given tiles and check if they match the tile size and format.
+    /// Tiles which are not loaded are handled according to the missing tile policy.
+    fn collect_tiles<'a>(
+        &self,
+        images: &'a Assets<Image>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<Tiles<'a>, TextureUtilsError> {
+        self.collect_tiles_with_cache(images, &mut HashMap::new(), positions_and_textures)
+    }
+
+    /// Like [TileMapTextureCreator::collect_tiles], but every image is only validated and converted once and then
+    /// stored in the given cache, which can be shared by multiple tile maps.
+    fn collect_tiles_with_cache<'a>(
+        &self,
+        images: &'a Assets<Image>,
+        cache: &mut HashMap<AssetId<Image>, Cow<'a, Image>>,
+        positions_and_textures: impl IntoIterator<Item=(Position, impl Into<TilePlacement>)>,
+    ) -> Result<Tiles<'a>, TextureUtilsError> {
+        let mut skipped = Vec::new();
+        let mut tiles = Tiles::new();
+
+        for (pos, placement) in positions_and_textures {
+            let placement = placement.into();
+            let texture = self.placed_texture(pos, &placement)?;
 
-        let width = (max_x - min_x) + 1;
-        let height = (max_y - min_y) + 1;
+            let (id, transform) = match (images.contains(texture.id()), &self.missing_tile_policy) {
+                (true, _) => (texture.id(), placement.transform()),
+                (false, MissingTilePolicy::Error) => return Err(TextureUtilsError::ImageNotLoaded { handle: texture.id().untyped() }),
+                (false, MissingTilePolicy::Skip) => {
+                    skipped.push(pos);
+                    continue;
+                }
+                (false, MissingTilePolicy::Placeholder(placeholder)) => (placeholder.id(), TileTransform::default())
+            };
+
+            self.check_transform(pos, transform)?;
+
+            let tile = match cache.get(&id) {
+                Some(tile) => tile.clone(),
+                None => {
+                    let texture = images
+                        .get(id)
+                        .ok_or(TextureUtilsError::ImageNotLoaded { handle: id.untyped() })?;
+                    let tile = self.prepare_tile(texture, Some(pos))?;
+                    cache.insert(id, tile.clone());
+                    tile
+                }
+            };
 
-        let mut data = vec![0u8; (width * self.tile_width * self.bytes_per_pixel) * (height * self.tile_height)];
+            tiles.insert(pos, (tile, transform));
+        }
+
+        if !skipped.is_empty() {
+            // skipped tiles still cover their area, so the tile map has the same size as the complete one
+            let data = match self.fill_data(Some(images))? {
+                Some(fill) => fill.into_owned(),
+                None => vec![0; self.tile_width * self.tile_height * self.bytes_per_pixel]
+            };
+            let empty_tile = ImageOptions::default().create_image((self.tile_width, self.tile_height), data, self.texture_format);
+
+            for pos in skipped {
+                tiles.entry(pos).or_insert_with(|| (Cow::Owned(empty_tile.clone()), TileTransform::default()));
+            }
+        }
 
-        for y in (min_y..=max_y).rev() {
-            for x in min_x..=max_x {
-                let absolute_pos = p!(x, y);
-                let relative_pos = p!(x - min_x, max_y - y);
+        Ok(tiles)
+    }
 
-                let image = match position_texture_map.get(&absolute_pos) {
-                    Some(image) => image,
-                    None => continue,
-                };
+    /// The texture of the given placement, which is picked from its variants if it has some.
+    fn placed_texture<'a>(&self, pos: Position, placement: &'a TilePlacement) -> Result<&'a Handle<Image>, TextureUtilsError> {
+        match &placement.variants {
+            Some(variants) => variants.pick(self.seed, pos),
+            None => Ok(&placement.handle)
+        }
+    }
 
-                let image_data = &image.data;
+    /// Check if all given tiles match the tile size and format and can be transformed.
+    fn validate_tiles<'a>(
+        &self,
+        positions_and_images: impl IntoIterator<Item=(Position, &'a Image, TileTransform)>,
+    ) -> Result<Tiles<'a>, TextureUtilsError> {
+        positions_and_images
+            .into_iter()
+            .map(|(pos, texture, transform)| {
+                self.check_transform(pos, transform)?;
+                Ok((pos, (self.prepare_tile(texture, Some(pos))?, transform)))
+            })
+            .collect::<Result<Tiles, TextureUtilsError>>()
+    }
+
+    /// Check if the tile at the given position can be transformed, as odd rotations only work with square tiles.
+    fn check_transform(&self, pos: Position, transform: TileTransform) -> Result<(), TextureUtilsError> {
+        match self.tile_width != self.tile_height && matches!(transform.rotation, Quarter::One | Quarter::Three) {
+            true => Err(TextureUtilsError::InvalidParameter(format!(
+                "The tile at {:?} can only be rotated by a quarter if the tiles are square.",
+                pos
+            ))),
+            false => Ok(())
+        }
+    }
+
+    /// Convert the given tile to the configured format if auto conversion is enabled and check if it matches
+    /// the tile size and format. The position is only used for errors.
+    fn prepare_tile<'a>(&self, tile: &'a Image, position: Option<Position>) -> Result<Cow<'a, Image>, TextureUtilsError> {
+        let tile = match self.auto_convert && tile.texture_descriptor.format != self.texture_format {
+            true => Cow::Owned(convert_format(tile, self.texture_format)?),
+            false => Cow::Borrowed(tile)
+        };
+
+        self.check_tile(&tile, position)?;
+        Ok(tile)
+    }
+
+    /// Check if the given tile matches the tile size and format. The position is only used for errors.
+    fn check_tile(&self, tile: &Image, position: Option<Position>) -> Result<(), TextureUtilsError> {
+        if tile.texture_descriptor.format != self.texture_format {
+            return Err(TextureUtilsError::FormatMismatch {
+                expected: self.texture_format,
+                actual: tile.texture_descriptor.format,
+                position,
+            });
+        }
+
+        if tile.width() as usize != self.tile_width || tile.height() as usize != self.tile_height {
+            return Err(TextureUtilsError::TileSizeMismatch {
+                expected: (self.tile_width, self.tile_height),
+                actual: (tile.width() as usize, tile.height() as usize),
+                position,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the data of a tile which is placed at every empty position, if a fill is set.
+    /// The images are required to resolve a fill tile.
+    fn fill_data<'a>(&self, images: Option<&'a Assets<Image>>) -> Result<Option<Cow<'a, [u8]>>, TextureUtilsError> {
+        match &self.fill {
+            None => Ok(None),
+            Some(TileFill::Color(color)) => {
+                let pixel = color_to_pixel_bytes(*color, self.texture_format)?;
+                Ok(Some(Cow::Owned(pixel.repeat(self.tile_width * self.tile_height))))
+            }
+            Some(TileFill::Tile(handle)) => {
+                let images = images.ok_or(TextureUtilsError::InvalidParameter(
+                    "A fill tile can only be used if the images are provided.".to_string()
+                ))?;
+                let tile = images
+                    .get(handle)
+                    .ok_or(TextureUtilsError::ImageNotLoaded { handle: handle.id().untyped() })?;
+                match self.prepare_tile(tile, None)? {
+                    Cow::Borrowed(tile) => Ok(Some(Cow::Borrowed(&tile.data))),
+                    Cow::Owned(tile) => Ok(Some(Cow::Owned(tile.data)))
+                }
+            }
+        }
+    }
+
+    fn create_image_from_tiles(&self, tiles: &Tiles, fill: Option<&[u8]>) -> Result<Image, TextureUtilsError> {
+        let bounds = TileBounds::of(tiles.keys())?;
+        let data = self.stitch_tiles(tiles, bounds, fill);
+
+        Ok(self.create_image_from_data(bounds.width(), bounds.height(), data))
+    }
+
+    /// Write the given tiles into the pixel data of a tile map with the given bounds.
+    /// Empty positions get the fill data, if provided.
+    /// Every row of tiles is written to a disjoint part of the data, so with the rayon
+    /// feature the rows are written in parallel. The `stitch_tiles` bench measures the speedup.
+    fn stitch_tiles(&self, tiles: &Tiles, bounds: TileBounds, fill: Option<&[u8]>) -> Vec<u8> {
+        let mut data = Vec::new();
+        self.stitch_tiles_into(tiles, bounds, fill, &mut data);
+        data
+    }
+
+    /// Like [TileMapTextureCreator::stitch_tiles], but writes into the given buffer, which is
+    /// zeroed and resized first. The buffer only allocates if its capacity is too small.
+    fn stitch_tiles_into(&self, tiles: &Tiles, bounds: TileBounds, fill: Option<&[u8]>, data: &mut Vec<u8>) {
+        self.stitch_tiles_into_with(tiles, bounds, fill, data, cfg!(feature = "rayon"))
+    }
+
+    /// Like [TileMapTextureCreator::stitch_tiles_into], but the rows are only written in parallel if requested
+    /// and the rayon feature is enabled, so both ways can be compared.
+    fn stitch_tiles_into_with(&self, tiles: &Tiles, bounds: TileBounds, fill: Option<&[u8]>, data: &mut Vec<u8>, parallel: bool) {
+        let width = bounds.width();
+        let tile_row_size = width * self.cell_width() * self.bytes_per_pixel * self.cell_height();
+        data.clear();
+        data.resize(tile_row_size * bounds.height(), 0);
+
+        let stitch_row = |(row, row_data): (usize, &mut [u8])| {
+            let y = (bounds.max_y - row) as isize;
+
+            for x in bounds.min_x..=bounds.max_x {
+                let target = p!(x - bounds.min_x, 0);
+
+                match (tiles.get(&p!(x, y)), fill) {
+                    (Some((image, transform)), _) => self.add_data_from_tile_image_at_position(width, row_data, &target, &image.data, *transform),
+                    (None, Some(fill)) => self.add_data_from_tile_image_at_position(width, row_data, &target, fill, TileTransform::default()),
+                    (None, None) => {}
+                }
+            }
+        };
+
+        match parallel {
+            #[cfg(feature = "rayon")]
+            true => data.par_chunks_mut(tile_row_size).enumerate().for_each(stitch_row),
+            _ => data.chunks_mut(tile_row_size).enumerate().for_each(stitch_row)
+        }
+    }
+
+    fn get_max_x<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, TextureUtilsError> {
+        let max_opt = positions
+            .into_iter()
+            .map(|pos| pos.x)
+            .max();
+
+        let max = match max_opt {
+            Some(max) => max,
+            None => return Err(TextureUtilsError::NoTilesProvided)
+        };
+
+        Ok(max as usize)
+    }
+
+    fn get_min_x<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, TextureUtilsError> {
+        let min_opt = positions
+            .into_iter()
+            .map(|pos| pos.x)
+            .min();
+
+        let min = match min_opt {
+            Some(min) => min,
+            None => return Err(TextureUtilsError::NoTilesProvided)
+        };
+
+        Ok(min as usize)
+    }
+
+    fn get_max_y<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, TextureUtilsError> {
+        let max_opt = positions
+            .into_iter()
+            .map(|pos| pos.y)
+            .max();
+
+        let max = match max_opt {
+            Some(max) => max,
+            None => return Err(TextureUtilsError::NoTilesProvided)
+        };
+
+        Ok(max as usize)
+    }
+
+    fn get_min_y<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, TextureUtilsError> {
+        let min_opt = positions
+            .into_iter()
+            .map(|pos| pos.y)
+            .min();
+
+        let min = match min_opt {
+            Some(min) => min,
+            None => return Err(TextureUtilsError::NoTilesProvided)
+        };
+
+        Ok(min as usize)
+    }
+
+    fn add_data_from_tile_image_at_position(
+        &self,
+        width: usize,
+        data: &mut [u8],
+        pos: &Position,
+        image_data: &[u8],
+        transform: TileTransform,
+    ) {
+        let map_width = width * self.cell_width();
+        let map_size = (map_width, data.len() / (map_width * self.bytes_per_pixel));
+        let cell = ((pos.x as usize * self.cell_width()) as isize, (pos.y as usize * self.cell_height()) as isize);
+        let tile = self.transform_tile(image_data, transform);
+
+        // the tiles are prepared to the tile size and the map data is checked, so the tile always fits
+        blit_data(
+            &tile,
+            (self.tile_width, self.tile_height),
+            PixelRect::new(0, 0, self.tile_width, self.tile_height),
+            data,
+            map_size,
+            (cell.0 + self.padding as isize, cell.1 + self.padding as isize),
+            self.bytes_per_pixel,
+        ).expect("The tile fits into the tile map");
+
+        if self.extrude && self.padding > 0 {
+            self.extrude_tile(data, map_size, cell);
+        }
+    }
+
+    /// Get the pixel data of the tile with the given transformation applied.
+    fn transform_tile<'a>(&self, image_data: &'a [u8], transform: TileTransform) -> Cow<'a, [u8]> {
+        if transform.is_identity() {
+            return Cow::Borrowed(image_data);
+        }
+
+        let bpp = self.bytes_per_pixel;
+        let mut data = Vec::with_capacity(self.tile_width * self.tile_height * bpp);
+
+        for y in 0..self.tile_height {
+            for x in 0..self.tile_width {
+                let (source_x, source_y) = transform.source_pixel(x, y, self.tile_width, self.tile_height);
+                let source = (source_y * self.tile_width + source_x) * bpp;
+                data.extend_from_slice(&image_data[source..source + bpp]);
+            }
+        }
+
+        Cow::Owned(data)
+    }
+
+    /// Repeat the border pixels of the tile in the cell with the given top left pixel into its padding.
+    fn extrude_tile(&self, data: &mut [u8], map_size: (usize, usize), (cell_x, cell_y): (isize, isize)) {
+        let padding = self.padding as isize;
+        let (tile_x, tile_y) = (cell_x + padding, cell_y + padding);
+        let (right, bottom) = (tile_x + self.tile_width as isize - 1, tile_y + self.tile_height as isize - 1);
+        let copy = |data: &mut [u8], (x, y, width, height): (isize, isize, usize, usize), dst_pos: (isize, isize)| {
+            let rect = PixelRect::new(x as usize, y as usize, width, height);
+            blit_within(data, map_size, rect, dst_pos, self.bytes_per_pixel).expect("The cell of the tile fits into the tile map");
+        };
+
+        for x in 0..padding {
+            copy(data, (tile_x, tile_y, 1, self.tile_height), (cell_x + x, tile_y));
+            copy(data, (right, tile_y, 1, self.tile_height), (right + 1 + x, tile_y));
+        }
+
+        for y in 0..padding {
+            copy(data, (cell_x, tile_y, self.cell_width(), 1), (cell_x, cell_y + y));
+            copy(data, (cell_x, bottom, self.cell_width(), 1), (cell_x, bottom + 1 + y));
+        }
+    }
+
+    /// The width of the area of a single tile in the tile map, including the padding
+    fn cell_width(&self) -> usize {
+        self.tile_width + 2 * self.padding
+    }
+
+    /// The height of the area of a single tile in the tile map, including the padding
+    fn cell_height(&self) -> usize {
+        self.tile_height + 2 * self.padding
+    }
+
+    fn create_image_from_data(&self, max_x: usize, max_y: usize, data: Vec<u8>) -> Image {
+        self.options.create_image(
+            (max_x * self.cell_width(), max_y * self.cell_height()),
+            data,
+            self.texture_format,
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy_asset::prelude::*;
+    use bevy_math::{Rect, Vec2};
+    use bevy_render::prelude::*;
+    use bevy_render::render_asset::RenderAssetUsages;
+    use bevy_render::render_resource::TextureFormat;
+    use bevy_render::texture::ImageSampler;
+    use pad::p;
+    use uuid::Uuid;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::export::save_image_png;
+    use crate::image_options::ImageOptions;
+    use crate::tile_map_texture::{MissingTilePolicy, Quarter, TileBounds, TileMapTextureCreator, TilePlacement, VariantSet};
+
+    #[test]
+    fn create_tile_map_texture_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 2);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::RED,
+                Color::RED, Color::RED
+            ],
+        ));
+        let green = images.add(create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::GREEN, Color::GREEN,
+                Color::GREEN, Color::GREEN
+            ],
+        ));
+
+        // act
+        let image_result = creator.create_tile_map_texture(
+            &mut images,
+            [
+                (p!(0, 0), red.clone()),
+                (p!(1, 0), green.clone()),
+                (p!(0, 1), green),
+                (p!(1, 1), red),
+            ],
+        );
+
+        // assert
+        assert!(image_result.is_ok());
+        let new_image_handle = image_result.unwrap();
+
+        let expected_image = create_image(
+            (4, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::GREEN, Color::GREEN, Color::RED, Color::RED,
+                Color::GREEN, Color::GREEN, Color::RED, Color::RED,
+                Color::RED, Color::RED, Color::GREEN, Color::GREEN,
+                Color::RED, Color::RED, Color::GREEN, Color::GREEN,
+            ],
+        );
+
+        assert_eq!(
+            &images.get(new_image_handle).unwrap().data,
+            &expected_image.data
+        );
+    }
+
+    /// Every row of a tile must end up in the right place, also if the tiles are not square.
+    #[test]
+    fn create_tile_map_texture_with_non_square_tiles_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 2);
+        let mut images = Assets::<Image>::default();
+        let red_green = images.add(create_image((1, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::GREEN]));
+        let blue_white = images.add(create_image((1, 2), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::WHITE]));
+
+        // act
+        let image_result = creator.create_tile_map_texture(&mut images, [(p!(0, 0), red_green), (p!(1, 0), blue_white)]);
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::BLUE,
+                Color::GREEN, Color::WHITE,
+            ],
+        );
+
+        assert_eq!(expected.data, images.get(image_result.unwrap()).unwrap().data);
+    }
+
+    #[test]
+    fn create_tile_map_texture_with_transformed_tiles_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 2);
+        let mut images = Assets::<Image>::default();
+        let tile = images.add(create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN,
+                Color::BLUE, Color::WHITE,
+            ],
+        ));
+
+        // act
+        let image_result = creator.create_tile_map_texture(
+            &mut images,
+            [
+                (p!(0, 0), TilePlacement::new(tile.clone()).with_flip_x(true)),
+                (p!(1, 0), TilePlacement::new(tile.clone()).with_rotation(Quarter::One)),
+                (p!(2, 0), TilePlacement::new(tile).with_flip_y(true).with_rotation(Quarter::Three)),
+            ],
+        );
+
+        // assert
+        let expected = create_image(
+            (6, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::GREEN, Color::RED, Color::BLUE, Color::RED, Color::WHITE, Color::GREEN,
+                Color::WHITE, Color::BLUE, Color::WHITE, Color::GREEN, Color::BLUE, Color::RED,
+            ],
+        );
+
+        assert_eq!(expected.data, images.get(image_result.unwrap()).unwrap().data);
+    }
+
+    #[test]
+    fn create_tile_map_texture_with_fill_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let placeholder = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::WHITE]));
+        let color_creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1).with_fill(Color::BLUE);
+        let tile_creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1).with_fill_tile(placeholder);
+
+        // act
+        let color_result = color_creator.create_tile_map_image(&images, [(p!(0, 0), red.clone()), (p!(1, 1), red.clone())]);
+        let tile_result = tile_creator.create_tile_map_image(&images, [(p!(0, 0), red.clone()), (p!(1, 1), red)]);
+
+        // assert
+        let expected_color = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::RED, Color::RED, Color::BLUE]);
+        let expected_tile = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::WHITE, Color::RED, Color::RED, Color::WHITE]);
+
+        assert_eq!(expected_color.data, color_result.unwrap().data);
+        assert_eq!(expected_tile.data, tile_result.unwrap().data);
+    }
+
+    #[test]
+    fn create_tile_map_texture_with_padding_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let tile = images.add(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]));
+        let padded_creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 1).with_padding(1);
+        let extruded_creator = padded_creator.clone().with_extrusion(true);
+
+        // act
+        let padded_result = padded_creator.create_tile_map_image(&images, [(p!(0, 0), tile.clone())]);
+        let extruded_result = extruded_creator.create_tile_map_texture_with_layout(&mut images, [(p!(0, 0), tile)]);
+
+        // assert
+        let expected_padded = create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::NONE, Color::NONE, Color::NONE, Color::NONE,
+                Color::NONE, Color::RED, Color::BLUE, Color::NONE,
+                Color::NONE, Color::NONE, Color::NONE, Color::NONE,
+            ],
+        );
+        let expected_extruded = create_image(
+            (4, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::RED, Color::BLUE, Color::BLUE,
+                Color::RED, Color::RED, Color::BLUE, Color::BLUE,
+                Color::RED, Color::RED, Color::BLUE, Color::BLUE,
+            ],
+        );
+        let extruded = extruded_result.unwrap();
+
+        assert_eq!(expected_padded.data, padded_result.unwrap().data);
+        assert_eq!(expected_extruded.data, images.get(&extruded.texture).unwrap().data);
+        assert_eq!(Some(Rect::new(1.0, 1.0, 3.0, 2.0)), extruded.rect(p!(0, 0)));
+    }
+
+    #[test]
+    fn create_tile_map_texture_chunks_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+
+        // act
+        let chunks = creator.create_tile_map_texture_chunks(
+            &mut images,
+            [
+                (p!(0, 0), red.clone()),
+                (p!(1, 1), red),
+                (p!(2, 0), blue.clone()),
+                (p!(3, 4), blue),
+            ],
+            2,
+        ).unwrap();
 
-                self.add_data_from_tile_image_at_position(width, &mut data, &relative_pos, image_data);
-            }
-        }
+        // assert
+        let chunk_data = |pos| images.get(&chunks.iter().find(|(p, _)| *p == pos).unwrap().1).unwrap().data.clone();
+        let expected_first = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::NONE, Color::RED, Color::RED, Color::NONE]);
+        let expected_second = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::NONE, Color::NONE, Color::BLUE, Color::NONE]);
+        let expected_last = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::NONE, Color::BLUE]);
 
-        let tiles_texture = self.create_image_from_data(width, height, data);
-        Ok(images.add(tiles_texture))
+        assert_eq!(3, chunks.len());
+        assert_eq!(expected_first.data, chunk_data(p!(0, 0)));
+        assert_eq!(expected_second.data, chunk_data(p!(2, 0)));
+        assert_eq!(expected_last.data, chunk_data(p!(2, 4)));
     }
 
-    fn get_max_x<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, &'static str> {
-        let max_opt = positions
-            .into_iter()
-            .map(|pos| pos.x)
-            .max();
+    #[test]
+    fn create_tile_map_image_from_images_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let red = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]);
+        let blue = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]);
 
-        let max = match max_opt {
-            Some(max) => max,
-            None => return Err("No tiles were provided!")
-        };
+        // act
+        let image_result = creator.create_tile_map_image_from_images([(p!(0, 0), &red), (p!(1, 1), &blue)]);
 
-        Ok(max as usize)
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::NONE, Color::BLUE,
+                Color::RED, Color::NONE,
+            ],
+        );
+
+        assert_eq!(expected.data, image_result.unwrap().data);
     }
 
-    fn get_min_x<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, &'static str> {
-        let min_opt = positions
-            .into_iter()
-            .map(|pos| pos.x)
-            .min();
+    #[test]
+    fn create_tile_map_texture_from_indices_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let tileset = images.add(create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, Color::GREEN,
+                Color::BLUE, Color::WHITE,
+            ],
+        ));
 
-        let min = match min_opt {
-            Some(min) => min,
-            None => return Err("No tiles were provided!")
-        };
+        // act
+        let handle = creator.create_tile_map_texture_from_indices(&mut images, &tileset, [(p!(0, 0), 2), (p!(1, 0), 1), (p!(0, 1), 2)]);
+        let result = creator.create_tile_map_texture_from_indices(&mut images, &tileset, [(p!(0, 0), 4)]);
 
-        Ok(min as usize)
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::BLUE, Color::NONE,
+                Color::BLUE, Color::GREEN,
+            ],
+        );
+
+        assert_eq!(expected.data, images.get(handle.unwrap()).unwrap().data);
+        assert!(matches!(result, Err(TextureUtilsError::NotFound { kind: "tile index", name }) if name == "4"));
     }
 
-    fn get_max_y<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, &'static str> {
-        let max_opt = positions
-            .into_iter()
-            .map(|pos| pos.y)
-            .max();
+    #[test]
+    fn create_tile_map_texture_from_str_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let wall = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GRAY]));
+        let water = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let legend = HashMap::from([('#', wall), ('~', water)]);
 
-        let max = match max_opt {
-            Some(max) => max,
-            None => return Err("No tiles were provided!")
-        };
+        // act
+        let handle = creator.create_tile_map_texture_from_str(&mut images, &legend, "
+            ###
+            # ~~
+        ");
+        let result = creator.create_tile_map_texture_from_str(&mut images, &legend, "#?");
 
-        Ok(max as usize)
-    }
+        // assert
+        let expected = create_image(
+            (4, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::GRAY, Color::GRAY, Color::GRAY, Color::NONE,
+                Color::GRAY, Color::NONE, Color::BLUE, Color::BLUE,
+            ],
+        );
 
-    fn get_min_y<'a>(positions: impl IntoIterator<Item=&'a Position>) -> Result<usize, &'static str> {
-        let min_opt = positions
-            .into_iter()
-            .map(|pos| pos.y)
-            .min();
+        assert_eq!(expected.data, images.get(handle.unwrap()).unwrap().data);
+        assert!(matches!(result, Err(TextureUtilsError::NotFound { kind: "legend character", name }) if name == "?"));
+    }
 
-        let min = match min_opt {
-            Some(min) => min,
-            None => return Err("No tiles were provided!")
+    #[test]
+    fn create_tile_map_texture_with_variant_sets_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let green = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]));
+        let lime = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::LIME_GREEN]));
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let grass = VariantSet::new().with_variant(green, 3.0).with_variant(lime, 1.0).with_variant(red, 0.0);
+        let positions = (0..20).flat_map(|x| (0..20).map(move |y| p!(x, y))).collect::<Vec<_>>();
+        let create = |seed: u64, images: &mut Assets<Image>| {
+            let handle = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+                .with_seed(seed)
+                .create_tile_map_texture(images, positions.iter().map(|pos| (*pos, grass.clone())))
+                .unwrap();
+            images.get(handle).unwrap().data.clone()
         };
 
-        Ok(min as usize)
-    }
-
-    fn add_data_from_tile_image_at_position(&self, width: usize, data: &mut [u8], pos: &Position, image_data: &[u8]) {
-        for y in 0..self.tile_height {
-            for x in 0..self.tile_width {
-                for i in 0..self.bytes_per_pixel {
-                    let image_index = y * self.tile_height * self.bytes_per_pixel + x * self.bytes_per_pixel + i;
+        // act
+        let data = create(1, &mut images);
+        let same_seed_data = create(1, &mut images);
+        let other_seed_data = create(2, &mut images);
+        let result = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+            .create_tile_map_texture(&mut images, [(p!(0, 0), VariantSet::new())]);
 
-                    let tiles_texture_index =
-                        (width * self.tile_width * self.bytes_per_pixel) * (pos.y as usize * self.tile_height) // move to the first row the tile is contained in
-                            + (pos.x as usize * self.tile_width * self.bytes_per_pixel) // than move to the first pixel of the tile
-                            + (self.tile_height * self.bytes_per_pixel * width * y) // than move to the current row of the tile
-                            + x * self.bytes_per_pixel // than move to the current pixel block
-                            + i; // than finally move to the current pixel
+        // assert
+        let count = |color: Color| data.chunks_exact(4).filter(|pixel| color.as_rgba_u8() == [pixel[0], pixel[1], pixel[2], pixel[3]]).count();
 
-                    data[tiles_texture_index] = image_data[image_index];
-                }
-            }
-        }
+        assert_eq!(data, same_seed_data);
+        assert_ne!(data, other_seed_data);
+        assert_eq!(0, count(Color::RED));
+        assert!((250..350).contains(&count(Color::GREEN)), "About three quarters of the tiles should be green, but {} were.", count(Color::GREEN));
+        assert_eq!(400, count(Color::GREEN) + count(Color::LIME_GREEN));
+        assert_eq!("A variant set requires at least one variant with a positive weight.", result.unwrap_err().to_string());
     }
 
-    fn create_image_from_data(&self, max_x: usize, max_y: usize, data: Vec<u8>) -> Image {
-        Image::new(
-            Extent3d {
-                width: (max_x * self.tile_width) as u32,
-                height: (max_y * self.tile_height) as u32,
-                depth_or_array_layers: 1,
-            },
-            TextureDimension::D2,
-            data,
-            self.texture_format,
-        )
-    }
-}
+    #[test]
+    fn create_tile_map_texture_with_layout_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 1);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED; 2]));
 
+        // act
+        let result = creator.create_tile_map_texture_with_layout(
+            &mut images,
+            [(p!(1, 1), red.clone()), (p!(2, 0), red)],
+        );
 
-#[cfg(test)]
-mod tests {
-    use bevy_asset::prelude::*;
-    use bevy_render::prelude::*;
-    use bevy_render::render_resource::TextureFormat;
-    use pad::p;
-    use uuid::Uuid;
+        // assert
+        let tile_map = result.unwrap();
 
-    use crate::tile_map_texture::TileMapTextureCreator;
-    use crate::test_utils::create_image;
+        assert_eq!(Vec2::new(4.0, 2.0), tile_map.layout.size);
+        assert_eq!(2, tile_map.layout.len());
+        assert_eq!(Some(Rect::new(0.0, 0.0, 2.0, 1.0)), tile_map.rect(p!(1, 1)));
+        assert_eq!(Some(Rect::new(2.0, 1.0, 4.0, 2.0)), tile_map.rect(p!(2, 0)));
+        assert_eq!(None, tile_map.rect(p!(0, 0)));
+    }
 
     #[test]
-    fn create_tile_map_texture_works() {
+    fn create_tile_map_array_texture_works() {
         // arrange
-        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 2);
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
         let mut images = Assets::<Image>::default();
-        let red = images.add(create_image(
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+
+        // act
+        let image_result = creator.create_tile_map_array_texture(
+            &mut images,
+            [(p!(0, 0), 0, red.clone()), (p!(1, 0), 0, red), (p!(1, 0), 5, blue)],
+        );
+
+        // assert
+        let array_texture = images.get(image_result.unwrap()).unwrap();
+        let expected = create_image(
             (2, 2),
             TextureFormat::Rgba8UnormSrgb,
             [
                 Color::RED, Color::RED,
-                Color::RED, Color::RED
-            ],
-        ));
-        let green = images.add(create_image(
-            (2, 2),
-            TextureFormat::Rgba8UnormSrgb,
-            [
-                Color::GREEN, Color::GREEN,
-                Color::GREEN, Color::GREEN
+                Color::NONE, Color::BLUE,
             ],
-        ));
+        );
 
-        // act
-        let image_result = creator.create_tile_map_texture(
+        assert_eq!(2, array_texture.texture_descriptor.size.depth_or_array_layers);
+        assert_eq!((2, 1), (array_texture.width(), array_texture.height()));
+        assert_eq!(expected.data, array_texture.data);
+    }
+
+    #[test]
+    fn replace_tile_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let tile_map = creator.create_tile_map_texture(
             &mut images,
-            [
-                (p!(0, 0), red.clone()),
-                (p!(1, 0), green.clone()),
-                (p!(0, 1), green),
-                (p!(1, 1), red),
-            ],
-        );
+            [(p!(0, 0), red.clone()), (p!(1, 0), red.clone()), (p!(0, 1), red.clone()), (p!(1, 1), red)],
+        ).unwrap();
+
+        // act
+        let result = creator.replace_tile(&mut images, &tile_map, p!(1, 0), &blue);
 
         // assert
-        assert!(image_result.is_ok());
-        let new_image_handle = image_result.unwrap();
+        assert!(result.is_ok());
 
-        let expected_image = create_image(
-            (4, 4),
+        let expected = create_image(
+            (2, 2),
             TextureFormat::Rgba8UnormSrgb,
             [
-                Color::GREEN, Color::GREEN, Color::RED, Color::RED,
-                Color::GREEN, Color::GREEN, Color::RED, Color::RED,
-                Color::RED, Color::RED, Color::GREEN, Color::GREEN,
-                Color::RED, Color::RED, Color::GREEN, Color::GREEN,
+                Color::RED, Color::RED,
+                Color::RED, Color::BLUE,
             ],
         );
 
-        assert_eq!(
-            &images.get(new_image_handle).unwrap().data,
-            &expected_image.data
-        );
+        assert_eq!(expected.data, images.get(tile_map).unwrap().data);
+    }
+
+    /// A tile map with another format than the creator must be rejected instead of being overwritten.
+    #[test]
+    fn replace_tile_in_image_with_wrong_tile_map_format_fails() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut tile_map = create_image((2, 2), TextureFormat::Rgba8Unorm, [Color::RED; 4]);
+        let blue = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]);
+
+        // act
+        let result = creator.replace_tile_in_image(&mut tile_map, p!(1, 0), &blue);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::FormatMismatch { position: None, .. })));
+    }
+
+    /// A tile map with less data than its size requires must be rejected instead of panicking.
+    #[test]
+    fn replace_tile_in_image_with_missing_tile_map_data_fails() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut tile_map = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]);
+        tile_map.data.truncate(8);
+        let blue = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]);
+
+        // act
+        let result = creator.replace_tile_in_image(&mut tile_map, p!(1, 0), &blue);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::SizeMismatch)));
+    }
+
+    /// The configured options must be applied to the created tile map texture.
+    #[test]
+    fn create_tile_map_texture_with_options_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+            .with_options(ImageOptions::new(RenderAssetUsages::RENDER_WORLD).with_sampler(ImageSampler::nearest()));
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+
+        // act
+        let image_result = creator.create_tile_map_texture(&mut images, [(p!(0, 0), red)]);
+
+        // assert
+        let new_image = images.get(image_result.unwrap()).unwrap();
+
+        assert_eq!(RenderAssetUsages::RENDER_WORLD, new_image.asset_usage);
+        assert!(matches!(new_image.sampler, ImageSampler::Descriptor(_)), "The sampler should be the configured one, but wasn't.");
+    }
+
+    /// With auto conversion, tiles with another format are converted instead of rejected.
+    #[test]
+    fn create_tile_map_texture_with_auto_convert_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1).with_auto_convert(true);
+        let mut images = Assets::<Image>::default();
+        // the colors are written as RGBA bytes, so red is blue in a BGRA image
+        let blue = images.add(create_image((1, 1), TextureFormat::Bgra8UnormSrgb, [Color::RED]));
+        let green = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]));
+
+        // act
+        let image_result = creator.create_tile_map_texture(&mut images, [(p!(0, 0), blue), (p!(1, 0), green)]);
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE, Color::GREEN]);
+        let texture = images.get(image_result.unwrap()).unwrap();
+
+        assert_eq!(expected.data, texture.data);
     }
 
     /// If the texture format does not match the configured format, an error should be returned indicating
@@ -264,7 +1733,14 @@ mod tests {
         assert!(image_result.is_err());
         let message = image_result.unwrap_err();
 
-        assert_eq!("Not all textures have the configured texture format 'Rgba8UnormSrgb'.", message)
+        assert!(matches!(
+            message,
+            TextureUtilsError::FormatMismatch {
+                expected: TextureFormat::Rgba8UnormSrgb,
+                actual: TextureFormat::Rgba8Unorm,
+                position,
+            } if position == Some(p!(0, 0))
+        ))
     }
 
     /// Providing handles to textures that are not loaded yet results in an error.
@@ -284,6 +1760,173 @@ mod tests {
         assert!(image_result.is_err());
         let message = image_result.unwrap_err();
 
-        assert_eq!("Not all textures are loaded yet.", message)
+        assert!(matches!(
+            message,
+            TextureUtilsError::ImageNotLoaded { handle } if handle == AssetId::<Image>::Uuid { uuid: Uuid::default() }.untyped()
+        ))
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn create_many_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+
+        // act
+        let handles = creator.create_many(&mut images, [
+            vec![(p!(0, 0), red.clone()), (p!(1, 0), blue.clone())],
+            vec![(p!(0, 0), blue.clone()), (p!(0, 1), red.clone())],
+        ]).unwrap();
+        let result = creator.create_many(&mut images, [vec![(p!(0, 0), red)], vec![]]);
+
+        // assert
+        let expected = [
+            create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]),
+            create_image((1, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]),
+        ];
+
+        assert_eq!(2, handles.len());
+
+        for (expected, handle) in expected.iter().zip(handles) {
+            assert_eq!(expected.data, images.get(handle).unwrap().data);
+        }
+
+        assert!(matches!(result, Err(TextureUtilsError::NoTilesProvided)));
+        assert_eq!(4, images.len());
+    }
+
+    /// The buffer of the tile map is reused, so rebuilding it doesn't allocate if it doesn't grow.
+    #[test]
+    fn rebuild_tile_map_texture_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let tile_map = creator.create_tile_map_texture(&mut images, [(p!(0, 0), red.clone()), (p!(1, 0), blue.clone())]).unwrap();
+        let buffer = images.get(&tile_map).unwrap().data.as_ptr();
+
+        // act
+        creator.rebuild_tile_map_texture(&mut images, &tile_map, [(p!(0, 0), blue), (p!(0, 1), red)]).unwrap();
+        let result = creator.rebuild_tile_map_texture(&mut images, &tile_map, Vec::<(_, Handle<Image>)>::new());
+
+        // assert
+        let expected = create_image((1, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::BLUE]);
+        let rebuilt = images.get(&tile_map).unwrap();
+
+        assert_eq!((1, 2), (rebuilt.width(), rebuilt.height()));
+        assert_eq!(expected.data, rebuilt.data);
+        assert_eq!(buffer, rebuilt.data.as_ptr());
+        assert!(matches!(result, Err(TextureUtilsError::NoTilesProvided)));
+        assert_eq!(expected.data, images.get(&tile_map).unwrap().data);
+    }
+
+    /// Missing tiles are skipped or replaced by the placeholder, depending on the policy.
+    #[test]
+    fn create_tile_map_texture_with_missing_tile_policy_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let placeholder = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::FUCHSIA]));
+        let missing = Handle::Weak(AssetId::<Image>::Uuid { uuid: Uuid::default() });
+        let tiles = [(p!(0, 0), red), (p!(1, 0), missing)];
+
+        // act
+        let skipped = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+            .with_missing_tile_policy(MissingTilePolicy::Skip)
+            .create_tile_map_image(&images, tiles.clone())
+            .unwrap();
+        let replaced = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1)
+            .with_missing_tile_policy(MissingTilePolicy::Placeholder(placeholder))
+            .create_tile_map_image(&images, tiles)
+            .unwrap();
+
+        // assert
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::NONE]).data, skipped.data);
+        assert_eq!(create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED, Color::FUCHSIA]).data, replaced.data);
+    }
+
+    #[test]
+    fn create_or_load_cached_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let blue = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]));
+        let cache_dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let cached_files = || std::fs::read_dir(&cache_dir).unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+
+        // act
+        let baked = creator.create_or_load_cached("map", &cache_dir, &mut images, [(p!(0, 0), red.clone())]).unwrap();
+        let baked_files = cached_files();
+
+        // replace the cached file, so it is visible that it is loaded instead of baking again
+        save_image_png(&create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]), &baked_files[0]).unwrap();
+        let loaded = creator.create_or_load_cached("map", &cache_dir, &mut images, [(p!(0, 0), red)]).unwrap();
+
+        let changed = creator.create_or_load_cached("map", &cache_dir, &mut images, [(p!(0, 0), blue)]).unwrap();
+        let changed_files = cached_files();
+
+        // assert
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+
+        assert_eq!(1, baked_files.len());
+        assert_eq!(Color::RED.as_rgba_u8(), images.get(&baked).unwrap().data[..]);
+        assert_eq!(Color::GREEN.as_rgba_u8(), images.get(&loaded).unwrap().data[..]);
+        assert_eq!(Color::BLUE.as_rgba_u8(), images.get(&changed).unwrap().data[..]);
+        assert_eq!(1, changed_files.len(), "The outdated tile map should have been removed, but wasn't.");
+        assert_ne!(baked_files, changed_files);
+    }
+
+    #[test]
+    fn create_or_load_cached_with_invalid_key_fails() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let cache_dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+
+        // act
+        let results = ["../map", "maps/map", "map\\1", ""]
+            .map(|key| creator.create_or_load_cached(key, &cache_dir, &mut images, [(p!(0, 0), red.clone())]));
+
+        // assert
+        assert!(results.iter().all(|result| matches!(result, Err(TextureUtilsError::InvalidParameter(_)))));
+        assert!(!cache_dir.exists(), "Nothing should be written for invalid keys, but was.");
+    }
+
+    /// The rows written in parallel must be the same as the ones written one after another.
+    #[test]
+    fn stitch_tiles_in_parallel_matches_sequential_stitching() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 2, 2)
+            .with_padding(1)
+            .with_extrusion(true)
+            .with_fill(Color::GRAY);
+        let mut images = Assets::<Image>::default();
+        let positions_and_textures = (0..12)
+            .flat_map(|x| (0..9).map(move |y| (x, y)))
+            .filter(|(x, y)| (x + y) % 5 != 0)
+            .map(|(x, y)| {
+                let color = Color::rgb_u8(x as u8 * 20, y as u8 * 25, 7);
+                let tile = images.add(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [color, Color::RED, Color::BLUE, color]));
+                let rotation = match (x + y) % 3 { 0 => Quarter::Zero, 1 => Quarter::One, _ => Quarter::Two };
+                (p!(x, y), TilePlacement::new(tile).with_flip_x(x % 2 == 0).with_rotation(rotation))
+            })
+            .collect::<Vec<_>>();
+        let tiles = creator.collect_tiles(&images, positions_and_textures).unwrap();
+        let fill = creator.fill_data(Some(&images)).unwrap();
+        let bounds = TileBounds::of(tiles.keys()).unwrap();
+        let (mut parallel, mut sequential) = (Vec::new(), Vec::new());
+
+        // act
+        creator.stitch_tiles_into_with(&tiles, bounds, fill.as_deref(), &mut parallel, true);
+        creator.stitch_tiles_into_with(&tiles, bounds, fill.as_deref(), &mut sequential, false);
+
+        // assert
+        assert_eq!(sequential.len(), parallel.len());
+        assert!(sequential == parallel, "The parallel stitching should match the sequential one, but didn't.");
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+use pad::Position;
+
+use crate::error::TextureUtilsError;
+use crate::tile_map_texture::{TilePlacement, VariantSet};
+
+/// A registered tile: its texture, optional texture variants and optional metadata.
+#[derive(Clone, Debug)]
+pub struct TileEntry {
+    pub texture: Handle<Image>,
+    /// Alternative textures of the tile with their weights. If present, the creator picks the texture of a position
+    /// from them with its seed, so maps look less repetitive. See [VariantSet].
+    pub variants: VariantSet,
+    /// Arbitrary data of the tile, like "solid" -> "true"
+    pub metadata: HashMap<String, String>,
+}
+
+impl TileEntry {
+    pub fn new(texture: Handle<Image>) -> Self {
+        Self { texture, variants: VariantSet::new(), metadata: HashMap::new() }
+    }
+
+    /// Add an alternative texture with the given weight. The texture of the entry is a variant as well, with the weight 1.0.
+    pub fn with_variant(mut self, variant: Handle<Image>, weight: f32) -> Self {
+        if self.variants.variants.is_empty() {
+            self.variants = self.variants.with_variant(self.texture.clone(), 1.0);
+        }
+
+        self.variants = self.variants.with_variant(variant, weight);
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// The placement of this tile, from which the creator picks a variant, if the tile has some.
+    pub fn placement(&self) -> TilePlacement {
+        match self.variants.variants.is_empty() {
+            true => TilePlacement::new(self.texture.clone()),
+            false => TilePlacement::new(self.texture.clone()).with_variants(self.variants.clone())
+        }
+    }
+}
+
+/// Maps tile names to their textures, so maps stored as names (like from files or the network)
+/// can be baked without looking up handles manually.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct TileRegistry {
+    tiles: HashMap<String, TileEntry>,
+}
+
+impl TileRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tile with the given name. Returns the entry previously registered with this name.
+    pub fn register(&mut self, name: impl Into<String>, entry: TileEntry) -> Option<TileEntry> {
+        self.tiles.insert(name.into(), entry)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TileEntry> {
+        self.tiles.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<TileEntry> {
+        self.tiles.remove(name)
+    }
+
+    /// Replace the given tile names with the placements of the registered tiles.
+    pub fn resolve<'a>(
+        &self,
+        positions_and_names: impl IntoIterator<Item=(Position, &'a str)>,
+    ) -> Result<Vec<(Position, TilePlacement)>, TextureUtilsError> {
+        positions_and_names
+            .into_iter()
+            .map(|(pos, name)| self.tiles
+                .get(name)
+                .map(|entry| (pos, entry.placement()))
+                .ok_or(TextureUtilsError::NotFound { kind: "tile", name: name.to_string() })
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::tile_map_texture::{TileMapTextureCreator, VariantSet};
+    use crate::tile_registry::{TileEntry, TileRegistry};
+
+    #[test]
+    fn create_tile_map_texture_from_names_works() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+        let mut images = Assets::<Image>::default();
+        let mut registry = TileRegistry::new();
+        registry.register("grass", TileEntry::new(images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]))));
+        registry.register("water", TileEntry::new(images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE]))));
+
+        // act
+        let image_result = creator.create_tile_map_texture_from_names(
+            &mut images,
+            &registry,
+            [(p!(0, 0), "grass"), (p!(1, 0), "water")],
+        );
+
+        // assert
+        let expected = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN, Color::BLUE]);
+
+        assert_eq!(expected.data, images.get(image_result.unwrap()).unwrap().data);
+    }
+
+    /// Tiles from the registry get the same variants as the same variant set placed directly with the same seed.
+    #[test]
+    fn variants_are_picked_with_the_seed_of_the_creator() {
+        // arrange
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1).with_seed(5);
+        let mut images = Assets::<Image>::default();
+        let green = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]));
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let mut registry = TileRegistry::new();
+        registry.register("grass", TileEntry::new(green.clone()).with_variant(red.clone(), 1.0));
+        let positions = (0..10).map(|x| p!(x, 3)).collect::<Vec<_>>();
+
+        // act
+        let from_names = creator
+            .create_tile_map_texture_from_names(&mut images, &registry, positions.iter().map(|pos| (*pos, "grass")))
+            .unwrap();
+        let variant_set = VariantSet::new().with_variant(green, 1.0).with_variant(red, 1.0);
+        let from_variants = creator
+            .create_tile_map_texture(&mut images, positions.iter().map(|pos| (*pos, variant_set.clone())))
+            .unwrap();
+
+        // assert
+        let data = images.get(from_names).unwrap().data.clone();
+
+        assert_eq!(data, images.get(from_variants).unwrap().data);
+        assert!(
+            data.chunks_exact(4).any(|pixel| pixel == Color::GREEN.as_rgba_u8()) && data.chunks_exact(4).any(|pixel| pixel == Color::RED.as_rgba_u8()),
+            "Both the texture and its variant should be used, but weren't."
+        );
+    }
+
+    #[test]
+    fn resolve_with_unknown_name_fails() {
+        // arrange
+        let registry = TileRegistry::new();
+
+        // act
+        let result = registry.resolve([(p!(0, 0), "lava")]);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::NotFound { kind: "tile", name }) if name == "lava"));
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+use pad::{p, Position};
+
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::image_options::ImageOptions;
+use crate::morphology::mask_channel;
+use crate::tile_map_layout::TileMapLayout;
+
+/// The side of a tile.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// The offset from a tile position to its neighbour on this side.
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Side::Top => (0, 1),
+            Side::Bottom => (0, -1),
+            Side::Left => (-1, 0),
+            Side::Right => (1, 0),
+        }
+    }
+}
+
+/// Composite transition overlays over the seams between different tile types of an assembled tile map,
+/// so edges like grass to sand look hand-authored.
+/// For every tile and each of its neighbours with a different type, the border provider is called with
+/// the neighbour type, the tile type and the side of the tile the neighbour is on. If it returns an overlay
+/// from the border tileset, the overlay is alpha blended over the whole tile. So an overlay usually is
+/// transparent except for a strip along the given side.
+/// The origin is the bottom left position of the tile map and the tile size is the width and height
+/// of a single tile in pixels, like they were used to create the tile map. The tile map must have an 8-bit
+/// RGBA or BGRA format and every overlay must have the tile size and the format of the tile map.
+pub fn composite_transitions<'a, T: Eq>(
+    tile_map: &mut Image,
+    origin: Position,
+    tile_size: (usize, usize),
+    positions_and_tiles: impl IntoIterator<Item=(Position, T)>,
+    border_provider: impl Fn(&T, &T, Side) -> Option<&'a Image>,
+) -> Result<(), TextureUtilsError> {
+    let format = tile_map.texture_descriptor.format;
+    is_srgb_rgba8(format)?;
+
+    let layout = TileMapLayout::new(tile_map, origin, tile_size)?;
+    let tiles = positions_and_tiles.into_iter().collect::<HashMap<_, _>>();
+
+    for (pos, tile) in &tiles {
+        let (tile_x, tile_y) = match layout.tile_origin(*pos) {
+            Some(tile_origin) => tile_origin,
+            None => continue
+        };
+
+        for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+            let (dx, dy) = side.offset();
+            let neighbour = match tiles.get(&p!(pos.x + dx, pos.y + dy)) {
+                Some(neighbour) if neighbour != tile => neighbour,
+                _ => continue
+            };
+
+            let overlay = match border_provider(neighbour, tile, side) {
+                Some(overlay) => overlay,
+                None => continue
+            };
+
+            if overlay.width() as usize != layout.tile_width || overlay.height() as usize != layout.tile_height {
+                return Err(TextureUtilsError::TileSizeMismatch {
+                    expected: (layout.tile_width, layout.tile_height),
+                    actual: (overlay.width() as usize, overlay.height() as usize),
+                    position: Some(*pos),
+                });
+            }
+
+            if overlay.texture_descriptor.format != format {
+                return Err(TextureUtilsError::FormatMismatch {
+                    expected: format,
+                    actual: overlay.texture_descriptor.format,
+                    position: Some(*pos),
+                });
+            }
+
+            if overlay.data.len() != layout.tile_width * layout.tile_height * 4 {
+                return Err(TextureUtilsError::UnsupportedPixelSize);
+            }
+
+            for y in 0..layout.tile_height {
+                for x in 0..layout.tile_width {
+                    let overlay_index = (y * layout.tile_width + x) * 4;
+                    let map_index = ((tile_y + y) * layout.width + tile_x + x) * 4;
+
+                    blend_pixel(&mut tile_map.data[map_index..map_index + 4], &overlay.data[overlay_index..overlay_index + 4]);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Create the 16 R8Unorm masks of a terrain tile for the tiles of an [AutotileKind::Edges16](crate::autotile::AutotileKind::Edges16)
+/// tileset, in the same order. A mask is 1.0 where the terrain is shown and fades to 0.0 over the given amount of pixels towards
+/// every side without a terrain neighbour, so the base tile below the terrain shows through at these sides.
+pub fn edge_transition_masks((width, height): (usize, usize), feather: usize) -> Vec<Image> {
+    (0..16)
+        .map(|index: usize| {
+            // the sides without terrain neighbours, with the bits of the north, east, south and west neighbour
+            let open_sides = [1, 2, 4, 8].into_iter().filter(|bit| index & bit == 0).collect::<Vec<_>>();
+
+            let data = (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let coverage = open_sides
+                        .iter()
+                        .map(|side| {
+                            let distance = match side {
+                                1 => y as f32 + 0.5,
+                                2 => (width - x) as f32 - 0.5,
+                                4 => (height - y) as f32 - 0.5,
+                                _ => x as f32 + 0.5
+                            };
+
+                            match feather {
+                                0 => 1.0,
+                                _ => (distance / feather as f32).min(1.0)
+                            }
+                        })
+                        .fold(1.0, f32::min);
+
+                    (coverage * 255.0).round() as u8
+                })
+                .collect();
+
+            ImageOptions::default().create_image((width, height), data, TextureFormat::R8Unorm)
+        })
+        .collect()
+}
+
+/// Bake transition tiles between two terrains, like grass on top of water, so only the base tiles have to be authored.
+/// For every mask, the terrain tile is blended over the base tile, where the mask tells how much of the terrain is shown.
+/// The masks can be created with [edge_transition_masks] or drawn by hand, as R8Unorm images or images whose alpha is used.
+/// The base and terrain tile must have the same size and format with 4-byte pixels, and the masks must have their size.
+pub fn bake_transition_tiles(base: &Image, terrain: &Image, masks: &[Image]) -> Result<Vec<Image>, TextureUtilsError> {
+    let (format, terrain_format) = (base.texture_descriptor.format, terrain.texture_descriptor.format);
+    let size = (base.width() as usize, base.height() as usize);
+
+    if format != terrain_format {
+        return Err(TextureUtilsError::FormatMismatch { expected: format, actual: terrain_format, position: None });
+    }
+
+    if size != (terrain.width() as usize, terrain.height() as usize) {
+        return Err(TextureUtilsError::SizeMismatch);
+    }
+
+    if base.data.len() != size.0 * size.1 * 4 {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    masks
+        .iter()
+        .map(|mask| {
+            let mask_size = (mask.width() as usize, mask.height() as usize);
+
+            if mask_size != size {
+                return Err(TextureUtilsError::TileSizeMismatch { expected: size, actual: mask_size, position: None });
+            }
+
+            let (pixel_size, channel) = mask_channel(mask.texture_descriptor.format)?;
+            let mut tile = base.clone();
+
+            for ((pixel, terrain_pixel), coverage) in tile.data
+                .chunks_exact_mut(4)
+                .zip(terrain.data.chunks_exact(4))
+                .zip(mask.data.iter().skip(channel).step_by(pixel_size))
+            {
+                let coverage = *coverage as f32 / 255.0;
+
+                for (value, terrain_value) in pixel.iter_mut().zip(terrain_pixel) {
+                    *value = (*terrain_value as f32 * coverage + *value as f32 * (1.0 - coverage)).round() as u8;
+                }
+            }
+
+            Ok(tile)
+        })
+        .collect()
+}
+
+/// Blend the source pixel over the destination pixel, using the alpha of the source.
+fn blend_pixel(destination: &mut [u8], source: &[u8]) {
+    let alpha = source[3] as f32 / 255.0;
+
+    for i in 0..3 {
+        destination[i] = (source[i] as f32 * alpha + destination[i] as f32 * (1.0 - alpha)).round() as u8;
+    }
+
+    destination[3] = (source[3] as f32 + destination[3] as f32 * (1.0 - alpha)).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::tile_transitions::{bake_transition_tiles, composite_transitions, edge_transition_masks, Side};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    enum Terrain {
+        Grass,
+        Sand,
+    }
+
+    #[test]
+    fn composite_transitions_works() {
+        // arrange
+        let mut tile_map = create_image(
+            (4, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::GREEN, Color::GREEN, Color::YELLOW, Color::YELLOW],
+        );
+        let grass_border_left = create_image(
+            (2, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::GREEN, Color::NONE],
+        );
+
+        // act
+        let result = composite_transitions(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 1),
+            [(p!(0, 0), Terrain::Grass), (p!(1, 0), Terrain::Sand)],
+            |neighbour, _, side| match (neighbour, side) {
+                (Terrain::Grass, Side::Left) => Some(&grass_border_left),
+                _ => None
+            },
+        );
+
+        // assert
+        assert!(result.is_ok());
+
+        let expected = create_image(
+            (4, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::GREEN, Color::GREEN, Color::GREEN, Color::YELLOW],
+        );
+
+        assert_eq!(expected.data, tile_map.data, "The sand tile should have a grass border on its left side, but hadn't.");
+    }
+
+    #[test]
+    fn composite_transitions_with_wrong_overlay_size_fails() {
+        // arrange
+        let mut tile_map = create_image((4, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 4]);
+        let overlay = create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN]);
+
+        // act
+        let result = composite_transitions(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 1),
+            [(p!(0, 0), Terrain::Grass), (p!(1, 0), Terrain::Sand)],
+            |_, _, _| Some(&overlay),
+        );
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            TextureUtilsError::TileSizeMismatch { expected: (2, 1), actual: (1, 1), .. }
+        ));
+    }
+
+    #[test]
+    fn composite_transitions_with_wrong_overlay_format_fails() {
+        // arrange
+        let mut tile_map = create_image((4, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 4]);
+        let overlay = create_image((2, 1), TextureFormat::Bgra8UnormSrgb, [Color::GREEN; 2]);
+
+        // act
+        let result = composite_transitions(
+            &mut tile_map,
+            p!(0, 0),
+            (2, 1),
+            [(p!(0, 0), Terrain::Grass), (p!(1, 0), Terrain::Sand)],
+            |_, _, _| Some(&overlay),
+        );
+
+        // assert
+        assert!(matches!(
+            result,
+            Err(TextureUtilsError::FormatMismatch { expected: TextureFormat::Rgba8UnormSrgb, actual: TextureFormat::Bgra8UnormSrgb, .. })
+        ));
+    }
+
+    #[test]
+    fn bake_transition_tiles_works() {
+        // arrange
+        let water = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::BLUE; 3]);
+        let grass = create_image((3, 1), TextureFormat::Rgba8UnormSrgb, [Color::GREEN; 3]);
+        let masks = edge_transition_masks((3, 1), 1);
+
+        // act
+        let tiles = bake_transition_tiles(&water, &grass, &masks).unwrap();
+
+        // assert
+        // the tile with grass to the north, south and west only fades out to the east
+        let expected = create_image(
+            (3, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            [Color::GREEN, Color::GREEN, Color::rgb_u8(0, 128, 127)],
+        );
+
+        assert_eq!(16, tiles.len());
+        assert_eq!(vec![255, 255, 128], masks[13].data);
+        assert_eq!(expected.data, tiles[13].data);
+        assert_eq!(grass.data, tiles[15].data);
+    }
+}
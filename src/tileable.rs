@@ -0,0 +1,122 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::TextureFormat;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::error::TextureUtilsError;
+
+/// Create a copy of the given texture which tiles without visible edges, like noise or photo textures used for terrain.
+/// The texture is offset by half its size, so its edges meet in the center and the pixels at the new edges
+/// continue on the opposite side. The seams in the center are then hidden by fading into the original texture,
+/// which is continuous there, over the given width in pixels on both sides of the seams.
+/// The blend width can be at most half of the width and height of the texture.
+/// Only works with 8-bit RGBA and BGRA formats, where the sRGB formats are blended in linear space.
+pub fn make_tileable(texture: &Image, blend_width: usize) -> Result<Image, TextureUtilsError> {
+    let srgb = match texture.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm | TextureFormat::Bgra8Unorm => false,
+        format => return Err(TextureUtilsError::UnsupportedFormat { format })
+    };
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+
+    if texture.data.len() != width * height * 4 {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    if blend_width > width / 2 || blend_width > height / 2 {
+        return Err(TextureUtilsError::OutOfRange {
+            name: "blend width",
+            value: blend_width as f64,
+            expected: "at most half of the width and height of the texture",
+        });
+    }
+
+    let (seam_x, seam_y) = (width / 2, height / 2);
+    // how much of the original texture is visible, depending on the distance to a seam
+    let weight = |position: usize, seam: usize| match blend_width {
+        0 => 0.0,
+        _ => 1.0 - (position.abs_diff(seam) as f32 / blend_width as f32).min(1.0)
+    };
+    let mix = |a: u8, b: u8, t: f32| match srgb {
+        true => linear_to_srgb(srgb_to_linear(a) + (srgb_to_linear(b) - srgb_to_linear(a)) * t),
+        false => (a as f32 + (b as f32 - a as f32) * t).round() as u8
+    };
+
+    let mut tileable = texture.clone();
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) * 4;
+            let shifted_index = (((y + seam_y) % height) * width + (x + seam_x) % width) * 4;
+            let t = weight(x, seam_x).max(weight(y, seam_y));
+
+            for c in 0..4 {
+                let shifted = texture.data[shifted_index + c];
+                let original = texture.data[index + c];
+
+                tileable.data[index + c] = match c {
+                    3 => (shifted as f32 + (original as f32 - shifted as f32) * t).round() as u8,
+                    _ => mix(shifted, original, t)
+                };
+            }
+        }
+    }
+
+    Ok(tileable)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::tileable::make_tileable;
+
+    fn create_texture() -> Image {
+        let colors = (0..16).map(|i| Color::rgba_u8(i * 16, 0, 0, 255));
+        create_image((4, 4), TextureFormat::Rgba8Unorm, colors)
+    }
+
+    /// Without blending, the texture is only offset by half its size.
+    #[test]
+    fn make_tileable_without_blending_offsets_texture() {
+        // arrange
+        let texture = create_texture();
+
+        // act
+        let tileable = make_tileable(&texture, 0).unwrap();
+
+        // assert
+        let red = tileable.data.chunks_exact(4).map(|pixel| pixel[0] / 16).collect::<Vec<_>>();
+
+        assert_eq!(vec![10, 11, 8, 9, 14, 15, 12, 13, 2, 3, 0, 1, 6, 7, 4, 5], red);
+    }
+
+    /// The pixels on the seams are taken from the original texture, the pixels far away from them are offset.
+    #[test]
+    fn make_tileable_blends_seams() {
+        // arrange
+        let texture = create_texture();
+
+        // act
+        let tileable = make_tileable(&texture, 2).unwrap();
+
+        // assert
+        let red = tileable.data.chunks_exact(4).map(|pixel| pixel[0]).collect::<Vec<_>>();
+
+        assert_eq!(160, red[0], "The corner is furthest from the seams and should be offset, but wasn't.");
+        assert_eq!(texture.data[(2 * 4 + 2) * 4], red[2 * 4 + 2], "The seams should show the original texture, but didn't.");
+        assert_eq!((176 + 16) / 2, red[1], "The pixel between should be blended, but wasn't.");
+    }
+
+    #[test]
+    fn make_tileable_with_too_wide_blending_fails() {
+        // act
+        let result = make_tileable(&create_texture(), 3);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), TextureUtilsError::OutOfRange { name: "blend width", .. }));
+    }
+}
@@ -0,0 +1,164 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::Extent3d;
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::blit::blit;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+
+// All transformations work with every uncompressed texture format. Besides the size and pixels,
+// the transformed images keep all properties of the original one, like their format and sampler.
+
+/// Create a copy of the given area of the image. The area must be inside of the image.
+pub fn crop(image: &Image, rect: PixelRect) -> Result<Image, TextureUtilsError> {
+    if !rect.fits_into((image.width() as usize, image.height() as usize)) {
+        return Err(TextureUtilsError::RectOutOfBounds { rect });
+    }
+
+    let mut cropped = with_size(image, (rect.width, rect.height));
+    blit(image, rect, &mut cropped, (0, 0))?;
+
+    Ok(cropped)
+}
+
+/// Create a copy of the image mirrored along the vertical axis, so left becomes right.
+pub fn flip_horizontal(image: &Image) -> Image {
+    let (width, height) = size(image);
+    map_pixels(image, (width, height), |x, y| (width - 1 - x, y))
+}
+
+/// Create a copy of the image mirrored along the horizontal axis, so top becomes bottom.
+pub fn flip_vertical(image: &Image) -> Image {
+    let (width, height) = size(image);
+    map_pixels(image, (width, height), |x, y| (x, height - 1 - y))
+}
+
+/// Create a copy of the image rotated clockwise by 90 degrees, which swaps its width and height.
+pub fn rotate_90(image: &Image) -> Image {
+    let (width, height) = size(image);
+    map_pixels(image, (height, width), |x, y| (y, height - 1 - x))
+}
+
+/// Create a copy of the image rotated by 180 degrees.
+pub fn rotate_180(image: &Image) -> Image {
+    let (width, height) = size(image);
+    map_pixels(image, (width, height), |x, y| (width - 1 - x, height - 1 - y))
+}
+
+/// Create a copy of the image rotated clockwise by 270 degrees, which swaps its width and height.
+pub fn rotate_270(image: &Image) -> Image {
+    let (width, height) = size(image);
+    map_pixels(image, (height, width), |x, y| (width - 1 - y, x))
+}
+
+fn size(image: &Image) -> (usize, usize) {
+    (image.width() as usize, image.height() as usize)
+}
+
+/// Create a copy of the image with the given size and zeroed pixels.
+fn with_size(image: &Image, (width, height): (usize, usize)) -> Image {
+    let mut copy = image.clone();
+    copy.texture_descriptor.size = Extent3d { width: width as u32, height: height as u32, depth_or_array_layers: 1 };
+    copy.data = vec![0; width * height * image.texture_descriptor.format.pixel_size()];
+    copy
+}
+
+/// Create an image with the given size, where every pixel is copied from the pixel of the original image
+/// the given function returns for it.
+fn map_pixels(image: &Image, (width, height): (usize, usize), source_pixel: impl Fn(usize, usize) -> (usize, usize)) -> Image {
+    let bytes_per_pixel = image.texture_descriptor.format.pixel_size();
+    let source_width = image.width() as usize;
+    let mut data = Vec::with_capacity(image.data.len());
+
+    for y in 0..height {
+        for x in 0..width {
+            let (source_x, source_y) = source_pixel(x, y);
+            let index = (source_y * source_width + source_x) * bytes_per_pixel;
+            data.extend_from_slice(&image.data[index..index + bytes_per_pixel]);
+        }
+    }
+
+    let mut mapped = with_size(image, (width, height));
+    mapped.data = data;
+    mapped
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::pixel_rect::PixelRect;
+    use crate::transform::{crop, flip_horizontal, flip_vertical, rotate_180, rotate_270, rotate_90};
+
+    const R: Color = Color::RED;
+    const G: Color = Color::GREEN;
+    const B: Color = Color::BLUE;
+    const W: Color = Color::WHITE;
+    const K: Color = Color::BLACK;
+    const Y: Color = Color::YELLOW;
+
+    /// A 3x2 image with the pixels
+    /// R G B
+    /// W K Y
+    fn create_texture() -> Image {
+        create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [R, G, B, W, K, Y])
+    }
+
+    #[test]
+    fn crop_works() {
+        // act
+        let cropped = crop(&create_texture(), PixelRect::new(1, 0, 2, 2)).unwrap();
+        let result = crop(&create_texture(), PixelRect::new(2, 0, 2, 2));
+
+        // assert
+        assert_eq!((2, 2), (cropped.width(), cropped.height()));
+        assert_eq!(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [G, B, K, Y]).data, cropped.data);
+        assert!(matches!(result, Err(TextureUtilsError::RectOutOfBounds { rect }) if rect == PixelRect::new(2, 0, 2, 2)));
+    }
+
+    #[test]
+    fn flip_works() {
+        // act
+        let horizontal = flip_horizontal(&create_texture());
+        let vertical = flip_vertical(&create_texture());
+
+        // assert
+        assert_eq!(create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [B, G, R, Y, K, W]).data, horizontal.data);
+        assert_eq!(create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [W, K, Y, R, G, B]).data, vertical.data);
+    }
+
+    #[test]
+    fn rotate_works() {
+        // act
+        let quarter = rotate_90(&create_texture());
+        let half = rotate_180(&create_texture());
+        let three_quarters = rotate_270(&create_texture());
+
+        // assert
+        assert_eq!((2, 3), (quarter.width(), quarter.height()));
+        assert_eq!(create_image((2, 3), TextureFormat::Rgba8UnormSrgb, [W, R, K, G, Y, B]).data, quarter.data);
+        assert_eq!(create_image((3, 2), TextureFormat::Rgba8UnormSrgb, [Y, K, W, B, G, R]).data, half.data);
+        assert_eq!((2, 3), (three_quarters.width(), three_quarters.height()));
+        assert_eq!(create_image((2, 3), TextureFormat::Rgba8UnormSrgb, [B, Y, G, K, R, W]).data, three_quarters.data);
+    }
+
+    /// The transformations copy whole pixels, no matter how many bytes they have.
+    #[test]
+    fn rotate_works_with_other_formats() {
+        // arrange
+        let mut texture = create_image((1, 1), TextureFormat::Rgba8Unorm, [K]);
+        texture.texture_descriptor.format = TextureFormat::R8Unorm;
+        texture.texture_descriptor.size.width = 2;
+        texture.texture_descriptor.size.height = 2;
+        texture.data = vec![1, 2, 3, 4];
+
+        // act
+        let rotated = rotate_90(&texture);
+
+        // assert
+        assert_eq!(vec![3, 1, 4, 2], rotated.data);
+    }
+}
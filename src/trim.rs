@@ -0,0 +1,106 @@
+use bevy_render::prelude::*;
+
+use crate::blit::is_visible;
+use crate::color::is_srgb_rgba8;
+use crate::error::TextureUtilsError;
+use crate::pixel_rect::PixelRect;
+use crate::transform::crop;
+
+/// Crop the given texture to the smallest area which contains all of its visible pixels, like sprites
+/// exported with a big transparent margin. Returns the cropped texture together with the area it covers
+/// in the original one, so the removed margins are known and the sprite can be positioned like before.
+/// Besides the size, the cropped texture keeps all properties of the original one.
+/// Returns None if the texture is completely transparent. The texture must have an 8-bit RGBA or BGRA format.
+pub fn trim_transparent(texture: &Image) -> Result<Option<(Image, PixelRect)>, TextureUtilsError> {
+    is_srgb_rgba8(texture.texture_descriptor.format)?;
+
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+
+    if texture.data.len() != width * height * 4 {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let bounds = texture.data
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(_, pixel)| is_visible(pixel))
+        .map(|(i, _)| PixelRect::new(i % width, i / width, 1, 1))
+        .reduce(|bounds, pixel| bounds.union(&pixel));
+
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return Ok(None)
+    };
+
+    Ok(Some((crop(texture, bounds)?, bounds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::image_options::ImageOptions;
+    use crate::pixel_rect::PixelRect;
+    use crate::trim::trim_transparent;
+
+    const N: Color = Color::NONE;
+
+    #[test]
+    fn trim_transparent_works() {
+        // arrange
+        let texture = create_image(
+            (4, 4),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                N, N, N, N,
+                N, Color::RED, N, N,
+                N, N, Color::BLUE, N,
+                N, N, N, N,
+            ],
+        );
+
+        // act
+        let (trimmed, area) = trim_transparent(&texture).unwrap().unwrap();
+
+        // assert
+        let expected = create_image(
+            (2, 2),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                Color::RED, N,
+                N, Color::BLUE,
+            ],
+        );
+
+        assert_eq!(PixelRect::new(1, 1, 2, 2), area);
+        assert_eq!((2, 2), (trimmed.width(), trimmed.height()));
+        assert_eq!(expected.data, trimmed.data);
+    }
+
+    #[test]
+    fn trim_transparent_of_transparent_texture_returns_none() {
+        // arrange
+        let texture = create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [N; 4]);
+
+        // act
+        let result = trim_transparent(&texture);
+
+        // assert
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn trim_transparent_with_unsupported_format_fails() {
+        // arrange
+        let texture = ImageOptions::default().create_image((2, 2), vec![0; 16], TextureFormat::Rg16Uint);
+
+        // act
+        let result = trim_transparent(&texture);
+
+        // assert
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::Rg16Uint })));
+    }
+}
@@ -0,0 +1,257 @@
+use bevy_render::prelude::*;
+use bevy_render::render_resource::{Extent3d, TextureFormat};
+use bevy_render::texture::TextureFormatPixelInfo;
+
+use crate::error::TextureUtilsError;
+
+/// An algorithm to enlarge pixel art without blurring it, which rounds diagonal edges instead of
+/// making them blocky like nearest filtering.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PixelArtScaler {
+    /// Doubles the size by only copying pixels, which keeps the palette of the art
+    Scale2x,
+    /// Triples the size by only copying pixels, which keeps the palette of the art
+    Scale3x,
+    /// Doubles the size and blends the pixels along the detected edges, which makes them smoother,
+    /// but adds new colors. Only works with 8-bit RGBA and BGRA formats.
+    Xbr2x,
+}
+
+impl PixelArtScaler {
+    /// The factor by which the algorithm enlarges an image
+    pub fn factor(&self) -> usize {
+        match self {
+            PixelArtScaler::Scale2x | PixelArtScaler::Xbr2x => 2,
+            PixelArtScaler::Scale3x => 3,
+        }
+    }
+}
+
+/// Create an enlarged copy of the given pixel art with the given algorithm, like sprites baked at twice their size
+/// at load time. Besides the size, the copy keeps all properties of the image, like its format and sampler.
+/// Scale2x and Scale3x work with every uncompressed format. Pixels outside of the image are treated like the
+/// closest edge pixel.
+pub fn upscale_pixel_art(image: &Image, scaler: PixelArtScaler) -> Result<Image, TextureUtilsError> {
+    let format = image.texture_descriptor.format;
+    let bytes_per_pixel = format.pixel_size();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    if image.data.len() != width * height * bytes_per_pixel {
+        return Err(TextureUtilsError::UnsupportedPixelSize);
+    }
+
+    let factor = scaler.factor();
+    let new_width = width * factor;
+    let mut data = vec![0; image.data.len() * factor * factor];
+    let pixel = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        let index = (y * width + x) * bytes_per_pixel;
+        &image.data[index..index + bytes_per_pixel]
+    };
+    let luma_weights = match (scaler, format) {
+        (PixelArtScaler::Xbr2x, TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm) => [0.299, 0.587, 0.114],
+        (PixelArtScaler::Xbr2x, TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm) => [0.114, 0.587, 0.299],
+        (PixelArtScaler::Xbr2x, format) => return Err(TextureUtilsError::UnsupportedFormat { format }),
+        _ => [0.0; 3]
+    };
+
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let block = match scaler {
+                PixelArtScaler::Scale2x => scale2x(|dx, dy| pixel(x + dx, y + dy)),
+                PixelArtScaler::Scale3x => scale3x(|dx, dy| pixel(x + dx, y + dy)),
+                PixelArtScaler::Xbr2x => xbr2x(|dx, dy| pixel(x + dx, y + dy), luma_weights),
+            };
+
+            for (i, block_pixel) in block.iter().enumerate() {
+                let target_x = x as usize * factor + i % factor;
+                let target_y = y as usize * factor + i / factor;
+                let index = (target_y * new_width + target_x) * bytes_per_pixel;
+                data[index..index + bytes_per_pixel].copy_from_slice(block_pixel);
+            }
+        }
+    }
+
+    let mut upscaled = image.clone();
+    upscaled.texture_descriptor.size = Extent3d {
+        width: new_width as u32,
+        height: (height * factor) as u32,
+        depth_or_array_layers: 1,
+    };
+    upscaled.data = data;
+    Ok(upscaled)
+}
+
+/// The 2x2 pixels which replace the pixel E, where the pixel function gets the offset of a neighbour:
+/// ```text
+/// A B C
+/// D E F
+/// G H I
+/// ```
+fn scale2x<'a>(pixel: impl Fn(isize, isize) -> &'a [u8]) -> Vec<Vec<u8>> {
+    let (b, d, e, f, h) = (pixel(0, -1), pixel(-1, 0), pixel(0, 0), pixel(1, 0), pixel(0, 1));
+
+    match b != h && d != f {
+        true => vec![
+            if d == b { d } else { e },
+            if b == f { f } else { e },
+            if d == h { d } else { e },
+            if h == f { f } else { e },
+        ],
+        false => vec![e; 4]
+    }
+    .into_iter()
+    .map(<[u8]>::to_vec)
+    .collect()
+}
+
+/// The 3x3 pixels which replace the pixel E, with the same neighbours like [scale2x].
+fn scale3x<'a>(pixel: impl Fn(isize, isize) -> &'a [u8]) -> Vec<Vec<u8>> {
+    let [a, b, c, d, e, f, g, h, i] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (0, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]
+        .map(|(dx, dy)| pixel(dx, dy));
+
+    match b != h && d != f {
+        true => vec![
+            if d == b { d } else { e },
+            if (d == b && e != c) || (b == f && e != a) { b } else { e },
+            if b == f { f } else { e },
+            if (d == b && e != g) || (d == h && e != a) { d } else { e },
+            e,
+            if (b == f && e != i) || (h == f && e != c) { f } else { e },
+            if d == h { d } else { e },
+            if (d == h && e != i) || (h == f && e != g) { h } else { e },
+            if h == f { f } else { e },
+        ],
+        false => vec![e; 9]
+    }
+    .into_iter()
+    .map(<[u8]>::to_vec)
+    .collect()
+}
+
+/// The 2x2 pixels which replace the center pixel, following the 2xBR rules by Hyllian. Every corner checks if an edge
+/// runs along the diagonal in front of it, by comparing the color differences along both diagonals in a 5x5 area.
+/// If so, the corner is blended with the closer of its neighbours.
+fn xbr2x<'a>(pixel: impl Fn(isize, isize) -> &'a [u8], luma_weights: [f32; 3]) -> Vec<Vec<u8>> {
+    let distance = |a: &[u8], b: &[u8]| {
+        let delta = [0, 1, 2, 3].map(|i| a[i] as f32 - b[i] as f32);
+        let y = luma_weights[0] * delta[0] + luma_weights[1] * delta[1] + luma_weights[2] * delta[2];
+        let u = delta[2] - y;
+        let v = delta[0] - y;
+
+        48.0 * y.abs() + 7.0 * u.abs() + 6.0 * v.abs() + 48.0 * delta[3].abs()
+    };
+    let e = pixel(0, 0);
+
+    [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+        .into_iter()
+        .map(|(sx, sy)| {
+            // the neighbours of the bottom right corner, mirrored towards the current corner
+            let p = |dx: isize, dy: isize| pixel(dx * sx, dy * sy);
+            let (b, c, d, f, g, h, i) = (p(0, -1), p(1, -1), p(-1, 0), p(1, 0), p(-1, 1), p(0, 1), p(1, 1));
+            let (f4, i4, h5, i5) = (p(2, 0), p(2, 1), p(0, 2), p(1, 2));
+
+            let along_edge = distance(e, c) + distance(e, g) + distance(i, h5) + distance(i, f4) + 4.0 * distance(h, f);
+            let across_edge = distance(h, d) + distance(h, i5) + distance(f, i4) + distance(f, b) + 4.0 * distance(e, i);
+
+            match along_edge < across_edge && e != f && e != h {
+                true => {
+                    let closest = if distance(e, f) <= distance(e, h) { f } else { h };
+                    e.iter().zip(closest).map(|(a, b)| (*a as u16 + *b as u16).div_ceil(2) as u8).collect()
+                }
+                false => e.to_vec()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+
+    use crate::builders::create_image;
+    use crate::error::TextureUtilsError;
+    use crate::upscale::{PixelArtScaler, upscale_pixel_art};
+
+    const K: Color = Color::BLACK;
+    const W: Color = Color::WHITE;
+
+    /// A diagonal line from the top left to the bottom right.
+    fn create_diagonal() -> Image {
+        create_image(
+            (3, 3),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                K, W, W,
+                W, K, W,
+                W, W, K,
+            ],
+        )
+    }
+
+    #[test]
+    fn upscale_pixel_art_scale2x_works() {
+        // act
+        let upscaled = upscale_pixel_art(&create_diagonal(), PixelArtScaler::Scale2x).unwrap();
+
+        // assert
+        // the corners of the image look like corners of an area, because the pixels outside are like the edge ones
+        let expected = create_image(
+            (6, 6),
+            TextureFormat::Rgba8UnormSrgb,
+            [
+                K, K, W, W, W, W,
+                K, W, K, W, W, W,
+                W, K, K, K, W, W,
+                W, W, K, K, K, W,
+                W, W, W, K, W, K,
+                W, W, W, W, K, K,
+            ],
+        );
+
+        assert_eq!((6, 6), (upscaled.width(), upscaled.height()));
+        assert_eq!(expected.data, upscaled.data);
+    }
+
+    #[test]
+    fn upscale_pixel_art_scale3x_keeps_uniform_areas() {
+        // arrange
+        let image = create_image((2, 1), TextureFormat::Rgba8UnormSrgb, [K, K]);
+
+        // act
+        let upscaled = upscale_pixel_art(&image, PixelArtScaler::Scale3x).unwrap();
+
+        // assert
+        assert_eq!((6, 3), (upscaled.width(), upscaled.height()));
+        assert_eq!(create_image((6, 3), TextureFormat::Rgba8UnormSrgb, [K; 18]).data, upscaled.data);
+    }
+
+    /// The corners along the diagonal are blended, all others keep their color.
+    #[test]
+    fn upscale_pixel_art_xbr2x_blends_edges() {
+        // act
+        let upscaled = upscale_pixel_art(&create_diagonal(), PixelArtScaler::Xbr2x).unwrap();
+
+        // assert
+        let pixel = |x: usize, y: usize| upscaled.data[(y * 6 + x) * 4];
+
+        assert_eq!((0, 255), (pixel(0, 0), pixel(3, 0)));
+        assert_eq!(128, pixel(2, 1), "The corner along the edge should be blended, but wasn't.");
+    }
+
+    #[test]
+    fn upscale_pixel_art_xbr2x_with_unsupported_format_fails() {
+        // arrange
+        let mut image = create_image((1, 1), TextureFormat::Rgba8Unorm, [K]);
+        image.texture_descriptor.format = TextureFormat::R32Float;
+
+        // act
+        let result = upscale_pixel_art(&image, PixelArtScaler::Xbr2x);
+
+        // assert
+        assert!(result.is_err());
+        assert!(matches!(result, Err(TextureUtilsError::UnsupportedFormat { format: TextureFormat::R32Float })));
+    }
+}
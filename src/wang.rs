@@ -0,0 +1,155 @@
+use bevy_asset::prelude::*;
+use bevy_render::prelude::*;
+use pad::{p, Position};
+
+use crate::error::TextureUtilsError;
+use crate::random::Random;
+use crate::tile_map_texture::TileMapTextureCreator;
+
+/// A tile whose edges are labeled, like with colors. Two tiles fit next to each other if the labels of their
+/// touching edges are equal, so the content of the tiles must continue seamlessly across edges with equal labels.
+#[derive(Clone, Debug)]
+pub struct WangTile {
+    pub texture: Handle<Image>,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl WangTile {
+    /// Create a tile with the labels of its top, right, bottom and left edge.
+    pub fn new(texture: Handle<Image>, [top, right, bottom, left]: [u32; 4]) -> Self {
+        Self { texture, top, right, bottom, left }
+    }
+}
+
+/// Fill a grid with the given size with randomly picked tiles, where all touching edges of neighbouring tiles match.
+/// Returns the index of the tile at every position, where (0, 0) is the bottom left position. The same seed always
+/// creates the same tiling. The tiles are placed row by row, so every combination of a bottom and left label which
+/// occurs in the tiling must be available. This is always the case for complete sets, which contain a tile for every
+/// combination of labels, like the 16 tiles with two labels for horizontal and two for vertical edges.
+pub fn generate_wang_tiling(
+    tiles: &[WangTile],
+    (width, height): (usize, usize),
+    seed: u64,
+) -> Result<Vec<(Position, usize)>, TextureUtilsError> {
+    if tiles.is_empty() {
+        return Err(TextureUtilsError::NoTilesProvided);
+    }
+
+    let mut random = Random::new(seed);
+    let mut indices: Vec<usize> = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let left = (x > 0).then(|| &tiles[indices[y * width + x - 1]]);
+            let below = (y > 0).then(|| &tiles[indices[(y - 1) * width + x]]);
+
+            let candidates = (0..tiles.len())
+                .filter(|i| left.is_none_or(|left| left.right == tiles[*i].left))
+                .filter(|i| below.is_none_or(|below| below.top == tiles[*i].bottom))
+                .collect::<Vec<_>>();
+
+            if candidates.is_empty() {
+                return Err(TextureUtilsError::InvalidParameter(format!(
+                    "No tile matches the left label {:?} and the bottom label {:?}.",
+                    left.map(|left| left.right),
+                    below.map(|below| below.top)
+                )));
+            }
+
+            indices.push(candidates[random.next_index(candidates.len())]);
+        }
+    }
+
+    Ok(indices
+        .into_iter()
+        .enumerate()
+        .map(|(i, index)| (p!(i % width, i / width), index))
+        .collect())
+}
+
+/// Create a tile map texture from a random tiling, see [generate_wang_tiling].
+pub fn bake_wang_tiling(
+    creator: &TileMapTextureCreator,
+    images: &mut Assets<Image>,
+    tiles: &[WangTile],
+    size: (usize, usize),
+    seed: u64,
+) -> Result<Handle<Image>, TextureUtilsError> {
+    let positions_and_textures = generate_wang_tiling(tiles, size, seed)?
+        .into_iter()
+        .map(|(pos, index)| (pos, tiles[index].texture.clone()));
+
+    creator.create_tile_map_texture(images, positions_and_textures)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+    use bevy_render::render_resource::TextureFormat;
+    use pad::p;
+
+    use crate::builders::create_image;
+    use crate::tile_map_texture::TileMapTextureCreator;
+    use crate::wang::{bake_wang_tiling, generate_wang_tiling, WangTile};
+
+    /// The complete set with two labels for every edge.
+    fn complete_set() -> Vec<WangTile> {
+        (0..16).map(|i| WangTile::new(Handle::default(), [i & 1, i >> 1 & 1, i >> 2 & 1, i >> 3 & 1])).collect()
+    }
+
+    #[test]
+    fn generate_wang_tiling_works() {
+        // arrange
+        let tiles = complete_set();
+
+        // act
+        let tiling = generate_wang_tiling(&tiles, (6, 5), 42).unwrap();
+        let same_seed_tiling = generate_wang_tiling(&tiles, (6, 5), 42).unwrap();
+        let other_seed_tiling = generate_wang_tiling(&tiles, (6, 5), 43).unwrap();
+
+        // assert
+        let tile_at = tiling.iter().map(|(pos, index)| (*pos, &tiles[*index])).collect::<HashMap<_, _>>();
+
+        assert_eq!(30, tiling.len());
+        assert!(tile_at.iter().all(|(pos, tile)| {
+            let right_matches = tile_at.get(&p!(pos.x + 1, pos.y)).is_none_or(|right| right.left == tile.right);
+            let top_matches = tile_at.get(&p!(pos.x, pos.y + 1)).is_none_or(|top| top.bottom == tile.top);
+            right_matches && top_matches
+        }), "All touching edges should match, but didn't.");
+        assert_eq!(tiling, same_seed_tiling);
+        assert_ne!(tiling, other_seed_tiling);
+    }
+
+    #[test]
+    fn generate_wang_tiling_with_incomplete_set_fails() {
+        // arrange
+        let tiles = vec![WangTile::new(Handle::default(), [0, 1, 0, 0])];
+
+        // act
+        let result = generate_wang_tiling(&tiles, (2, 1), 0);
+
+        // assert
+        assert_eq!("No tile matches the left label Some(1) and the bottom label None.", result.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn bake_wang_tiling_works() {
+        // arrange
+        let mut images = Assets::<Image>::default();
+        let red = images.add(create_image((1, 1), TextureFormat::Rgba8UnormSrgb, [Color::RED]));
+        let tiles = vec![WangTile::new(red, [0; 4])];
+        let creator = TileMapTextureCreator::new(TextureFormat::Rgba8UnormSrgb, 1, 1);
+
+        // act
+        let handle = bake_wang_tiling(&creator, &mut images, &tiles, (2, 2), 0).unwrap();
+
+        // assert
+        assert_eq!(create_image((2, 2), TextureFormat::Rgba8UnormSrgb, [Color::RED; 4]).data, images.get(handle).unwrap().data);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bevy_asset::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_render::prelude::*;
+
+/// The priority of a texture job. Jobs with a higher priority are always processed first.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum WorkPriority {
+    /// Work that can wait, like minimap refreshes or LOD bakes
+    Low,
+    Normal,
+    /// Work that is visible right away, like patches of visible chunks
+    High,
+}
+
+/// The result of a single step of a texture job.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum JobProgress {
+    /// The job is finished and gets removed from the queue.
+    Done,
+    /// The job has more work to do and gets resumed later.
+    Pending,
+}
+
+type TextureJob = Box<dyn FnMut(&mut Assets<Image>) -> JobProgress + Send + Sync>;
+
+/// Queue of texture jobs which are processed in the background with a time budget per frame,
+/// keeping frame times stable under heavy texture work.
+/// A job is called repeatedly until it is done. Each call should only do a small part of the
+/// work, so the queue can stop after the budget is used up and resume in the next frame.
+#[derive(Resource)]
+pub struct TextureWorkQueue {
+    /// The time the queue may spend per frame
+    budget: Duration,
+    /// The queued jobs per priority, from low to high
+    jobs: [VecDeque<TextureJob>; 3],
+}
+
+impl Default for TextureWorkQueue {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(2))
+    }
+}
+
+impl TextureWorkQueue {
+    pub fn new(budget: Duration) -> Self {
+        Self { budget, jobs: Default::default() }
+    }
+
+    /// Set the time the queue may spend per frame.
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Add a job with the given priority. Jobs with the same priority are processed in the order they were added.
+    pub fn push(
+        &mut self,
+        priority: WorkPriority,
+        job: impl FnMut(&mut Assets<Image>) -> JobProgress + Send + Sync + 'static,
+    ) {
+        self.jobs[priority as usize].push_back(Box::new(job));
+    }
+
+    /// The amount of jobs which are not done yet.
+    pub fn len(&self) -> usize {
+        self.jobs.iter().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Process jobs until all are done or the budget is used up. At least one step is
+    /// always processed, so the queue makes progress even with a tiny budget.
+    /// A pending job is resumed before any other job of the same priority.
+    pub fn run(&mut self, images: &mut Assets<Image>) {
+        let start = Instant::now();
+
+        loop {
+            let jobs = match self.jobs.iter_mut().rev().find(|jobs| !jobs.is_empty()) {
+                Some(jobs) => jobs,
+                None => return
+            };
+
+            let mut job = jobs.pop_front().expect("the queue is not empty");
+
+            if job(images) == JobProgress::Pending {
+                jobs.push_front(job);
+            }
+
+            if start.elapsed() >= self.budget {
+                return;
+            }
+        }
+    }
+}
+
+pub(crate) fn run_texture_work_queue(
+    mut queue: ResMut<TextureWorkQueue>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    queue.run(&mut images)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use bevy_asset::prelude::*;
+    use bevy_render::prelude::*;
+
+    use crate::work_queue::{JobProgress, TextureWorkQueue, WorkPriority};
+
+    /// Jobs with a higher priority must be processed first, no matter when they were added.
+    #[test]
+    fn run_processes_high_priority_first() {
+        // arrange
+        let mut queue = TextureWorkQueue::new(Duration::from_secs(10));
+        let mut images = Assets::<Image>::default();
+        let order = Arc::new(Mutex::new(vec![]));
+
+        for (name, priority) in [("low", WorkPriority::Low), ("high", WorkPriority::High), ("normal", WorkPriority::Normal)] {
+            let order = order.clone();
+            queue.push(priority, move |_| {
+                order.lock().unwrap().push(name);
+                JobProgress::Done
+            });
+        }
+
+        // act
+        queue.run(&mut images);
+
+        // assert
+        assert_eq!(vec!["high", "normal", "low"], *order.lock().unwrap());
+        assert!(queue.is_empty());
+    }
+
+    /// If the budget is used up, the remaining work must be resumed in the next run.
+    #[test]
+    fn run_resumes_pending_jobs() {
+        // arrange
+        let mut queue = TextureWorkQueue::new(Duration::ZERO);
+        let mut images = Assets::<Image>::default();
+        let steps = Arc::new(Mutex::new(0));
+
+        let job_steps = steps.clone();
+        queue.push(WorkPriority::Normal, move |_| {
+            let mut steps = job_steps.lock().unwrap();
+            *steps += 1;
+
+            match *steps {
+                3 => JobProgress::Done,
+                _ => JobProgress::Pending
+            }
+        });
+
+        // act
+        queue.run(&mut images);
+        let steps_after_first_run = *steps.lock().unwrap();
+        queue.run(&mut images);
+        queue.run(&mut images);
+
+        // assert
+        assert_eq!(1, steps_after_first_run, "Only one step should be processed with a zero budget.");
+        assert_eq!(3, *steps.lock().unwrap());
+        assert!(queue.is_empty());
+    }
+}